@@ -24,6 +24,19 @@ pub enum ClientMessage {
     Close {
         sub_id: String,
     },
+    /// Start a NIP-77 reconciliation, see [`crate::relay::negentropy`]
+    NegOpen {
+        sub_id: String,
+        filter: Filter,
+        message: String,
+    },
+    NegMsg {
+        sub_id: String,
+        message: String,
+    },
+    NegClose {
+        sub_id: String,
+    },
     Raw(String),
 }
 
@@ -46,6 +59,22 @@ impl ClientMessage {
         ClientMessage::Close { sub_id }
     }
 
+    pub fn neg_open(sub_id: String, filter: Filter, message: String) -> Self {
+        ClientMessage::NegOpen {
+            sub_id,
+            filter,
+            message,
+        }
+    }
+
+    pub fn neg_msg(sub_id: String, message: String) -> Self {
+        ClientMessage::NegMsg { sub_id, message }
+    }
+
+    pub fn neg_close(sub_id: String) -> Self {
+        ClientMessage::NegClose { sub_id }
+    }
+
     pub fn to_json(&self) -> Result<String, Error> {
         Ok(match self {
             Self::Event(ecm) => ecm.to_json(),
@@ -65,6 +94,18 @@ impl ClientMessage {
                 }
             }
             Self::Close { sub_id } => json!(["CLOSE", sub_id]).to_string(),
+            Self::NegOpen {
+                sub_id,
+                filter,
+                message,
+            } => format!(
+                "[\"NEG-OPEN\",{},{},{}]",
+                json!(sub_id),
+                filter.json()?,
+                json!(message)
+            ),
+            Self::NegMsg { sub_id, message } => json!(["NEG-MSG", sub_id, message]).to_string(),
+            Self::NegClose { sub_id } => json!(["NEG-CLOSE", sub_id]).to_string(),
         })
     }
 }
@@ -28,6 +28,12 @@ pub enum Error {
     #[error("invalid relay url")]
     InvalidRelayUrl,
 
+    #[error("relay requires authentication")]
+    AuthRequired,
+
+    #[error("message is too long for relay ({len} > {max} bytes)")]
+    MessageTooLong { len: u64, max: u64 },
+
     // Secp(secp256k1::Error),
     #[error("json error: {0}")]
     Json(#[from] serde_json::Error),
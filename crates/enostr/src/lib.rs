@@ -14,9 +14,12 @@ pub use filter::Filter;
 pub use keypair::{FilledKeypair, FullKeypair, Keypair, KeypairUnowned, SerializableKeypair};
 pub use nostr::SecretKey;
 pub use note::{Note, NoteId};
-pub use profile::ProfileState;
+pub use profile::{ProfileFieldChange, ProfileState};
 pub use pubkey::{Pubkey, PubkeyRef};
 pub use relay::message::{RelayEvent, RelayMessage};
+pub use relay::mux::{RelayMux, DEFAULT_SUB_BUDGET};
+pub use relay::negentropy::{NegOutcome, NegSessions, Negentropy};
+pub use relay::nip11::{RelayInfo, RelayInfoCache, RelayInfoState, RelayLimitation};
 pub use relay::pool::{PoolEvent, PoolRelay, RelayPool};
 pub use relay::subs_debug::{OwnedRelayEvent, RelayLogEvent, SubsDebug, TransferStats};
 pub use relay::{Relay, RelayStatus};
@@ -3,6 +3,14 @@ use serde_json::{Map, Value};
 #[derive(Debug, Clone)]
 pub struct ProfileState(Value);
 
+/// A field that differs between two profile states
+#[derive(Debug, PartialEq)]
+pub struct ProfileFieldChange<'a> {
+    pub key: &'a str,
+    pub old: Option<&'a Value>,
+    pub new: Option<&'a Value>,
+}
+
 impl Default for ProfileState {
     fn default() -> Self {
         ProfileState::new(Map::default())
@@ -59,7 +67,7 @@ impl ProfileState {
 
     #[inline]
     pub fn banner(&self) -> Option<&str> {
-        self.get_str("name")
+        self.get_str("banner")
     }
 
     #[inline]
@@ -97,6 +105,42 @@ impl ProfileState {
         self.get_str("website")
     }
 
+    /// A copy without the empty fields that [`ProfileState::str_mut`]
+    /// added since `original`. Everything else, including fields we don't
+    /// know about, is kept verbatim.
+    pub fn without_new_empty_fields(&self, original: &ProfileState) -> Self {
+        let mut cleaned = self.clone();
+        let original = original.value().as_object();
+
+        cleaned.values_mut().retain(|key, value| {
+            value.as_str() != Some("") || original.is_some_and(|orig| orig.contains_key(key))
+        });
+
+        cleaned
+    }
+
+    /// The fields that differ from `original`, sorted by key
+    pub fn diff<'a>(&'a self, original: &'a ProfileState) -> Vec<ProfileFieldChange<'a>> {
+        let empty = Map::new();
+        let new = self.value().as_object().unwrap_or(&empty);
+        let old = original.value().as_object().unwrap_or(&empty);
+
+        let mut keys: Vec<&String> = new.keys().chain(old.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter_map(|key| {
+                let change = ProfileFieldChange {
+                    key,
+                    old: old.get(key),
+                    new: new.get(key),
+                };
+                (change.old != change.new).then_some(change)
+            })
+            .collect()
+    }
+
     pub fn from_note_contents(contents: &str) -> Self {
         let json = serde_json::from_str(contents);
         let data = if let Ok(Value::Object(data)) = json {
@@ -108,3 +152,44 @@ impl ProfileState {
         Self::new(data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unknown_fields_are_preserved() {
+        let original =
+            ProfileState::from_note_contents(r#"{"name":"jb55","custom":{"a":1},"about":""}"#);
+
+        let mut edited = original.clone();
+        edited.str_mut("name").push('!');
+        // touched by the edit form but left empty
+        edited.str_mut("website");
+
+        let cleaned = edited.without_new_empty_fields(&original);
+        assert_eq!(
+            cleaned.value(),
+            &json!({"name": "jb55!", "custom": {"a": 1}, "about": ""})
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let original = ProfileState::from_note_contents(r#"{"name":"a","lud16":"a@b.c"}"#);
+        let mut edited = original.clone();
+        *edited.str_mut("name") = "b".to_string();
+        *edited.str_mut("about") = "hi".to_string();
+        edited.values_mut().remove("lud16");
+
+        let diff = edited.diff(&original);
+        let keys: Vec<&str> = diff.iter().map(|c| c.key).collect();
+        assert_eq!(keys, vec!["about", "lud16", "name"]);
+        assert_eq!(diff[0].old, None);
+        assert_eq!(diff[1].new, None);
+        assert_eq!(diff[2].new, Some(&json!("b")));
+
+        assert!(original.diff(&original).is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! Keeping what we send within a relay's NIP-11 limitation block.
+//!
+//! Relays answer requests that break their limits with CLOSED or NOTICE
+//! messages, which we'd otherwise have to notice and recover from. Instead
+//! we shape each message for the relay before sending it: filter limits are
+//! clamped, requests with too many filters are split into parts that each
+//! get their own subscription, and anything that can't fit is refused up
+//! front. Which subscription each part ends up on is up to
+//! [`super::mux::RelayMux`].
+
+use nostrdb::Filter;
+
+use super::nip11::RelayLimitation;
+use crate::{ClientMessage, Error, Result};
+
+/// The wire id for the nth part of a split subscription. The first part
+/// keeps the original id so EOSE and friends still line up for requests
+/// that didn't need splitting.
+pub fn part_id(sub_id: &str, part: usize) -> String {
+    if part == 0 {
+        sub_id.to_owned()
+    } else {
+        format!("{sub_id}:{part}")
+    }
+}
+
+/// We can't authenticate (NIP-42) yet, so relays that want it won't talk
+/// to us
+pub fn check_auth(limitation: &RelayLimitation) -> Result<()> {
+    if limitation.auth_required() {
+        return Err(Error::AuthRequired);
+    }
+    Ok(())
+}
+
+pub fn check_length(limitation: &RelayLimitation, msg: &ClientMessage) -> Result<()> {
+    let Some(max) = limitation.max_message_length else {
+        return Ok(());
+    };
+
+    let len = msg.to_json()?.len() as u64;
+    if len > max {
+        return Err(Error::MessageTooLong { len, max });
+    }
+
+    Ok(())
+}
+
+/// Clamp the filters of a request and split them into parts that each fit
+/// in a REQ to this relay
+pub fn split_filters(
+    limitation: &RelayLimitation,
+    sub_id: &str,
+    filters: &[Filter],
+) -> Result<Vec<Vec<Filter>>> {
+    check_auth(limitation)?;
+
+    let filters: Vec<Filter> = filters
+        .iter()
+        .map(|filter| clamp_limit(limitation, filter))
+        .collect();
+
+    let mut parts: Vec<Vec<Filter>> = match limitation.max_filters {
+        Some(max) if max > 0 && filters.len() as u64 > max => filters
+            .chunks(max as usize)
+            .map(<[Filter]>::to_vec)
+            .collect(),
+        _ => vec![filters],
+    };
+
+    // split further until every part fits in a message
+    let mut i = 0;
+    while i < parts.len() {
+        let req = ClientMessage::req(part_id(sub_id, i), parts[i].clone());
+        match check_length(limitation, &req) {
+            Ok(()) => i += 1,
+            Err(err) if parts[i].len() <= 1 => return Err(err),
+            Err(_) => {
+                let half = parts[i].len() / 2;
+                let rest = parts[i].split_off(half);
+                parts.insert(i + 1, rest);
+            }
+        }
+    }
+
+    Ok(parts)
+}
+
+fn clamp_limit(limitation: &RelayLimitation, filter: &Filter) -> Filter {
+    let filter = filter.clone();
+    match (limitation.max_limit, filter.limit()) {
+        (Some(max), Some(limit)) if limit > max => filter.limit_mut(max),
+        _ => filter,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::relay::nip11::RelayInfo;
+
+    pub fn parse_limitation(json: &str) -> RelayLimitation {
+        RelayInfo::parse(format!(r#"{{"limitation": {json}}}"#).as_bytes())
+            .unwrap()
+            .limitation()
+    }
+
+    pub fn filter(kind: u64, limit: u64) -> Filter {
+        Filter::new().kinds([kind]).limit(limit).build()
+    }
+
+    fn part_limits(parts: &[Vec<Filter>]) -> Vec<Vec<Option<u64>>> {
+        parts
+            .iter()
+            .map(|part| part.iter().map(|f| f.limit()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_no_limits() {
+        let parts = split_filters(
+            &RelayLimitation::default(),
+            "sub",
+            &[filter(1, 5000), filter(0, 10)],
+        )
+        .unwrap();
+
+        assert_eq!(part_limits(&parts), vec![vec![Some(5000), Some(10)]]);
+    }
+
+    #[test]
+    fn test_clamp_and_split() {
+        let limitation = parse_limitation(r#"{"max_limit": 500, "max_filters": 2}"#);
+        let parts = split_filters(
+            &limitation,
+            "sub",
+            &[filter(1, 5000), filter(0, 10), filter(3, 1000)],
+        )
+        .unwrap();
+
+        assert_eq!(
+            part_limits(&parts),
+            vec![vec![Some(500), Some(10)], vec![Some(500)]]
+        );
+        assert_eq!(part_id("sub", 0), "sub");
+        assert_eq!(part_id("sub", 1), "sub:1");
+    }
+
+    #[test]
+    fn test_message_length() {
+        let filters = vec![filter(1, 10), filter(0, 10)];
+        let one = ClientMessage::req("sub".to_owned(), vec![filters[0].clone()]);
+        let max = one.to_json().unwrap().len() + 2;
+
+        // both filters don't fit, one does
+        let limitation = parse_limitation(&format!(r#"{{"max_message_length": {max}}}"#));
+        let parts = split_filters(&limitation, "sub", &filters).unwrap();
+        assert_eq!(parts.len(), 2);
+
+        let limitation = parse_limitation(r#"{"max_message_length": 10}"#);
+        let event = ClientMessage::Raw(r#"["EVENT",{"too":"long"}]"#.to_owned());
+        assert!(matches!(
+            check_length(&limitation, &event),
+            Err(Error::MessageTooLong { max: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn test_auth_required() {
+        let limitation = parse_limitation(r#"{"auth_required": true}"#);
+
+        assert!(matches!(
+            split_filters(&limitation, "sub", &[filter(1, 10)]),
+            Err(Error::AuthRequired)
+        ));
+    }
+}
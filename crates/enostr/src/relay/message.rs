@@ -18,6 +18,10 @@ pub enum RelayMessage<'a> {
     Eose(&'a str),
     Event(&'a str, &'a str),
     Notice(&'a str),
+    /// NIP-77 reconciliation reply: subscription id and hex message
+    NegMsg(&'a str, &'a str),
+    /// NIP-77 reconciliation failure: subscription id and reason
+    NegErr(&'a str, &'a str),
 }
 
 #[derive(Debug)]
@@ -135,6 +139,19 @@ impl<'a> RelayMessage<'a> {
             ));
         }
 
+        // Negentropy (NIP-77)
+        // Relay response format: ["NEG-MSG", <subscription id>, <message>]
+        // or ["NEG-ERR", <subscription id>, <reason>]
+        if msg.starts_with("[\"NEG-") {
+            let (kind, subid, payload): (&str, &str, &str) = serde_json::from_str(msg)
+                .map_err(|err| Error::DecodeFailed(format!("invalid {msg}: {err}")))?;
+            return match kind {
+                "NEG-MSG" => Ok(RelayMessage::NegMsg(subid, payload)),
+                "NEG-ERR" => Ok(RelayMessage::NegErr(subid, payload)),
+                _ => Err(Error::DecodeFailed(format!("unknown message type {kind}"))),
+            };
+        }
+
         // OK (NIP-20)
         // Relay response format: ["OK",<event_id>, <true|false>, <message>]
         if &msg[0..=5] == "[\"OK\"," && msg.len() >= 78 {
@@ -207,6 +224,14 @@ mod tests {
                     "pow: difficulty 25>=24",
                 )),
             ),
+            (
+                r#"["NEG-MSG", "neg", "6100"]"#,
+                Ok(RelayMessage::NegMsg("neg", "6100")),
+            ),
+            (
+                r#"["NEG-ERR","neg","blocked: too many records"]"#,
+                Ok(RelayMessage::NegErr("neg", "blocked: too many records")),
+            ),
             // Invalid cases
             (
                 r#"["EVENT","random_string"]"#,
@@ -240,6 +265,10 @@ mod tests {
                 r#"["OK","b1a649ebe8b435ec71d3784793f3bbf4b93e64e17568a741aecd4c7ddeafce30",hello,404]"#,
                 Err(Error::DecodeFailed("bad boolean value".into())),
             ),
+            (
+                r#"["NEG-FOO","neg","6100"]"#,
+                Err(Error::DecodeFailed("unknown message type NEG-FOO".into())),
+            ),
         ];
 
         for (input, expected) in tests {
@@ -11,7 +11,11 @@ use std::hash::{Hash, Hasher};
 use std::net::Ipv4Addr;
 use tracing::{debug, error};
 
+pub mod limits;
 pub mod message;
+pub mod mux;
+pub mod negentropy;
+pub mod nip11;
 pub mod pool;
 pub mod subs_debug;
 
@@ -0,0 +1,878 @@
+//! Subscription multiplexing.
+//!
+//! Every column, thread and profile wants its own subscription, but relays
+//! only let a connection keep so many open. Each relay gets a budget of
+//! subscriptions (its NIP-11 `max_subscriptions`, or
+//! [`DEFAULT_SUB_BUDGET`]). While there's room, a subscription gets a wire
+//! subscription of its own with the same id, so nothing changes for the
+//! caller. Once the budget is used up, new subscriptions are merged into
+//! the existing wire subscriptions that still have room for their filters,
+//! and when none do they wait in a queue until a subscription closes.
+//!
+//! Merging isn't free: when a subscription joins or leaves a shared wire
+//! subscription its REQ goes out again, and relays replay stored events
+//! for a REQ they've already seen. Members that already got their EOSE
+//! only ask for events from then on.
+//!
+//! Events and EOSEs arrive with wire subscription ids, so [`RelayMux`] maps
+//! them back to the subscriptions they belong to.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use nostrdb::Filter;
+use serde::Deserialize;
+use tracing::debug;
+
+use super::limits::{check_auth, check_length, part_id, split_filters};
+use super::nip11::RelayLimitation;
+use crate::{ClientMessage, Result};
+
+/// How many subscriptions we keep open on a relay that doesn't tell us
+pub const DEFAULT_SUB_BUDGET: usize = 20;
+
+/// The fields of an event that filters look at
+#[derive(Deserialize)]
+struct EventFields {
+    id: String,
+    pubkey: String,
+    kind: u64,
+    created_at: u64,
+    #[serde(default)]
+    tags: Vec<Vec<String>>,
+}
+
+/// A filter we can check events against, parsed from the filter's json
+#[derive(Debug, Default, Deserialize)]
+struct FilterMatcher {
+    ids: Option<Vec<String>>,
+    authors: Option<Vec<String>>,
+    kinds: Option<Vec<u64>>,
+    since: Option<u64>,
+    until: Option<u64>,
+    /// tag queries (`#e`, `#p`, ...) along with anything else
+    #[serde(flatten)]
+    rest: HashMap<String, serde_json::Value>,
+}
+
+fn has_prefix(values: &Option<Vec<String>>, s: &str) -> bool {
+    values
+        .as_ref()
+        .is_none_or(|values| values.iter().any(|v| s.starts_with(v.as_str())))
+}
+
+impl FilterMatcher {
+    /// Filters we can't make sense of match everything, so we err on the
+    /// side of delivering too much
+    fn new(filter: &Filter) -> Self {
+        filter
+            .json()
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn matches(&self, event: &EventFields) -> bool {
+        if !has_prefix(&self.ids, &event.id) || !has_prefix(&self.authors, &event.pubkey) {
+            return false;
+        }
+
+        if !self.kinds.as_ref().is_none_or(|k| k.contains(&event.kind))
+            || self.since.is_some_and(|since| event.created_at < since)
+            || self.until.is_some_and(|until| event.created_at > until)
+        {
+            return false;
+        }
+
+        self.rest.iter().all(|(key, values)| {
+            let (Some(tag), Some(values)) = (key.strip_prefix('#'), values.as_array()) else {
+                return true;
+            };
+
+            event.tags.iter().any(|t| {
+                t.first().map(String::as_str) == Some(tag)
+                    && t.get(1)
+                        .is_some_and(|v| values.iter().any(|x| x.as_str() == Some(v)))
+            })
+        })
+    }
+}
+
+/// One part of a subscription, sitting on a wire subscription
+struct Member {
+    sub_id: String,
+    filters: Vec<Filter>,
+    matchers: Vec<FilterMatcher>,
+    eosed: bool,
+}
+
+impl Member {
+    fn new(sub_id: &str, filters: Vec<Filter>) -> Self {
+        Member {
+            sub_id: sub_id.to_owned(),
+            matchers: filters.iter().map(FilterMatcher::new).collect(),
+            filters,
+            eosed: false,
+        }
+    }
+}
+
+/// A subscription as the relay sees it
+#[derive(Default)]
+struct Wire {
+    members: Vec<Member>,
+}
+
+/// The union of the filters, without duplicates
+fn dedup_filters<'a>(filters: impl IntoIterator<Item = &'a Filter>) -> Vec<Filter> {
+    let mut seen = HashSet::new();
+    filters
+        .into_iter()
+        .filter(|filter| match filter.json() {
+            Ok(json) => seen.insert(json),
+            Err(_) => true,
+        })
+        .cloned()
+        .collect()
+}
+
+impl Wire {
+    fn filters(&self) -> Vec<Filter> {
+        dedup_filters(self.members.iter().flat_map(|m| m.filters.iter()))
+    }
+
+    /// The filters to send when the wire's REQ goes out again. The relay
+    /// treats it as a new subscription and replays its stored events, so
+    /// members that already have theirs only ask for what's new.
+    fn req_filters(&self, now: u64) -> Vec<Filter> {
+        let filters: Vec<Filter> = self
+            .members
+            .iter()
+            .flat_map(|m| {
+                m.filters
+                    .iter()
+                    .zip(&m.matchers)
+                    .map(move |(filter, matcher)| {
+                        if !m.eosed {
+                            return filter.clone();
+                        }
+                        let since = matcher.since.map_or(now, |since| since.max(now));
+                        filter.clone().since_mut(since)
+                    })
+            })
+            .collect();
+        dedup_filters(&filters)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The subscriptions we have on a relay, and how they map onto the
+/// subscriptions the relay knows about
+#[derive(Default)]
+pub struct RelayMux {
+    wires: HashMap<String, Wire>,
+    /// The wire subscriptions holding the parts of each subscription
+    placed: HashMap<String, Vec<String>>,
+    /// Subscriptions waiting for room, already split into parts
+    queue: VecDeque<(String, Vec<Vec<Filter>>)>,
+}
+
+impl RelayMux {
+    /// The number of subscriptions open on the wire
+    pub fn open(&self) -> usize {
+        self.wires.len()
+    }
+
+    /// The number of subscriptions waiting for room
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Subscriptions don't survive a reconnect
+    pub fn clear(&mut self) {
+        self.wires.clear();
+        self.placed.clear();
+        self.queue.clear();
+    }
+
+    /// Turn a message for this relay into what we actually send
+    pub fn prepare(
+        &mut self,
+        limitation: &RelayLimitation,
+        msg: &ClientMessage,
+    ) -> Result<Vec<ClientMessage>> {
+        match msg {
+            ClientMessage::Req { sub_id, filters } => {
+                let parts = split_filters(limitation, sub_id, filters)?;
+                let mut msgs = self.remove(sub_id);
+                self.queue.push_back((sub_id.clone(), parts));
+                msgs.extend(self.drain_queue(limitation));
+                Ok(last_per_wire(msgs))
+            }
+
+            ClientMessage::Close { sub_id } => {
+                let known = self.placed.contains_key(sub_id)
+                    || self.queue.iter().any(|(id, _)| id == sub_id);
+                let mut msgs = if known {
+                    self.remove(sub_id)
+                } else {
+                    vec![ClientMessage::close(sub_id.clone())]
+                };
+                msgs.extend(self.drain_queue(limitation));
+                Ok(last_per_wire(msgs))
+            }
+
+            // reconciliations don't share wires, they're short lived
+            ClientMessage::Event(_)
+            | ClientMessage::NegOpen { .. }
+            | ClientMessage::NegMsg { .. }
+            | ClientMessage::NegClose { .. }
+            | ClientMessage::Raw(_) => {
+                check_auth(limitation)?;
+                check_length(limitation, msg)?;
+                Ok(vec![msg.clone()])
+            }
+        }
+    }
+
+    /// Shape the subscriptions we already have for new limits. Relay
+    /// information usually arrives after the first REQs went out, so they
+    /// were sent without knowing the relay's limits. Wire subscriptions
+    /// that come out the same aren't sent again.
+    pub fn reshape(&mut self, limitation: &RelayLimitation) -> Vec<ClientMessage> {
+        let mut sub_ids: Vec<&String> = self.placed.keys().collect();
+        sub_ids.sort();
+        let mut subs: Vec<(String, Vec<Filter>)> = sub_ids
+            .into_iter()
+            .map(|sub_id| (sub_id.clone(), self.sub_filters(sub_id)))
+            .collect();
+        subs.extend(
+            self.queue
+                .iter()
+                .map(|(sub_id, parts)| (sub_id.clone(), parts.concat())),
+        );
+
+        let old_wires = std::mem::take(&mut self.wires);
+        self.clear();
+
+        let mut msgs = vec![];
+        for (sub_id, filters) in subs {
+            match self.prepare(limitation, &ClientMessage::req(sub_id.clone(), filters)) {
+                Ok(prepared) => msgs.extend(prepared),
+                Err(err) => debug!("dropping {sub_id} for the relay's limits: {err}"),
+            }
+        }
+
+        let now = unix_now();
+        for (wire_id, old) in &old_wires {
+            let Some(wire) = self.wires.get_mut(wire_id) else {
+                msgs.push(ClientMessage::close(wire_id.clone()));
+                continue;
+            };
+
+            for member in &mut wire.members {
+                member.eosed = old
+                    .members
+                    .iter()
+                    .any(|m| m.sub_id == member.sub_id && m.eosed);
+            }
+
+            // nothing changed on the relay's end, so it doesn't hear about it
+            if same_filters(&wire.filters(), &old.filters()) {
+                msgs.retain(
+                    |msg| !matches!(msg, ClientMessage::Req { sub_id, .. } if sub_id == wire_id),
+                );
+                continue;
+            }
+
+            for msg in &mut msgs {
+                if let ClientMessage::Req { sub_id, filters } = msg {
+                    if sub_id == wire_id {
+                        *filters = wire.req_filters(now);
+                    }
+                }
+            }
+        }
+
+        last_per_wire(msgs)
+    }
+
+    /// All the filters of a subscription, across its parts
+    fn sub_filters(&self, sub_id: &str) -> Vec<Filter> {
+        let Some(wire_ids) = self.placed.get(sub_id) else {
+            return vec![];
+        };
+
+        let mut wire_ids = wire_ids.clone();
+        wire_ids.dedup();
+        wire_ids
+            .iter()
+            .filter_map(|wire_id| self.wires.get(wire_id))
+            .flat_map(|wire| wire.members.iter().filter(|m| m.sub_id == sub_id))
+            .flat_map(|member| member.filters.iter().cloned())
+            .collect()
+    }
+
+    /// The subscriptions an event that arrived on a wire subscription is for
+    pub fn demux_event(&self, wire_id: &str, event_json: &str) -> Vec<String> {
+        let Some(wire) = self.wires.get(wire_id) else {
+            return vec![wire_id.to_owned()];
+        };
+
+        let event = serde_json::from_str::<EventFields>(event_json).ok();
+        let mut subs: Vec<String> = vec![];
+        for member in &wire.members {
+            let matches = match &event {
+                Some(event) => member.matchers.iter().any(|m| m.matches(event)),
+                None => true,
+            };
+            if matches && !subs.contains(&member.sub_id) {
+                subs.push(member.sub_id.clone());
+            }
+        }
+
+        if subs.is_empty() {
+            // every member should have matched something, deliver it
+            // everywhere rather than dropping it
+            return wire_sub_ids(wire);
+        }
+
+        subs
+    }
+
+    /// The subscriptions that are done with stored events now that a wire
+    /// subscription got its EOSE. A subscription only gets its EOSE once,
+    /// when every one of its parts has.
+    pub fn demux_eose(&mut self, wire_id: &str) -> Vec<String> {
+        let Some(wire) = self.wires.get_mut(wire_id) else {
+            return vec![wire_id.to_owned()];
+        };
+
+        let mut candidates = vec![];
+        for member in &mut wire.members {
+            if !member.eosed {
+                member.eosed = true;
+                if !candidates.contains(&member.sub_id) {
+                    candidates.push(member.sub_id.clone());
+                }
+            }
+        }
+
+        candidates
+            .into_iter()
+            .filter(|sub_id| self.is_eosed(sub_id))
+            .collect()
+    }
+
+    fn is_eosed(&self, sub_id: &str) -> bool {
+        let Some(wire_ids) = self.placed.get(sub_id) else {
+            return false;
+        };
+
+        wire_ids.iter().all(|wire_id| {
+            self.wires.get(wire_id).is_none_or(|wire| {
+                wire.members
+                    .iter()
+                    .filter(|m| m.sub_id == sub_id)
+                    .all(|m| m.eosed)
+            })
+        })
+    }
+
+    /// Take a subscription off the relay or out of the queue, returning the
+    /// CLOSEs and updated REQs for the wire subscriptions it was on
+    fn remove(&mut self, sub_id: &str) -> Vec<ClientMessage> {
+        self.queue.retain(|(id, _)| id != sub_id);
+
+        let Some(mut wire_ids) = self.placed.remove(sub_id) else {
+            return vec![];
+        };
+        wire_ids.dedup();
+
+        let mut msgs = vec![];
+        for wire_id in wire_ids {
+            let Some(wire) = self.wires.get_mut(&wire_id) else {
+                continue;
+            };
+
+            wire.members.retain(|m| m.sub_id != sub_id);
+            if wire.members.is_empty() {
+                self.wires.remove(&wire_id);
+                msgs.push(ClientMessage::close(wire_id));
+            } else {
+                msgs.push(ClientMessage::req(wire_id, wire.req_filters(unix_now())));
+            }
+        }
+
+        msgs
+    }
+
+    fn drain_queue(&mut self, limitation: &RelayLimitation) -> Vec<ClientMessage> {
+        let mut msgs = vec![];
+
+        while let Some((sub_id, parts)) = self.queue.front() {
+            let Some(plan) = self.plan(limitation, parts) else {
+                debug!(
+                    "{} subscriptions waiting for room, {} open",
+                    self.queue.len(),
+                    self.wires.len()
+                );
+                break;
+            };
+
+            let (sub_id, parts) = (sub_id.clone(), parts.clone());
+            self.queue.pop_front();
+            msgs.extend(self.apply(&sub_id, parts, plan));
+        }
+
+        msgs
+    }
+
+    /// Where each part of a subscription would go: a wire subscription of
+    /// its own while we're within budget, otherwise an existing one with
+    /// room for it. None when there isn't room for every part.
+    fn plan(
+        &self,
+        limitation: &RelayLimitation,
+        parts: &[Vec<Filter>],
+    ) -> Option<Vec<Option<String>>> {
+        let budget = limitation
+            .max_subscriptions
+            .map(|max| max as usize)
+            .unwrap_or(DEFAULT_SUB_BUDGET)
+            .max(1);
+        let mut free = budget.saturating_sub(self.wires.len());
+        let mut merged: HashMap<&str, Vec<Filter>> = HashMap::new();
+        let mut plan = vec![];
+
+        for part in parts {
+            if free > 0 {
+                free -= 1;
+                plan.push(None);
+                continue;
+            }
+
+            let mut best: Option<(&str, Vec<Filter>)> = None;
+            for (wire_id, wire) in &self.wires {
+                let pending = merged.get(wire_id.as_str());
+                let filters = dedup_filters(
+                    wire.members
+                        .iter()
+                        .flat_map(|m| m.filters.iter())
+                        .chain(pending.into_iter().flatten())
+                        .chain(part.iter()),
+                );
+
+                let fits = limitation
+                    .max_filters
+                    .is_none_or(|max| filters.len() as u64 <= max)
+                    && check_length(
+                        limitation,
+                        &ClientMessage::req(wire_id.clone(), filters.clone()),
+                    )
+                    .is_ok();
+
+                let better = best.as_ref().is_none_or(|(_, b)| filters.len() < b.len());
+                if fits && better {
+                    best = Some((wire_id, filters));
+                }
+            }
+
+            let (wire_id, _) = best?;
+            merged
+                .entry(wire_id)
+                .or_default()
+                .extend(part.iter().cloned());
+            plan.push(Some(wire_id.to_owned()));
+        }
+
+        Some(plan)
+    }
+
+    fn apply(
+        &mut self,
+        sub_id: &str,
+        parts: Vec<Vec<Filter>>,
+        plan: Vec<Option<String>>,
+    ) -> Vec<ClientMessage> {
+        let mut wire_ids: Vec<String> = vec![];
+
+        for (i, (part, target)) in parts.into_iter().zip(plan).enumerate() {
+            let wire_id = target.unwrap_or_else(|| self.new_wire_id(sub_id, i));
+            self.wires
+                .entry(wire_id.clone())
+                .or_default()
+                .members
+                .push(Member::new(sub_id, part));
+            if !wire_ids.contains(&wire_id) {
+                wire_ids.push(wire_id);
+            }
+        }
+
+        let now = unix_now();
+        let msgs = wire_ids
+            .iter()
+            .filter_map(|wire_id| {
+                let wire = self.wires.get(wire_id)?;
+                Some(ClientMessage::req(wire_id.clone(), wire.req_filters(now)))
+            })
+            .collect();
+
+        self.placed.insert(sub_id.to_owned(), wire_ids);
+        msgs
+    }
+
+    fn new_wire_id(&self, sub_id: &str, part: usize) -> String {
+        let mut wire_id = part_id(sub_id, part);
+        let mut n = 0;
+        while self.wires.contains_key(&wire_id) {
+            n += 1;
+            wire_id = format!("{}~{n}", part_id(sub_id, part));
+        }
+        wire_id
+    }
+}
+
+fn same_filters(a: &[Filter], b: &[Filter]) -> bool {
+    let json = |filters: &[Filter]| -> Vec<Option<String>> {
+        filters.iter().map(|filter| filter.json().ok()).collect()
+    };
+    json(a) == json(b)
+}
+
+fn wire_sub_ids(wire: &Wire) -> Vec<String> {
+    let mut subs: Vec<String> = vec![];
+    for member in &wire.members {
+        if !subs.contains(&member.sub_id) {
+            subs.push(member.sub_id.clone());
+        }
+    }
+    subs
+}
+
+/// Only the last REQ or CLOSE for each wire subscription matters, the
+/// relay would just replace the earlier ones
+fn last_per_wire(msgs: Vec<ClientMessage>) -> Vec<ClientMessage> {
+    let mut seen = HashSet::new();
+    let mut kept: Vec<ClientMessage> = msgs
+        .into_iter()
+        .rev()
+        .filter(|msg| match msg {
+            ClientMessage::Req { sub_id, .. } | ClientMessage::Close { sub_id } => {
+                seen.insert(sub_id.clone())
+            }
+            _ => true,
+        })
+        .collect();
+    kept.reverse();
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::relay::limits::tests::{filter, parse_limitation};
+
+    fn req(sub_id: &str, filters: Vec<Filter>) -> ClientMessage {
+        ClientMessage::req(sub_id.to_owned(), filters)
+    }
+
+    fn close(sub_id: &str) -> ClientMessage {
+        ClientMessage::close(sub_id.to_owned())
+    }
+
+    fn json(msgs: &[ClientMessage]) -> Vec<String> {
+        msgs.iter().map(|msg| msg.to_json().unwrap()).collect()
+    }
+
+    fn wire_ids(msgs: &[ClientMessage]) -> Vec<String> {
+        msgs.iter()
+            .map(|msg| match msg {
+                ClientMessage::Req { sub_id, .. } => format!("REQ {sub_id}"),
+                ClientMessage::Close { sub_id } => format!("CLOSE {sub_id}"),
+                _ => "other".to_owned(),
+            })
+            .collect()
+    }
+
+    fn event(kind: u64, pubkey: &str, tags: &str) -> String {
+        format!(
+            r#"{{"id":"abcd","pubkey":"{pubkey}","created_at":100,"kind":{kind},"tags":{tags},"content":"","sig":""}}"#
+        )
+    }
+
+    #[test]
+    fn test_within_budget_keeps_ids() {
+        let mut mux = RelayMux::default();
+        let limits = RelayLimitation::default();
+
+        let msgs = mux
+            .prepare(&limits, &req("a", vec![filter(1, 10)]))
+            .unwrap();
+        assert_eq!(wire_ids(&msgs), vec!["REQ a"]);
+
+        assert_eq!(mux.demux_event("a", &event(1, "00", "[]")), vec!["a"]);
+        assert_eq!(mux.demux_eose("a"), vec!["a"]);
+
+        // things we don't know about pass through untouched
+        assert_eq!(mux.demux_eose("other"), vec!["other"]);
+        assert_eq!(
+            json(&mux.prepare(&limits, &close("other")).unwrap()),
+            vec![r#"["CLOSE","other"]"#]
+        );
+
+        let msgs = mux.prepare(&limits, &close("a")).unwrap();
+        assert_eq!(wire_ids(&msgs), vec!["CLOSE a"]);
+        assert_eq!(mux.open(), 0);
+    }
+
+    #[test]
+    fn test_split_parts() {
+        let mut mux = RelayMux::default();
+        let limits = parse_limitation(r#"{"max_filters": 1}"#);
+
+        let msgs = mux
+            .prepare(&limits, &req("sub", vec![filter(1, 10), filter(0, 10)]))
+            .unwrap();
+        assert_eq!(wire_ids(&msgs), vec!["REQ sub", "REQ sub:1"]);
+
+        // the subscription is only done once both parts are
+        assert!(mux.demux_eose("sub").is_empty());
+        assert_eq!(mux.demux_eose("sub:1"), vec!["sub"]);
+
+        // replacing it with fewer parts closes the one it doesn't need
+        let msgs = mux
+            .prepare(&limits, &req("sub", vec![filter(1, 10)]))
+            .unwrap();
+        assert_eq!(wire_ids(&msgs), vec!["CLOSE sub:1", "REQ sub"]);
+        assert_eq!(mux.open(), 1);
+
+        let msgs = mux.prepare(&limits, &close("sub")).unwrap();
+        assert_eq!(wire_ids(&msgs), vec!["CLOSE sub"]);
+    }
+
+    #[test]
+    fn test_split_events_reach_sub() {
+        let mut mux = RelayMux::default();
+        let limits = parse_limitation(r#"{"max_filters": 1}"#);
+
+        mux.prepare(&limits, &req("sub", vec![filter(1, 10), filter(0, 10)]))
+            .unwrap();
+
+        // events on either part are for the subscription we asked for
+        assert_eq!(mux.demux_event("sub", &event(1, "00", "[]")), vec!["sub"]);
+        assert_eq!(mux.demux_event("sub:1", &event(0, "00", "[]")), vec!["sub"]);
+
+        // closing closes every part
+        let msgs = mux.prepare(&limits, &close("sub")).unwrap();
+        assert_eq!(
+            json(&msgs),
+            vec![r#"["CLOSE","sub"]"#, r#"["CLOSE","sub:1"]"#]
+        );
+        assert_eq!(mux.open(), 0);
+    }
+
+    #[test]
+    fn test_max_subscriptions() {
+        let mut mux = RelayMux::default();
+        let limits = parse_limitation(r#"{"max_subscriptions": 2}"#);
+
+        mux.prepare(&limits, &req("a", vec![filter(1, 10)]))
+            .unwrap();
+        mux.prepare(&limits, &req("b", vec![filter(3, 10)]))
+            .unwrap();
+        mux.prepare(&limits, &req("c", vec![filter(7, 10)]))
+            .unwrap();
+        assert_eq!(mux.open(), 2);
+
+        // replacing an open subscription doesn't take more room
+        mux.prepare(&limits, &req("a", vec![filter(1, 20)]))
+            .unwrap();
+        assert_eq!(mux.open(), 2);
+
+        // subscriptions don't survive a reconnect
+        mux.clear();
+        let msgs = mux
+            .prepare(&limits, &req("c", vec![filter(7, 10)]))
+            .unwrap();
+        assert_eq!(wire_ids(&msgs), vec!["REQ c"]);
+    }
+
+    #[test]
+    fn test_info_arrives_after_req() {
+        let mut mux = RelayMux::default();
+
+        // we don't know the relay's limits yet
+        mux.prepare(
+            &RelayLimitation::default(),
+            &req("sub", vec![filter(1, 5000), filter(0, 10)]),
+        )
+        .unwrap();
+        mux.prepare(
+            &RelayLimitation::default(),
+            &req("other", vec![filter(3, 10)]),
+        )
+        .unwrap();
+        assert_eq!(mux.demux_eose("other"), vec!["other"]);
+
+        let limits = parse_limitation(r#"{"max_filters": 1, "max_limit": 500}"#);
+        let msgs = mux.reshape(&limits);
+
+        // only the subscription that doesn't fit is sent again
+        assert_eq!(wire_ids(&msgs), vec!["REQ sub", "REQ sub:1"]);
+        let ClientMessage::Req { filters, .. } = &msgs[0] else {
+            panic!("expected a REQ");
+        };
+        assert_eq!(filters[0].limit(), Some(500));
+
+        // and the one that was left alone still had its EOSE
+        assert!(mux.demux_eose("other").is_empty());
+        assert!(mux.demux_eose("sub").is_empty());
+        assert_eq!(mux.demux_eose("sub:1"), vec!["sub"]);
+
+        // nothing to do when the limits don't change anything
+        assert!(mux.reshape(&limits).is_empty());
+    }
+
+    #[test]
+    fn test_merge_over_budget() {
+        let mut mux = RelayMux::default();
+        let limits = parse_limitation(r#"{"max_subscriptions": 2}"#);
+
+        mux.prepare(&limits, &req("a", vec![filter(1, 10)]))
+            .unwrap();
+        mux.prepare(&limits, &req("b", vec![filter(3, 10)]))
+            .unwrap();
+        assert_eq!(mux.demux_eose("a"), vec!["a"]);
+        assert_eq!(mux.demux_eose("b"), vec!["b"]);
+
+        // no room left, so c shares a wire subscription
+        let msgs = mux
+            .prepare(&limits, &req("c", vec![filter(7, 10)]))
+            .unwrap();
+        assert_eq!(msgs.len(), 1);
+        let ClientMessage::Req { sub_id, filters } = &msgs[0] else {
+            panic!("expected a REQ");
+        };
+        assert_eq!(filters.len(), 2);
+        assert_eq!(mux.open(), 2);
+
+        // events go to whichever subscription they match
+        let shared = sub_id.clone();
+        let own_kind = if shared == "a" { 1 } else { 3 };
+        assert_eq!(mux.demux_event(&shared, &event(7, "00", "[]")), vec!["c"]);
+        assert_eq!(
+            mux.demux_event(&shared, &event(own_kind, "00", "[]")),
+            vec![shared.clone()]
+        );
+
+        // a second EOSE only reaches the subscription that hadn't seen one
+        assert_eq!(mux.demux_eose(&shared), vec!["c"]);
+        assert!(mux.demux_eose(&shared).is_empty());
+
+        // closing c just narrows the shared subscription again
+        let msgs = mux.prepare(&limits, &close("c")).unwrap();
+        assert_eq!(wire_ids(&msgs), vec![format!("REQ {shared}")]);
+    }
+
+    #[test]
+    fn test_resent_req_bounds_eosed_members() {
+        let mut mux = RelayMux::default();
+        let limits = parse_limitation(r#"{"max_subscriptions": 1}"#);
+
+        mux.prepare(&limits, &req("a", vec![filter(1, 10)]))
+            .unwrap();
+        assert_eq!(mux.demux_eose("a"), vec!["a"]);
+
+        let before = unix_now();
+        let since = |msgs: &[ClientMessage]| -> Vec<Option<u64>> {
+            let ClientMessage::Req { filters, .. } = &msgs[0] else {
+                panic!("expected a REQ");
+            };
+            filters
+                .iter()
+                .map(|filter| FilterMatcher::new(filter).since)
+                .collect()
+        };
+
+        // a already has its stored events, b doesn't
+        let msgs = mux
+            .prepare(&limits, &req("b", vec![filter(3, 10)]))
+            .unwrap();
+        assert_eq!(wire_ids(&msgs), vec!["REQ a"]);
+        let bounds = since(&msgs);
+        assert!(bounds[0].is_some_and(|since| since >= before));
+        assert_eq!(bounds[1], None);
+
+        // once b has its EOSE, both stay bounded when one leaves
+        assert_eq!(mux.demux_eose("a"), vec!["b"]);
+        let msgs = mux
+            .prepare(&limits, &req("c", vec![filter(5, 10)]))
+            .unwrap();
+        let bounds = since(&msgs);
+        assert!(bounds[..2].iter().all(|b| b.is_some_and(|s| s >= before)));
+        assert_eq!(bounds[2], None);
+
+        let msgs = mux.prepare(&limits, &close("b")).unwrap();
+        let bounds = since(&msgs);
+        assert!(bounds[0].is_some_and(|since| since >= before));
+        assert_eq!(bounds[1], None);
+    }
+
+    #[test]
+    fn test_queue_when_full() {
+        let mut mux = RelayMux::default();
+        let limits = parse_limitation(r#"{"max_subscriptions": 1, "max_filters": 1}"#);
+
+        mux.prepare(&limits, &req("a", vec![filter(1, 10)]))
+            .unwrap();
+        let msgs = mux
+            .prepare(&limits, &req("b", vec![filter(3, 10)]))
+            .unwrap();
+        assert!(msgs.is_empty());
+        assert_eq!(mux.queued(), 1);
+
+        // closing a makes room for b
+        let msgs = mux.prepare(&limits, &close("a")).unwrap();
+        assert_eq!(wire_ids(&msgs), vec!["CLOSE a", "REQ b"]);
+        assert_eq!(mux.queued(), 0);
+
+        // closing something that's still queued never reaches the relay
+        mux.prepare(&limits, &req("c", vec![filter(5, 10)]))
+            .unwrap();
+        assert!(mux.prepare(&limits, &close("c")).unwrap().is_empty());
+        assert_eq!(mux.queued(), 0);
+
+        mux.clear();
+        assert_eq!(mux.open(), 0);
+    }
+
+    #[test]
+    fn test_filter_matcher() {
+        let tagged = Filter::new()
+            .kinds([1])
+            .authors([&[0xab; 32]])
+            .event(&[0xcd; 32])
+            .since(50)
+            .build();
+        let matcher = FilterMatcher::new(&tagged);
+
+        let pubkey = hex::encode([0xab; 32]);
+        let e_tag = format!(r#"[["e","{}"]]"#, hex::encode([0xcd; 32]));
+        let matches = |json: &str| {
+            let event: EventFields = serde_json::from_str(json).unwrap();
+            matcher.matches(&event)
+        };
+
+        assert!(matches(&event(1, &pubkey, &e_tag)));
+        assert!(!matches(&event(1, &pubkey, "[]")));
+        assert!(!matches(&event(2, &pubkey, &e_tag)));
+        assert!(!matches(&event(1, "00", &e_tag)));
+    }
+}
@@ -0,0 +1,649 @@
+//! NIP-77 negentropy set reconciliation.
+//!
+//! Rather than asking a relay for everything that matches a filter and
+//! having it resend notes we already have, we describe what we hold as
+//! fingerprints over ranges of `(created_at, id)` pairs. Both sides narrow
+//! down the ranges that differ until we know exactly which ids we are
+//! missing, which we then fetch with a plain REQ.
+//!
+//! This implements version 1 of the protocol, see
+//! <https://github.com/hoytech/negentropy>.
+
+use std::collections::{HashMap, HashSet};
+
+use nostrdb::Filter;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::{ClientMessage, Error, Result};
+
+const PROTOCOL_VERSION: u8 = 0x61;
+const ID_SIZE: usize = 32;
+const FINGERPRINT_SIZE: usize = 16;
+
+/// How many ranges a mismatched range is split into
+const BUCKETS: usize = 16;
+
+/// A note we hold: its `created_at` and id
+pub type Item = (u64, [u8; 32]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Skip = 0,
+    Fingerprint = 1,
+    IdList = 2,
+}
+
+impl Mode {
+    fn decode(n: u64) -> Result<Self> {
+        match n {
+            0 => Ok(Mode::Skip),
+            1 => Ok(Mode::Fingerprint),
+            2 => Ok(Mode::IdList),
+            n => Err(decode_error(format!("unknown mode {n}"))),
+        }
+    }
+}
+
+/// The upper end of a range. Ids are truncated to the shortest prefix that
+/// still separates the range from the next one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bound {
+    timestamp: u64,
+    id: Vec<u8>,
+}
+
+impl Bound {
+    fn zero() -> Self {
+        Bound {
+            timestamp: 0,
+            id: vec![],
+        }
+    }
+
+    fn infinity() -> Self {
+        Bound {
+            timestamp: u64::MAX,
+            id: vec![],
+        }
+    }
+
+    /// The smallest bound that is above `prev` but not above `curr`
+    fn between(prev: &Item, curr: &Item) -> Self {
+        if prev.0 != curr.0 {
+            return Bound {
+                timestamp: curr.0,
+                id: vec![],
+            };
+        }
+
+        let shared = prev
+            .1
+            .iter()
+            .zip(curr.1.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        Bound {
+            timestamp: curr.0,
+            id: curr.1[..(shared + 1).min(ID_SIZE)].to_vec(),
+        }
+    }
+
+    fn key(&self) -> Item {
+        let mut id = [0u8; ID_SIZE];
+        id[..self.id.len()].copy_from_slice(&self.id);
+        (self.timestamp, id)
+    }
+}
+
+fn decode_error(msg: impl Into<String>) -> Error {
+    Error::DecodeFailed(format!("negentropy: {}", msg.into()))
+}
+
+fn encode_varint(mut n: u64, out: &mut Vec<u8>) {
+    let mut digits = vec![(n & 0x7f) as u8];
+    n >>= 7;
+    while n > 0 {
+        digits.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    out.extend(digits.iter().rev());
+}
+
+fn decode_varint(input: &mut &[u8]) -> Result<u64> {
+    let mut n: u64 = 0;
+    loop {
+        let (&byte, rest) = input
+            .split_first()
+            .ok_or_else(|| decode_error("premature end of varint"))?;
+        *input = rest;
+
+        n = n
+            .checked_mul(128)
+            .ok_or_else(|| decode_error("varint overflow"))?
+            | u64::from(byte & 0x7f);
+
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+}
+
+fn take_bytes<'a>(input: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if input.len() < n {
+        return Err(decode_error("premature end of message"));
+    }
+    let (bytes, rest) = input.split_at(n);
+    *input = rest;
+    Ok(bytes)
+}
+
+/// sha256 over the sum of the ids (as little endian 256-bit numbers) and
+/// the number of ids
+fn fingerprint(items: &[Item]) -> [u8; FINGERPRINT_SIZE] {
+    let mut sum = [0u8; ID_SIZE];
+    for (_, id) in items {
+        let mut carry = 0u16;
+        for (acc, byte) in sum.iter_mut().zip(id.iter()) {
+            let total = u16::from(*acc) + u16::from(*byte) + carry;
+            *acc = total as u8;
+            carry = total >> 8;
+        }
+    }
+
+    let mut count = vec![];
+    encode_varint(items.len() as u64, &mut count);
+
+    let hash = Sha256::new()
+        .chain_update(sum)
+        .chain_update(count)
+        .finalize();
+    let mut fingerprint = [0u8; FINGERPRINT_SIZE];
+    fingerprint.copy_from_slice(&hash[..FINGERPRINT_SIZE]);
+    fingerprint
+}
+
+/// One side of a reconciliation over a sorted set of items
+pub struct Negentropy {
+    items: Vec<Item>,
+    initiator: bool,
+    last_timestamp_in: u64,
+    last_timestamp_out: u64,
+}
+
+impl Negentropy {
+    pub fn new(mut items: Vec<Item>) -> Self {
+        items.sort_unstable();
+        items.dedup();
+        Negentropy {
+            items,
+            initiator: false,
+            last_timestamp_in: 0,
+            last_timestamp_out: 0,
+        }
+    }
+
+    /// The first message, describing everything we hold
+    pub fn initiate(&mut self) -> Vec<u8> {
+        self.initiator = true;
+        self.last_timestamp_out = 0;
+
+        let mut out = vec![PROTOCOL_VERSION];
+        self.split_range(0, self.items.len(), Bound::infinity(), &mut out);
+        out
+    }
+
+    /// Handle the other side's reply. Ids only we have are added to `have`
+    /// and ids only they have to `need`. Returns the next message to send,
+    /// or `None` once we're done.
+    pub fn reconcile(
+        &mut self,
+        query: &[u8],
+        have: &mut Vec<[u8; 32]>,
+        need: &mut Vec<[u8; 32]>,
+    ) -> Result<Option<Vec<u8>>> {
+        if !self.initiator {
+            return Err(decode_error("reconcile called before initiate"));
+        }
+
+        let out = self.reconcile_aux(query, have, need)?;
+        Ok(if out.len() == 1 { None } else { Some(out) })
+    }
+
+    /// Answer a message as the relay would
+    pub fn respond(&mut self, query: &[u8]) -> Result<Vec<u8>> {
+        if self.initiator {
+            return Err(decode_error("the initiator can't respond"));
+        }
+        self.reconcile_aux(query, &mut vec![], &mut vec![])
+    }
+
+    fn reconcile_aux(
+        &mut self,
+        mut query: &[u8],
+        have: &mut Vec<[u8; 32]>,
+        need: &mut Vec<[u8; 32]>,
+    ) -> Result<Vec<u8>> {
+        self.last_timestamp_in = 0;
+        self.last_timestamp_out = 0;
+
+        let mut out = vec![PROTOCOL_VERSION];
+
+        let version = take_bytes(&mut query, 1)?[0];
+        if version != PROTOCOL_VERSION {
+            if self.initiator {
+                return Err(decode_error(format!("unsupported version {version:#x}")));
+            }
+            // tell them which version we speak
+            return Ok(out);
+        }
+
+        let mut prev_bound = Bound::zero();
+        let mut prev_index = 0;
+        let mut skip = false;
+
+        while !query.is_empty() {
+            let curr_bound = self.decode_bound(&mut query)?;
+            let mode = Mode::decode(decode_varint(&mut query)?)?;
+
+            let lower = prev_index;
+            let upper = self.lower_bound(prev_index, &curr_bound);
+
+            match mode {
+                Mode::Skip => skip = true,
+
+                Mode::Fingerprint => {
+                    let theirs = take_bytes(&mut query, FINGERPRINT_SIZE)?;
+                    if *theirs == fingerprint(&self.items[lower..upper]) {
+                        skip = true;
+                    } else {
+                        self.flush_skip(&mut skip, &prev_bound, &mut out);
+                        self.split_range(lower, upper, curr_bound.clone(), &mut out);
+                    }
+                }
+
+                Mode::IdList => {
+                    let num_ids = decode_varint(&mut query)?;
+                    let mut theirs = HashSet::new();
+                    for _ in 0..num_ids {
+                        let id: [u8; 32] = take_bytes(&mut query, ID_SIZE)?.try_into()?;
+                        theirs.insert(id);
+                    }
+
+                    for (_, id) in &self.items[lower..upper] {
+                        if !theirs.remove(id) && self.initiator {
+                            have.push(*id);
+                        }
+                    }
+
+                    if self.initiator {
+                        skip = true;
+                        need.extend(theirs);
+                    } else {
+                        self.flush_skip(&mut skip, &prev_bound, &mut out);
+                        self.encode_id_list(lower, upper, &curr_bound, &mut out);
+                    }
+                }
+            }
+
+            prev_index = upper;
+            prev_bound = curr_bound;
+        }
+
+        Ok(out)
+    }
+
+    /// Index of the first item at or above `bound`
+    fn lower_bound(&self, from: usize, bound: &Bound) -> usize {
+        let key = bound.key();
+        from + self.items[from..].partition_point(|item| *item < key)
+    }
+
+    fn flush_skip(&mut self, skip: &mut bool, bound: &Bound, out: &mut Vec<u8>) {
+        if *skip {
+            *skip = false;
+            self.encode_bound(bound, out);
+            encode_varint(Mode::Skip as u64, out);
+        }
+    }
+
+    /// Describe a range: small ones as a list of ids, larger ones as
+    /// fingerprints of evenly sized buckets
+    fn split_range(&mut self, lower: usize, upper: usize, upper_bound: Bound, out: &mut Vec<u8>) {
+        let num_items = upper - lower;
+
+        if num_items < BUCKETS * 2 {
+            self.encode_id_list(lower, upper, &upper_bound, out);
+            return;
+        }
+
+        let per_bucket = num_items / BUCKETS;
+        let with_extra = num_items % BUCKETS;
+        let mut curr = lower;
+
+        for bucket in 0..BUCKETS {
+            let size = per_bucket + usize::from(bucket < with_extra);
+            let fingerprint = fingerprint(&self.items[curr..curr + size]);
+            curr += size;
+
+            let next_bound = if curr == upper {
+                upper_bound.clone()
+            } else {
+                Bound::between(&self.items[curr - 1], &self.items[curr])
+            };
+
+            self.encode_bound(&next_bound, out);
+            encode_varint(Mode::Fingerprint as u64, out);
+            out.extend_from_slice(&fingerprint);
+        }
+    }
+
+    fn encode_id_list(&mut self, lower: usize, upper: usize, bound: &Bound, out: &mut Vec<u8>) {
+        self.encode_bound(bound, out);
+        encode_varint(Mode::IdList as u64, out);
+        encode_varint((upper - lower) as u64, out);
+        for (_, id) in &self.items[lower..upper] {
+            out.extend_from_slice(id);
+        }
+    }
+
+    /// Timestamps are sent as deltas from the previous one, with 0 meaning
+    /// infinity
+    fn encode_bound(&mut self, bound: &Bound, out: &mut Vec<u8>) {
+        if bound.timestamp == u64::MAX {
+            self.last_timestamp_out = u64::MAX;
+            encode_varint(0, out);
+        } else {
+            let delta = bound.timestamp.saturating_sub(self.last_timestamp_out);
+            self.last_timestamp_out = bound.timestamp;
+            encode_varint(delta + 1, out);
+        }
+
+        encode_varint(bound.id.len() as u64, out);
+        out.extend_from_slice(&bound.id);
+    }
+
+    fn decode_bound(&mut self, input: &mut &[u8]) -> Result<Bound> {
+        let timestamp = match decode_varint(input)? {
+            0 => u64::MAX,
+            n => n - 1,
+        };
+
+        let timestamp = if timestamp == u64::MAX || self.last_timestamp_in == u64::MAX {
+            u64::MAX
+        } else {
+            timestamp.saturating_add(self.last_timestamp_in)
+        };
+        self.last_timestamp_in = timestamp;
+
+        let len = decode_varint(input)? as usize;
+        if len > ID_SIZE {
+            return Err(decode_error("bound id is too long"));
+        }
+        let id = take_bytes(input, len)?.to_vec();
+
+        Ok(Bound { timestamp, id })
+    }
+}
+
+/// How a reconciliation with a relay ended
+#[derive(Debug)]
+pub enum NegOutcome {
+    /// Ids the relay has that we don't
+    Missing(Vec<[u8; 32]>),
+
+    /// The relay couldn't reconcile, so fetch the filter the usual way
+    Fallback(Filter),
+}
+
+struct Session {
+    neg: Negentropy,
+    filter: Filter,
+    need: Vec<[u8; 32]>,
+}
+
+/// The reconciliations we're running with a relay, by subscription id
+#[derive(Default)]
+pub struct NegSessions {
+    sessions: HashMap<String, Session>,
+}
+
+impl NegSessions {
+    /// Start reconciling `items` against what the relay has for `filter`.
+    /// Returns the NEG-OPEN to send.
+    pub fn open(&mut self, sub_id: String, filter: Filter, items: Vec<Item>) -> ClientMessage {
+        let mut neg = Negentropy::new(items);
+        let message = hex::encode(neg.initiate());
+
+        self.sessions.insert(
+            sub_id.clone(),
+            Session {
+                neg,
+                filter: filter.clone(),
+                need: vec![],
+            },
+        );
+
+        ClientMessage::neg_open(sub_id, filter, message)
+    }
+
+    /// Handle a NEG-MSG. Returns the message to reply with, and how the
+    /// reconciliation ended if it did.
+    pub fn message(
+        &mut self,
+        sub_id: &str,
+        message: &str,
+    ) -> Option<(ClientMessage, Option<NegOutcome>)> {
+        let session = self.sessions.get_mut(sub_id)?;
+
+        let mut have = vec![];
+        let result = hex::decode(message)
+            .map_err(Error::from)
+            .and_then(|query| session.neg.reconcile(&query, &mut have, &mut session.need));
+
+        let close = ClientMessage::neg_close(sub_id.to_owned());
+        match result {
+            Ok(Some(next)) => Some((
+                ClientMessage::neg_msg(sub_id.to_owned(), hex::encode(next)),
+                None,
+            )),
+            Ok(None) => {
+                let session = self.sessions.remove(sub_id)?;
+                Some((close, Some(NegOutcome::Missing(session.need))))
+            }
+            Err(err) => {
+                warn!("negentropy {sub_id}: {err}");
+                let session = self.sessions.remove(sub_id)?;
+                Some((close, Some(NegOutcome::Fallback(session.filter))))
+            }
+        }
+    }
+
+    /// The relay gave up on a reconciliation (NEG-ERR)
+    pub fn error(&mut self, sub_id: &str) -> Option<NegOutcome> {
+        let session = self.sessions.remove(sub_id)?;
+        Some(NegOutcome::Fallback(session.filter))
+    }
+
+    /// Give up on every reconciliation, for a relay that turned out not
+    /// to speak NIP-77 and won't answer
+    pub fn fallback_all(&mut self) -> Vec<NegOutcome> {
+        self.sessions
+            .drain()
+            .map(|(_, session)| NegOutcome::Fallback(session.filter))
+            .collect()
+    }
+
+    pub fn remove(&mut self, sub_id: &str) {
+        self.sessions.remove(sub_id);
+    }
+
+    pub fn clear(&mut self) {
+        self.sessions.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(timestamp: u64, n: u32) -> Item {
+        let mut id = [0u8; 32];
+        id[..4].copy_from_slice(&n.to_be_bytes());
+        // spread ids out so prefixes differ in more than the first byte
+        id[31] = (n % 251) as u8;
+        (timestamp, id)
+    }
+
+    /// Run a reconciliation to completion, returning what the client has
+    /// and needs
+    fn reconcile(client: Vec<Item>, relay: Vec<Item>) -> (Vec<[u8; 32]>, Vec<[u8; 32]>) {
+        let mut client = Negentropy::new(client);
+        let mut relay = Negentropy::new(relay);
+
+        let mut have = vec![];
+        let mut need = vec![];
+        let mut msg = client.initiate();
+        for _ in 0..32 {
+            let reply = relay.respond(&msg).unwrap();
+            match client.reconcile(&reply, &mut have, &mut need).unwrap() {
+                Some(next) => msg = next,
+                None => {
+                    have.sort();
+                    need.sort();
+                    return (have, need);
+                }
+            }
+        }
+        panic!("reconciliation didn't finish");
+    }
+
+    fn ids(items: &[Item]) -> Vec<[u8; 32]> {
+        let mut ids: Vec<[u8; 32]> = items.iter().map(|(_, id)| *id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_varint() {
+        for n in [0, 1, 127, 128, 300, 16_383, 16_384, u64::MAX] {
+            let mut out = vec![];
+            encode_varint(n, &mut out);
+            let mut input = out.as_slice();
+            assert_eq!(decode_varint(&mut input).unwrap(), n);
+            assert!(input.is_empty());
+        }
+
+        let mut out = vec![];
+        encode_varint(300, &mut out);
+        assert_eq!(out, vec![0x82, 0x2c]);
+
+        assert!(decode_varint(&mut [0x80u8].as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_fingerprint_of_nothing() {
+        // sha256 of 32 zero bytes followed by a zero varint
+        let mut expected = Sha256::new();
+        expected.update([0u8; 33]);
+        assert_eq!(fingerprint(&[]), expected.finalize()[..FINGERPRINT_SIZE]);
+    }
+
+    #[test]
+    fn test_fingerprint_carries() {
+        let mut a = [0u8; 32];
+        a[0] = 0xff;
+        let mut b = [0u8; 32];
+        b[0] = 0x01;
+        let mut sum = [0u8; 32];
+        sum[1] = 0x01;
+
+        let mut expected = Sha256::new();
+        expected.update(sum);
+        expected.update([2]);
+        assert_eq!(
+            fingerprint(&[(0, a), (5, b)]),
+            expected.finalize()[..FINGERPRINT_SIZE]
+        );
+    }
+
+    #[test]
+    fn test_identical_sets() {
+        let items: Vec<Item> = (0..1000).map(|n| item(1000 + n as u64 / 3, n)).collect();
+        let (have, need) = reconcile(items.clone(), items);
+        assert!(have.is_empty());
+        assert!(need.is_empty());
+    }
+
+    #[test]
+    fn test_small_sets() {
+        let client = vec![item(10, 1), item(20, 2), item(30, 3)];
+        let relay = vec![item(20, 2), item(40, 4)];
+        let (have, need) = reconcile(client, relay);
+        assert_eq!(have, ids(&[item(10, 1), item(30, 3)]));
+        assert_eq!(need, ids(&[item(40, 4)]));
+    }
+
+    #[test]
+    fn test_large_sets() {
+        let shared: Vec<Item> = (0..5000).map(|n| item(1000 + n as u64 / 7, n)).collect();
+        let only_client: Vec<Item> = (5000..5010).map(|n| item(1200, n)).collect();
+        let only_relay: Vec<Item> = (6000..6100).map(|n| item(500 + n as u64, n)).collect();
+
+        let client = [shared.clone(), only_client.clone()].concat();
+        let relay = [shared, only_relay.clone()].concat();
+
+        let (have, need) = reconcile(client, relay);
+        assert_eq!(have, ids(&only_client));
+        assert_eq!(need, ids(&only_relay));
+    }
+
+    #[test]
+    fn test_empty_client() {
+        let relay: Vec<Item> = (0..100).map(|n| item(n as u64, n)).collect();
+        let (have, need) = reconcile(vec![], relay.clone());
+        assert!(have.is_empty());
+        assert_eq!(need, ids(&relay));
+    }
+
+    #[test]
+    fn test_sessions() {
+        let mut relay = Negentropy::new(vec![item(10, 1), item(20, 2)]);
+        let mut sessions = NegSessions::default();
+
+        let open = sessions.open(
+            "neg".to_owned(),
+            Filter::new().kinds([1]).build(),
+            vec![item(10, 1)],
+        );
+        let ClientMessage::NegOpen { message, .. } = open else {
+            panic!("expected NEG-OPEN");
+        };
+
+        let reply = hex::encode(relay.respond(&hex::decode(message).unwrap()).unwrap());
+        let Some((ClientMessage::NegClose { .. }, Some(NegOutcome::Missing(need)))) =
+            sessions.message("neg", &reply)
+        else {
+            panic!("expected reconciliation to finish");
+        };
+        assert_eq!(need, vec![item(20, 2).1]);
+
+        // finished sessions are forgotten
+        assert!(sessions.message("neg", &reply).is_none());
+
+        sessions.open("neg".to_owned(), Filter::new().kinds([1]).build(), vec![]);
+        assert!(matches!(
+            sessions.message("neg", "zz"),
+            Some((_, Some(NegOutcome::Fallback(_))))
+        ));
+        assert!(sessions.error("neg").is_none());
+
+        sessions.open("neg".to_owned(), Filter::new().kinds([1]).build(), vec![]);
+        assert!(matches!(
+            sessions.fallback_all().as_slice(),
+            [NegOutcome::Fallback(_)]
+        ));
+        assert!(sessions.fallback_all().is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+//! NIP-11 relay information documents.
+//!
+//! Relays describe themselves when their websocket url is fetched over http
+//! with an `Accept: application/nostr+json` header. We fetch the document
+//! once per relay, show it in the relay list and use its limits to shape
+//! what we send (see [`super::limits`]).
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use serde::{Deserialize, Deserializer};
+use tracing::debug;
+use url::Url;
+
+/// Limits a relay puts on its clients
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct RelayLimitation {
+    pub max_message_length: Option<u64>,
+    pub max_subscriptions: Option<u64>,
+    pub max_filters: Option<u64>,
+    pub max_limit: Option<u64>,
+    pub max_subid_length: Option<u64>,
+    pub max_event_tags: Option<u64>,
+    pub max_content_length: Option<u64>,
+    pub min_pow_difficulty: Option<u64>,
+    pub auth_required: Option<bool>,
+    pub payment_required: Option<bool>,
+    pub restricted_writes: Option<bool>,
+}
+
+impl RelayLimitation {
+    pub fn auth_required(&self) -> bool {
+        self.auth_required.unwrap_or(false)
+    }
+
+    pub fn payment_required(&self) -> bool {
+        self.payment_required.unwrap_or(false)
+    }
+}
+
+/// A relay's information document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct RelayInfo {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub pubkey: Option<String>,
+    pub contact: Option<String>,
+    pub software: Option<String>,
+    pub version: Option<String>,
+    pub icon: Option<String>,
+    #[serde(deserialize_with = "lenient_nips")]
+    pub supported_nips: Vec<u32>,
+    pub limitation: Option<RelayLimitation>,
+    pub payments_url: Option<String>,
+}
+
+/// Some relays list nips as strings, or mix in things that aren't nips at
+/// all. Keep whatever looks like a number.
+fn lenient_nips<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u32>, D::Error> {
+    let values: Option<Vec<serde_json::Value>> = Option::deserialize(deserializer)?;
+    Ok(values
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|value| match value {
+            serde_json::Value::Number(n) => n.as_u64().map(|n| n as u32),
+            serde_json::Value::String(s) => s.parse().ok(),
+            _ => None,
+        })
+        .collect())
+}
+
+impl RelayInfo {
+    pub fn parse(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(bytes)
+    }
+
+    pub fn limitation(&self) -> RelayLimitation {
+        self.limitation.clone().unwrap_or_default()
+    }
+
+    pub fn supports(&self, nip: u32) -> bool {
+        self.supported_nips.contains(&nip)
+    }
+}
+
+/// The http url a relay serves its information document on
+pub fn info_url(relay_url: &str) -> Option<String> {
+    let mut url = Url::parse(relay_url).ok()?;
+    let scheme = match url.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        _ => return None,
+    };
+    url.set_scheme(scheme).ok()?;
+    Some(url.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayInfoState {
+    Fetching,
+    Loaded(RelayInfo),
+    Failed(String),
+}
+
+impl RelayInfoState {
+    pub fn info(&self) -> Option<&RelayInfo> {
+        if let RelayInfoState::Loaded(info) = self {
+            Some(info)
+        } else {
+            None
+        }
+    }
+}
+
+type InfoResult = (String, Result<RelayInfo, String>);
+
+/// Relay information documents by relay url
+pub struct RelayInfoCache {
+    infos: HashMap<String, RelayInfoState>,
+    sender: Sender<InfoResult>,
+    receiver: Receiver<InfoResult>,
+}
+
+impl Default for RelayInfoCache {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        RelayInfoCache {
+            infos: HashMap::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl RelayInfoCache {
+    pub fn get(&self, relay_url: &str) -> Option<&RelayInfoState> {
+        self.infos.get(relay_url)
+    }
+
+    /// The loaded information document for a relay, if we have it
+    pub fn info(&self, relay_url: &str) -> Option<&RelayInfo> {
+        self.get(relay_url).and_then(RelayInfoState::info)
+    }
+
+    pub fn insert(&mut self, relay_url: String, info: RelayInfo) {
+        self.infos.insert(relay_url, RelayInfoState::Loaded(info));
+    }
+
+    /// Fetch a relay's information document unless we already have it or
+    /// are already fetching it
+    pub fn fetch(&mut self, relay_url: &str, wakeup: impl Fn() + Send + Sync + 'static) {
+        if self.infos.contains_key(relay_url) {
+            return;
+        }
+
+        let Some(url) = info_url(relay_url) else {
+            return;
+        };
+
+        self.infos
+            .insert(relay_url.to_owned(), RelayInfoState::Fetching);
+
+        let mut request = ehttp::Request::get(url);
+        request.headers.insert("Accept", "application/nostr+json");
+
+        let sender = self.sender.clone();
+        let relay_url = relay_url.to_owned();
+        ehttp::fetch(request, move |response| {
+            let result = match response {
+                Ok(resp) if resp.ok => RelayInfo::parse(&resp.bytes).map_err(|err| err.to_string()),
+                Ok(resp) => Err(format!("{} {}", resp.status, resp.status_text)),
+                Err(err) => Err(err),
+            };
+
+            if sender.send((relay_url, result)).is_ok() {
+                wakeup();
+            }
+        });
+    }
+
+    /// Collect finished fetches. Call this once per frame. Returns whether
+    /// anything changed.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((relay_url, result)) = self.receiver.try_recv() {
+            changed = true;
+            let state = match result {
+                Ok(info) => RelayInfoState::Loaded(info),
+                Err(err) => {
+                    debug!("could not fetch relay info for {relay_url}: {err}");
+                    RelayInfoState::Failed(err)
+                }
+            };
+            self.infos.insert(relay_url, state);
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_relay_info() {
+        let json = br#"{
+            "name": "damus",
+            "description": "a relay",
+            "software": "git+https://github.com/hoytech/strfry.git",
+            "version": "1.0.0",
+            "supported_nips": [1, "11", 40, "nope"],
+            "limitation": {
+                "max_message_length": 16384,
+                "max_subscriptions": 20,
+                "max_filters": 10,
+                "max_limit": 500,
+                "auth_required": false,
+                "payment_required": true
+            },
+            "unknown": {"fields": "are ignored"}
+        }"#;
+
+        let info = RelayInfo::parse(json).unwrap();
+        assert_eq!(info.name.as_deref(), Some("damus"));
+        assert_eq!(info.supported_nips, vec![1, 11, 40]);
+        assert!(info.supports(11));
+
+        let limitation = info.limitation();
+        assert_eq!(limitation.max_subscriptions, Some(20));
+        assert_eq!(limitation.max_limit, Some(500));
+        assert!(limitation.payment_required());
+        assert!(!limitation.auth_required());
+    }
+
+    #[test]
+    fn test_parse_minimal_relay_info() {
+        let info = RelayInfo::parse(b"{}").unwrap();
+        assert_eq!(info, RelayInfo::default());
+        assert_eq!(info.limitation(), RelayLimitation::default());
+        assert!(RelayInfo::parse(b"<html>").is_err());
+    }
+
+    #[test]
+    fn test_info_url() {
+        assert_eq!(
+            info_url("wss://relay.damus.io").as_deref(),
+            Some("https://relay.damus.io/")
+        );
+        assert_eq!(
+            info_url("ws://localhost:8080/path").as_deref(),
+            Some("http://localhost:8080/path")
+        );
+        assert_eq!(info_url("multicast"), None);
+    }
+
+    #[test]
+    fn test_cache_poll() {
+        let mut cache = RelayInfoCache::default();
+        cache.insert("wss://a/".to_owned(), RelayInfo::default());
+        assert!(cache.info("wss://a/").is_some());
+
+        cache
+            .sender
+            .send(("wss://b/".to_owned(), Err("404".to_owned())))
+            .unwrap();
+        assert!(cache.poll());
+        assert!(!cache.poll());
+        assert_eq!(
+            cache.get("wss://b/"),
+            Some(&RelayInfoState::Failed("404".to_owned()))
+        );
+        assert!(cache.info("wss://b/").is_none());
+    }
+}
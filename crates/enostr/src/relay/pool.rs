@@ -2,7 +2,7 @@ use crate::relay::{setup_multicast_relay, MulticastRelay, Relay, RelayStatus};
 use crate::{ClientMessage, Error, Result};
 use nostrdb::Filter;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::time::{Duration, Instant};
 
 use url::Url;
@@ -10,6 +10,9 @@ use url::Url;
 use ewebsock::{WsEvent, WsMessage};
 use tracing::{debug, error, trace};
 
+use super::mux::RelayMux;
+use super::negentropy::{Item, NegOutcome, NegSessions};
+use super::nip11::{RelayInfoCache, RelayLimitation};
 use super::subs_debug::SubsDebug;
 
 #[derive(Debug)]
@@ -42,6 +45,12 @@ pub struct WebsocketRelay {
     pub last_ping: Instant,
     pub last_connect_attempt: Instant,
     pub retry_connect_after: Duration,
+    pub mux: RelayMux,
+    pub limitation: RelayLimitation,
+    /// Whether the relay says it speaks NIP-77, None until we have its
+    /// information document
+    pub negentropy: Option<bool>,
+    pub neg_sessions: NegSessions,
 }
 
 impl PoolRelay {
@@ -78,7 +87,9 @@ impl PoolRelay {
     pub fn send(&mut self, msg: &ClientMessage) -> Result<()> {
         match self {
             Self::Websocket(wsr) => {
-                wsr.relay.send(msg);
+                for msg in wsr.mux.prepare(&wsr.limitation, msg)? {
+                    wsr.relay.send(&msg);
+                }
                 Ok(())
             }
 
@@ -96,6 +107,77 @@ impl PoolRelay {
         self.send(&ClientMessage::req(subid, filter))
     }
 
+    /// The subscriptions an event that arrived on `sub_id` is for
+    pub fn demux_event(&self, sub_id: &str, event_json: &str) -> Vec<String> {
+        match self {
+            Self::Websocket(wsr) => wsr.mux.demux_event(sub_id, event_json),
+            Self::Multicast(_) => vec![sub_id.to_owned()],
+        }
+    }
+
+    /// The subscriptions that are done with stored events now that `sub_id`
+    /// got its EOSE
+    pub fn demux_eose(&mut self, sub_id: &str) -> Vec<String> {
+        match self {
+            Self::Websocket(wsr) => wsr.mux.demux_eose(sub_id),
+            Self::Multicast(_) => vec![sub_id.to_owned()],
+        }
+    }
+
+    /// Whether we can try reconciling with the relay. Its information
+    /// document often arrives after the first subscriptions go out, so
+    /// until it does we assume it can. Relays that can't answer NEG-ERR,
+    /// or have their reconciliations fall back once we know.
+    pub fn supports_negentropy(&self) -> bool {
+        matches!(self, Self::Websocket(wsr) if wsr.negentropy != Some(false))
+    }
+
+    /// Start reconciling the notes we hold for `filter` with the relay,
+    /// see [`super::negentropy`]
+    pub fn neg_open(&mut self, sub_id: String, filter: Filter, items: Vec<Item>) -> Result<()> {
+        let msg = match self {
+            Self::Websocket(wsr) => wsr.neg_sessions.open(sub_id.clone(), filter, items),
+            Self::Multicast(_) => return Err(Error::Generic("multicast can't reconcile".into())),
+        };
+
+        let result = self.send(&msg);
+        if result.is_err() {
+            if let Self::Websocket(wsr) = self {
+                wsr.neg_sessions.remove(&sub_id);
+            }
+        }
+        result
+    }
+
+    /// Handle a NEG-MSG, replying to the relay. Returns how the
+    /// reconciliation ended once it's done.
+    pub fn neg_msg(&mut self, sub_id: &str, message: &str) -> Option<NegOutcome> {
+        let (reply, outcome) = match self {
+            Self::Websocket(wsr) => wsr.neg_sessions.message(sub_id, message)?,
+            Self::Multicast(_) => return None,
+        };
+
+        if let Err(err) = self.send(&reply) {
+            error!("error replying to {sub_id} on {}: {err}", self.url());
+        }
+        outcome
+    }
+
+    /// The relay gave up on a reconciliation
+    pub fn neg_err(&mut self, sub_id: &str) -> Option<NegOutcome> {
+        match self {
+            Self::Websocket(wsr) => wsr.neg_sessions.error(sub_id),
+            Self::Multicast(_) => None,
+        }
+    }
+
+    fn clear_subs(&mut self) {
+        if let Self::Websocket(wsr) = self {
+            wsr.mux.clear();
+            wsr.neg_sessions.clear();
+        }
+    }
+
     pub fn websocket(relay: Relay) -> Self {
         Self::Websocket(WebsocketRelay::new(relay))
     }
@@ -112,6 +194,10 @@ impl WebsocketRelay {
             last_ping: Instant::now(),
             last_connect_attempt: Instant::now(),
             retry_connect_after: Self::initial_reconnect_duration(),
+            mux: RelayMux::default(),
+            limitation: RelayLimitation::default(),
+            negentropy: None,
+            neg_sessions: NegSessions::default(),
         }
     }
 
@@ -124,6 +210,10 @@ pub struct RelayPool {
     pub relays: Vec<PoolRelay>,
     pub ping_rate: Duration,
     pub debug: Option<SubsDebug>,
+    pub relay_info: RelayInfoCache,
+    /// The relays that sent EOSE for subscriptions we're watching, see
+    /// [`RelayPool::watch_eose`]
+    watched_eoses: HashMap<String, BTreeSet<String>>,
 }
 
 impl Default for RelayPool {
@@ -139,6 +229,8 @@ impl RelayPool {
             relays: vec![],
             ping_rate: Duration::from_secs(45),
             debug: None,
+            relay_info: RelayInfoCache::default(),
+            watched_eoses: HashMap::new(),
         }
     }
 
@@ -206,14 +298,12 @@ impl RelayPool {
 
     pub fn subscribe(&mut self, subid: String, filter: Vec<Filter>) {
         for relay in &mut self.relays {
+            let cmd = ClientMessage::req(subid.clone(), filter.clone());
             if let Some(debug) = &mut self.debug {
-                debug.send_cmd(
-                    relay.url().to_owned(),
-                    &ClientMessage::req(subid.clone(), filter.clone()),
-                );
+                debug.send_cmd(relay.url().to_owned(), &cmd);
             }
 
-            if let Err(err) = relay.send(&ClientMessage::req(subid.clone(), filter.clone())) {
+            if let Err(err) = relay.send(&cmd) {
                 error!("error subscribing to {}: {err}", relay.url());
             }
         }
@@ -283,6 +373,105 @@ impl RelayPool {
         }
     }
 
+    /// Collect fetched relay information documents and start respecting
+    /// the limits they tell us about
+    /// Apply relay information documents that arrived. Returns the
+    /// reconciliations that have to fall back because their relay turned
+    /// out not to speak NIP-77, with the relay's url.
+    pub fn poll_relay_info(&mut self) -> Vec<(String, NegOutcome)> {
+        let mut fallbacks = vec![];
+        if !self.relay_info.poll() {
+            return fallbacks;
+        }
+
+        for relay in &mut self.relays {
+            if let PoolRelay::Websocket(wsr) = relay {
+                if let Some(info) = self.relay_info.info(wsr.relay.url.as_str()) {
+                    wsr.limitation = info.limitation();
+                    wsr.negentropy = Some(info.supports(77));
+
+                    // the first REQs usually went out before we knew
+                    for msg in wsr.mux.reshape(&wsr.limitation) {
+                        if let Some(debug) = &mut self.debug {
+                            debug.send_cmd(wsr.relay.url.as_str().to_owned(), &msg);
+                        }
+                        wsr.relay.send(&msg);
+                    }
+
+                    if wsr.negentropy == Some(false) {
+                        let url = wsr.relay.url.as_str();
+                        fallbacks.extend(
+                            wsr.neg_sessions
+                                .fallback_all()
+                                .into_iter()
+                                .map(|outcome| (url.to_owned(), outcome)),
+                        );
+                    }
+                }
+            }
+        }
+
+        fallbacks
+    }
+
+    /// The subscriptions an event from a relay is for. Subscriptions can
+    /// share a subscription on the relay, see [`super::mux`].
+    pub fn demux_event(&self, relay_url: &str, sub_id: &str, event_json: &str) -> Vec<String> {
+        match self.relays.iter().find(|r| r.url() == relay_url) {
+            Some(relay) => relay.demux_event(sub_id, event_json),
+            None => vec![sub_id.to_owned()],
+        }
+    }
+
+    /// The subscriptions that are done with stored events now that a
+    /// relay sent EOSE for `sub_id`
+    pub fn demux_eose(&mut self, relay_url: &str, sub_id: &str) -> Vec<String> {
+        let sub_ids = match self.relays.iter_mut().find(|r| r.url() == relay_url) {
+            Some(relay) => relay.demux_eose(sub_id),
+            None => vec![sub_id.to_owned()],
+        };
+
+        for sub_id in &sub_ids {
+            if let Some(relays) = self.watched_eoses.get_mut(sub_id) {
+                relays.insert(relay_url.to_owned());
+            }
+        }
+
+        sub_ids
+    }
+
+    /// Keep track of the relays that send EOSE for `sub_id`. Only one app
+    /// reads events from the pool, so apps that subscribe on the side use
+    /// this to find out when their subscriptions are done.
+    pub fn watch_eose(&mut self, sub_id: String) {
+        self.watched_eoses.entry(sub_id).or_default();
+    }
+
+    /// The relays that sent EOSE for a watched subscription so far
+    pub fn eosed(&self, sub_id: &str) -> Option<&BTreeSet<String>> {
+        self.watched_eoses.get(sub_id)
+    }
+
+    pub fn unwatch_eose(&mut self, sub_id: &str) {
+        self.watched_eoses.remove(sub_id);
+    }
+
+    /// Handle a NEG-MSG from a relay, see [`PoolRelay::neg_msg`]
+    pub fn neg_msg(&mut self, relay_url: &str, sub_id: &str, message: &str) -> Option<NegOutcome> {
+        self.relays
+            .iter_mut()
+            .find(|r| r.url() == relay_url)?
+            .neg_msg(sub_id, message)
+    }
+
+    /// Handle a NEG-ERR from a relay
+    pub fn neg_err(&mut self, relay_url: &str, sub_id: &str) -> Option<NegOutcome> {
+        self.relays
+            .iter_mut()
+            .find(|r| r.url() == relay_url)?
+            .neg_err(sub_id)
+    }
+
     /// check whether a relay url is valid to add
     pub fn is_valid_url(&self, url: &str) -> bool {
         if url.is_empty() {
@@ -313,11 +502,20 @@ impl RelayPool {
             return Ok(());
         }
         let relay = Relay::new(
-            nostr::RelayUrl::parse(url).map_err(|_| Error::InvalidRelayUrl)?,
-            wakeup,
+            nostr::RelayUrl::parse(&url).map_err(|_| Error::InvalidRelayUrl)?,
+            wakeup.clone(),
         )?;
-        let pool_relay = PoolRelay::websocket(relay);
+        let mut pool_relay = PoolRelay::websocket(relay);
 
+        if let PoolRelay::Websocket(wsr) = &mut pool_relay {
+            match self.relay_info.info(wsr.relay.url.as_str()) {
+                Some(info) => {
+                    wsr.limitation = info.limitation();
+                    wsr.negentropy = Some(info.supports(77));
+                }
+                None => self.relay_info.fetch(wsr.relay.url.as_str(), wakeup),
+            }
+        }
         self.relays.push(pool_relay);
 
         Ok(())
@@ -340,7 +538,7 @@ impl RelayPool {
     }
 
     // standardize the format (ie, trailing slashes)
-    fn canonicalize_url(url: String) -> String {
+    pub fn canonicalize_url(url: String) -> String {
         match Url::parse(&url) {
             Ok(parsed_url) => parsed_url.to_string(),
             Err(_) => url, // If parsing fails, return the original URL.
@@ -369,10 +567,12 @@ impl RelayPool {
                     }
                     WsEvent::Closed => {
                         relay.set_status(RelayStatus::Disconnected);
+                        relay.clear_subs();
                     }
                     WsEvent::Error(err) => {
                         error!("{:?}", err);
                         relay.set_status(RelayStatus::Disconnected);
+                        relay.clear_subs();
                     }
                     WsEvent::Message(ev) => {
                         // let's just handle pongs here.
@@ -406,3 +606,26 @@ impl RelayPool {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_eose() {
+        let mut pool = RelayPool::new();
+        let relay = "wss://relay.example.com/";
+
+        pool.watch_eose("dave-1".to_owned());
+        assert_eq!(pool.demux_eose(relay, "dave-1"), vec!["dave-1"]);
+        pool.demux_eose(relay, "other");
+
+        assert!(pool
+            .eosed("dave-1")
+            .is_some_and(|relays| relays.contains(relay)));
+        assert!(pool.eosed("other").is_none());
+
+        pool.unwatch_eose("dave-1");
+        assert!(pool.eosed("dave-1").is_none());
+    }
+}
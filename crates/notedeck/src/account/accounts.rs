@@ -2,20 +2,24 @@ use uuid::Uuid;
 
 use crate::account::cache::AccountCache;
 use crate::account::contacts::Contacts;
+use crate::account::emoji::AccountEmojiData;
 use crate::account::mute::AccountMutedData;
 use crate::account::relay::{
     modify_advertised_relays, update_relay_configuration, AccountRelayData, RelayAction,
     RelayDefaults,
 };
+use crate::emoji::{CustomEmoji, UserEmojiList};
+use crate::reaction::{ReactionHistory, ReactionUse};
 use crate::storage::AccountStorageWriter;
 use crate::user_account::UserAccountSerializable;
 use crate::{
-    AccountStorage, MuteFun, SingleUnkIdAction, UnifiedSubscription, UnknownIds, UserAccount,
-    ZapWallet,
+    AccountStorage, MuteFun, RelaySpec, SingleUnkIdAction, UnifiedSubscription, UnknownIds,
+    UserAccount, ZapWallet,
 };
 use enostr::{ClientMessage, FilledKeypair, Keypair, Pubkey, RelayPool};
 use nostrdb::{Ndb, Note, Transaction};
 
+use std::collections::BTreeSet;
 // TODO: remove this
 use std::sync::Arc;
 
@@ -26,6 +30,7 @@ pub struct Accounts {
     storage_writer: Option<AccountStorageWriter>,
     relay_defaults: RelayDefaults,
     subs: AccountSubs,
+    reactions: ReactionHistory,
 }
 
 impl Accounts {
@@ -94,6 +99,7 @@ impl Accounts {
             storage_writer,
             relay_defaults,
             subs,
+            reactions: ReactionHistory::in_memory(),
         }
     }
 
@@ -272,6 +278,42 @@ impl Accounts {
         Box::new(Arc::clone(&account_data.muted.muted))
     }
 
+    /// The custom emoji the selected account can use, from its emoji list
+    /// and the emoji sets the list points at
+    pub fn custom_emojis(
+        &self,
+        ndb: &Ndb,
+        txn: &Transaction,
+        unknown_ids: &mut UnknownIds,
+    ) -> Vec<CustomEmoji> {
+        self.get_selected_account_data()
+            .emoji_list()
+            .resolve(ndb, txn, unknown_ids)
+    }
+
+    /// Remember the reactions each account sends in `history`
+    pub fn with_reaction_history(mut self, history: ReactionHistory) -> Self {
+        self.reactions = history;
+        self
+    }
+
+    /// The reactions the selected account used last
+    pub fn recent_reactions(&self, limit: usize) -> Vec<&ReactionUse> {
+        self.reactions.recent(self.selected_account_pubkey(), limit)
+    }
+
+    /// The reactions the selected account uses the most
+    pub fn frequent_reactions(&self, limit: usize) -> Vec<&ReactionUse> {
+        self.reactions
+            .frequent(self.selected_account_pubkey(), limit)
+    }
+
+    pub fn record_reaction(&mut self, content: &str, emoji: Option<&CustomEmoji>) {
+        let pubkey = *self.selected_account_pubkey();
+        self.reactions
+            .record(&pubkey, content, emoji, crate::unix_time_secs());
+    }
+
     pub fn update_max_hashtags_per_note(&mut self, max_hashtags: usize) {
         for account in self.cache.accounts_mut() {
             account.data.muted.update_max_hashtags(max_hashtags);
@@ -288,6 +330,14 @@ impl Accounts {
             ),
             relay_url,
         );
+        // send the active account's dm relay list subscription
+        pool.send_to(
+            &ClientMessage::req(
+                self.subs.dm_relays.remote.clone(),
+                vec![data.relay.dm_filter.clone()],
+            ),
+            relay_url,
+        );
         // send the active account's muted subscription
         pool.send_to(
             &ClientMessage::req(
@@ -296,6 +346,14 @@ impl Accounts {
             ),
             relay_url,
         );
+        // send the active account's emoji list subscription
+        pool.send_to(
+            &ClientMessage::req(
+                self.subs.emoji.remote.clone(),
+                vec![data.emoji.filter.clone()],
+            ),
+            relay_url,
+        );
         pool.send_to(
             &ClientMessage::req(
                 self.subs.contacts.remote.clone(),
@@ -421,6 +479,7 @@ fn get_acc_from_storage(user_account_serializable: UserAccountSerializable) -> O
 pub struct AccountData {
     pub(crate) relay: AccountRelayData,
     pub(crate) muted: AccountMutedData,
+    pub(crate) emoji: AccountEmojiData,
     pub contacts: Contacts,
 }
 
@@ -429,6 +488,7 @@ impl AccountData {
         Self {
             relay: AccountRelayData::new(pubkey),
             muted: AccountMutedData::new(pubkey),
+            emoji: AccountEmojiData::new(pubkey),
             contacts: Contacts::new(pubkey),
         }
     }
@@ -444,17 +504,37 @@ impl AccountData {
             resp = Some(AccountDataUpdate::Relay);
         }
 
+        self.relay
+            .poll_for_dm_updates(ndb, &txn, subs.dm_relays.local);
+
         self.muted.poll_for_updates(ndb, &txn, subs.mute.local);
+        self.emoji.poll_for_updates(ndb, &txn, subs.emoji.local);
         self.contacts
             .poll_for_updates(ndb, &txn, subs.contacts.local);
 
         resp
     }
 
+    /// The relays advertised in the account's NIP-65 relay list
+    pub fn advertised_relays(&self) -> &BTreeSet<RelaySpec> {
+        &self.relay.advertised
+    }
+
+    /// The relays the account wants DMs sent to, from its kind 10050 list
+    pub fn dm_relays(&self) -> &BTreeSet<String> {
+        &self.relay.dm_inbox
+    }
+
+    /// The account's custom emoji list
+    pub fn emoji_list(&self) -> &UserEmojiList {
+        &self.emoji.list
+    }
+
     /// Note: query should be called as close to the subscription as possible
     pub(super) fn query(&mut self, ndb: &Ndb, txn: &Transaction) {
         self.relay.query(ndb, txn);
         self.muted.query(ndb, txn);
+        self.emoji.query(ndb, txn);
         self.contacts.query(ndb, txn);
     }
 }
@@ -470,7 +550,9 @@ pub struct AddAccountResponse {
 
 pub struct AccountSubs {
     relay: UnifiedSubscription,
+    dm_relays: UnifiedSubscription,
     mute: UnifiedSubscription,
+    emoji: UnifiedSubscription,
     pub contacts: UnifiedSubscription,
 }
 
@@ -484,13 +566,17 @@ impl AccountSubs {
         wakeup: impl Fn() + Send + Sync + Clone + 'static,
     ) -> Self {
         let relay = subscribe(ndb, pool, &data.relay.filter);
+        let dm_relays = subscribe(ndb, pool, &data.relay.dm_filter);
         let mute = subscribe(ndb, pool, &data.muted.filter);
+        let emoji = subscribe(ndb, pool, &data.emoji.filter);
         let contacts = subscribe(ndb, pool, &data.contacts.filter);
         update_relay_configuration(pool, relay_defaults, pk, &data.relay, wakeup);
 
         Self {
             relay,
+            dm_relays,
             mute,
+            emoji,
             contacts,
         }
     }
@@ -505,7 +591,9 @@ impl AccountSubs {
         wakeup: impl Fn() + Send + Sync + Clone + 'static,
     ) {
         unsubscribe(ndb, pool, &self.relay);
+        unsubscribe(ndb, pool, &self.dm_relays);
         unsubscribe(ndb, pool, &self.mute);
+        unsubscribe(ndb, pool, &self.emoji);
         unsubscribe(ndb, pool, &self.contacts);
 
         *self = AccountSubs::new(ndb, pool, relay_defaults, pk, new_selection_data, wakeup);
@@ -0,0 +1,49 @@
+use nostrdb::{Filter, Ndb, Subscription, Transaction};
+use tracing::debug;
+
+use crate::emoji::UserEmojiList;
+
+/// The account's NIP-30 emoji list (kind 10030)
+#[derive(Clone)]
+pub(crate) struct AccountEmojiData {
+    pub filter: Filter,
+    pub list: UserEmojiList,
+}
+
+impl AccountEmojiData {
+    pub fn new(pubkey: &[u8; 32]) -> Self {
+        AccountEmojiData {
+            filter: UserEmojiList::filter(pubkey),
+            list: UserEmojiList::default(),
+        }
+    }
+
+    pub(super) fn query(&mut self, ndb: &Ndb, txn: &Transaction) {
+        let lim = self
+            .filter
+            .limit()
+            .unwrap_or(crate::filter::default_limit()) as i32;
+        let Ok(results) = ndb.query(txn, std::slice::from_ref(&self.filter), lim) else {
+            return;
+        };
+
+        if let Some(result) = results.first() {
+            self.list = UserEmojiList::from_note(&result.note);
+            debug!("initial emoji list {:?}", self.list);
+        }
+    }
+
+    pub(super) fn poll_for_updates(&mut self, ndb: &Ndb, txn: &Transaction, sub: Subscription) {
+        let nks = ndb.poll_for_notes(sub, 1);
+
+        let Some(note) = nks
+            .first()
+            .and_then(|nk| ndb.get_note_by_key(txn, *nk).ok())
+        else {
+            return;
+        };
+
+        self.list = UserEmojiList::from_note(&note);
+        debug!("updated emoji list {:?}", self.list);
+    }
+}
@@ -1,6 +1,7 @@
 pub mod accounts;
 pub mod cache;
 pub mod contacts;
+pub mod emoji;
 pub mod mute;
 pub mod relay;
 
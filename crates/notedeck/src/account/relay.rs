@@ -11,6 +11,8 @@ pub(crate) struct AccountRelayData {
     pub filter: Filter,
     pub local: BTreeSet<RelaySpec>, // used locally but not advertised
     pub advertised: BTreeSet<RelaySpec>, // advertised via NIP-65
+    pub dm_filter: Filter,
+    pub dm_inbox: BTreeSet<String>, // where we want DMs sent, kind 10050
 }
 
 impl AccountRelayData {
@@ -22,10 +24,19 @@ impl AccountRelayData {
             .limit(1)
             .build();
 
+        // and for the relays we want to receive DMs on
+        let dm_filter = Filter::new()
+            .authors([pubkey])
+            .kinds([10050])
+            .limit(1)
+            .build();
+
         AccountRelayData {
             filter,
             local: BTreeSet::new(),
             advertised: BTreeSet::new(),
+            dm_filter,
+            dm_inbox: BTreeSet::new(),
         }
     }
 
@@ -44,7 +55,18 @@ impl AccountRelayData {
         let relays = Self::harvest_nip65_relays(ndb, txn, &nks);
         debug!("initial relays {:?}", relays);
 
-        self.advertised = relays.into_iter().collect()
+        self.advertised = relays.into_iter().collect();
+
+        let nks = ndb
+            .query(txn, std::slice::from_ref(&self.dm_filter), 1)
+            .expect("query user dm relays results")
+            .iter()
+            .map(|qr| qr.note_key)
+            .collect::<Vec<NoteKey>>();
+        self.dm_inbox = Self::harvest_dm_relays(ndb, txn, &nks)
+            .into_iter()
+            .collect();
+        debug!("initial dm relays {:?}", self.dm_inbox);
     }
 
     // standardize the format (ie, trailing slashes) to avoid dups
@@ -96,6 +118,23 @@ impl AccountRelayData {
         relays
     }
 
+    pub(crate) fn harvest_dm_relays(ndb: &Ndb, txn: &Transaction, nks: &[NoteKey]) -> Vec<String> {
+        let mut relays = Vec::new();
+        for nk in nks.iter() {
+            if let Ok(note) = ndb.get_note_by_key(txn, *nk) {
+                for tag in note.tags() {
+                    if tag.get(0).and_then(|t| t.variant().str()) != Some("relay") {
+                        continue;
+                    }
+                    if let Some(url) = tag.get(1).and_then(|f| f.variant().str()) {
+                        relays.push(Self::canonicalize_url(url));
+                    }
+                }
+            }
+        }
+        relays
+    }
+
     pub fn publish_nip65_relays(&self, seckey: &[u8; 32], pool: &mut RelayPool) {
         let mut builder = NoteBuilder::new().kind(10002).content("");
         for rs in &self.advertised {
@@ -110,6 +149,28 @@ impl AccountRelayData {
         pool.send(&enostr::ClientMessage::event(&note).expect("note client message"));
     }
 
+    pub fn publish_dm_relays(&self, seckey: &[u8; 32], pool: &mut RelayPool) {
+        let mut builder = NoteBuilder::new().kind(10050).content("");
+        for url in &self.dm_inbox {
+            builder = builder.start_tag().tag_str("relay").tag_str(url);
+        }
+        let note = builder.sign(seckey).build().expect("note build");
+        pool.send(&enostr::ClientMessage::event(&note).expect("note client message"));
+    }
+
+    pub fn poll_for_dm_updates(&mut self, ndb: &Ndb, txn: &Transaction, sub: Subscription) {
+        let nks = ndb.poll_for_notes(sub, 1);
+
+        if nks.is_empty() {
+            return;
+        }
+
+        self.dm_inbox = Self::harvest_dm_relays(ndb, txn, &nks)
+            .into_iter()
+            .collect();
+        debug!("updated dm relays {:?}", self.dm_inbox);
+    }
+
     pub fn poll_for_updates(&mut self, ndb: &Ndb, txn: &Transaction, sub: Subscription) -> bool {
         let nks = ndb.poll_for_notes(sub, 1);
 
@@ -211,6 +272,14 @@ pub(super) fn update_relay_configuration(
 pub enum RelayAction {
     Add(String),
     Remove(String),
+    /// Change the read/write markers of an advertised relay
+    SetUsage {
+        url: String,
+        read: bool,
+        write: bool,
+    },
+    AddDmRelay(String),
+    RemoveDmRelay(String),
 }
 
 impl RelayAction {
@@ -218,6 +287,9 @@ impl RelayAction {
         match self {
             RelayAction::Add(url) => url,
             RelayAction::Remove(url) => url,
+            RelayAction::SetUsage { url, .. } => url,
+            RelayAction::AddDmRelay(url) => url,
+            RelayAction::RemoveDmRelay(url) => url,
         }
     }
 }
@@ -233,6 +305,13 @@ pub(super) fn modify_advertised_relays(
     match action {
         RelayAction::Add(_) => info!("add advertised relay \"{}\"", relay_url),
         RelayAction::Remove(_) => info!("remove advertised relay \"{}\"", relay_url),
+        RelayAction::SetUsage { read, write, .. } => info!(
+            "set advertised relay \"{}\" read: {} write: {}",
+            relay_url, read, write
+        ),
+        RelayAction::AddDmRelay(_) | RelayAction::RemoveDmRelay(_) => {
+            return modify_dm_relays(kp, action, relay_url, pool, account_data);
+        }
     }
 
     // let selected = self.cache.selected_mut();
@@ -250,6 +329,16 @@ pub(super) fn modify_advertised_relays(
         RelayAction::Remove(_) => {
             advertised.remove(&RelaySpec::new(relay_url, false, false));
         }
+        RelayAction::SetUsage { read, write, .. } => {
+            // no marker means both, so a relay has to be at least one of
+            // them. Removing a relay is its own action, ignore this.
+            if !read && !write {
+                return;
+            }
+            // specs compare by url, so this replaces the old markers
+            advertised.replace(RelaySpec::new(relay_url, read, write));
+        }
+        RelayAction::AddDmRelay(_) | RelayAction::RemoveDmRelay(_) => {}
     }
 
     // If we have the secret key publish the NIP-65 relay list
@@ -259,3 +348,30 @@ pub(super) fn modify_advertised_relays(
             .publish_nip65_relays(&secretkey.to_secret_bytes(), pool);
     }
 }
+
+fn modify_dm_relays(
+    kp: &Keypair,
+    action: RelayAction,
+    relay_url: String,
+    pool: &mut RelayPool,
+    account_data: &mut AccountData,
+) {
+    let dm_inbox = &mut account_data.relay.dm_inbox;
+    match action {
+        RelayAction::AddDmRelay(_) => {
+            info!("add dm relay \"{}\"", relay_url);
+            dm_inbox.insert(relay_url);
+        }
+        RelayAction::RemoveDmRelay(_) => {
+            info!("remove dm relay \"{}\"", relay_url);
+            dm_inbox.remove(&relay_url);
+        }
+        _ => return,
+    }
+
+    if let Some(secretkey) = &kp.secret_key {
+        account_data
+            .relay
+            .publish_dm_relays(&secretkey.to_secret_bytes(), pool);
+    }
+}
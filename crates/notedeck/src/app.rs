@@ -1,13 +1,15 @@
 use crate::account::FALLBACK_PUBKEY;
 use crate::clipboard::PlatformClipboard;
 use crate::i18n::Localization;
+use crate::media::video;
 use crate::persist::{AppSizeHandler, SettingsHandler};
+use crate::reaction::ReactionHistory;
 use crate::wallet::GlobalWallet;
 use crate::zaps::Zaps;
 use crate::NotedeckOptions;
 use crate::{
     frame_history::FrameHistory, AccountStorage, Accounts, AppContext, Args, DataPath,
-    DataPathType, Directory, Images, NoteAction, NoteCache, RelayDebugView, UnknownIds,
+    DataPathType, Directory, Images, Nip05Cache, NoteAction, NoteCache, RelayDebugView, UnknownIds,
 };
 use crate::{Error, JobCache};
 use crate::{JobPool, MediaJobs};
@@ -28,6 +30,13 @@ use android_activity::AndroidApp;
 pub enum AppAction {
     Note(NoteAction),
     ToggleChrome,
+    /// Open the composer with some prefilled content. Apps that suggest
+    /// notes use this so that the user always reviews and publishes
+    /// them manually.
+    Compose {
+        content: String,
+        reply_to: Option<enostr::NoteId>,
+    },
 }
 
 pub trait App {
@@ -79,6 +88,7 @@ pub struct Notedeck {
     job_pool: JobPool,
     media_jobs: MediaJobs,
     i18n: Localization,
+    nip05_cache: Nip05Cache,
 
     #[cfg(target_os = "android")]
     android_app: Option<AndroidApp>,
@@ -137,6 +147,11 @@ impl eframe::App for Notedeck {
         self.zaps
             .process(&mut self.accounts, &mut self.global_wallet, &self.ndb);
 
+        self.nip05_cache.poll();
+        self.img_cache.link_previews.poll();
+        self.img_cache.players.poll();
+        self.note_cache.translations.poll();
+
         render_notedeck(self, ctx);
 
         self.settings.update_batch(|settings| {
@@ -258,7 +273,8 @@ impl Notedeck {
             &mut pool,
             ctx,
             &mut unknown_ids,
-        );
+        )
+        .with_reaction_history(ReactionHistory::new(path.path(DataPathType::Setting)));
 
         {
             for key in &parsed_args.keys {
@@ -274,8 +290,16 @@ impl Notedeck {
             accounts.select_account(&first.pubkey, &mut ndb, &txn, &mut pool, ctx);
         }
 
-        let img_cache = Images::new(img_cache_dir);
-        let note_cache = NoteCache::default();
+        let nip05_cache = Nip05Cache::new(img_cache_dir.clone(), ctx.clone());
+        let mut img_cache = Images::new(img_cache_dir);
+        video::probe_programs(ctx);
+        img_cache
+            .link_previews
+            .set_policy(settings.link_preview_policy());
+        let mut note_cache = NoteCache::default();
+        note_cache
+            .translations
+            .set_config(&settings.translation_config());
 
         let app_size = AppSizeHandler::new(&path);
 
@@ -329,6 +353,7 @@ impl Notedeck {
             job_pool,
             media_jobs: media_job_cache,
             i18n,
+            nip05_cache,
             #[cfg(target_os = "android")]
             android_app: None,
         }
@@ -395,6 +420,7 @@ impl Notedeck {
             job_pool: &mut self.job_pool,
             media_jobs: &mut self.media_jobs,
             i18n: &mut self.i18n,
+            nip05_cache: &mut self.nip05_cache,
             #[cfg(target_os = "android")]
             android: self.android_app.as_ref().unwrap().clone(),
         }
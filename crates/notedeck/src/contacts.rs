@@ -1,5 +1,6 @@
 use crate::{
     filter::{self, HybridFilter, ValidKind},
+    poll::POLL_KIND,
     Error,
 };
 use nostrdb::{Filter, Note};
@@ -20,11 +21,13 @@ pub fn hybrid_contacts_filter(
             .into_query_package(ValidKind::One, filter::default_limit()),
         filter::filter_from_tags(note, add_pk, with_hashtags)?
             .into_query_package(ValidKind::Six, filter::default_limit()),
+        filter::filter_from_tags(note, add_pk, with_hashtags)?
+            .into_query_package(ValidKind::Poll, filter::default_limit()),
         filter::filter_from_tags(note, add_pk, with_hashtags)?
             .into_query_package(ValidKind::Zero, filter::default_limit()),
     ];
     let remote = filter::filter_from_tags(note, add_pk, with_hashtags)?
-        .into_filter(vec![1, 0], filter::default_remote_limit());
+        .into_filter(vec![1, POLL_KIND as u64, 0], filter::default_remote_limit());
 
     Ok(HybridFilter::split(local, remote))
 }
@@ -1,11 +1,7 @@
 use crate::{
-    account::accounts::Accounts,
-    clipboard::Clipboard,
-    frame_history::FrameHistory,
-    i18n::Localization,
-    wallet::GlobalWallet,
-    zaps::Zaps,
-    Args, DataPath, Images, JobPool, MediaJobs, NoteCache, SettingsHandler, UnknownIds,
+    account::accounts::Accounts, clipboard::Clipboard, frame_history::FrameHistory,
+    i18n::Localization, wallet::GlobalWallet, zaps::Zaps, Args, DataPath, Images, JobPool,
+    MediaJobs, Nip05Cache, NoteCache, SettingsHandler, UnknownIds,
 };
 
 use enostr::RelayPool;
@@ -33,6 +29,7 @@ pub struct AppContext<'a> {
     pub job_pool: &'a mut JobPool,
     pub media_jobs: &'a mut MediaJobs,
     pub i18n: &'a mut Localization,
+    pub nip05_cache: &'a mut Nip05Cache,
 
     #[cfg(target_os = "android")]
     pub android: AndroidApp,
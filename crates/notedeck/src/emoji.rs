@@ -0,0 +1,291 @@
+//! NIP-30 custom emoji.
+//!
+//! Notes (and profiles) can use `:shortcode:` in their text when they carry
+//! a matching `["emoji", shortcode, url]` tag. Reactions can be a single
+//! custom emoji too, with the content set to its `:shortcode:`. People keep
+//! the emoji they like to use in a kind 10030 list, which can point at kind
+//! 30030 emoji sets with `a` tags.
+
+use nostrdb::{Filter, Ndb, Note, NoteKey, ProfileRecord, Transaction};
+use serde::{Deserialize, Serialize};
+
+use crate::{NoteAddress, UnknownIds};
+
+pub const USER_EMOJI_LIST_KIND: u32 = 10030;
+pub const EMOJI_SET_KIND: u32 = 30030;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CustomEmoji {
+    pub shortcode: String,
+    pub url: String,
+}
+
+impl CustomEmoji {
+    pub fn new(shortcode: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            shortcode: shortcode.into(),
+            url: url.into(),
+        }
+    }
+
+    /// How the emoji is written in text, eg. `:soapbox:`
+    pub fn code(&self) -> String {
+        format!(":{}:", self.shortcode)
+    }
+}
+
+/// Shortcodes are made of letters, numbers, underscores and hyphens
+pub fn is_valid_shortcode(shortcode: &str) -> bool {
+    !shortcode.is_empty()
+        && shortcode
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// The custom emoji a note declares in its `emoji` tags
+pub fn note_emojis(note: &Note) -> Vec<CustomEmoji> {
+    let mut emojis: Vec<CustomEmoji> = Vec::new();
+    for tag in note.tags() {
+        if tag.count() < 3 || tag.get_str(0) != Some("emoji") {
+            continue;
+        }
+
+        let (Some(shortcode), Some(url)) = (tag.get_str(1), tag.get_str(2)) else {
+            continue;
+        };
+
+        if is_valid_shortcode(shortcode) && !emojis.iter().any(|e| e.shortcode == shortcode) {
+            emojis.push(CustomEmoji::new(shortcode, url));
+        }
+    }
+    emojis
+}
+
+/// The custom emoji a profile's metadata note declares, for rendering its
+/// names. Cheap when the names don't use any.
+pub fn profile_emojis(ndb: &Ndb, txn: &Transaction, profile: &ProfileRecord) -> Vec<CustomEmoji> {
+    let uses_emoji = profile.record().profile().is_some_and(|p| {
+        p.name().is_some_and(|n| n.contains(':'))
+            || p.display_name().is_some_and(|n| n.contains(':'))
+    });
+    if !uses_emoji {
+        return Vec::new();
+    }
+
+    ndb.get_note_by_key(txn, NoteKey::new(profile.record().note_key()))
+        .map(|note| note_emojis(&note))
+        .unwrap_or_default()
+}
+
+/// A custom emoji reaction: its content is a single `:shortcode:` with a
+/// matching `emoji` tag
+pub fn reaction_emoji(reaction: &Note) -> Option<CustomEmoji> {
+    let shortcode = reaction
+        .content()
+        .trim()
+        .strip_prefix(':')?
+        .strip_suffix(':')?;
+
+    note_emojis(reaction)
+        .into_iter()
+        .find(|emoji| emoji.shortcode == shortcode)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmojiText<'a> {
+    Text(&'a str),
+    Emoji(&'a CustomEmoji),
+}
+
+/// Split text into plain text and the custom emoji it uses. Shortcodes we
+/// don't have an emoji for stay text.
+pub fn split_emoji<'a>(text: &'a str, emojis: &'a [CustomEmoji]) -> Vec<EmojiText<'a>> {
+    let mut parts = Vec::new();
+    if emojis.is_empty() {
+        parts.push(EmojiText::Text(text));
+        return parts;
+    }
+
+    let mut text_start = 0;
+    let mut pos = 0;
+    while let Some(open) = text[pos..].find(':').map(|i| pos + i) {
+        let Some(close) = text[open + 1..].find(':').map(|i| open + 1 + i) else {
+            break;
+        };
+
+        let shortcode = &text[open + 1..close];
+        let emoji = is_valid_shortcode(shortcode)
+            .then(|| emojis.iter().find(|e| e.shortcode == shortcode))
+            .flatten();
+
+        match emoji {
+            Some(emoji) => {
+                if text_start < open {
+                    parts.push(EmojiText::Text(&text[text_start..open]));
+                }
+                parts.push(EmojiText::Emoji(emoji));
+                text_start = close + 1;
+                pos = close + 1;
+            }
+            // the closing colon might open the next shortcode
+            None => pos = close,
+        }
+    }
+
+    if text_start < text.len() {
+        parts.push(EmojiText::Text(&text[text_start..]));
+    }
+
+    parts
+}
+
+/// The emoji from `available` that `text` uses, for tagging a new note
+pub fn used_emojis<'a>(text: &str, available: &'a [CustomEmoji]) -> Vec<&'a CustomEmoji> {
+    let mut used: Vec<&CustomEmoji> = Vec::new();
+    for part in split_emoji(text, available) {
+        if let EmojiText::Emoji(emoji) = part {
+            if !used.iter().any(|e| e.shortcode == emoji.shortcode) {
+                used.push(emoji);
+            }
+        }
+    }
+    used
+}
+
+/// A user's emoji list (kind 10030): the emoji in it and the emoji sets it
+/// points at
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserEmojiList {
+    pub emojis: Vec<CustomEmoji>,
+    pub sets: Vec<NoteAddress>,
+}
+
+impl UserEmojiList {
+    pub fn filter(pubkey: &[u8; 32]) -> Filter {
+        Filter::new()
+            .authors([pubkey])
+            .kinds([USER_EMOJI_LIST_KIND as u64])
+            .limit(1)
+            .build()
+    }
+
+    pub fn from_note(note: &Note) -> Self {
+        let sets = note
+            .tags()
+            .iter()
+            .filter(|tag| tag.count() >= 2 && tag.get_str(0) == Some("a"))
+            .filter_map(|tag| NoteAddress::parse(tag.get_str(1)?))
+            .filter(|address| address.kind == EMOJI_SET_KIND)
+            .collect();
+
+        Self {
+            emojis: note_emojis(note),
+            sets,
+        }
+    }
+
+    /// Every emoji in the list, including the ones in its sets that we
+    /// have. Sets we don't have yet are requested through `unknown_ids`.
+    pub fn resolve(
+        &self,
+        ndb: &Ndb,
+        txn: &Transaction,
+        unknown_ids: &mut UnknownIds,
+    ) -> Vec<CustomEmoji> {
+        let mut emojis = self.emojis.clone();
+
+        for set in &self.sets {
+            let Some(note) = set.get_note(ndb, txn) else {
+                unknown_ids.add_address_if_missing(ndb, txn, set);
+                continue;
+            };
+
+            for emoji in note_emojis(&note) {
+                if !emojis.iter().any(|e| e.shortcode == emoji.shortcode) {
+                    emojis.push(emoji);
+                }
+            }
+        }
+
+        emojis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emojis() -> Vec<CustomEmoji> {
+        vec![
+            CustomEmoji::new("soapbox", "https://example.com/soapbox.png"),
+            CustomEmoji::new("gleasonator", "https://example.com/gleasonator.png"),
+        ]
+    }
+
+    fn codes(parts: &[EmojiText]) -> Vec<String> {
+        parts
+            .iter()
+            .map(|part| match part {
+                EmojiText::Text(text) => text.to_string(),
+                EmojiText::Emoji(emoji) => format!("<{}>", emoji.shortcode),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_shortcode() {
+        assert!(is_valid_shortcode("soapbox"));
+        assert!(is_valid_shortcode("blob_cat-2"));
+        assert!(!is_valid_shortcode(""));
+        assert!(!is_valid_shortcode("two words"));
+        assert!(!is_valid_shortcode("http://x"));
+    }
+
+    #[test]
+    fn test_split_emoji() {
+        let emojis = emojis();
+
+        assert_eq!(
+            codes(&split_emoji(
+                "Hello :gleasonator: 😂 :ablobcatrainbow: :disputed:yolo:soapbox:",
+                &emojis
+            )),
+            vec![
+                "Hello ",
+                "<gleasonator>",
+                " 😂 :ablobcatrainbow: :disputed:yolo",
+                "<soapbox>",
+            ]
+        );
+
+        assert_eq!(
+            codes(&split_emoji(":soapbox::soapbox:", &emojis)),
+            vec!["<soapbox>", "<soapbox>"]
+        );
+        assert_eq!(
+            codes(&split_emoji("time is 12:30:00", &emojis)),
+            vec!["time is 12:30:00"]
+        );
+        assert_eq!(codes(&split_emoji(":soapbox:", &[])), vec![":soapbox:"]);
+    }
+
+    #[test]
+    fn test_parse_emoji_set_address() {
+        let pk = "79c2cae114ea28a981e7559b4fe7854a473521a8d22a66bbab9fa248eb820ff6";
+        let address = NoteAddress::parse(&format!("30030:{pk}:blobcats:v2")).unwrap();
+        assert_eq!(address.kind, EMOJI_SET_KIND);
+        assert_eq!(address.pubkey.hex(), pk);
+        assert_eq!(address.identifier, "blobcats:v2");
+
+        assert!(NoteAddress::parse("30030:nothex:blobcats").is_none());
+        assert!(NoteAddress::parse("30030").is_none());
+    }
+
+    #[test]
+    fn test_used_emojis() {
+        let emojis = emojis();
+        let used = used_emojis(":soapbox: and :soapbox: but not :nope:", &emojis);
+        assert_eq!(used, vec![&emojis[0]]);
+        assert!(used_emojis("no emoji here", &emojis).is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::{Error, FilterError};
 use crate::note::NoteRef;
+use crate::poll::POLL_KIND;
 use nostrdb::{Filter, FilterBuilder, Note, Subscription};
 use std::collections::HashMap;
 use tracing::{debug, warn};
@@ -269,7 +270,7 @@ impl FilteredTags {
         }
 
         if let Some(hashtags) = self.hashtags {
-            if matches!(&kind, ValidKind::One | ValidKind::Zero) {
+            if matches!(&kind, ValidKind::One | ValidKind::Zero | ValidKind::Poll) {
                 filters.push(hashtags.kinds(vec![kind.kind()]).limit(limit).build())
             }
         }
@@ -339,6 +340,7 @@ pub enum ValidKind {
     Zero,
     One,
     Six,
+    Poll,
 }
 
 impl ValidKind {
@@ -347,6 +349,7 @@ impl ValidKind {
             ValidKind::Zero => 0,
             ValidKind::One => 1,
             ValidKind::Six => 6,
+            ValidKind::Poll => POLL_KIND as u64,
         }
     }
 }
@@ -0,0 +1,278 @@
+//! NIP-84 highlights.
+//!
+//! A highlight is a kind 9802 note whose content is an excerpt of something
+//! else: a note (`e` tag), an addressable note like a long-form article
+//! (`a` tag) or a web page (`r` tag). The text around the excerpt can go in
+//! a `context` tag, and a highlight shared with a few words of the
+//! highlighter's own has them in a `comment` tag.
+
+use std::ops::Range;
+
+use enostr::NoteId;
+use nostrdb::{Note, NoteBuilder};
+
+use crate::NoteAddress;
+
+pub const HIGHLIGHT_KIND: u32 = 9802;
+
+/// Addressable (replaceable and parameterized) kinds, which we point at
+/// with an `a` tag so the highlight follows edits
+const ADDRESSABLE_KINDS: Range<u32> = 30000..40000;
+
+/// What was highlighted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HighlightSource<'a> {
+    Note(&'a [u8; 32]),
+    Address(NoteAddress),
+    Url(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight<'a> {
+    pub text: &'a str,
+    pub context: Option<&'a str>,
+    pub comment: Option<&'a str>,
+    pub source: Option<HighlightSource<'a>>,
+    /// Who wrote what was highlighted
+    pub author: Option<&'a [u8; 32]>,
+}
+
+impl<'a> Highlight<'a> {
+    pub fn from_note(note: &'a Note<'a>) -> Option<Self> {
+        if note.kind() != HIGHLIGHT_KIND {
+            return None;
+        }
+
+        let mut context = None;
+        let mut comment = None;
+        let mut event = None;
+        let mut address = None;
+        let mut url = None;
+        let mut author = None;
+
+        for tag in note.tags() {
+            match tag.get_str(0) {
+                Some("context") => context = tag.get_str(1).filter(|s| !s.is_empty()),
+                Some("comment") => comment = tag.get_str(1).filter(|s| !s.is_empty()),
+                Some("e") if event.is_none() => event = tag.get_id(1),
+                Some("a") if address.is_none() => {
+                    address = tag.get_str(1).and_then(NoteAddress::parse);
+                }
+                // urls marked as mentions come up in comments, they aren't
+                // what was highlighted
+                Some("r") if url.is_none() && tag.get_str(2) != Some("mention") => {
+                    url = tag.get_str(1);
+                }
+                Some("p") => {
+                    let Some(pubkey) = tag.get_id(1) else {
+                        continue;
+                    };
+                    // prefer whoever is marked as the author over editors
+                    // and other mentions
+                    if author.is_none() || tag.get_str(3) == Some("author") {
+                        author = Some(pubkey);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // articles are often tagged with both the address and the version
+        // that was highlighted, the address is what we want to link to
+        let source = address
+            .map(HighlightSource::Address)
+            .or(event.map(HighlightSource::Note))
+            .or(url.map(HighlightSource::Url));
+
+        Some(Self {
+            text: note.content(),
+            context,
+            comment,
+            source,
+            author,
+        })
+    }
+}
+
+/// A highlight of a note, ready to be signed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewHighlight {
+    pub source: NoteId,
+    pub text: String,
+    pub context: Option<String>,
+    pub comment: String,
+}
+
+impl NewHighlight {
+    /// Highlight part of a note's content. `selection` is a byte range.
+    pub fn new(source: &Note, selection: Range<usize>, comment: String) -> Option<Self> {
+        let content = source.content();
+        let text = content.get(selection.clone())?.trim();
+        if text.is_empty() {
+            return None;
+        }
+
+        let context = surrounding_paragraph(content, selection)
+            .filter(|paragraph| paragraph.trim() != text)
+            .map(|paragraph| paragraph.trim().to_owned());
+
+        Some(Self {
+            source: NoteId::new(*source.id()),
+            text: text.to_owned(),
+            context,
+            comment: comment.trim().to_owned(),
+        })
+    }
+
+    pub fn to_note(&self, seckey: &[u8; 32], source: &Note) -> Option<Note<'_>> {
+        let mut builder = NoteBuilder::new().kind(HIGHLIGHT_KIND).content(&self.text);
+
+        let d_tag = source
+            .tags()
+            .iter()
+            .find(|tag| tag.get_str(0) == Some("d"))
+            .and_then(|tag| tag.get_str(1));
+
+        builder = match d_tag {
+            Some(d_tag) if ADDRESSABLE_KINDS.contains(&source.kind()) => {
+                builder.start_tag().tag_str("a").tag_str(&format!(
+                    "{}:{}:{}",
+                    source.kind(),
+                    hex::encode(source.pubkey()),
+                    d_tag
+                ))
+            }
+            _ => builder.start_tag().tag_str("e").tag_id(source.id()),
+        };
+
+        builder = builder
+            .start_tag()
+            .tag_str("p")
+            .tag_id(source.pubkey())
+            .tag_str("")
+            .tag_str("author");
+
+        if let Some(context) = &self.context {
+            builder = builder.start_tag().tag_str("context").tag_str(context);
+        }
+
+        if !self.comment.is_empty() {
+            builder = builder
+                .start_tag()
+                .tag_str("comment")
+                .tag_str(&self.comment);
+        }
+
+        builder.sign(seckey).build()
+    }
+}
+
+/// The paragraph of `content` that `selection` is in, if it's all in one
+pub fn surrounding_paragraph(content: &str, selection: Range<usize>) -> Option<&str> {
+    let start = content
+        .get(..selection.start)?
+        .rfind("\n\n")
+        .map_or(0, |i| i + 2);
+    let end = content
+        .get(selection.end..)?
+        .find("\n\n")
+        .map_or(content.len(), |i| selection.end + i);
+
+    let paragraph = &content[start..end];
+    (!paragraph.contains("\n\n")).then_some(paragraph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+
+    const CONTENT: &str =
+        "First paragraph.\n\nThe quick brown fox jumps over the lazy dog.\n\nLast.";
+
+    fn note(kind: u32, tags: &[&[&str]]) -> Note<'static> {
+        let kp = FullKeypair::generate();
+        let mut builder = NoteBuilder::new().kind(kind).content(CONTENT);
+        for tag in tags {
+            builder = builder.start_tag();
+            for value in *tag {
+                builder = builder.tag_str(value);
+            }
+        }
+        builder
+            .sign(&kp.secret_key.secret_bytes())
+            .build()
+            .expect("note")
+    }
+
+    #[test]
+    fn test_surrounding_paragraph() {
+        let fox = CONTENT.find("brown fox").unwrap();
+        assert_eq!(
+            surrounding_paragraph(CONTENT, fox..fox + 9),
+            Some("The quick brown fox jumps over the lazy dog.")
+        );
+        assert_eq!(surrounding_paragraph(CONTENT, 0..CONTENT.len()), None);
+    }
+
+    #[test]
+    fn test_highlight_note() {
+        let kp = FullKeypair::generate();
+        let source = note(1, &[]);
+        let fox = CONTENT.find("brown fox").unwrap();
+
+        let new = NewHighlight::new(&source, fox..fox + 9, " so true ".to_owned()).unwrap();
+        let highlight_note = new.to_note(&kp.secret_key.secret_bytes(), &source).unwrap();
+        let highlight = Highlight::from_note(&highlight_note).unwrap();
+
+        assert_eq!(highlight.text, "brown fox");
+        assert_eq!(
+            highlight.context,
+            Some("The quick brown fox jumps over the lazy dog.")
+        );
+        assert_eq!(highlight.comment, Some("so true"));
+        assert_eq!(highlight.source, Some(HighlightSource::Note(source.id())));
+        assert_eq!(highlight.author, Some(source.pubkey()));
+    }
+
+    #[test]
+    fn test_highlight_article() {
+        let kp = FullKeypair::generate();
+        let article = note(30023, &[&["d", "my-article"], &["title", "Foxes"]]);
+        let selection = 0..CONTENT.find('.').unwrap();
+
+        let new = NewHighlight::new(&article, selection, String::new()).unwrap();
+        let highlight_note = new
+            .to_note(&kp.secret_key.secret_bytes(), &article)
+            .unwrap();
+        let highlight = Highlight::from_note(&highlight_note).unwrap();
+
+        assert_eq!(highlight.text, "First paragraph");
+        assert_eq!(highlight.comment, None);
+        assert_eq!(
+            highlight.source,
+            Some(HighlightSource::Address(NoteAddress::new(
+                30023,
+                enostr::Pubkey::new(*article.pubkey()),
+                "my-article".to_owned(),
+            )))
+        );
+    }
+
+    #[test]
+    fn test_highlight_url() {
+        let page = note(
+            HIGHLIGHT_KIND,
+            &[
+                &["r", "https://example.com/other", "mention"],
+                &["r", "https://example.com/post"],
+            ],
+        );
+        let highlight = Highlight::from_note(&page).unwrap();
+        assert_eq!(
+            highlight.source,
+            Some(HighlightSource::Url("https://example.com/post"))
+        );
+        assert_eq!(highlight.author, None);
+    }
+}
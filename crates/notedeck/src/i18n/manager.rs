@@ -430,6 +430,13 @@ impl Localization {
         &self.current_locale
     }
 
+    /// The languages the user reads, as language codes without a region,
+    /// eg. `de`. We only know about the app's locale for now, so notes in
+    /// other languages the user reads still count as foreign.
+    pub fn user_languages(&self) -> Vec<&str> {
+        vec![self.current_locale.language.as_str()]
+    }
+
     /// Gets all available locales
     pub fn get_available_locales(&self) -> &[LanguageIdentifier] {
         &self.available_locales
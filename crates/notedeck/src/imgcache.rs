@@ -1,9 +1,11 @@
 use crate::jobs::MediaJobSender;
+use crate::link_preview::LinkPreviews;
 use crate::media::gif::AnimatedImgTexCache;
 use crate::media::images::ImageType;
 use crate::media::static_imgs::StaticImgTexCache;
 use crate::media::{
-    AnimationMode, BlurCache, NoLoadingLatestTex, TrustedMediaLatestTex, UntrustedMediaLatestTex,
+    AnimationMode, BlurCache, MediaMetadata, MediaPlayers, NoLoadingLatestTex,
+    TrustedMediaLatestTex, UntrustedMediaLatestTex,
 };
 use crate::urls::{UrlCache, UrlMimes};
 use crate::ImageMetadata;
@@ -102,6 +104,10 @@ pub struct MediaCache {
 pub enum MediaCacheType {
     Image,
     Gif,
+    /// Shown as a poster frame, which is cached as a static image
+    Video,
+    /// Shown as a player bar, there's nothing to cache
+    Audio,
 }
 
 impl MediaCache {
@@ -136,6 +142,8 @@ impl MediaCache {
         match cache_type {
             MediaCacheType::Image => "img",
             MediaCacheType::Gif => "gif",
+            MediaCacheType::Video => "video",
+            MediaCacheType::Audio => "audio",
         }
     }
 
@@ -276,8 +284,14 @@ pub struct Images {
     pub gifs: MediaCache,
     pub textures: TexturesCache,
     pub urls: UrlMimes,
+    /// OpenGraph previews for links that aren't media
+    pub link_previews: LinkPreviews,
     /// cached imeta data
     pub metadata: HashMap<String, ImageMetadata>,
+    /// cached imeta mime types and video posters
+    pub media_metadata: HashMap<String, MediaMetadata>,
+    /// video and audio that is playing
+    pub players: MediaPlayers,
     pub gif_states: GifStateMap,
 }
 
@@ -289,8 +303,11 @@ impl Images {
             static_imgs: MediaCache::new(&path, MediaCacheType::Image),
             gifs: MediaCache::new(&path, MediaCacheType::Gif),
             urls: UrlMimes::new(UrlCache::new(path.join(UrlCache::rel_dir()))),
+            link_previews: LinkPreviews::new(path.clone()),
             gif_states: Default::default(),
             metadata: Default::default(),
+            media_metadata: Default::default(),
+            players: MediaPlayers::default(),
             textures: TexturesCache::new(path.clone()),
         }
     }
@@ -301,25 +318,37 @@ impl Images {
     }
 
     pub fn get_renderable_media(&mut self, url: &str) -> Option<RenderableMedia> {
-        Self::find_renderable_media(&mut self.urls, &self.metadata, url)
+        Self::find_renderable_media(&mut self.urls, &self.metadata, &self.media_metadata, url)
     }
 
     pub fn find_renderable_media(
         urls: &mut UrlMimes,
         imeta: &HashMap<String, ImageMetadata>,
+        media_imeta: &HashMap<String, MediaMetadata>,
         url: &str,
     ) -> Option<RenderableMedia> {
-        let media_type = crate::urls::supported_mime_hosted_at_url(urls, url)?;
+        let media_meta = media_imeta.get(url);
+
+        // trust the note's imeta mime type so we don't have to fetch it
+        let media_type = match media_meta.and_then(|meta| meta.media_type()) {
+            Some(media_type) => media_type,
+            None => crate::urls::supported_mime_hosted_at_url(urls, url)?,
+        };
 
         let obfuscation_type = match imeta.get(url) {
             Some(blur) => ObfuscationType::Blurhash(blur.clone()),
             None => ObfuscationType::Default,
         };
 
+        let poster = media_meta
+            .and_then(|meta| meta.poster.clone())
+            .filter(|_| media_type == MediaCacheType::Video);
+
         Some(RenderableMedia {
             url: url.to_string(),
             media_type,
             obfuscation_type,
+            poster,
         })
     }
 
@@ -341,16 +370,22 @@ impl Images {
         loader.latest(jobs, ui.ctx(), url, cache_type, img_type, animation_mode)
     }
 
+    /// Video posters live with the static images. Audio has nothing cached,
+    /// it gets the static image cache too.
     pub fn get_cache(&self, cache_type: MediaCacheType) -> &MediaCache {
         match cache_type {
-            MediaCacheType::Image => &self.static_imgs,
+            MediaCacheType::Image | MediaCacheType::Video | MediaCacheType::Audio => {
+                &self.static_imgs
+            }
             MediaCacheType::Gif => &self.gifs,
         }
     }
 
     pub fn get_cache_mut(&mut self, cache_type: MediaCacheType) -> &mut MediaCache {
         match cache_type {
-            MediaCacheType::Image => &mut self.static_imgs,
+            MediaCacheType::Image | MediaCacheType::Video | MediaCacheType::Audio => {
+                &mut self.static_imgs
+            }
             MediaCacheType::Gif => &mut self.gifs,
         }
     }
@@ -368,9 +403,11 @@ impl Images {
         }
 
         self.urls.cache.clear();
+        self.link_previews.clear();
         self.static_imgs.clear();
         self.gifs.clear();
         self.gif_states.clear();
+        self.players.stop_all();
 
         Ok(())
     }
@@ -400,8 +437,12 @@ impl Images {
 
     pub fn user_trusts_img(&self, url: &str, media_type: MediaCacheType) -> bool {
         match media_type {
-            MediaCacheType::Image => self.textures.static_image.contains(url),
+            MediaCacheType::Image | MediaCacheType::Video => {
+                self.textures.static_image.contains(url)
+            }
             MediaCacheType::Gif => self.textures.animated.contains(url),
+            // nothing is fetched until it's played
+            MediaCacheType::Audio => true,
         }
     }
 }
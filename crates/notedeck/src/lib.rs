@@ -8,23 +8,30 @@ pub mod clipboard;
 pub mod contacts;
 mod context;
 pub mod debouncer;
+pub mod emoji;
 mod error;
 pub mod filter;
 pub mod fonts;
 mod frame_history;
+pub mod highlight;
 pub mod i18n;
 mod imgcache;
 pub mod jobs;
+pub mod link_preview;
+pub mod markdown;
 pub mod media;
 mod muted;
 pub mod name;
+pub mod nip05;
 mod nip51_set;
 pub mod note;
 mod notecache;
 mod options;
 mod persist;
 pub mod platform;
+pub mod poll;
 pub mod profile;
+pub mod reaction;
 pub mod relay_debug;
 pub mod relayspec;
 mod result;
@@ -36,6 +43,7 @@ pub mod theme;
 mod time;
 mod timecache;
 mod timed_serializer;
+pub mod translate;
 pub mod ui;
 mod unknowns;
 mod urls;
@@ -63,16 +71,19 @@ pub use jobs::{
     deliver_completed_media_job, run_media_job_pre_action, JobCache, JobPool, MediaJobSender,
     MediaJobs,
 };
+pub use link_preview::{LinkPreview, LinkPreviewPolicy, LinkPreviews};
 pub use media::{
-    update_imeta_blurhashes, ImageMetadata, ImageType, MediaAction, ObfuscationType,
-    PixelDimensions, PointDimensions, RenderableMedia,
+    update_imeta_blurhashes, update_imeta_media, ImageMetadata, ImageType, MediaAction,
+    MediaMetadata, MediaPlayers, ObfuscationType, PixelDimensions, PlayerState, PointDimensions,
+    RenderableMedia,
 };
 pub use muted::{MuteFun, Muted};
 pub use name::NostrName;
+pub use nip05::{Nip05Cache, Nip05Status};
 pub use nip51_set::{create_nip51_set, Nip51Set, Nip51SetCache};
 pub use note::{
-    BroadcastContext, ContextSelection, NoteAction, NoteContext, NoteContextSelection, NoteRef,
-    RootIdError, RootNoteId, RootNoteIdBuf, ScrollInfo, ZapAction,
+    BroadcastContext, ContextSelection, DraggedNote, NoteAction, NoteContext, NoteContextSelection,
+    NoteRef, RootIdError, RootNoteId, RootNoteIdBuf, ScrollInfo, ZapAction,
 };
 pub use notecache::{CachedNote, NoteCache};
 pub use options::NotedeckOptions;
@@ -89,7 +100,10 @@ pub use time::{
     is_future_timestamp, time_ago_since, time_format, unix_time_secs, MAX_FUTURE_NOTE_SKEW_SECS,
 };
 pub use timecache::TimeCached;
-pub use unknowns::{get_unknown_note_ids, NoteRefsUnkIdAction, SingleUnkIdAction, UnknownIds};
+pub use translate::{TranslationConfig, Translations};
+pub use unknowns::{
+    get_unknown_note_ids, NoteAddress, NoteRefsUnkIdAction, SingleUnkIdAction, UnknownIds,
+};
 pub use urls::{supported_mime_hosted_at_url, SupportedMimeType, UrlMimes};
 pub use user_account::UserAccount;
 pub use wallet::{
@@ -97,8 +111,9 @@ pub use wallet::{
     WalletType, WalletUIState, ZapWallet,
 };
 pub use zaps::{
-    get_current_default_msats, AnyZapState, DefaultZapError, DefaultZapMsats, NoteZapTarget,
-    NoteZapTargetOwned, PendingDefaultZapState, ZapTarget, ZapTargetOwned, ZappingError,
+    generate_endpoint_url, get_current_default_msats, AnyZapState, DefaultZapError,
+    DefaultZapMsats, NoteZapTarget, NoteZapTargetOwned, PendingDefaultZapState, ZapTarget,
+    ZapTargetOwned, ZappingError,
 };
 
 // export libs
@@ -0,0 +1,752 @@
+//! OpenGraph link previews.
+//!
+//! Urls in notes that aren't media we can show get a preview card with the
+//! page's title, description, site name and image. We read those from the
+//! OpenGraph (and twitter card) meta tags in the page's `<head>`, fetching
+//! only the start of the page with a time limit. Results are cached on disk
+//! so we don't fetch every page again on each start.
+//!
+//! Fetching a link tells its site that we're looking at it, so previews can
+//! be turned off entirely and sites can be allowed or blocked by domain
+//! (see [`LinkPreviewPolicy`]).
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+use url::{Host, Url};
+
+use crate::debouncer::Debouncer;
+use crate::media::network::http_req_prefix;
+use crate::{storage, Directory};
+
+const FILE_NAME: &str = "link_previews.json";
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+const PREVIEW_TTL: Duration = Duration::from_secs(60 * 60 * 24 * 7); // one week
+const FAILURE_TTL: Duration = Duration::from_secs(60 * 60 * 6);
+const MAX_ENTRIES: usize = 4096;
+
+/// The most of a page we'll download. The meta tags we want live in the
+/// `<head>`, which is almost always well within this.
+const MAX_HTML_BYTES: usize = 512 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_TEXT_LEN: usize = 300;
+
+/// What we show for a link
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkPreview {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub site_name: Option<String>,
+    pub image: Option<String>,
+}
+
+impl LinkPreview {
+    /// Whether there is anything worth showing
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none() && self.image.is_none()
+    }
+}
+
+/// Which links we're allowed to fetch previews for. Previews are off until
+/// they're turned on, since fetching a link tells its site about us.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkPreviewPolicy {
+    pub enabled: bool,
+
+    /// When not empty, only these domains (and their subdomains) are fetched
+    pub allowed_domains: Vec<String>,
+
+    /// These domains (and their subdomains) are never fetched
+    pub blocked_domains: Vec<String>,
+}
+
+impl Default for LinkPreviewPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_domains: Vec::new(),
+            blocked_domains: Vec::new(),
+        }
+    }
+}
+
+impl LinkPreviewPolicy {
+    /// Whether we may fetch `url`. Addresses on the local network are
+    /// refused, but only when the url spells them out: domains aren't
+    /// checked again once they resolve, so a domain pointing at a local
+    /// address still gets through.
+    pub fn allows(&self, url: &Url) -> bool {
+        if !self.enabled || !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+
+        let host = match url.host() {
+            Some(Host::Domain(domain)) => domain.to_ascii_lowercase(),
+            // don't go poking around the local network
+            Some(Host::Ipv4(ip)) if !is_public_ip(IpAddr::V4(ip)) => return false,
+            Some(Host::Ipv6(ip)) if !is_public_ip(IpAddr::V6(ip)) => return false,
+            Some(host) => host.to_string(),
+            None => return false,
+        };
+
+        if host == "localhost" || host.ends_with(".local") {
+            return false;
+        }
+
+        if self
+            .blocked_domains
+            .iter()
+            .any(|domain| domain_matches(&host, domain))
+        {
+            return false;
+        }
+
+        self.allowed_domains.is_empty()
+            || self
+                .allowed_domains
+                .iter()
+                .any(|domain| domain_matches(&host, domain))
+    }
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            // 100.64.0.0/10, carrier-grade NAT
+            let [a, b, ..] = ip.octets();
+            let shared = a == 100 && (b & 0xc0) == 64;
+
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || shared)
+        }
+        IpAddr::V6(ip) => {
+            // ::ffff:10.0.0.1 is just 10.0.0.1
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+
+            let first = ip.segments()[0];
+            let unique_local = first & 0xfe00 == 0xfc00;
+            let link_local = first & 0xffc0 == 0xfe80;
+
+            !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+        }
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Parse a list of domains as typed by the user, eg. `"youtube.com,
+/// *.example.org https://news.site/path"`
+pub fn parse_domains(text: &str) -> Vec<String> {
+    let mut domains: Vec<String> = Vec::new();
+    for word in text.split(|c: char| c == ',' || c.is_whitespace()) {
+        let word = word.trim().to_ascii_lowercase();
+        let word = word
+            .split_once("://")
+            .map_or(word.as_str(), |(_, rest)| rest);
+        let word = word.split('/').next().unwrap_or_default();
+        let word = word.trim_start_matches("*.").trim_matches('.');
+
+        if !word.is_empty() && !domains.iter().any(|d| d == word) {
+            domains.push(word.to_owned());
+        }
+    }
+    domains
+}
+
+/// Pull a preview out of a page's meta tags. `page_url` is used to resolve
+/// relative image urls.
+pub fn parse_link_preview(html: &str, page_url: &Url) -> LinkPreview {
+    let lower = html.to_ascii_lowercase();
+    // everything we want is in the head
+    let end = lower.find("</head").unwrap_or(lower.len());
+    let (html, lower) = (&html[..end], &lower[..end]);
+
+    let mut metas: HashMap<String, String> = HashMap::new();
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find("<meta").map(|i| pos + i) {
+        let Some(len) = lower[start..].find('>') else {
+            break;
+        };
+        let attrs = parse_attributes(&html[start + "<meta".len()..start + len]);
+        pos = start + len;
+
+        let key = attrs
+            .get("property")
+            .or_else(|| attrs.get("name"))
+            .map(|key| key.to_ascii_lowercase());
+        if let (Some(key), Some(content)) = (key, attrs.get("content")) {
+            // the first one wins, like most crawlers do
+            metas.entry(key).or_insert_with(|| content.clone());
+        }
+    }
+
+    let meta = |keys: &[&str]| -> Option<String> {
+        keys.iter()
+            .filter_map(|key| metas.get(*key))
+            .map(|value| clean_text(value))
+            .find(|value| !value.is_empty())
+    };
+
+    let title = meta(&["og:title", "twitter:title"]).or_else(|| title_tag(html, lower));
+    let image = meta(&[
+        "og:image:secure_url",
+        "og:image",
+        "og:image:url",
+        "twitter:image",
+        "twitter:image:src",
+    ])
+    .and_then(|image| page_url.join(&image).ok())
+    .filter(|image| matches!(image.scheme(), "http" | "https"))
+    .map(String::from);
+
+    LinkPreview {
+        title,
+        description: meta(&["og:description", "twitter:description", "description"]),
+        site_name: meta(&["og:site_name"]),
+        image,
+    }
+}
+
+fn title_tag(html: &str, lower: &str) -> Option<String> {
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    Some(clean_text(&html[start..end])).filter(|title| !title.is_empty())
+}
+
+/// Parse the attributes of a tag, eg. `property="og:title" content='hi'`
+fn parse_attributes(tag: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let mut chars = tag.char_indices().peekable();
+
+    loop {
+        // skip to the start of a name
+        while chars
+            .next_if(|(_, c)| c.is_whitespace() || *c == '/')
+            .is_some()
+        {}
+        let Some(&(name_start, _)) = chars.peek() else {
+            break;
+        };
+
+        let mut name_end = tag.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() || c == '=' || c == '/' {
+                name_end = i;
+                break;
+            }
+            chars.next();
+        }
+        let name = tag[name_start..name_end].to_ascii_lowercase();
+
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        if chars.next_if(|(_, c)| *c == '=').is_none() {
+            attrs.insert(name, String::new());
+            continue;
+        }
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+        let value = match chars.peek().copied() {
+            Some((i, quote @ ('"' | '\''))) => {
+                chars.next();
+                let start = i + 1;
+                let mut end = tag.len();
+                for (i, c) in chars.by_ref() {
+                    if c == quote {
+                        end = i;
+                        break;
+                    }
+                }
+                &tag[start..end]
+            }
+            Some((start, _)) => {
+                let mut end = tag.len();
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_whitespace() {
+                        end = i;
+                        break;
+                    }
+                    chars.next();
+                }
+                &tag[start..end]
+            }
+            None => "",
+        };
+
+        attrs.insert(name, decode_entities(value));
+    }
+
+    attrs
+}
+
+/// Collapse whitespace, decode entities and cap the length of some text
+fn clean_text(text: &str) -> String {
+    let text = decode_entities(text);
+    let mut cleaned = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some((i, _)) = cleaned.char_indices().nth(MAX_TEXT_LEN) {
+        cleaned.truncate(i);
+        cleaned.push('…');
+    }
+    cleaned
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_owned();
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&len| len <= 10)
+            .and_then(|len| Some((decode_entity(&rest[1..=len])?, len + 2)));
+
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    let code = if let Some(hex) = entity
+        .strip_prefix("#x")
+        .or_else(|| entity.strip_prefix("#X"))
+    {
+        u32::from_str_radix(hex, 16).ok()?
+    } else if let Some(dec) = entity.strip_prefix('#') {
+        dec.parse().ok()?
+    } else {
+        return match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => None,
+        };
+    };
+
+    char::from_u32(code)
+}
+
+fn secs_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredEntry {
+    url: String,
+    /// None when we couldn't get a preview for the url
+    preview: Option<LinkPreview>,
+    fetched_at_secs: u64,
+}
+
+impl StoredEntry {
+    fn is_expired(&self, now: SystemTime) -> bool {
+        let ttl = if self.preview.is_some() {
+            PREVIEW_TTL
+        } else {
+            FAILURE_TTL
+        };
+        self.fetched_at_secs + ttl.as_secs() <= secs_since_epoch(now)
+    }
+}
+
+type FetchResult = (String, Option<LinkPreview>);
+
+/// Fetches link previews in the background and remembers them by url.
+/// Saves to disk on interval [`SAVE_INTERVAL`].
+pub struct LinkPreviews {
+    policy: LinkPreviewPolicy,
+    directory: Option<Directory>,
+    entries: HashMap<String, StoredEntry>,
+    in_flight: HashSet<String>,
+    sender: Sender<FetchResult>,
+    receiver: Receiver<FetchResult>,
+    debouncer: Debouncer,
+    dirty: bool,
+}
+
+impl LinkPreviews {
+    pub fn new(dir: PathBuf) -> Self {
+        let directory = Directory::new(dir);
+        let entries = directory
+            .get_file(FILE_NAME.to_owned())
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Vec<StoredEntry>>(&contents).ok())
+            .unwrap_or_default();
+
+        let mut previews = Self::in_memory();
+        previews.directory = Some(directory);
+
+        let now = SystemTime::now();
+        for entry in entries {
+            if !entry.is_expired(now) {
+                previews.entries.insert(entry.url.clone(), entry);
+            }
+        }
+
+        previews
+    }
+
+    /// A cache that never touches the disk
+    pub fn in_memory() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            policy: LinkPreviewPolicy::default(),
+            directory: None,
+            entries: HashMap::new(),
+            in_flight: HashSet::new(),
+            sender,
+            receiver,
+            debouncer: Debouncer::new(SAVE_INTERVAL),
+            dirty: false,
+        }
+    }
+
+    pub fn policy(&self) -> &LinkPreviewPolicy {
+        &self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: LinkPreviewPolicy) {
+        self.policy = policy;
+    }
+
+    /// The preview for a url, if we have one and are allowed to show it.
+    /// Starts fetching it in the background if we don't have a fresh one
+    /// yet, waking up `ctx` once it's there.
+    pub fn get(&mut self, ctx: &egui::Context, url: &str) -> Option<&LinkPreview> {
+        let parsed = Url::parse(url).ok()?;
+        if !self.policy.allows(&parsed) {
+            return None;
+        }
+
+        // look things up the way we store them after fetching
+        let key = parsed.as_str();
+        let fresh = self
+            .entries
+            .get(key)
+            .is_some_and(|entry| !entry.is_expired(SystemTime::now()));
+        if !fresh && !self.in_flight.contains(key) {
+            self.fetch(ctx.clone(), parsed.clone());
+        }
+
+        self.entries
+            .get(parsed.as_str())?
+            .preview
+            .as_ref()
+            .filter(|preview| !preview.is_empty())
+    }
+
+    fn fetch(&mut self, ctx: egui::Context, url: Url) {
+        debug!("fetching link preview for {url}");
+
+        let key = url.to_string();
+        self.in_flight.insert(key.clone());
+        let sender = self.sender.clone();
+
+        // redirects get the same scrutiny as the link itself
+        let policy = self.policy.clone();
+        let allow_redirect = move |target: &Url| policy.allows(target);
+
+        tokio::spawn(async move {
+            let preview = match tokio::time::timeout(
+                FETCH_TIMEOUT,
+                http_req_prefix(url.as_str(), MAX_HTML_BYTES, allow_redirect),
+            )
+            .await
+            {
+                Ok(Ok(res)) if is_html(res.content_type.as_deref()) => {
+                    let html = String::from_utf8_lossy(&res.bytes);
+                    Some(parse_link_preview(&html, &url))
+                }
+                Ok(Ok(res)) => {
+                    debug!(
+                        "not previewing {url} with content type {:?}",
+                        res.content_type
+                    );
+                    None
+                }
+                Ok(Err(err)) => {
+                    debug!("could not fetch link preview for {url}: {err}");
+                    None
+                }
+                Err(_) => {
+                    debug!("timed out fetching link preview for {url}");
+                    None
+                }
+            };
+
+            if sender.send((key, preview)).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn insert(&mut self, url: String, preview: Option<LinkPreview>, now: SystemTime) {
+        self.in_flight.remove(&url);
+        let entry = StoredEntry {
+            url: url.clone(),
+            preview,
+            fetched_at_secs: secs_since_epoch(now),
+        };
+        self.entries.insert(url, entry);
+        self.dirty = true;
+    }
+
+    /// Collect finished fetches and save to disk every once in a while.
+    /// Call this once per frame.
+    pub fn poll(&mut self) {
+        let now = SystemTime::now();
+        while let Ok((url, preview)) = self.receiver.try_recv() {
+            self.insert(url, preview, now);
+        }
+
+        if self.dirty && self.debouncer.should_act() {
+            self.save(now);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.dirty = true;
+    }
+
+    fn prune(&mut self, now: SystemTime) {
+        self.entries.retain(|_, entry| !entry.is_expired(now));
+
+        if self.entries.len() > MAX_ENTRIES {
+            let mut ages: Vec<u64> = self
+                .entries
+                .values()
+                .map(|entry| entry.fetched_at_secs)
+                .collect();
+            ages.sort_unstable_by(|a, b| b.cmp(a));
+            let cutoff = ages[MAX_ENTRIES - 1];
+            self.entries
+                .retain(|_, entry| entry.fetched_at_secs >= cutoff);
+        }
+    }
+
+    fn save(&mut self, now: SystemTime) {
+        self.dirty = false;
+        self.debouncer.bounce();
+        self.prune(now);
+
+        let Some(directory) = &self.directory else {
+            return;
+        };
+
+        let entries: Vec<&StoredEntry> = self.entries.values().collect();
+        let json = match serde_json::to_string(&entries) {
+            Ok(json) => json,
+            Err(err) => {
+                error!("could not serialize link previews: {err}");
+                return;
+            }
+        };
+
+        if let Err(err) = storage::write_file(&directory.file_path, FILE_NAME.to_owned(), &json) {
+            error!("could not save link previews: {err}");
+        }
+    }
+}
+
+fn is_html(content_type: Option<&str>) -> bool {
+    // be lenient with servers that don't say what they're sending
+    content_type.is_none_or(|content_type| {
+        let content_type = content_type.to_ascii_lowercase();
+        content_type.starts_with("text/html") || content_type.starts_with("application/xhtml")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn enabled() -> LinkPreviewPolicy {
+        LinkPreviewPolicy {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_open_graph() {
+        let html = r#"<!doctype html>
+            <html><head>
+            <title>Fallback title</title>
+            <meta charset="utf-8">
+            <META property="og:title" content="Tom &amp; Jerry&#39;s   day out" />
+            <meta property='og:description' content='A "classic"'>
+            <meta property=og:site_name content=Cartoons>
+            <meta content="/img/cover.png" property="og:image">
+            <meta property="og:title" content="second title is ignored">
+            </head><body><meta property="og:image" content="https://body.example/no.png">
+            </body></html>"#;
+
+        let preview = parse_link_preview(html, &url("https://example.com/shows/1"));
+        assert_eq!(preview.title.as_deref(), Some("Tom & Jerry's day out"));
+        assert_eq!(preview.description.as_deref(), Some("A \"classic\""));
+        assert_eq!(preview.site_name.as_deref(), Some("Cartoons"));
+        assert_eq!(
+            preview.image.as_deref(),
+            Some("https://example.com/img/cover.png")
+        );
+    }
+
+    #[test]
+    fn test_parse_fallbacks() {
+        let html = r#"<head><title>
+            Just a   title </title>
+            <meta name="description" content="plain description">
+            <meta name="twitter:image" content="javascript:alert(1)">
+            </head>"#;
+
+        let preview = parse_link_preview(html, &url("https://example.com"));
+        assert_eq!(preview.title.as_deref(), Some("Just a title"));
+        assert_eq!(preview.description.as_deref(), Some("plain description"));
+        assert_eq!(preview.site_name, None);
+        assert_eq!(preview.image, None);
+
+        let empty = parse_link_preview("<html>nothing here", &url("https://example.com"));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_clean_text() {
+        assert_eq!(
+            clean_text("a &lt;b&gt; &#x41; &bogus; &"),
+            "a <b> A &bogus; &"
+        );
+
+        let long = "x".repeat(MAX_TEXT_LEN + 10);
+        assert_eq!(clean_text(&long).chars().count(), MAX_TEXT_LEN + 1);
+    }
+
+    #[test]
+    fn test_policy() {
+        assert!(!LinkPreviewPolicy::default().allows(&url("https://example.com/a")));
+
+        let mut policy = enabled();
+        assert!(policy.allows(&url("https://example.com/a")));
+        assert!(!policy.allows(&url("ftp://example.com/a")));
+        assert!(!policy.allows(&url("http://localhost:8080")));
+        assert!(!policy.allows(&url("http://192.168.1.1/admin")));
+        assert!(policy.allows(&url("http://1.1.1.1/")));
+        assert!(!policy.allows(&url("http://100.64.0.1/")));
+        assert!(!policy.allows(&url("http://[::1]/")));
+        assert!(!policy.allows(&url("http://[::ffff:127.0.0.1]/")));
+        assert!(!policy.allows(&url("http://[::ffff:10.0.0.1]/")));
+        assert!(!policy.allows(&url("http://[fd12:3456::1]/")));
+        assert!(!policy.allows(&url("http://[fe80::1]/")));
+        assert!(policy.allows(&url("http://[2606:4700::1111]/")));
+        assert!(policy.allows(&url("http://[::ffff:1.1.1.1]/")));
+
+        policy.blocked_domains = parse_domains("tracker.com");
+        assert!(!policy.allows(&url("https://tracker.com")));
+        assert!(!policy.allows(&url("https://www.tracker.com")));
+        assert!(policy.allows(&url("https://nottracker.com")));
+
+        policy.allowed_domains = parse_domains("example.com");
+        assert!(policy.allows(&url("https://blog.example.com")));
+        assert!(!policy.allows(&url("https://other.org")));
+
+        policy.enabled = false;
+        assert!(!policy.allows(&url("https://example.com")));
+    }
+
+    #[test]
+    fn test_parse_domains() {
+        assert_eq!(
+            parse_domains("YouTube.com, *.example.org\nhttps://news.site/path  youtube.com"),
+            vec!["youtube.com", "example.org", "news.site"]
+        );
+        assert!(parse_domains(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_cache_keeps_results() {
+        let ctx = egui::Context::default();
+        let mut previews = LinkPreviews::in_memory();
+        previews.set_policy(enabled());
+        let link = "https://example.com/";
+        let preview = LinkPreview {
+            title: Some("hi".to_owned()),
+            ..Default::default()
+        };
+
+        previews.in_flight.insert(link.to_owned());
+        previews
+            .sender
+            .send((link.to_owned(), Some(preview.clone())))
+            .unwrap();
+        previews.poll();
+
+        assert!(previews.in_flight.is_empty());
+        assert_eq!(previews.get(&ctx, link), Some(&preview));
+
+        // blocked domains aren't shown even when we have them
+        previews.set_policy(LinkPreviewPolicy {
+            blocked_domains: vec!["example.com".to_owned()],
+            ..enabled()
+        });
+        assert_eq!(previews.get(&ctx, link), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let ctx = egui::Context::default();
+        let link = "https://example.com/";
+        let preview = LinkPreview {
+            title: Some("hi".to_owned()),
+            ..Default::default()
+        };
+
+        let mut previews = LinkPreviews::new(tmp.path().to_path_buf());
+        previews.insert(link.to_owned(), Some(preview.clone()), SystemTime::now());
+        previews.save(SystemTime::now());
+
+        let mut loaded = LinkPreviews::new(tmp.path().to_path_buf());
+        loaded.set_policy(enabled());
+        assert_eq!(loaded.get(&ctx, link), Some(&preview));
+    }
+}
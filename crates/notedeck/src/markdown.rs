@@ -0,0 +1,599 @@
+//! Lightweight markdown for note text.
+//!
+//! nostrdb already splits a note's content into blocks: text, mentions,
+//! hashtags, urls and so on. We only ever look at the text blocks, so
+//! everything the block parser found keeps working. What's supported is
+//! small on purpose: `inline code`, fenced code blocks, **bold**, *italic*
+//! and `>` block quotes.
+//!
+//! Code blocks and quotes can span several text blocks (a link in the
+//! middle of a quote, say), so [`MarkdownState`] is carried from one text
+//! block to the next. Emphasis and inline code have to be closed on the same
+//! line of the same block, otherwise the markers are shown as they are.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub quote: bool,
+}
+
+/// What a piece of a fenced code block is, for highlighting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Plain,
+    Keyword,
+    String,
+    Number,
+    Comment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Span<'a> {
+    Text(&'a str, Style),
+    /// The start of a block quote line
+    Quote,
+    /// A piece of a fenced code block
+    Code(&'a str, Token),
+}
+
+/// The languages we know keywords and comments for. Anything else is
+/// highlighted for strings and numbers only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    Go,
+    C,
+    Shell,
+    Other,
+}
+
+impl Language {
+    pub fn from_tag(tag: &str) -> Self {
+        match tag.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Language::Rust,
+            "python" | "py" => Language::Python,
+            "javascript" | "js" | "typescript" | "ts" | "jsx" | "tsx" => Language::JavaScript,
+            "go" | "golang" => Language::Go,
+            "c" | "h" | "cpp" | "c++" | "cc" | "hpp" => Language::C,
+            "sh" | "bash" | "shell" | "zsh" | "console" => Language::Shell,
+            _ => Language::Other,
+        }
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "as", "async", "await", "break", "const", "continue", "crate", "else", "enum",
+                "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+                "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+                "trait", "true", "type", "unsafe", "use", "where", "while",
+            ],
+            Language::Python => &[
+                "and", "as", "async", "await", "break", "class", "continue", "def", "elif", "else",
+                "except", "False", "finally", "for", "from", "if", "import", "in", "is", "lambda",
+                "None", "not", "or", "pass", "raise", "return", "self", "True", "try", "while",
+                "with", "yield",
+            ],
+            Language::JavaScript => &[
+                "async",
+                "await",
+                "break",
+                "case",
+                "catch",
+                "class",
+                "const",
+                "continue",
+                "default",
+                "else",
+                "export",
+                "extends",
+                "false",
+                "for",
+                "function",
+                "if",
+                "import",
+                "in",
+                "interface",
+                "let",
+                "new",
+                "null",
+                "of",
+                "return",
+                "switch",
+                "this",
+                "throw",
+                "true",
+                "try",
+                "type",
+                "typeof",
+                "undefined",
+                "var",
+                "while",
+            ],
+            Language::Go => &[
+                "break",
+                "case",
+                "chan",
+                "const",
+                "continue",
+                "default",
+                "defer",
+                "else",
+                "false",
+                "for",
+                "func",
+                "go",
+                "if",
+                "import",
+                "interface",
+                "map",
+                "nil",
+                "package",
+                "range",
+                "return",
+                "select",
+                "struct",
+                "switch",
+                "true",
+                "type",
+                "var",
+            ],
+            Language::C => &[
+                "auto",
+                "break",
+                "case",
+                "char",
+                "class",
+                "const",
+                "continue",
+                "default",
+                "double",
+                "else",
+                "enum",
+                "extern",
+                "float",
+                "for",
+                "if",
+                "include",
+                "int",
+                "long",
+                "namespace",
+                "return",
+                "short",
+                "signed",
+                "sizeof",
+                "static",
+                "struct",
+                "switch",
+                "template",
+                "typedef",
+                "union",
+                "unsigned",
+                "void",
+                "while",
+            ],
+            Language::Shell => &[
+                "case", "do", "done", "echo", "elif", "else", "esac", "export", "fi", "for",
+                "function", "if", "in", "local", "return", "then", "while",
+            ],
+            Language::Other => &[],
+        }
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        match self {
+            Language::Rust | Language::JavaScript | Language::Go | Language::C => Some("//"),
+            Language::Python | Language::Shell => Some("#"),
+            Language::Other => None,
+        }
+    }
+
+    /// Rust uses `'` for lifetimes, so it doesn't start a string there
+    fn is_quote(&self, c: char) -> bool {
+        c == '"'
+            || (c == '`' && *self == Language::JavaScript)
+            || (c == '\'' && *self != Language::Rust)
+    }
+}
+
+/// Where we are in the note's text, carried from one text block to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownState {
+    /// The language of the fenced code block we're in, if we're in one
+    fence: Option<Language>,
+    line_start: bool,
+    quote: bool,
+}
+
+impl Default for MarkdownState {
+    fn default() -> Self {
+        Self {
+            fence: None,
+            line_start: true,
+            quote: false,
+        }
+    }
+}
+
+impl MarkdownState {
+    /// Something other than text (a mention, a link, ...) was shown, so the
+    /// next text block doesn't start a line
+    pub fn other_block(&mut self) {
+        self.line_start = false;
+    }
+
+    pub fn in_code_block(&self) -> bool {
+        self.fence.is_some()
+    }
+}
+
+/// Split a text block into styled spans
+pub fn parse<'a>(text: &'a str, state: &mut MarkdownState) -> Vec<Span<'a>> {
+    let mut spans = Vec::new();
+
+    for line in text.split_inclusive('\n') {
+        let line_start = state.line_start;
+        state.line_start = line.ends_with('\n');
+
+        if line_start {
+            state.quote = false;
+
+            if let Some(tag) = line.trim_start().strip_prefix("```") {
+                // fence lines aren't shown
+                state.fence = match state.fence {
+                    Some(_) => None,
+                    None => Some(Language::from_tag(tag.trim())),
+                };
+                continue;
+            }
+        }
+
+        if let Some(language) = state.fence {
+            highlight(line, language, &mut spans);
+            continue;
+        }
+
+        let mut line = line;
+        if line_start {
+            if let Some(quoted) = line.strip_prefix('>') {
+                state.quote = true;
+                spans.push(Span::Quote);
+                line = quoted.strip_prefix(' ').unwrap_or(quoted);
+            }
+        }
+
+        let style = Style {
+            quote: state.quote,
+            ..Default::default()
+        };
+        parse_inline(line, style, &mut spans);
+    }
+
+    spans
+}
+
+fn parse_inline<'a>(text: &'a str, style: Style, spans: &mut Vec<Span<'a>>) {
+    let mut plain_start = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        let prev = text[..i].chars().next_back();
+
+        let found = if rest.starts_with('`') {
+            closing(rest, "`", prev).map(|(inner, len)| {
+                let code = Style {
+                    code: true,
+                    ..style
+                };
+                (len, vec![Span::Text(inner, code)])
+            })
+        } else if rest.starts_with("**") || rest.starts_with("__") {
+            closing(rest, &rest[..2], prev).map(|(inner, len)| {
+                let mut inner_spans = Vec::new();
+                parse_inline(
+                    inner,
+                    Style {
+                        bold: true,
+                        ..style
+                    },
+                    &mut inner_spans,
+                );
+                (len, inner_spans)
+            })
+        } else if rest.starts_with('*') || rest.starts_with('_') {
+            closing(rest, &rest[..1], prev).map(|(inner, len)| {
+                let mut inner_spans = Vec::new();
+                parse_inline(
+                    inner,
+                    Style {
+                        italic: true,
+                        ..style
+                    },
+                    &mut inner_spans,
+                );
+                (len, inner_spans)
+            })
+        } else {
+            None
+        };
+
+        match found {
+            Some((len, inner_spans)) => {
+                if plain_start < i {
+                    spans.push(Span::Text(&text[plain_start..i], style));
+                }
+                spans.extend(inner_spans);
+                i += len;
+                plain_start = i;
+            }
+            None => {
+                i += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+
+    if plain_start < text.len() {
+        spans.push(Span::Text(&text[plain_start..], style));
+    }
+}
+
+/// Find the end of a span that starts with `marker`, returning what's
+/// between the markers and the length of the whole span. Like markdown,
+/// emphasis can't start or end next to a space, and `_` only counts at word
+/// boundaries so snake_case names stay as they are.
+fn closing<'a>(text: &'a str, marker: &str, prev: Option<char>) -> Option<(&'a str, usize)> {
+    let after = &text[marker.len()..];
+    let line_end = after.find('\n').unwrap_or(after.len());
+    let after = &after[..line_end];
+
+    let is_code = marker == "`";
+    let is_underscore = marker.starts_with('_');
+
+    if !is_code && after.starts_with(char::is_whitespace) {
+        return None;
+    }
+    if is_underscore && prev.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+
+    let mut search = 0;
+    while let Some(offset) = after[search..].find(marker) {
+        let end = search + offset;
+        let inner = &after[..end];
+        let next = after[end + marker.len()..].chars().next();
+        search = end + marker.len();
+
+        if inner.is_empty() {
+            continue;
+        }
+        if !is_code && inner.ends_with(char::is_whitespace) {
+            continue;
+        }
+        if is_underscore && next.is_some_and(char::is_alphanumeric) {
+            continue;
+        }
+        // a single `*` next to another one is part of a `**`
+        let single = marker
+            .chars()
+            .next()
+            .filter(|_| marker.len() == 1 && !is_code);
+        if single.is_some() && (next == single || inner.chars().next_back() == single) {
+            continue;
+        }
+
+        return Some((inner, end + marker.len() * 2));
+    }
+
+    None
+}
+
+/// Split a line of code into highlighted tokens
+fn highlight<'a>(line: &'a str, language: Language, spans: &mut Vec<Span<'a>>) {
+    let mut plain_start = 0;
+    let mut i = 0;
+
+    let push = |spans: &mut Vec<Span<'a>>, plain_start: usize, start: usize, end, token| {
+        if plain_start < start {
+            spans.push(Span::Code(&line[plain_start..start], Token::Plain));
+        }
+        spans.push(Span::Code(&line[start..end], token));
+    };
+
+    while i < line.len() {
+        let rest = &line[i..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        if language
+            .line_comment()
+            .is_some_and(|comment| rest.starts_with(comment))
+        {
+            let end = line.trim_end_matches('\n').len().max(i);
+            push(spans, plain_start, i, end, Token::Comment);
+            i = end;
+            plain_start = i;
+            break;
+        }
+
+        if language.is_quote(c) {
+            let mut end = line.len();
+            let mut escaped = false;
+            for (offset, next) in rest.char_indices().skip(1) {
+                if next == '\n' {
+                    end = i + offset;
+                    break;
+                }
+                if next == c && !escaped {
+                    end = i + offset + c.len_utf8();
+                    break;
+                }
+                escaped = next == '\\' && !escaped;
+            }
+            push(spans, plain_start, i, end, Token::String);
+            i = end;
+            plain_start = i;
+            continue;
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+
+            if c.is_ascii_digit() {
+                push(spans, plain_start, i, i + len, Token::Number);
+                plain_start = i + len;
+            } else {
+                // a '.' only belongs to numbers
+                let word = word.split('.').next().unwrap_or(word);
+                if language.keywords().contains(&word) {
+                    push(spans, plain_start, i, i + word.len(), Token::Keyword);
+                    plain_start = i + word.len();
+                }
+                i += word.len();
+                continue;
+            }
+
+            i += len;
+            continue;
+        }
+
+        i += c.len_utf8();
+    }
+
+    if plain_start < line.len() {
+        spans.push(Span::Code(&line[plain_start..], Token::Plain));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str, style: Style) -> Span<'_> {
+        Span::Text(text, style)
+    }
+
+    const PLAIN: Style = Style {
+        bold: false,
+        italic: false,
+        code: false,
+        quote: false,
+    };
+
+    #[test]
+    fn test_inline() {
+        let mut state = MarkdownState::default();
+        let spans = parse("run `cargo test` **now** or *later*", &mut state);
+
+        assert_eq!(
+            spans,
+            vec![
+                text("run ", PLAIN),
+                text(
+                    "cargo test",
+                    Style {
+                        code: true,
+                        ..PLAIN
+                    }
+                ),
+                text(" ", PLAIN),
+                text(
+                    "now",
+                    Style {
+                        bold: true,
+                        ..PLAIN
+                    }
+                ),
+                text(" or ", PLAIN),
+                text(
+                    "later",
+                    Style {
+                        italic: true,
+                        ..PLAIN
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_and_snake_case() {
+        let mut state = MarkdownState::default();
+        let input = "2 * 3 = 6, my_var_name and **dangling";
+        assert_eq!(parse(input, &mut state), vec![text(input, PLAIN)]);
+    }
+
+    #[test]
+    fn test_quotes_across_blocks() {
+        let mut state = MarkdownState::default();
+        let spans = parse("> see ", &mut state);
+        assert_eq!(
+            spans,
+            vec![
+                Span::Quote,
+                text(
+                    "see ",
+                    Style {
+                        quote: true,
+                        ..PLAIN
+                    }
+                )
+            ]
+        );
+
+        // a link was shown, the rest of the line is still quoted
+        state.other_block();
+        let spans = parse(" please\nok", &mut state);
+        assert_eq!(
+            spans,
+            vec![
+                text(
+                    " please\n",
+                    Style {
+                        quote: true,
+                        ..PLAIN
+                    }
+                ),
+                text("ok", PLAIN),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_block() {
+        let mut state = MarkdownState::default();
+        let spans = parse("look:\n```rust\nlet x = \"hi\"; // 1\n", &mut state);
+        assert!(state.in_code_block());
+        assert_eq!(
+            spans,
+            vec![
+                text("look:\n", PLAIN),
+                Span::Code("let", Token::Keyword),
+                Span::Code(" x = ", Token::Plain),
+                Span::Code("\"hi\"", Token::String),
+                Span::Code("; ", Token::Plain),
+                Span::Code("// 1", Token::Comment),
+                Span::Code("\n", Token::Plain),
+            ]
+        );
+
+        // markdown isn't parsed inside code
+        let spans = parse("**x** 42\n```\ndone", &mut state);
+        assert!(!state.in_code_block());
+        assert_eq!(
+            spans,
+            vec![
+                Span::Code("**x** ", Token::Plain),
+                Span::Code("42", Token::Number),
+                Span::Code("\n", Token::Plain),
+                text("done", PLAIN),
+            ]
+        );
+    }
+}
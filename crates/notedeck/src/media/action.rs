@@ -116,6 +116,10 @@ impl MediaAction {
                         .animated
                         .request(jobs, ctx, &url, ImageType::Content(None))
                 }
+                MediaCacheType::Video => {
+                    images.textures.static_image.request_poster(jobs, ctx, &url)
+                }
+                MediaCacheType::Audio => {}
             },
             MediaAction::DoneLoading { url, cache_type: _ } => {
                 images.textures.blurred.finished_transitioning(&url);
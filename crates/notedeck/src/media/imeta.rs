@@ -1 +1,111 @@
+//! NIP-92 media metadata we use to decide how to show media without having
+//! to fetch it first: its mime type and, for videos, a poster image.
 
+use std::collections::HashMap;
+
+use nostrdb::Note;
+
+use crate::urls::SupportedMimeType;
+use crate::MediaCacheType;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MediaMetadata {
+    /// The `m` field
+    pub mime: Option<String>,
+
+    /// The `image` field, a preview image for videos
+    pub poster: Option<String>,
+}
+
+impl MediaMetadata {
+    /// The media type the mime type says this is, if we can show it
+    pub fn media_type(&self) -> Option<MediaCacheType> {
+        let mime = self.mime.as_ref()?.parse().ok()?;
+        SupportedMimeType::from_mime(mime)
+            .ok()
+            .map(|supported| supported.to_cache_type())
+    }
+}
+
+/// Find mime types and posters in a note's imeta tags and update our cache
+pub fn update_imeta_media(note: &Note, media: &mut HashMap<String, MediaMetadata>) {
+    for tag in note.tags() {
+        let mut tag_iter = tag.into_iter();
+        if tag_iter
+            .next()
+            .and_then(|s| s.str())
+            .filter(|s| *s == "imeta")
+            .is_none()
+        {
+            continue;
+        }
+
+        let Some((url, meta)) = parse_imeta(tag_iter.filter_map(|elem| elem.str())) else {
+            continue;
+        };
+
+        media.insert(url, meta);
+    }
+}
+
+fn parse_imeta<'a>(fields: impl Iterator<Item = &'a str>) -> Option<(String, MediaMetadata)> {
+    let mut url = None;
+    let mut meta = MediaMetadata::default();
+
+    for field in fields {
+        let Some((key, value)) = field.split_once(' ') else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+
+        match key {
+            "url" => url = Some(value.to_owned()),
+            "m" => meta.mime = Some(value.to_ascii_lowercase()),
+            "image" => meta.poster = Some(value.to_owned()),
+            _ => {}
+        }
+    }
+
+    if meta == MediaMetadata::default() {
+        return None;
+    }
+
+    Some((url?, meta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_imeta() {
+        let (url, meta) = parse_imeta(
+            [
+                "url https://example.com/clip.mp4",
+                "m video/mp4",
+                "image https://example.com/clip.jpg",
+                "dim 1280x720",
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(url, "https://example.com/clip.mp4");
+        assert_eq!(meta.poster.as_deref(), Some("https://example.com/clip.jpg"));
+        assert_eq!(meta.media_type(), Some(MediaCacheType::Video));
+
+        let (_, meta) =
+            parse_imeta(["url https://example.com/song", "m audio/mpeg"].into_iter()).unwrap();
+        assert_eq!(meta.media_type(), Some(MediaCacheType::Audio));
+
+        let (_, meta) =
+            parse_imeta(["url https://example.com/doc", "m application/pdf"].into_iter()).unwrap();
+        assert_eq!(meta.media_type(), None);
+
+        // nothing we use
+        assert!(parse_imeta(["url https://example.com/a.png", "dim 1x1"].into_iter()).is_none());
+    }
+}
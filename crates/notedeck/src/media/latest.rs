@@ -82,6 +82,15 @@ impl<'a> NoLoadingLatestTex<'a> {
                     TextureState::Loaded(t) => LatestImageTex::Loaded(t),
                 }
             }
+            MediaCacheType::Video => {
+                match self.static_cache.get_or_request_poster(jobs, ctx, url) {
+                    TextureState::Pending => LatestImageTex::Pending,
+                    TextureState::Error(error) => LatestImageTex::Error(error),
+                    TextureState::Loaded(t) => LatestImageTex::Loaded(t),
+                }
+            }
+            // audio is shown as a player, it has no texture
+            MediaCacheType::Audio => LatestImageTex::Pending,
             MediaCacheType::Gif => {
                 match self.animated_cache.get_or_request(jobs, ctx, url, imgtype) {
                     TextureState::Pending => LatestImageTex::Pending,
@@ -7,6 +7,7 @@ pub mod latest;
 pub mod network;
 pub mod renderable;
 pub mod static_imgs;
+pub mod video;
 
 pub use action::{MediaAction, MediaInfo, ViewMediaInfo};
 pub use blur::{
@@ -15,10 +16,12 @@ pub use blur::{
 };
 use egui::{ColorImage, TextureHandle};
 pub use images::ImageType;
+pub use imeta::{update_imeta_media, MediaMetadata};
 pub use latest::{
     MediaRenderState, NoLoadingLatestTex, TrustedMediaLatestTex, UntrustedMediaLatestTex,
 };
 pub use renderable::RenderableMedia;
+pub use video::{MediaPlayers, PlayerState};
 
 #[derive(Copy, Clone, Debug)]
 pub enum AnimationMode {
@@ -4,7 +4,7 @@ use http_body_util::{BodyExt, Empty};
 use hyper::{
     body::Bytes,
     header::{self},
-    Request, Uri,
+    Request, Response, Uri,
 };
 use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 use hyper_util::{
@@ -19,6 +19,88 @@ const MAX_BODY_BYTES: usize = 20 * 1024 * 1024;
 type HttpsClient = Client<HttpsConnector<HttpConnector>, Empty<Bytes>>;
 
 pub async fn http_req(url: &str) -> Result<HyperHttpResponse, HyperHttpError> {
+    let res = request_following_redirects(url, |_| true).await?;
+
+    let content_type = response_content_type(&res);
+
+    let content_length = res
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|s: &hyper::header::HeaderValue| s.to_str().ok())
+        .and_then(|s: &str| s.parse::<usize>().ok());
+
+    if let Some(len) = content_length {
+        if len > MAX_BODY_BYTES {
+            return Err(HyperHttpError::BodyTooLarge);
+        }
+    }
+
+    let body: hyper::body::Incoming = res.into_body();
+    let mut bytes = Vec::with_capacity(content_length.unwrap_or(0).min(MAX_BODY_BYTES));
+
+    // Collect the body using BodyExt
+    let collected = BodyExt::collect(body)
+        .await
+        .map_err(|e| HyperHttpError::Hyper(Box::new(e)))?;
+
+    let chunk = collected.to_bytes();
+    if chunk.len() > MAX_BODY_BYTES {
+        return Err(HyperHttpError::BodyTooLarge);
+    }
+
+    bytes.extend_from_slice(&chunk);
+
+    Ok(HyperHttpResponse {
+        content_type,
+        bytes,
+    })
+}
+
+/// Fetch at most the first `max_bytes` of a url's body. Unlike [`http_req`],
+/// bodies that are too large are cut off rather than refused, which is what
+/// we want when we only care about the start of a document, like the
+/// `<head>` of a web page. Redirects are only followed to urls
+/// `allow_redirect` accepts.
+pub async fn http_req_prefix(
+    url: &str,
+    max_bytes: usize,
+    allow_redirect: impl Fn(&Url) -> bool,
+) -> Result<HyperHttpResponse, HyperHttpError> {
+    let res = request_following_redirects(url, allow_redirect).await?;
+    let content_type = response_content_type(&res);
+
+    let mut body: hyper::body::Incoming = res.into_body();
+    let mut bytes = Vec::new();
+
+    while bytes.len() < max_bytes {
+        let Some(frame) = body.frame().await else {
+            break;
+        };
+
+        let frame = frame.map_err(|e| HyperHttpError::Hyper(Box::new(e)))?;
+        if let Ok(data) = frame.into_data() {
+            let take = data.len().min(max_bytes - bytes.len());
+            bytes.extend_from_slice(&data[..take]);
+        }
+    }
+
+    Ok(HyperHttpResponse {
+        content_type,
+        bytes,
+    })
+}
+
+fn response_content_type(res: &Response<hyper::body::Incoming>) -> Option<String> {
+    res.headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|t: &hyper::header::HeaderValue| t.to_str().ok())
+        .map(|s: &str| s.to_string())
+}
+
+async fn request_following_redirects(
+    url: &str,
+    allow_redirect: impl Fn(&Url) -> bool,
+) -> Result<Response<hyper::body::Incoming>, HyperHttpError> {
     let mut current_uri: Uri = url.parse().map_err(|_| HyperHttpError::Uri)?;
 
     let https: HttpsConnector<HttpConnector> = HttpsConnectorBuilder::new()
@@ -36,7 +118,7 @@ pub async fn http_req(url: &str) -> Result<HyperHttpResponse, HyperHttpError> {
     const MAX_REDIRECTS: usize = 5;
     let mut redirects = 0;
 
-    let res = loop {
+    loop {
         let authority = current_uri.authority().ok_or(HyperHttpError::Host)?.clone();
 
         // Fetch the url...
@@ -72,51 +154,12 @@ pub async fn http_req(url: &str) -> Result<HyperHttpResponse, HyperHttpError> {
                 .await
                 .map_err(|e| HyperHttpError::Hyper(Box::new(e)))?;
 
-            current_uri = resolve_redirect(&current_uri, &location)?;
+            current_uri = follow_redirect(&current_uri, &location, &allow_redirect)?;
             redirects += 1;
-            continue;
         } else {
-            break res;
-        }
-    };
-
-    let content_type = res
-        .headers()
-        .get(hyper::header::CONTENT_TYPE)
-        .and_then(|t: &hyper::header::HeaderValue| t.to_str().ok())
-        .map(|s: &str| s.to_string());
-
-    let content_length = res
-        .headers()
-        .get(header::CONTENT_LENGTH)
-        .and_then(|s: &hyper::header::HeaderValue| s.to_str().ok())
-        .and_then(|s: &str| s.parse::<usize>().ok());
-
-    if let Some(len) = content_length {
-        if len > MAX_BODY_BYTES {
-            return Err(HyperHttpError::BodyTooLarge);
+            return Ok(res);
         }
     }
-
-    let body: hyper::body::Incoming = res.into_body();
-    let mut bytes = Vec::with_capacity(content_length.unwrap_or(0).min(MAX_BODY_BYTES));
-
-    // Collect the body using BodyExt
-    let collected = BodyExt::collect(body)
-        .await
-        .map_err(|e| HyperHttpError::Hyper(Box::new(e)))?;
-
-    let chunk = collected.to_bytes();
-    if chunk.len() > MAX_BODY_BYTES {
-        return Err(HyperHttpError::BodyTooLarge);
-    }
-
-    bytes.extend_from_slice(&chunk);
-
-    Ok(HyperHttpResponse {
-        content_type,
-        bytes,
-    })
 }
 
 #[derive(Debug)]
@@ -128,6 +171,7 @@ pub enum HyperHttpError {
     TooManyRedirects,
     MissingRedirectLocation,
     InvalidRedirectLocation,
+    RedirectNotAllowed,
 }
 
 #[derive(Debug)]
@@ -155,6 +199,7 @@ impl fmt::Display for HyperHttpError {
             Self::TooManyRedirects => write!(f, "Too many redirect responses"),
             Self::MissingRedirectLocation => write!(f, "Redirect response missing Location header"),
             Self::InvalidRedirectLocation => write!(f, "Invalid redirect Location header"),
+            Self::RedirectNotAllowed => write!(f, "Redirect to a url we may not fetch"),
         }
     }
 }
@@ -176,3 +221,52 @@ fn resolve_redirect(current: &Uri, location: &str) -> Result<Uri, HyperHttpError
         .parse::<Uri>()
         .map_err(|_| HyperHttpError::InvalidRedirectLocation)
 }
+
+/// Where a redirect takes us, if we're allowed to go there
+fn follow_redirect(
+    current: &Uri,
+    location: &str,
+    allow_redirect: impl Fn(&Url) -> bool,
+) -> Result<Uri, HyperHttpError> {
+    let target = resolve_redirect(current, location)?;
+    let url =
+        Url::parse(&target.to_string()).map_err(|_| HyperHttpError::InvalidRedirectLocation)?;
+    if !allow_redirect(&url) {
+        return Err(HyperHttpError::RedirectNotAllowed);
+    }
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LinkPreviewPolicy;
+
+    #[test]
+    fn test_redirects_follow_the_policy() {
+        let policy = LinkPreviewPolicy {
+            enabled: true,
+            blocked_domains: vec!["tracker.com".to_owned()],
+            ..Default::default()
+        };
+        let allow = |url: &Url| policy.allows(url);
+        let current: Uri = "https://example.com/a".parse().unwrap();
+
+        let target = follow_redirect(&current, "/b", allow).unwrap();
+        assert_eq!(target.to_string(), "https://example.com/b");
+
+        // a public link can't send us onto the local network or to a site
+        // we wouldn't fetch from directly
+        for location in [
+            "http://192.168.1.1/",
+            "http://localhost:8080/",
+            "https://www.tracker.com/",
+        ] {
+            assert!(matches!(
+                follow_redirect(&current, location, allow),
+                Err(HyperHttpError::RedirectNotAllowed)
+            ));
+        }
+    }
+}
@@ -6,4 +6,18 @@ pub struct RenderableMedia {
     pub url: String,
     pub media_type: MediaCacheType,
     pub obfuscation_type: ObfuscationType,
+
+    /// A video's poster image from its imeta, if it has one
+    pub poster: Option<String>,
+}
+
+impl RenderableMedia {
+    /// Where the texture we show for this media comes from. Videos show their
+    /// poster image, or a frame from the video when they don't have one.
+    pub fn texture_source(&self) -> (&str, MediaCacheType) {
+        match (self.media_type, &self.poster) {
+            (MediaCacheType::Video, Some(poster)) => (poster, MediaCacheType::Image),
+            (media_type, _) => (&self.url, media_type),
+        }
+    }
 }
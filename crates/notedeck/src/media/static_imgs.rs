@@ -18,6 +18,7 @@ use crate::{
         images::{buffer_to_color_image, parse_img_response},
         load_texture_checked,
         network::http_req,
+        video,
     },
     MediaCache,
 };
@@ -100,6 +101,81 @@ impl StaticImgTexCache {
 
         &TextureState::Pending
     }
+
+    pub fn request_poster(&self, jobs: &MediaJobSender, ctx: &egui::Context, url: &str) {
+        let _ = self.get_or_request_poster(jobs, ctx, url);
+    }
+
+    /// A frame from a video to show before it's played. We save it with the
+    /// static images, keyed by the video's url.
+    pub fn get_or_request_poster(
+        &self,
+        jobs: &MediaJobSender,
+        ctx: &egui::Context,
+        url: &str,
+    ) -> &TextureState<TextureHandle> {
+        if let Some(res) = self.cache.get(url) {
+            return res;
+        }
+
+        let key = MediaCache::key(url);
+        let path = self.static_img_cache_path.join(key);
+        let ctx = ctx.clone();
+        let url = url.to_owned();
+
+        let job = if path.exists() {
+            let url = url.clone();
+            JobRun::Sync(Box::new(move || {
+                JobOutput::Complete(CompleteResponse::new(MediaJobResult::StaticImg(
+                    fetch_static_img_from_disk(ctx.clone(), &url, &path),
+                )))
+            }))
+        } else {
+            let url = url.clone();
+            let path = self.static_img_cache_path.clone();
+            // ffmpeg can take a while, keep it off the pool that loads images
+            JobRun::Async(Box::pin(async move {
+                tokio::task::spawn_blocking(move || extract_poster(ctx, url, path))
+                    .await
+                    .unwrap_or_else(|e| {
+                        JobOutput::complete(MediaJobResult::StaticImg(Err(crate::Error::Generic(
+                            e.to_string(),
+                        ))))
+                    })
+            }))
+        };
+
+        if let Err(e) = jobs.send(JobPackage::new(
+            url,
+            MediaJobKind::StaticImg,
+            RunType::Output(job),
+        )) {
+            tracing::error!("{e}");
+        }
+
+        &TextureState::Pending
+    }
+}
+
+fn extract_poster(ctx: egui::Context, url: String, path: PathBuf) -> JobOutput<MediaJobResult> {
+    tracing::trace!("extracting poster frame from {url}");
+    let img = match video::poster_frame(&url) {
+        Ok(img) => img,
+        Err(e) => return JobOutput::complete(MediaJobResult::StaticImg(Err(e))),
+    };
+
+    let texture_handle = load_texture_checked(&ctx, url.clone(), img.clone(), Default::default());
+
+    JobOutput::Complete(
+        CompleteResponse::new(MediaJobResult::StaticImg(Ok(texture_handle))).run_no_output(
+            NoOutputRun::Sync(Box::new(move || {
+                tracing::trace!("poster frame: saving output from {url}");
+                if let Err(e) = MediaCache::write(&path, &url, img) {
+                    tracing::error!("{e}");
+                }
+            })),
+        ),
+    )
 }
 
 pub fn fetch_static_img_from_disk(
@@ -0,0 +1,653 @@
+//! Video and audio playback.
+//!
+//! Videos are decoded in software by `ffmpeg`, which we run as a child
+//! process and read raw RGBA frames from, so playback doesn't need a GPU
+//! video decoder and works on GPU-less Linux. Audio, including a video's
+//! sound, is played by `ffplay`. A video's sound is a separate process that
+//! starts along with the frames, nothing keeps the two in sync afterwards.
+//!
+//! `ffmpeg`, `ffprobe` and `ffplay` aren't bundled and have to be on the
+//! `PATH`. We look for them once at startup ([`probe_programs`]) and only
+//! offer video and audio once they're found, see [`playback_supported`].
+//! Mobile platforms can't run them at all, so there we don't look.
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use egui::{ColorImage, TextureHandle};
+
+use crate::media::load_texture_checked;
+use crate::Error;
+
+/// Whether we can run ffmpeg's programs on this platform at all
+const PLATFORM_SUPPORTED: bool = cfg!(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux"
+));
+
+const PROGRAMS: [&str; 3] = ["ffmpeg", "ffprobe", "ffplay"];
+
+/// Whether [`probe_programs`] is done looking
+static PROBED: AtomicBool = AtomicBool::new(false);
+
+/// The first of ffmpeg's programs we found missing
+static MISSING_PROGRAM: OnceLock<&'static str> = OnceLock::new();
+
+/// Look for ffmpeg's programs in the background. Call this once at
+/// startup, video and audio aren't offered until it's done.
+pub fn probe_programs(ctx: &egui::Context) {
+    if !PLATFORM_SUPPORTED {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        for program in PROGRAMS {
+            let status = Command::new(program)
+                .arg("-version")
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+
+            if let Err(err) = status {
+                command_error(program, err);
+                break;
+            }
+        }
+
+        PROBED.store(true, Ordering::Release);
+        ctx.request_repaint();
+    });
+}
+
+/// Whether we can play video and audio: ffmpeg's programs were found
+pub fn playback_supported() -> bool {
+    PLATFORM_SUPPORTED && PROBED.load(Ordering::Acquire) && MISSING_PROGRAM.get().is_none()
+}
+
+/// We scale videos down so their longest side is at most this, decoding in
+/// software is expensive
+const MAX_VIDEO_SIZE: u32 = 1280;
+
+/// How many frames we decode ahead of the one we're showing
+const FRAME_BUFFER: usize = 4;
+
+/// Videos that haven't been shown for this long are stopped
+const IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const DEFAULT_FPS: f32 = 30.0;
+
+/// How long ffmpeg waits on the network before giving up, in microseconds
+const NETWORK_TIMEOUT_US: &str = "10000000";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerState {
+    Loading,
+    Playing,
+    Paused,
+    Ended,
+    Failed(String),
+}
+
+/// The videos and audio being played. Only one audio file plays at a time.
+#[derive(Default)]
+pub struct MediaPlayers {
+    videos: HashMap<String, VideoPlayer>,
+    audio: Option<AudioPlayer>,
+}
+
+impl MediaPlayers {
+    /// Start playing a video, or keep it playing. Call this every frame the
+    /// video is shown, videos that stop being shown are stopped by
+    /// [`Self::poll`].
+    pub fn play_video(&mut self, ctx: &egui::Context, url: &str) {
+        let player = self
+            .videos
+            .entry(url.to_owned())
+            .or_insert_with(|| VideoPlayer::start(url));
+
+        player.last_shown = Instant::now();
+        player.update(ctx);
+    }
+
+    /// The video's current frame, once we have one
+    pub fn video_texture(&self, url: &str) -> Option<&TextureHandle> {
+        self.videos.get(url)?.texture.as_ref()
+    }
+
+    /// The video's state and how far into it we are
+    pub fn video_state(&self, url: &str) -> Option<(PlayerState, Duration)> {
+        self.videos
+            .get(url)
+            .map(|player| (player.state.clone(), player.position))
+    }
+
+    /// Pause or resume a video, or play it again once it has ended
+    pub fn toggle_video(&mut self, url: &str) {
+        let Some(player) = self.videos.get_mut(url) else {
+            return;
+        };
+
+        match player.state {
+            PlayerState::Loading => {}
+            PlayerState::Playing => player.pause(),
+            PlayerState::Paused => player.resume(),
+            PlayerState::Ended | PlayerState::Failed(_) => *player = VideoPlayer::start(url),
+        }
+    }
+
+    /// The audio file's state and how far into it we are, if it's the one
+    /// being played
+    pub fn audio_state(&self, url: &str) -> Option<(PlayerState, Duration)> {
+        self.audio
+            .as_ref()
+            .filter(|player| player.url == url)
+            .map(|player| (player.state.clone(), player.position()))
+    }
+
+    /// Play or pause an audio file, stopping any other audio
+    pub fn toggle_audio(&mut self, url: &str) {
+        match &mut self.audio {
+            Some(player) if player.url == url => player.toggle(),
+            _ => {
+                let mut player = AudioPlayer::new(url);
+                player.toggle();
+                self.audio = Some(player);
+            }
+        }
+    }
+
+    /// Stop videos that aren't being shown anymore and notice when audio
+    /// has finished. Called every frame.
+    pub fn poll(&mut self) {
+        self.videos
+            .retain(|_, player| player.last_shown.elapsed() < IDLE_TIMEOUT);
+
+        if let Some(audio) = &mut self.audio {
+            audio.poll();
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        self.videos.clear();
+        self.audio = None;
+    }
+}
+
+enum Decoded {
+    Started { fps: f32 },
+    Frame(ColorImage),
+    Failed(String),
+}
+
+struct VideoPlayer {
+    url: String,
+    frames: Receiver<Decoded>,
+    texture: Option<TextureHandle>,
+    state: PlayerState,
+    fps: f32,
+    frames_shown: u64,
+
+    /// Our playback clock, only moves while playing
+    position: Duration,
+    last_tick: Instant,
+    last_shown: Instant,
+
+    /// ffplay playing the video's sound
+    sound: Option<Child>,
+}
+
+impl VideoPlayer {
+    fn start(url: &str) -> Self {
+        let (sender, frames) = mpsc::sync_channel(FRAME_BUFFER);
+        let decode_url = url.to_owned();
+        thread::spawn(move || decode_video(decode_url, sender));
+
+        Self {
+            url: url.to_owned(),
+            frames,
+            texture: None,
+            state: PlayerState::Loading,
+            fps: DEFAULT_FPS,
+            frames_shown: 0,
+            position: Duration::ZERO,
+            last_tick: Instant::now(),
+            last_shown: Instant::now(),
+            sound: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        if self.state == PlayerState::Playing {
+            self.position += now - self.last_tick;
+        }
+        self.last_tick = now;
+
+        let mut latest = None;
+        loop {
+            match self.state {
+                PlayerState::Loading => {}
+                PlayerState::Playing => {
+                    // the next frame isn't due yet
+                    let due = (self.position.as_secs_f32() * self.fps) as u64;
+                    if self.frames_shown > due {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+
+            match self.frames.try_recv() {
+                Ok(Decoded::Started { fps }) => {
+                    self.fps = fps;
+                    self.state = PlayerState::Playing;
+                    self.position = Duration::ZERO;
+                    self.sound = play_sound(&self.url, Duration::ZERO, true);
+                }
+                Ok(Decoded::Frame(image)) => {
+                    self.frames_shown += 1;
+                    latest = Some(image);
+                }
+                Ok(Decoded::Failed(err)) => {
+                    self.state = PlayerState::Failed(err);
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.state = PlayerState::Ended;
+                    self.stop_sound();
+                    break;
+                }
+            }
+        }
+
+        if let Some(image) = latest {
+            match &mut self.texture {
+                Some(texture) => texture.set(image, Default::default()),
+                None => {
+                    self.texture = Some(load_texture_checked(
+                        ctx,
+                        self.url.clone(),
+                        image,
+                        Default::default(),
+                    ))
+                }
+            }
+        }
+
+        match self.state {
+            PlayerState::Playing => {
+                ctx.request_repaint_after(Duration::from_secs_f32(1.0 / self.fps));
+            }
+            PlayerState::Loading => ctx.request_repaint_after(Duration::from_millis(100)),
+            _ => {}
+        }
+    }
+
+    /// Pausing stops taking frames, which in turn blocks the decoder
+    fn pause(&mut self) {
+        self.state = PlayerState::Paused;
+        self.stop_sound();
+    }
+
+    fn resume(&mut self) {
+        self.state = PlayerState::Playing;
+        self.last_tick = Instant::now();
+        self.sound = play_sound(&self.url, self.position, true);
+    }
+
+    fn stop_sound(&mut self) {
+        if let Some(mut sound) = self.sound.take() {
+            let _ = sound.kill();
+            let _ = sound.wait();
+        }
+    }
+}
+
+impl Drop for VideoPlayer {
+    fn drop(&mut self) {
+        // the decoder stops once it can't send us frames
+        self.stop_sound();
+    }
+}
+
+struct AudioPlayer {
+    url: String,
+    child: Option<Child>,
+    state: PlayerState,
+
+    /// Where we were when we last paused
+    paused_at: Duration,
+    started: Option<Instant>,
+}
+
+impl AudioPlayer {
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            child: None,
+            state: PlayerState::Paused,
+            paused_at: Duration::ZERO,
+            started: None,
+        }
+    }
+
+    fn position(&self) -> Duration {
+        self.paused_at
+            + self
+                .started
+                .map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    fn toggle(&mut self) {
+        match self.state {
+            PlayerState::Playing => {
+                self.paused_at = self.position();
+                self.stop();
+                self.state = PlayerState::Paused;
+            }
+            _ => {
+                if self.state == PlayerState::Ended {
+                    self.paused_at = Duration::ZERO;
+                }
+
+                self.child = play_sound(&self.url, self.paused_at, false);
+                if self.child.is_some() {
+                    self.state = PlayerState::Playing;
+                    self.started = Some(Instant::now());
+                } else {
+                    let program = MISSING_PROGRAM.get().copied().unwrap_or("ffplay");
+                    self.state = PlayerState::Failed(not_found_message(program));
+                }
+            }
+        }
+    }
+
+    fn poll(&mut self) {
+        let Some(child) = &mut self.child else {
+            return;
+        };
+
+        if let Ok(Some(_)) = child.try_wait() {
+            self.child = None;
+            self.started = None;
+            self.paused_at = Duration::ZERO;
+            self.state = PlayerState::Ended;
+        }
+    }
+
+    fn stop(&mut self) {
+        self.started = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Drop for AudioPlayer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Grab a frame from the start of a video to show before it's played
+pub fn poster_frame(url: &str) -> Result<ColorImage, Error> {
+    check_url(url)?;
+    check_programs()?;
+
+    // skip a possibly black first frame, unless the video is that short
+    for seek in ["0.5", "0"] {
+        let output = Command::new("ffmpeg")
+            .args(["-ss", seek])
+            .args(input_args(url))
+            .args(["-frames:v", "1"])
+            .args(["-vf", &format!("scale={}", fit_filter())])
+            .args(["-f", "image2pipe", "-vcodec", "png", "-"])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|err| command_error("ffmpeg", err))?;
+
+        if output.stdout.is_empty() {
+            continue;
+        }
+
+        let image = image::load_from_memory(&output.stdout)?.to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+        return Ok(ColorImage::from_rgba_unmultiplied(size, image.as_raw()));
+    }
+
+    Err(Error::Generic(format!("Could not get a frame from {url}")))
+}
+
+fn decode_video(url: String, sender: SyncSender<Decoded>) {
+    let info = match check_url(&url)
+        .and_then(|_| check_programs())
+        .and_then(|_| probe_video(&url))
+    {
+        Ok(info) => info,
+        Err(err) => {
+            let _ = sender.send(Decoded::Failed(err.to_string()));
+            return;
+        }
+    };
+
+    let [width, height] = info.scaled_size(MAX_VIDEO_SIZE);
+    let mut child = match Command::new("ffmpeg")
+        .args(input_args(&url))
+        .args(["-an", "-vf", &format!("scale={width}:{height}")])
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            let _ = sender.send(Decoded::Failed(command_error("ffmpeg", err).to_string()));
+            return;
+        }
+    };
+
+    if let Some(mut stdout) = child.stdout.take() {
+        if sender.send(Decoded::Started { fps: info.fps }).is_ok() {
+            let mut buf = vec![0u8; width as usize * height as usize * 4];
+            while stdout.read_exact(&mut buf).is_ok() {
+                let image =
+                    ColorImage::from_rgba_unmultiplied([width as usize, height as usize], &buf);
+                if sender.send(Decoded::Frame(image)).is_err() {
+                    // the player was dropped
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn play_sound(url: &str, from: Duration, video: bool) -> Option<Child> {
+    if check_url(url).is_err() || check_programs().is_err() {
+        return None;
+    }
+
+    let mut command = Command::new("ffplay");
+    command
+        .args(["-nodisp", "-autoexit"])
+        .args(["-ss", &format!("{:.3}", from.as_secs_f32())]);
+    if video {
+        command.arg("-vn");
+    }
+
+    match command
+        .args(input_args(url))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => Some(child),
+        Err(err) => {
+            tracing::warn!(
+                "could not play sound for {url}: {}",
+                command_error("ffplay", err)
+            );
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct VideoInfo {
+    width: u32,
+    height: u32,
+    fps: f32,
+}
+
+impl VideoInfo {
+    /// Parse `width,height,avg_frame_rate` as printed by ffprobe
+    fn parse(output: &str) -> Option<Self> {
+        let line = output.lines().find(|line| !line.trim().is_empty())?;
+        let mut fields = line.trim().split(',');
+
+        let width = fields.next()?.parse::<u32>().ok().filter(|w| *w > 0)?;
+        let height = fields.next()?.parse::<u32>().ok().filter(|h| *h > 0)?;
+        let fps = fields
+            .next()
+            .and_then(parse_frame_rate)
+            .unwrap_or(DEFAULT_FPS);
+
+        Some(Self { width, height, fps })
+    }
+
+    /// Our size for the video, so its longest side is at most `max`
+    fn scaled_size(&self, max: u32) -> [u32; 2] {
+        let longest = self.width.max(self.height);
+        if longest <= max {
+            return [self.width, self.height];
+        }
+
+        let scale = max as f32 / longest as f32;
+        [
+            ((self.width as f32 * scale).round() as u32).max(1),
+            ((self.height as f32 * scale).round() as u32).max(1),
+        ]
+    }
+}
+
+/// Frame rates are fractions like `30000/1001`, `0/0` when unknown
+fn parse_frame_rate(rate: &str) -> Option<f32> {
+    let (num, den) = rate.trim().split_once('/')?;
+    let fps = num.parse::<f32>().ok()? / den.parse::<f32>().ok()?;
+    (fps.is_finite() && fps > 0.0).then_some(fps)
+}
+
+fn probe_video(url: &str) -> Result<VideoInfo, Error> {
+    let output = Command::new("ffprobe")
+        .args(["-select_streams", "v:0"])
+        .args(["-show_entries", "stream=width,height,avg_frame_rate"])
+        .args(["-of", "csv=p=0"])
+        .args(input_args(url))
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| command_error("ffprobe", err))?;
+
+    VideoInfo::parse(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| Error::Generic(format!("Could not find a video stream in {url}")))
+}
+
+/// Only let ffmpeg fetch from the network, urls in notes shouldn't be able to
+/// read local files
+fn input_args(url: &str) -> [&str; 8] {
+    [
+        "-v",
+        "error",
+        "-protocol_whitelist",
+        "http,https,tcp,tls",
+        "-rw_timeout",
+        NETWORK_TIMEOUT_US,
+        "-i",
+        url,
+    ]
+}
+
+fn check_url(url: &str) -> Result<(), Error> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(Error::Generic(format!("Can't play {url}")))
+    }
+}
+
+/// Scale down to fit [`MAX_VIDEO_SIZE`], keeping the aspect ratio
+fn fit_filter() -> String {
+    format!(
+        "w='min(iw,{MAX_VIDEO_SIZE})':h='min(ih,{MAX_VIDEO_SIZE})':force_original_aspect_ratio=decrease"
+    )
+}
+
+fn not_found_message(program: &str) -> String {
+    format!("{program} is needed to play media but wasn't found")
+}
+
+/// Don't keep trying to run programs we know aren't there
+fn check_programs() -> Result<(), Error> {
+    match MISSING_PROGRAM.get() {
+        Some(program) => Err(Error::Generic(not_found_message(program))),
+        None => Ok(()),
+    }
+}
+
+fn command_error(program: &'static str, err: io::Error) -> Error {
+    if err.kind() != io::ErrorKind::NotFound {
+        return Error::Io(err);
+    }
+
+    if MISSING_PROGRAM.set(program).is_ok() {
+        tracing::error!("{program} wasn't found, video and audio playback is off until restart");
+    }
+    Error::Generic(not_found_message(program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_video_info() {
+        let info = VideoInfo::parse("1920,1080,30000/1001\n").unwrap();
+        assert_eq!((info.width, info.height), (1920, 1080));
+        assert!((info.fps - 29.97).abs() < 0.01);
+
+        // unknown frame rate
+        let info = VideoInfo::parse("640,480,0/0").unwrap();
+        assert_eq!(info.fps, DEFAULT_FPS);
+
+        assert!(VideoInfo::parse("").is_none());
+        assert!(VideoInfo::parse("N/A,N/A,25/1").is_none());
+    }
+
+    #[test]
+    fn test_scaled_size() {
+        let landscape = VideoInfo::parse("1920,1080,25/1").unwrap();
+        assert_eq!(landscape.scaled_size(1280), [1280, 720]);
+
+        let portrait = VideoInfo::parse("1080,1920,25/1").unwrap();
+        assert_eq!(portrait.scaled_size(1280), [720, 1280]);
+
+        let small = VideoInfo::parse("640,360,25/1").unwrap();
+        assert_eq!(small.scaled_size(1280), [640, 360]);
+    }
+}
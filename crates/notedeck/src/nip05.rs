@@ -0,0 +1,395 @@
+//! NIP-05 verification.
+//!
+//! Profiles can claim a `name@domain` identifier. We only treat it as
+//! verified once `https://domain/.well-known/nostr.json?name=name` maps the
+//! name back to the profile's pubkey. Results are cached on disk so we don't
+//! hit every domain again on each start.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ehttp::{Request, Response};
+use enostr::Pubkey;
+use nostrdb::ProfileRecord;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use crate::debouncer::Debouncer;
+use crate::{storage, Directory};
+
+const FILE_NAME: &str = "nip05.json";
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+const VERIFIED_TTL: Duration = Duration::from_secs(60 * 60 * 24); // one day
+const MISMATCH_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+const UNREACHABLE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The response of a `/.well-known/nostr.json` request
+#[derive(Deserialize, Serialize)]
+pub struct Nip05Result {
+    pub names: HashMap<String, String>,
+    pub relays: Option<HashMap<String, Vec<String>>>,
+}
+
+pub fn nip05_url(user: &str, host: &str) -> String {
+    format!("https://{host}/.well-known/nostr.json?name={user}")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Nip05Status {
+    /// We haven't heard back from the domain yet
+    Pending,
+
+    /// The domain maps the name to the profile's pubkey
+    Verified,
+
+    /// The domain doesn't map the name to the profile's pubkey. When it maps
+    /// it to someone else, the profile is likely impersonating them.
+    Mismatch { actual: Option<Pubkey> },
+
+    /// We couldn't fetch or parse the domain's nostr.json
+    Unreachable,
+}
+
+impl Nip05Status {
+    pub fn is_impersonation(&self) -> bool {
+        matches!(self, Nip05Status::Mismatch { actual: Some(_) })
+    }
+
+    fn ttl(&self) -> Duration {
+        match self {
+            Nip05Status::Pending => Duration::ZERO,
+            Nip05Status::Verified => VERIFIED_TTL,
+            Nip05Status::Mismatch { .. } => MISMATCH_TTL,
+            Nip05Status::Unreachable => UNREACHABLE_TTL,
+        }
+    }
+}
+
+/// Split a nip05 identifier into its lowercased name and domain
+fn parse_nip05(nip05: &str) -> Option<(String, String)> {
+    let (user, host) = nip05.trim().split_once('@')?;
+    if user.is_empty() || host.is_empty() || host.contains('@') || host.contains('/') {
+        return None;
+    }
+
+    Some((user.to_lowercase(), host.to_lowercase()))
+}
+
+fn verify(result: &Nip05Result, user: &str, pubkey: &Pubkey) -> Nip05Status {
+    let found = result
+        .names
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(user))
+        .and_then(|(_, hex)| Pubkey::from_hex(hex).ok());
+
+    match found {
+        Some(actual) if actual == *pubkey => Nip05Status::Verified,
+        actual => Nip05Status::Mismatch { actual },
+    }
+}
+
+fn secs_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+type Key = (Pubkey, String);
+
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredEntry {
+    pubkey: Pubkey,
+    nip05: String,
+    status: Nip05Status,
+    checked_at_secs: u64,
+}
+
+impl StoredEntry {
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.checked_at_secs + self.status.ttl().as_secs() <= secs_since_epoch(now)
+    }
+}
+
+/// Verifies nip05 identifiers in the background and remembers the results,
+/// keyed by pubkey and identifier. Saves to disk on interval
+/// [`SAVE_INTERVAL`].
+pub struct Nip05Cache {
+    directory: Option<Directory>,
+    entries: HashMap<Key, StoredEntry>,
+    in_flight: HashSet<Key>,
+    sender: Sender<(Key, Nip05Status)>,
+    receiver: Receiver<(Key, Nip05Status)>,
+    wakeup: Option<egui::Context>,
+    debouncer: Debouncer,
+    dirty: bool,
+}
+
+impl Nip05Cache {
+    pub fn new(dir: PathBuf, ctx: egui::Context) -> Self {
+        let directory = Directory::new(dir);
+        let entries = directory
+            .get_file(FILE_NAME.to_owned())
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Vec<StoredEntry>>(&contents).ok())
+            .unwrap_or_default();
+
+        let mut cache = Self::in_memory();
+        cache.directory = Some(directory);
+        cache.wakeup = Some(ctx);
+
+        let now = SystemTime::now();
+        for entry in entries {
+            if !entry.is_expired(now) {
+                cache
+                    .entries
+                    .insert((entry.pubkey, entry.nip05.clone()), entry);
+            }
+        }
+
+        cache
+    }
+
+    /// A cache that never touches the disk
+    pub fn in_memory() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            directory: None,
+            entries: HashMap::new(),
+            in_flight: HashSet::new(),
+            sender,
+            receiver,
+            wakeup: None,
+            debouncer: Debouncer::new(SAVE_INTERVAL),
+            dirty: false,
+        }
+    }
+
+    /// The verification status of `nip05` for `pubkey`. Starts a check in
+    /// the background if we don't have a fresh result yet.
+    pub fn status(&mut self, pubkey: &Pubkey, nip05: &str) -> Nip05Status {
+        let Some((user, host)) = parse_nip05(nip05) else {
+            // nothing we could ever look up
+            return Nip05Status::Unreachable;
+        };
+
+        let key = (*pubkey, format!("{user}@{host}"));
+        let cached = self.entries.get(&key);
+        let fresh = cached.is_some_and(|entry| !entry.is_expired(SystemTime::now()));
+        let status = cached
+            .map(|entry| entry.status)
+            .unwrap_or(Nip05Status::Pending);
+
+        if !fresh && !self.in_flight.contains(&key) {
+            self.fetch(key, user, host);
+        }
+
+        status
+    }
+
+    /// The verification status of the nip05 in a profile, if it has one
+    pub fn profile_status(
+        &mut self,
+        pubkey: &Pubkey,
+        profile: Option<&ProfileRecord<'_>>,
+    ) -> Option<Nip05Status> {
+        let nip05 = profile?.record().profile()?.nip05()?;
+        Some(self.status(pubkey, nip05))
+    }
+
+    fn fetch(&mut self, key: Key, user: String, host: String) {
+        debug!("verifying nip05 {} for {}", key.1, key.0);
+
+        self.in_flight.insert(key.clone());
+        let sender = self.sender.clone();
+        let wakeup = self.wakeup.clone();
+        let pubkey = key.0;
+
+        let request = Request::get(nip05_url(&user, &host));
+        ehttp::fetch(request, move |response: Result<Response, String>| {
+            let status = match response {
+                Ok(resp) if resp.ok => match serde_json::from_slice::<Nip05Result>(&resp.bytes) {
+                    Ok(result) => verify(&result, &user, &pubkey),
+                    Err(err) => {
+                        debug!("bad nostr.json from {host}: {err}");
+                        Nip05Status::Unreachable
+                    }
+                },
+                Ok(resp) => {
+                    debug!("nostr.json from {host} returned {}", resp.status);
+                    Nip05Status::Unreachable
+                }
+                Err(err) => {
+                    debug!("could not fetch nostr.json from {host}: {err}");
+                    Nip05Status::Unreachable
+                }
+            };
+
+            if sender.send((key, status)).is_ok() {
+                if let Some(ctx) = wakeup {
+                    ctx.request_repaint();
+                }
+            }
+        });
+    }
+
+    fn insert(&mut self, key: Key, status: Nip05Status, now: SystemTime) {
+        self.in_flight.remove(&key);
+        let entry = StoredEntry {
+            pubkey: key.0,
+            nip05: key.1.clone(),
+            status,
+            checked_at_secs: secs_since_epoch(now),
+        };
+        self.entries.insert(key, entry);
+        self.dirty = true;
+    }
+
+    /// Collect finished verifications and save to disk every once in a while.
+    /// Call this once per frame.
+    pub fn poll(&mut self) {
+        let now = SystemTime::now();
+        while let Ok((key, status)) = self.receiver.try_recv() {
+            self.insert(key, status, now);
+        }
+
+        if self.dirty && self.debouncer.should_act() {
+            self.save(now);
+        }
+    }
+
+    fn save(&mut self, now: SystemTime) {
+        self.dirty = false;
+        self.debouncer.bounce();
+        self.entries.retain(|_, entry| !entry.is_expired(now));
+
+        let Some(directory) = &self.directory else {
+            return;
+        };
+
+        let entries: Vec<&StoredEntry> = self.entries.values().collect();
+        let json = match serde_json::to_string(&entries) {
+            Ok(json) => json,
+            Err(err) => {
+                error!("could not serialize nip05 cache: {err}");
+                return;
+            }
+        };
+
+        if let Err(err) = storage::write_file(&directory.file_path, FILE_NAME.to_owned(), &json) {
+            error!("could not save nip05 cache: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pk(byte: u8) -> Pubkey {
+        Pubkey::new([byte; 32])
+    }
+
+    fn result(names: &[(&str, &Pubkey)]) -> Nip05Result {
+        Nip05Result {
+            names: names
+                .iter()
+                .map(|(name, pk)| (name.to_string(), pk.hex()))
+                .collect(),
+            relays: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_nip05() {
+        assert_eq!(
+            parse_nip05("Bob@Example.com"),
+            Some(("bob".to_string(), "example.com".to_string()))
+        );
+        assert_eq!(
+            parse_nip05("_@example.com"),
+            Some(("_".to_string(), "example.com".to_string()))
+        );
+        assert_eq!(parse_nip05("example.com"), None);
+        assert_eq!(parse_nip05("@example.com"), None);
+        assert_eq!(parse_nip05("bob@"), None);
+        assert_eq!(parse_nip05("bob@a@b"), None);
+    }
+
+    #[test]
+    fn test_verify() {
+        let bob = pk(1);
+        let mallory = pk(2);
+
+        assert_eq!(
+            verify(&result(&[("bob", &bob)]), "bob", &bob),
+            Nip05Status::Verified
+        );
+        // names aren't case sensitive
+        assert_eq!(
+            verify(&result(&[("Bob", &bob)]), "bob", &bob),
+            Nip05Status::Verified
+        );
+
+        let impersonated = verify(&result(&[("bob", &bob)]), "bob", &mallory);
+        assert_eq!(impersonated, Nip05Status::Mismatch { actual: Some(bob) });
+        assert!(impersonated.is_impersonation());
+
+        let missing = verify(&result(&[("alice", &bob)]), "bob", &bob);
+        assert_eq!(missing, Nip05Status::Mismatch { actual: None });
+        assert!(!missing.is_impersonation());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let now = SystemTime::now();
+        let entry = |status, age: Duration| StoredEntry {
+            pubkey: pk(1),
+            nip05: "bob@example.com".to_string(),
+            status,
+            checked_at_secs: secs_since_epoch(now - age),
+        };
+
+        let hour = Duration::from_secs(60 * 60);
+        assert!(!entry(Nip05Status::Verified, hour * 2).is_expired(now));
+        assert!(entry(Nip05Status::Verified, hour * 25).is_expired(now));
+        assert!(!entry(Nip05Status::Unreachable, hour / 2).is_expired(now));
+        assert!(entry(Nip05Status::Unreachable, hour * 2).is_expired(now));
+    }
+
+    #[test]
+    fn test_cache_keeps_results() {
+        let mut cache = Nip05Cache::in_memory();
+        let key = (pk(1), "bob@example.com".to_string());
+
+        cache.in_flight.insert(key.clone());
+        cache.sender.send((key, Nip05Status::Verified)).unwrap();
+        cache.poll();
+
+        assert!(cache.in_flight.is_empty());
+        assert_eq!(
+            cache.status(&pk(1), "bob@example.com"),
+            Nip05Status::Verified
+        );
+        assert_eq!(
+            cache.status(&pk(1), "Bob@Example.com"),
+            Nip05Status::Verified
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut cache = Nip05Cache::new(tmp.path().to_path_buf(), egui::Context::default());
+        let key = (pk(1), "bob@example.com".to_string());
+        cache.insert(key, Nip05Status::Verified, SystemTime::now());
+        cache.save(SystemTime::now());
+
+        let mut loaded = Nip05Cache::new(tmp.path().to_path_buf(), egui::Context::default());
+        assert_eq!(
+            loaded.status(&pk(1), "bob@example.com"),
+            Nip05Status::Verified
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use super::context::ContextSelection;
-use crate::{zaps::NoteZapTargetOwned, MediaAction};
+use crate::{emoji::CustomEmoji, zaps::NoteZapTargetOwned, MediaAction};
 use egui::Vec2;
 use enostr::{NoteId, Pubkey};
 
@@ -20,12 +20,18 @@ pub enum NoteAction {
     /// User has clicked the repost button
     Repost(NoteId),
 
+    /// User has voted in a poll
+    Vote(VoteAction),
+
     /// User has clicked a hashtag
     Hashtag(String),
 
     /// User has clicked a profile
     Profile(Pubkey),
 
+    /// User wants to add a relay that was mentioned in a note
+    AddRelay(String),
+
     /// User has clicked a note link
     Note {
         note_id: NoteId,
@@ -59,15 +65,37 @@ impl NoteAction {
 #[derive(Debug, Clone)]
 pub struct ReactAction {
     pub note_id: NoteId,
-    pub content: &'static str,
+    pub content: String,
+    /// The custom emoji (NIP-30) we're reacting with, if any. `content` is
+    /// its `:shortcode:` then.
+    pub emoji: Option<CustomEmoji>,
 }
 
 impl ReactAction {
-    pub const fn new(note_id: NoteId, content: &'static str) -> Self {
-        Self { note_id, content }
+    pub fn new(note_id: NoteId, content: impl Into<String>) -> Self {
+        Self {
+            note_id,
+            content: content.into(),
+            emoji: None,
+        }
+    }
+
+    pub fn custom_emoji(note_id: NoteId, emoji: CustomEmoji) -> Self {
+        Self {
+            note_id,
+            content: emoji.code(),
+            emoji: Some(emoji),
+        }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteAction {
+    pub poll_id: NoteId,
+    /// The ids of the chosen options
+    pub options: Vec<String>,
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum ZapAction {
     Send(ZapTargetAmount),
@@ -19,6 +19,7 @@ pub enum NoteContextSelection {
     CopyNoteJSON,
     Broadcast(BroadcastContext),
     CopyNeventLink,
+    Highlight,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -92,6 +93,8 @@ impl NoteContextSelection {
                     ui.ctx().copy_text(damus_url(bech));
                 }
             }
+            // the app opens its highlight composer for this one
+            NoteContextSelection::Highlight => {}
         }
     }
 }
@@ -1,7 +1,7 @@
 mod action;
 mod context;
 
-pub use action::{NoteAction, ReactAction, ScrollInfo, ZapAction, ZapTargetAmount};
+pub use action::{NoteAction, ReactAction, ScrollInfo, VoteAction, ZapAction, ZapTargetAmount};
 pub use context::{BroadcastContext, ContextSelection, NoteContextSelection};
 
 use crate::clipboard::Clipboard;
@@ -9,6 +9,7 @@ use crate::jobs::MediaJobSender;
 use crate::Accounts;
 use crate::GlobalWallet;
 use crate::Localization;
+use crate::Nip05Cache;
 use crate::UnknownIds;
 use crate::{notecache::NoteCache, zaps::Zaps, Images};
 use enostr::{NoteId, RelayPool};
@@ -31,6 +32,16 @@ pub struct NoteContext<'d> {
     pub jobs: &'d MediaJobSender,
     pub unknown_ids: &'d mut UnknownIds,
     pub clipboard: &'d mut dyn Clipboard,
+    pub nip05_cache: &'d mut Nip05Cache,
+}
+
+/// Drag and drop payload for a note that is dragged out of a timeline,
+/// eg. onto a notebook canvas
+#[derive(Debug, Clone)]
+pub struct DraggedNote {
+    pub note_id: NoteId,
+    /// Relays the note was seen on
+    pub relays: Vec<String>,
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
@@ -214,6 +225,22 @@ pub fn event_tag<'a>(ev: &nostrdb::Note<'a>, name: &str) -> Option<&'a str> {
     })
 }
 
+/// The reason a note gives for its NIP-36 `content-warning` tag, if it has
+/// one. Notes can set the tag without a reason, which gives an empty string.
+pub fn content_warning<'a>(ev: &nostrdb::Note<'a>) -> Option<&'a str> {
+    ev.tags().iter().find_map(|tag| {
+        if tag.count() < 1 || tag.get_str(0)? != "content-warning" {
+            return None;
+        }
+
+        Some(if tag.count() >= 2 {
+            tag.get_str(1).unwrap_or_default().trim()
+        } else {
+            ""
+        })
+    })
+}
+
 /// Temporary way of checking whether a user has sent a reaction.
 /// Should be replaced with nostrdb metadata
 pub fn reaction_sent_id(sender_pk: &enostr::Pubkey, note_reacted_to: &[u8; 32]) -> egui::Id {
@@ -1,9 +1,11 @@
+use crate::translate::{detect_language, Translations};
 use nostrdb::{Note, NoteKey, NoteReply, NoteReplyBuf};
 use std::collections::HashMap;
 
 #[derive(Default)]
 pub struct NoteCache {
     pub cache: HashMap<NoteKey, CachedNote>,
+    pub translations: Translations,
 }
 
 impl NoteCache {
@@ -33,6 +35,8 @@ pub struct CachedNote {
     //reltime: TimeCached<String>,
     pub client: Option<String>,
     pub reply: NoteReplyBuf,
+    /// Our guess at the language of the note's content
+    pub language: Option<&'static str>,
 }
 
 impl CachedNote {
@@ -54,6 +58,7 @@ impl CachedNote {
             client: client.map(|c| c.to_string()),
             //   reltime,
             reply,
+            language: detect_language(note.content()),
         }
     }
 
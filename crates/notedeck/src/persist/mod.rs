@@ -3,6 +3,7 @@ mod settings_handler;
 mod token_handler;
 
 pub use app_size::AppSizeHandler;
+pub use settings_handler::ContentWarningMode;
 pub use settings_handler::Settings;
 pub use settings_handler::SettingsHandler;
 pub use settings_handler::DEFAULT_MAX_HASHTAGS_PER_NOTE;
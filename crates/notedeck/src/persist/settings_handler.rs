@@ -1,5 +1,7 @@
 use crate::{
-    storage::delete_file, timed_serializer::TimedSerializer, DataPath, DataPathType, Directory,
+    storage::{delete_file, KeyringStore},
+    timed_serializer::TimedSerializer,
+    DataPath, DataPathType, Directory, LinkPreviewPolicy, TranslationConfig,
 };
 use egui::ThemePreference;
 use serde::{Deserialize, Serialize};
@@ -8,6 +10,8 @@ use tracing::{error, info};
 const THEME_FILE: &str = "theme.txt";
 const ZOOM_FACTOR_FILE: &str = "zoom_level.json";
 const SETTINGS_FILE: &str = "settings.json";
+/// The translation server's api key is kept in the keyring under this name
+const TRANSLATION_API_KEY: &str = "translate:api_key";
 
 const DEFAULT_THEME: ThemePreference = ThemePreference::Dark;
 const DEFAULT_LOCALE: &str = "en-US";
@@ -19,6 +23,7 @@ pub const DEFAULT_NOTE_BODY_FONT_SIZE: f32 = 13.0;
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 pub const DEFAULT_NOTE_BODY_FONT_SIZE: f32 = 16.0;
 pub const DEFAULT_MAX_HASHTAGS_PER_NOTE: usize = 3;
+const DEFAULT_LINK_PREVIEWS: bool = false;
 
 fn deserialize_theme(serialized_theme: &str) -> Option<ThemePreference> {
     match serialized_theme {
@@ -29,6 +34,18 @@ fn deserialize_theme(serialized_theme: &str) -> Option<ThemePreference> {
     }
 }
 
+/// What to do with notes that carry a content warning
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentWarningMode {
+    /// Hide them behind their warning until clicked
+    #[default]
+    Ask,
+    AlwaysShow,
+    /// Hide them behind their warning without a way to reveal them
+    AlwaysHide,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct Settings {
     pub theme: ThemePreference,
@@ -40,12 +57,31 @@ pub struct Settings {
     #[serde(default = "default_animate_nav_transitions")]
     pub animate_nav_transitions: bool,
     pub max_hashtags_per_note: usize,
+    #[serde(default = "default_link_previews")]
+    pub link_previews: bool,
+    #[serde(default)]
+    pub link_preview_allowed_domains: Vec<String>,
+    #[serde(default)]
+    pub link_preview_blocked_domains: Vec<String>,
+    #[serde(default)]
+    pub content_warnings: ContentWarningMode,
+    /// A LibreTranslate compatible server to translate notes with
+    #[serde(default)]
+    pub translation_endpoint: String,
+    #[serde(default)]
+    pub auto_translate: bool,
+    #[serde(default)]
+    pub markdown: bool,
 }
 
 fn default_animate_nav_transitions() -> bool {
     true
 }
 
+fn default_link_previews() -> bool {
+    DEFAULT_LINK_PREVIEWS
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
@@ -57,14 +93,40 @@ impl Default for Settings {
             note_body_font_size: DEFAULT_NOTE_BODY_FONT_SIZE,
             animate_nav_transitions: default_animate_nav_transitions(),
             max_hashtags_per_note: DEFAULT_MAX_HASHTAGS_PER_NOTE,
+            link_previews: DEFAULT_LINK_PREVIEWS,
+            link_preview_allowed_domains: Vec::new(),
+            link_preview_blocked_domains: Vec::new(),
+            content_warnings: ContentWarningMode::default(),
+            translation_endpoint: String::new(),
+            auto_translate: false,
+            markdown: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn link_preview_policy(&self) -> LinkPreviewPolicy {
+        LinkPreviewPolicy {
+            enabled: self.link_previews,
+            allowed_domains: self.link_preview_allowed_domains.clone(),
+            blocked_domains: self.link_preview_blocked_domains.clone(),
         }
     }
 }
 
+/// Settings that used to be saved in settings.json but don't belong there
+#[derive(Deserialize)]
+struct LegacySettings {
+    #[serde(default)]
+    translation_api_key: String,
+}
+
 pub struct SettingsHandler {
     directory: Directory,
     serializer: TimedSerializer<Settings>,
     current_settings: Option<Settings>,
+    keyring: KeyringStore,
+    translation_api_key: String,
 }
 
 impl SettingsHandler {
@@ -123,10 +185,17 @@ impl SettingsHandler {
             directory,
             serializer,
             current_settings: None,
+            keyring: KeyringStore::default(),
+            translation_api_key: String::new(),
         }
     }
 
     pub fn load(mut self) -> Self {
+        match self.keyring.get_named_secret(TRANSLATION_API_KEY) {
+            Ok(api_key) => self.translation_api_key = api_key.unwrap_or_default(),
+            Err(err) => error!("could not read the translation api key: {err}"),
+        }
+
         if self.migrate_to_settings_file() {
             return self;
         }
@@ -137,6 +206,7 @@ impl SettingsHandler {
                 match serde_json::from_str::<Settings>(&contents_str) {
                     Ok(settings) => {
                         self.current_settings = Some(settings);
+                        self.migrate_secrets(&contents_str);
                     }
                     Err(_) => {
                         error!("Invalid settings format. Using defaults");
@@ -153,6 +223,35 @@ impl SettingsHandler {
         self
     }
 
+    /// Move secrets older versions saved in plain text to the keyring
+    fn migrate_secrets(&mut self, contents: &str) {
+        let Ok(legacy) = serde_json::from_str::<LegacySettings>(contents) else {
+            return;
+        };
+
+        if legacy.translation_api_key.is_empty() {
+            return;
+        }
+
+        info!("moving the translation api key to the keyring");
+        self.set_translation_api_key(legacy.translation_api_key);
+        self.try_save_settings();
+    }
+
+    fn set_translation_api_key(&mut self, api_key: String) {
+        let result = if api_key.is_empty() {
+            self.keyring.remove_named_secret(TRANSLATION_API_KEY)
+        } else {
+            self.keyring
+                .store_named_secret(TRANSLATION_API_KEY, &api_key)
+        };
+
+        if let Err(err) = result {
+            error!("could not save the translation api key: {err}");
+        }
+        self.translation_api_key = api_key;
+    }
+
     pub(crate) fn try_save_settings(&mut self) {
         let settings = self.get_settings_mut().clone();
         self.serializer.try_save(settings);
@@ -211,6 +310,39 @@ impl SettingsHandler {
         self.try_save_settings();
     }
 
+    pub fn set_link_previews(&mut self, value: bool) {
+        self.get_settings_mut().link_previews = value;
+        self.try_save_settings();
+    }
+
+    pub fn set_link_preview_domains(&mut self, allowed: Vec<String>, blocked: Vec<String>) {
+        let settings = self.get_settings_mut();
+        settings.link_preview_allowed_domains = allowed;
+        settings.link_preview_blocked_domains = blocked;
+        self.try_save_settings();
+    }
+
+    pub fn set_content_warnings(&mut self, mode: ContentWarningMode) {
+        self.get_settings_mut().content_warnings = mode;
+        self.try_save_settings();
+    }
+
+    pub fn set_translation_backend(&mut self, endpoint: String, api_key: String) {
+        self.get_settings_mut().translation_endpoint = endpoint;
+        self.set_translation_api_key(api_key);
+        self.try_save_settings();
+    }
+
+    pub fn set_auto_translate(&mut self, value: bool) {
+        self.get_settings_mut().auto_translate = value;
+        self.try_save_settings();
+    }
+
+    pub fn set_markdown(&mut self, value: bool) {
+        self.get_settings_mut().markdown = value;
+        self.try_save_settings();
+    }
+
     pub fn update_batch<F>(&mut self, update_fn: F)
     where
         F: FnOnce(&mut Settings),
@@ -277,4 +409,41 @@ impl SettingsHandler {
             .map(|s| s.max_hashtags_per_note)
             .unwrap_or(DEFAULT_MAX_HASHTAGS_PER_NOTE)
     }
+
+    pub fn link_preview_policy(&self) -> LinkPreviewPolicy {
+        self.current_settings
+            .as_ref()
+            .map(Settings::link_preview_policy)
+            .unwrap_or_default()
+    }
+
+    pub fn content_warnings(&self) -> ContentWarningMode {
+        self.current_settings
+            .as_ref()
+            .map(|s| s.content_warnings)
+            .unwrap_or_default()
+    }
+
+    pub fn translation_config(&self) -> TranslationConfig {
+        let Some(settings) = &self.current_settings else {
+            return TranslationConfig::default();
+        };
+
+        TranslationConfig {
+            endpoint: settings.translation_endpoint.clone(),
+            api_key: self.translation_api_key.clone(),
+            auto_translate: settings.auto_translate,
+        }
+    }
+
+    pub fn translation_api_key(&self) -> &str {
+        &self.translation_api_key
+    }
+
+    pub fn markdown(&self) -> bool {
+        self.current_settings
+            .as_ref()
+            .map(|s| s.markdown)
+            .unwrap_or_default()
+    }
 }
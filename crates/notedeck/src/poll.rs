@@ -0,0 +1,368 @@
+//! NIP-88 polls.
+//!
+//! A poll is a kind 1068 note whose content is the question, with an
+//! `option` tag per answer. Votes are kind 1018 responses that point at the
+//! poll with an `e` tag and name the chosen options with `response` tags.
+//! Each pubkey gets one vote, their latest, and votes made after the poll's
+//! `endsAt` don't count.
+
+use std::collections::HashMap;
+
+use enostr::RelayPool;
+use nostrdb::{Filter, Ndb, Note, Transaction};
+
+pub const POLL_KIND: u32 = 1068;
+pub const POLL_RESPONSE_KIND: u32 = 1018;
+
+/// How many responses to a poll we look at when counting votes
+const MAX_POLL_RESPONSES: i32 = 1000;
+
+/// How many polls we keep a relay subscription for responses open for
+const MAX_WATCHED_POLLS: usize = 16;
+
+const RESPONSES_SUBID: &str = "poll-responses";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PollType {
+    #[default]
+    SingleChoice,
+    MultipleChoice,
+}
+
+impl PollType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PollType::SingleChoice => "singlechoice",
+            PollType::MultipleChoice => "multiplechoice",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PollOption {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poll {
+    pub options: Vec<PollOption>,
+    pub poll_type: PollType,
+    pub ends_at: Option<u64>,
+}
+
+impl Poll {
+    pub fn from_note(note: &Note) -> Option<Self> {
+        if note.kind() != POLL_KIND {
+            return None;
+        }
+
+        let mut options: Vec<PollOption> = Vec::new();
+        let mut poll_type = PollType::SingleChoice;
+        let mut ends_at = None;
+
+        for tag in note.tags() {
+            match tag.get_str(0) {
+                Some("option") => {
+                    let (Some(id), Some(label)) = (tag.get_str(1), tag.get_str(2)) else {
+                        continue;
+                    };
+                    if options.iter().any(|option| option.id == id) {
+                        continue;
+                    }
+                    options.push(PollOption {
+                        id: id.to_owned(),
+                        label: label.to_owned(),
+                    });
+                }
+                Some("polltype") => {
+                    if tag.get_str(1) == Some(PollType::MultipleChoice.as_str()) {
+                        poll_type = PollType::MultipleChoice;
+                    }
+                }
+                Some("endsAt") => {
+                    ends_at = tag.get_str(1).and_then(|s| s.parse::<u64>().ok());
+                }
+                _ => {}
+            }
+        }
+
+        if options.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            options,
+            poll_type,
+            ends_at,
+        })
+    }
+
+    pub fn has_ended(&self, now: u64) -> bool {
+        self.ends_at.is_some_and(|ends_at| now > ends_at)
+    }
+}
+
+/// A kind 1018 response
+#[derive(Debug, Clone)]
+pub struct PollVote<'a> {
+    pub pubkey: &'a [u8; 32],
+    pub created_at: u64,
+    pub responses: Vec<&'a str>,
+}
+
+impl<'a> PollVote<'a> {
+    pub fn from_note(note: &'a Note<'a>) -> Self {
+        let responses = note
+            .tags()
+            .iter()
+            .filter(|tag| tag.get_str(0) == Some("response"))
+            .filter_map(|tag| tag.get_str(1))
+            .collect();
+
+        Self {
+            pubkey: note.pubkey(),
+            created_at: note.created_at(),
+            responses,
+        }
+    }
+}
+
+/// The votes for each of a poll's options, in the poll's order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PollTally {
+    pub counts: Vec<u32>,
+    /// How many pubkeys voted
+    pub voters: u32,
+    /// The options the current account voted for
+    pub my_votes: Vec<String>,
+}
+
+impl PollTally {
+    /// The share of voters that chose each option
+    pub fn fraction(&self, option: usize) -> f32 {
+        if self.voters == 0 {
+            return 0.0;
+        }
+        self.counts.get(option).copied().unwrap_or(0) as f32 / self.voters as f32
+    }
+}
+
+/// Count votes: one per pubkey, the latest one made before the poll ended.
+/// Single choice polls only count the first option of a vote.
+pub fn tally_votes<'a>(
+    poll: &Poll,
+    votes: impl IntoIterator<Item = PollVote<'a>>,
+    me: Option<&[u8; 32]>,
+) -> PollTally {
+    let mut latest: HashMap<&'a [u8; 32], PollVote<'a>> = HashMap::new();
+    for vote in votes {
+        if poll
+            .ends_at
+            .is_some_and(|ends_at| vote.created_at > ends_at)
+        {
+            continue;
+        }
+
+        match latest.get_mut(vote.pubkey) {
+            Some(existing) if existing.created_at < vote.created_at => *existing = vote,
+            Some(_) => {}
+            None => {
+                latest.insert(vote.pubkey, vote);
+            }
+        }
+    }
+
+    let mut tally = PollTally {
+        counts: vec![0; poll.options.len()],
+        ..Default::default()
+    };
+
+    for vote in latest.into_values() {
+        let mut chosen: Vec<usize> = Vec::new();
+        for response in &vote.responses {
+            let Some(index) = poll.options.iter().position(|o| o.id == *response) else {
+                continue;
+            };
+            if !chosen.contains(&index) {
+                chosen.push(index);
+            }
+        }
+
+        if poll.poll_type == PollType::SingleChoice {
+            chosen.truncate(1);
+        }
+
+        if chosen.is_empty() {
+            continue;
+        }
+
+        tally.voters += 1;
+        for &index in &chosen {
+            tally.counts[index] += 1;
+        }
+
+        if me.is_some_and(|me| me == vote.pubkey) {
+            tally.my_votes = chosen
+                .iter()
+                .map(|&index| poll.options[index].id.clone())
+                .collect();
+        }
+    }
+
+    tally
+}
+
+fn responses_filter(poll_id: &[u8; 32]) -> Filter {
+    Filter::new()
+        .kinds([POLL_RESPONSE_KIND as u64])
+        .event(poll_id)
+        .limit(MAX_POLL_RESPONSES as u64)
+        .build()
+}
+
+/// Count the votes for a poll we have locally
+pub fn poll_tally(
+    ndb: &Ndb,
+    txn: &Transaction,
+    poll_id: &[u8; 32],
+    poll: &Poll,
+    me: Option<&[u8; 32]>,
+) -> PollTally {
+    let Ok(results) = ndb.query(txn, &[responses_filter(poll_id)], MAX_POLL_RESPONSES) else {
+        return tally_votes(poll, [], me);
+    };
+
+    let notes: Vec<Note> = results.into_iter().map(|r| r.note).collect();
+    tally_votes(poll, notes.iter().map(PollVote::from_note), me)
+}
+
+/// The polls we ask our relays for responses to, with the frame each was
+/// last shown in
+#[derive(Debug, Clone, Default)]
+pub struct WatchedPolls {
+    polls: Vec<([u8; 32], u64)>,
+}
+
+impl WatchedPolls {
+    /// Ask our relays for the responses to a poll shown in `frame`. We keep
+    /// one subscription for the most recently shown polls, and replace it
+    /// when the set changes: a new poll takes the place of the one shown
+    /// longest ago, unless every poll we watch was shown this frame.
+    /// Returns whether the subscription changed.
+    pub fn watch(&mut self, pool: &mut RelayPool, poll_id: &[u8; 32], frame: u64) -> bool {
+        if let Some((_, shown)) = self.polls.iter_mut().find(|(id, _)| id == poll_id) {
+            *shown = frame;
+            return false;
+        }
+
+        if self.polls.len() < MAX_WATCHED_POLLS {
+            self.polls.push((*poll_id, frame));
+        } else {
+            let Some(oldest) = self.polls.iter_mut().min_by_key(|(_, shown)| *shown) else {
+                return false;
+            };
+            if oldest.1 == frame {
+                return false;
+            }
+            *oldest = (*poll_id, frame);
+        }
+
+        let filters = self
+            .polls
+            .iter()
+            .map(|(id, _)| responses_filter(id))
+            .collect();
+        pool.subscribe(RESPONSES_SUBID.to_owned(), filters);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll(poll_type: PollType, ends_at: Option<u64>) -> Poll {
+        Poll {
+            options: ["a", "b", "c"]
+                .into_iter()
+                .map(|id| PollOption {
+                    id: id.to_owned(),
+                    label: id.to_uppercase(),
+                })
+                .collect(),
+            poll_type,
+            ends_at,
+        }
+    }
+
+    fn vote<'a>(pubkey: &'a [u8; 32], created_at: u64, responses: &[&'a str]) -> PollVote<'a> {
+        PollVote {
+            pubkey,
+            created_at,
+            responses: responses.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_watched_polls() {
+        let mut pool = RelayPool::new();
+        let mut watched = WatchedPolls::default();
+
+        for n in 0..MAX_WATCHED_POLLS as u8 {
+            assert!(watched.watch(&mut pool, &[n; 32], 1));
+        }
+
+        // more polls on screen than we watch doesn't churn the subscription
+        assert!(!watched.watch(&mut pool, &[100; 32], 1));
+        assert!(!watched.watch(&mut pool, &[0; 32], 1));
+
+        // the next frame, polls that weren't shown again make room
+        for n in 1..MAX_WATCHED_POLLS as u8 {
+            assert!(!watched.watch(&mut pool, &[n; 32], 2));
+        }
+        assert!(watched.watch(&mut pool, &[100; 32], 2));
+        assert!(!watched.watch(&mut pool, &[100; 32], 2));
+        assert!(!watched.watch(&mut pool, &[0; 32], 2));
+    }
+
+    #[test]
+    fn test_latest_vote_wins() {
+        let (alice, bob) = ([1; 32], [2; 32]);
+        let votes = [
+            vote(&alice, 10, &["a"]),
+            vote(&bob, 11, &["b"]),
+            vote(&alice, 20, &["c"]),
+            // unknown options don't count
+            vote(&bob, 5, &["z"]),
+        ];
+
+        let tally = tally_votes(&poll(PollType::SingleChoice, None), votes, Some(&alice));
+        assert_eq!(tally.counts, vec![0, 1, 1]);
+        assert_eq!(tally.voters, 2);
+        assert_eq!(tally.my_votes, vec!["c".to_owned()]);
+    }
+
+    #[test]
+    fn test_choices_and_end() {
+        let (alice, bob) = ([1; 32], [2; 32]);
+        let votes = [
+            vote(&alice, 10, &["a", "b", "a"]),
+            vote(&bob, 10, &["c"]),
+            // too late, bob's first vote stands
+            vote(&bob, 200, &["a"]),
+        ];
+
+        let single = tally_votes(
+            &poll(PollType::SingleChoice, Some(100)),
+            votes.clone(),
+            None,
+        );
+        assert_eq!(single.counts, vec![1, 0, 1]);
+
+        let multiple = tally_votes(&poll(PollType::MultipleChoice, Some(100)), votes, None);
+        assert_eq!(multiple.counts, vec![1, 1, 1]);
+        assert_eq!(multiple.voters, 2);
+        assert!((multiple.fraction(0) - 0.5).abs() < f32::EPSILON);
+    }
+}
@@ -0,0 +1,315 @@
+//! NIP-25 reactions.
+//!
+//! A reaction's content can be any emoji, a `:shortcode:` custom emoji or
+//! `+`/empty for a plain like. We group reactions by what they say so we
+//! can show counts, and remember which ones each account uses so the
+//! reaction picker can offer them first.
+
+use std::path::PathBuf;
+
+use enostr::Pubkey;
+use nostrdb::{Filter, Ndb, Note, Transaction};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::emoji::{reaction_emoji, CustomEmoji};
+use crate::{storage, Directory};
+
+const FILE_NAME: &str = "reactions.json";
+
+/// How many distinct reactions we remember per account
+const MAX_HISTORY: usize = 64;
+
+/// How many reactions we look at when grouping a note's reactions
+const MAX_NOTE_REACTIONS: i32 = 500;
+
+/// The reaction we send when the like button is clicked
+pub const DEFAULT_REACTION: &str = "🤙🏻";
+
+/// Offered in the reaction picker before an account has history
+pub const DEFAULT_REACTIONS: &[&str] = &[
+    "🤙🏻", "❤️", "😂", "🔥", "👀", "🫡", "💯", "🙏", "😢", "🤔", "👍", "🎉", "🚀", "⚡", "🫂", "👎",
+];
+
+/// What a reaction's content means for display. `+` and empty content are
+/// plain likes.
+pub fn reaction_display(content: &str) -> &str {
+    match content.trim() {
+        "" | "+" => "❤️",
+        content => content,
+    }
+}
+
+/// Reactions that say the same thing, and how many there are
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReactionCount {
+    pub content: String,
+    pub emoji: Option<CustomEmoji>,
+    pub count: usize,
+}
+
+/// Group reactions by what they say, most common first. Ties keep the order
+/// they were first seen in.
+pub fn group_reactions<'a>(
+    reactions: impl IntoIterator<Item = (&'a str, Option<&'a CustomEmoji>)>,
+) -> Vec<ReactionCount> {
+    let mut groups: Vec<ReactionCount> = Vec::new();
+    for (content, emoji) in reactions {
+        let content = reaction_display(content);
+        if let Some(group) = groups
+            .iter_mut()
+            .find(|group| group.content == content && group.emoji.as_ref() == emoji)
+        {
+            group.count += 1;
+            continue;
+        }
+
+        groups.push(ReactionCount {
+            content: content.to_owned(),
+            emoji: emoji.cloned(),
+            count: 1,
+        });
+    }
+
+    // stable, so ties keep their order
+    groups.sort_by(|a, b| b.count.cmp(&a.count));
+    groups
+}
+
+/// The reactions to a note we have locally, grouped. One reaction per
+/// author, their latest.
+pub fn note_reactions(ndb: &Ndb, txn: &Transaction, note_id: &[u8; 32]) -> Vec<ReactionCount> {
+    let filter = Filter::new()
+        .kinds([7])
+        .event(note_id)
+        .limit(MAX_NOTE_REACTIONS as u64)
+        .build();
+
+    let Ok(results) = ndb.query(txn, &[filter], MAX_NOTE_REACTIONS) else {
+        return Vec::new();
+    };
+
+    let mut notes: Vec<Note> = results.into_iter().map(|r| r.note).collect();
+    notes.sort_by(|a, b| b.created_at().cmp(&a.created_at()));
+
+    let mut seen: Vec<&[u8; 32]> = Vec::new();
+    let mut reactions: Vec<(&str, Option<CustomEmoji>)> = Vec::new();
+    for note in &notes {
+        if seen.contains(&note.pubkey()) {
+            continue;
+        }
+        seen.push(note.pubkey());
+        reactions.push((note.content(), reaction_emoji(note)));
+    }
+
+    group_reactions(
+        reactions
+            .iter()
+            .map(|(content, emoji)| (*content, emoji.as_ref())),
+    )
+}
+
+/// A reaction an account has sent, and how often
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionUse {
+    pub content: String,
+    #[serde(default)]
+    pub emoji: Option<CustomEmoji>,
+    pub count: u32,
+    pub last_used: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AccountReactions {
+    pubkey: Pubkey,
+    uses: Vec<ReactionUse>,
+}
+
+/// The reactions each account has sent, so the picker can offer recent
+/// and frequently used ones. Saved whenever it changes.
+#[derive(Default)]
+pub struct ReactionHistory {
+    directory: Option<Directory>,
+    accounts: Vec<AccountReactions>,
+}
+
+impl ReactionHistory {
+    pub fn new(dir: PathBuf) -> Self {
+        let directory = Directory::new(dir);
+        let accounts = directory
+            .get_file(FILE_NAME.to_owned())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            directory: Some(directory),
+            accounts,
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    fn uses(&self, account: &Pubkey) -> &[ReactionUse] {
+        self.accounts
+            .iter()
+            .find(|a| &a.pubkey == account)
+            .map(|a| a.uses.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The account's reactions, most recently used first
+    pub fn recent(&self, account: &Pubkey, limit: usize) -> Vec<&ReactionUse> {
+        let mut uses: Vec<&ReactionUse> = self.uses(account).iter().collect();
+        uses.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+        uses.truncate(limit);
+        uses
+    }
+
+    /// The account's reactions, most used first
+    pub fn frequent(&self, account: &Pubkey, limit: usize) -> Vec<&ReactionUse> {
+        let mut uses: Vec<&ReactionUse> = self.uses(account).iter().collect();
+        uses.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_used.cmp(&a.last_used)));
+        uses.truncate(limit);
+        uses
+    }
+
+    pub fn record(
+        &mut self,
+        account: &Pubkey,
+        content: &str,
+        emoji: Option<&CustomEmoji>,
+        now: u64,
+    ) {
+        let index = match self.accounts.iter().position(|a| &a.pubkey == account) {
+            Some(index) => index,
+            None => {
+                self.accounts.push(AccountReactions {
+                    pubkey: *account,
+                    uses: Vec::new(),
+                });
+                self.accounts.len() - 1
+            }
+        };
+        let uses = &mut self.accounts[index].uses;
+
+        match uses
+            .iter_mut()
+            .find(|u| u.content == content && u.emoji.as_ref() == emoji)
+        {
+            Some(existing) => {
+                existing.count += 1;
+                existing.last_used = now;
+            }
+            None => {
+                if uses.len() >= MAX_HISTORY {
+                    // forget the least recently used
+                    if let Some(oldest) = uses
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, u)| u.last_used)
+                        .map(|(i, _)| i)
+                    {
+                        uses.swap_remove(oldest);
+                    }
+                }
+
+                uses.push(ReactionUse {
+                    content: content.to_owned(),
+                    emoji: emoji.cloned(),
+                    count: 1,
+                    last_used: now,
+                });
+            }
+        }
+
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(directory) = &self.directory else {
+            return;
+        };
+
+        let json = match serde_json::to_string(&self.accounts) {
+            Ok(json) => json,
+            Err(err) => {
+                error!("could not serialize reaction history: {err}");
+                return;
+            }
+        };
+
+        if let Err(err) = storage::write_file(&directory.file_path, FILE_NAME.to_owned(), &json) {
+            error!("could not save reaction history: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pk(byte: u8) -> Pubkey {
+        Pubkey::new([byte; 32])
+    }
+
+    #[test]
+    fn test_group_reactions() {
+        let blob = CustomEmoji::new("blobcat", "https://example.com/blobcat.png");
+        let groups = group_reactions([
+            ("🔥", None),
+            ("+", None),
+            (":blobcat:", Some(&blob)),
+            ("", None),
+            ("🔥", None),
+            ("🔥", None),
+        ]);
+
+        let summary: Vec<(&str, usize)> = groups
+            .iter()
+            .map(|g| (g.content.as_str(), g.count))
+            .collect();
+        assert_eq!(summary, vec![("🔥", 3), ("❤️", 2), (":blobcat:", 1)]);
+        assert_eq!(groups[2].emoji.as_ref(), Some(&blob));
+    }
+
+    #[test]
+    fn test_history_is_per_account() {
+        let mut history = ReactionHistory::in_memory();
+        history.record(&pk(1), "🔥", None, 10);
+        history.record(&pk(1), "😂", None, 20);
+        history.record(&pk(1), "🔥", None, 5);
+        history.record(&pk(2), "🫡", None, 30);
+
+        let recent: Vec<&str> = history
+            .recent(&pk(1), 8)
+            .iter()
+            .map(|u| u.content.as_str())
+            .collect();
+        assert_eq!(recent, vec!["😂", "🔥"]);
+
+        let frequent: Vec<&str> = history
+            .frequent(&pk(1), 8)
+            .iter()
+            .map(|u| u.content.as_str())
+            .collect();
+        assert_eq!(frequent, vec!["🔥", "😂"]);
+
+        assert_eq!(history.recent(&pk(2), 8).len(), 1);
+        assert!(history.recent(&pk(3), 8).is_empty());
+    }
+
+    #[test]
+    fn test_history_forgets_least_recent() {
+        let mut history = ReactionHistory::in_memory();
+        for i in 0..=MAX_HISTORY as u64 {
+            history.record(&pk(1), &i.to_string(), None, i);
+        }
+
+        let recent = history.recent(&pk(1), usize::MAX);
+        assert_eq!(recent.len(), MAX_HISTORY);
+        assert!(recent.iter().all(|u| u.content != "0"));
+    }
+}
@@ -85,6 +85,9 @@ impl RelayDebugView<'_> {
                                     enostr::ClientMessage::Event { .. } => "Event",
                                     enostr::ClientMessage::Req { .. } => "Req",
                                     enostr::ClientMessage::Close { .. } => "Close",
+                                    enostr::ClientMessage::NegOpen { .. } => "NegOpen",
+                                    enostr::ClientMessage::NegMsg { .. } => "NegMsg",
+                                    enostr::ClientMessage::NegClose { .. } => "NegClose",
                                     enostr::ClientMessage::Raw(_) => "Raw",
                                 };
 
@@ -143,6 +143,26 @@ impl KeyringStore {
             .map_err(Error::from)
     }
 
+    /// Store a secret that isn't a nostr key, like an api token. `name`
+    /// should be namespaced so it can't collide with account pubkeys.
+    pub fn store_named_secret(&self, name: &str, secret: &str) -> Result<()> {
+        self.backend
+            .set(KEYRING_SERVICE_NAME, name, secret)
+            .map_err(Error::from)
+    }
+
+    pub fn get_named_secret(&self, name: &str) -> Result<Option<String>> {
+        self.backend
+            .get(KEYRING_SERVICE_NAME, name)
+            .map_err(Error::from)
+    }
+
+    pub fn remove_named_secret(&self, name: &str) -> Result<()> {
+        self.backend
+            .delete(KEYRING_SERVICE_NAME, name)
+            .map_err(Error::from)
+    }
+
     fn account_id(pubkey: &Pubkey) -> String {
         pubkey.hex()
     }
@@ -190,3 +210,23 @@ impl MemoryKeyringBackend {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_secrets() {
+        let store = KeyringStore::in_memory();
+        assert_eq!(store.get_named_secret("test:token").unwrap(), None);
+
+        store.store_named_secret("test:token", "hunter2").unwrap();
+        assert_eq!(
+            store.get_named_secret("test:token").unwrap(),
+            Some("hunter2".to_string())
+        );
+
+        store.remove_named_secret("test:token").unwrap();
+        assert_eq!(store.get_named_secret("test:token").unwrap(), None);
+    }
+}
@@ -0,0 +1,528 @@
+//! Note translation.
+//!
+//! Notes are translated by a [`Translator`] backend. We ship one that speaks
+//! the LibreTranslate API, which its public instances, self-hosted servers
+//! and local model runners all implement, and anything else can be plugged
+//! in with [`Translations::set_translator`].
+//!
+//! We guess a note's language locally from its script and common words, so
+//! notes are only sent off to be translated when the user asks for it, or
+//! when auto-translate is on and the note clearly isn't in one of their
+//! languages. Translations are kept per note id for the session.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+use ehttp::{Request, Response};
+use serde::Deserialize;
+use tracing::debug;
+
+/// How many translations we keep around
+const MAX_ENTRIES: usize = 512;
+
+/// Notes with fewer letters than this are too short to guess the language of
+const MIN_DETECT_LETTERS: usize = 12;
+
+pub struct TranslationRequest {
+    pub text: String,
+    /// The language the text is in, when we know it
+    pub source: Option<String>,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    pub text: String,
+    /// The language the backend says the text was in
+    pub source: Option<String>,
+}
+
+pub type TranslationCallback = Box<dyn FnOnce(Result<Translation, String>) + Send>;
+
+/// Something that can translate text. `on_done` may be called from any
+/// thread.
+pub trait Translator: Send + Sync {
+    fn translate(&self, request: TranslationRequest, on_done: TranslationCallback);
+}
+
+/// A LibreTranslate compatible `/translate` endpoint
+pub struct LibreTranslate {
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl LibreTranslate {
+    pub fn new(endpoint: impl Into<String>, api_key: Option<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_key,
+        }
+    }
+
+    fn translate_url(&self) -> String {
+        format!("{}/translate", self.endpoint.trim_end_matches('/'))
+    }
+}
+
+impl Translator for LibreTranslate {
+    fn translate(&self, request: TranslationRequest, on_done: TranslationCallback) {
+        let mut body = serde_json::json!({
+            "q": request.text,
+            "source": request.source.as_deref().unwrap_or("auto"),
+            "target": request.target,
+            "format": "text",
+        });
+        if let Some(api_key) = &self.api_key {
+            body["api_key"] = api_key.as_str().into();
+        }
+
+        let mut http_request = Request::post(self.translate_url(), body.to_string().into_bytes());
+        http_request
+            .headers
+            .insert("Content-Type", "application/json");
+
+        ehttp::fetch(http_request, move |response: Result<Response, String>| {
+            let result = match response {
+                Ok(resp) if resp.ok => parse_libretranslate_response(&resp.bytes),
+                Ok(resp) => Err(parse_libretranslate_error(&resp.bytes)
+                    .unwrap_or_else(|| format!("{} {}", resp.status, resp.status_text))),
+                Err(err) => Err(err),
+            };
+            on_done(result);
+        });
+    }
+}
+
+#[derive(Deserialize)]
+struct LibreTranslateResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+    #[serde(rename = "detectedLanguage")]
+    detected_language: Option<DetectedLanguage>,
+}
+
+#[derive(Deserialize)]
+struct DetectedLanguage {
+    language: String,
+}
+
+#[derive(Deserialize)]
+struct LibreTranslateError {
+    error: String,
+}
+
+fn parse_libretranslate_response(bytes: &[u8]) -> Result<Translation, String> {
+    let response: LibreTranslateResponse =
+        serde_json::from_slice(bytes).map_err(|err| format!("bad translation response: {err}"))?;
+
+    Ok(Translation {
+        text: response.translated_text,
+        source: response.detected_language.map(|detected| detected.language),
+    })
+}
+
+fn parse_libretranslate_error(bytes: &[u8]) -> Option<String> {
+    serde_json::from_slice::<LibreTranslateError>(bytes)
+        .ok()
+        .map(|error| error.error)
+}
+
+/// Where notes get translated, as configured in the settings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TranslationConfig {
+    /// A LibreTranslate compatible server. Translation is off when empty.
+    pub endpoint: String,
+    pub api_key: String,
+    /// Translate notes that aren't in one of the user's languages without
+    /// being asked
+    pub auto_translate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranslationState {
+    Pending,
+    Done(Translation),
+    Failed(String),
+}
+
+type TranslationResult = ([u8; 32], Result<Translation, String>);
+
+/// Translates notes in the background and remembers the results by note id
+pub struct Translations {
+    translator: Option<Arc<dyn Translator>>,
+    auto_translate: bool,
+    entries: HashMap<[u8; 32], TranslationState>,
+    /// Note ids in the order they were requested, to forget the oldest
+    order: VecDeque<[u8; 32]>,
+    sender: Sender<TranslationResult>,
+    receiver: Receiver<TranslationResult>,
+}
+
+impl Default for Translations {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            translator: None,
+            auto_translate: false,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl Translations {
+    pub fn set_config(&mut self, config: &TranslationConfig) {
+        let endpoint = config.endpoint.trim();
+        let translator = (!endpoint.is_empty()).then(|| {
+            let api_key = Some(config.api_key.trim())
+                .filter(|key| !key.is_empty())
+                .map(str::to_owned);
+            Arc::new(LibreTranslate::new(endpoint, api_key)) as Arc<dyn Translator>
+        });
+
+        self.set_translator(translator);
+        self.auto_translate = config.auto_translate;
+    }
+
+    /// Use a different backend. Translations we already have are kept,
+    /// failed ones can be tried again.
+    pub fn set_translator(&mut self, translator: Option<Arc<dyn Translator>>) {
+        self.translator = translator;
+        self.entries
+            .retain(|_, state| matches!(state, TranslationState::Done(_)));
+        self.order.retain(|id| self.entries.contains_key(id));
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.translator.is_some()
+    }
+
+    pub fn auto_translate(&self) -> bool {
+        self.auto_translate && self.is_enabled()
+    }
+
+    pub fn get(&self, note_id: &[u8; 32]) -> Option<&TranslationState> {
+        self.entries.get(note_id)
+    }
+
+    /// Start translating a note, unless it's already being translated.
+    /// `ctx` is woken up once it's done.
+    pub fn request(
+        &mut self,
+        ctx: &egui::Context,
+        note_id: &[u8; 32],
+        text: &str,
+        source: Option<&str>,
+        target: &str,
+    ) {
+        let Some(translator) = &self.translator else {
+            return;
+        };
+
+        if matches!(
+            self.entries.get(note_id),
+            Some(TranslationState::Pending | TranslationState::Done(_))
+        ) {
+            return;
+        }
+
+        debug!("translating note {} to {target}", hex::encode(note_id));
+
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        let id = *note_id;
+        translator.translate(
+            TranslationRequest {
+                text: text.to_owned(),
+                source: source.map(str::to_owned),
+                target: target.to_owned(),
+            },
+            Box::new(move |result| {
+                if sender.send((id, result)).is_ok() {
+                    ctx.request_repaint();
+                }
+            }),
+        );
+
+        self.insert(*note_id, TranslationState::Pending);
+    }
+
+    fn insert(&mut self, note_id: [u8; 32], state: TranslationState) {
+        if self.entries.insert(note_id, state).is_none() {
+            self.order.push_back(note_id);
+        }
+
+        while self.order.len() > MAX_ENTRIES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Collect finished translations. Call this once per frame.
+    pub fn poll(&mut self) {
+        while let Ok((note_id, result)) = self.receiver.try_recv() {
+            // forgotten while it was in flight
+            if !self.entries.contains_key(&note_id) {
+                continue;
+            }
+
+            let state = match result {
+                Ok(translation) => TranslationState::Done(translation),
+                Err(err) => {
+                    debug!("could not translate note {}: {err}", hex::encode(note_id));
+                    TranslationState::Failed(err)
+                }
+            };
+            self.entries.insert(note_id, state);
+        }
+    }
+}
+
+/// Our guess at the language of some text, as a two letter code, when we're
+/// fairly sure. Links, mentions and hashtags are ignored.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let words: Vec<&str> = text
+        .split_whitespace()
+        .filter(|word| {
+            !(word.starts_with("http://")
+                || word.starts_with("https://")
+                || word.starts_with("nostr:")
+                || word.starts_with('#')
+                || word.starts_with('@'))
+        })
+        .collect();
+
+    let mut scripts: HashMap<&'static str, usize> = HashMap::new();
+    let mut letters = 0;
+    for c in words.iter().flat_map(|word| word.chars()) {
+        if !c.is_alphabetic() {
+            continue;
+        }
+        letters += 1;
+        if let Some(script) = script_language(c) {
+            *scripts.entry(script).or_default() += 1;
+        }
+    }
+
+    if letters < MIN_DETECT_LETTERS {
+        return None;
+    }
+
+    // any kana means japanese, which is mostly written with han characters
+    if scripts.get("ja").is_some_and(|&kana| kana * 10 >= letters) {
+        return Some("ja");
+    }
+
+    let (script, count) = scripts.into_iter().max_by_key(|(_, count)| *count)?;
+    if count * 2 < letters {
+        return None;
+    }
+
+    if script == "latin" {
+        latin_language(&words)
+    } else {
+        Some(script)
+    }
+}
+
+/// The language a character's script is mostly used for, or `latin`
+fn script_language(c: char) -> Option<&'static str> {
+    Some(match c as u32 {
+        0x0041..=0x024F => "latin",
+        0x0370..=0x03FF => "el",
+        0x0400..=0x04FF => "ru",
+        0x0590..=0x05FF => "he",
+        0x0600..=0x06FF => "ar",
+        0x0900..=0x097F => "hi",
+        0x0E00..=0x0E7F => "th",
+        0x3040..=0x30FF => "ja",
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => "ko",
+        0x4E00..=0x9FFF => "zh",
+        _ => return None,
+    })
+}
+
+const LATIN_STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "of", "to", "that", "it", "you", "for", "with", "this",
+            "was", "have", "not", "what",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "que", "y", "es", "por", "con", "para", "una", "del", "pero", "lo",
+            "se", "muy", "como",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "les", "des", "et", "est", "une", "pour", "pas", "dans", "je", "vous", "du",
+            "ce", "qui", "sur", "avec",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ich", "ein", "eine", "zu", "mit", "den",
+            "auf", "sie", "auch", "wir",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "os", "que", "é", "do", "da", "em", "um", "uma", "não", "para", "com", "mas", "você",
+            "muito", "isso", "como",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "di", "che", "è", "un", "una", "per", "non", "del", "della", "sono", "con", "mi",
+            "ma", "questo", "anche",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "het", "een", "en", "van", "is", "dat", "niet", "ik", "je", "op", "te", "zijn", "met",
+            "voor", "maar", "ook",
+        ],
+    ),
+];
+
+/// Tell latin script languages apart by their most common words
+fn latin_language(words: &[&str]) -> Option<&'static str> {
+    let words: Vec<String> = words
+        .iter()
+        .map(|word| {
+            word.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .collect();
+
+    let mut scores: Vec<(&'static str, usize)> = LATIN_STOPWORDS
+        .iter()
+        .map(|(language, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|word| stopwords.contains(&word.as_str()))
+                .count();
+            (*language, hits)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let (language, best) = scores[0];
+    let runner_up = scores[1].1;
+    (best >= 2 && best > runner_up).then_some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("This is what I have been saying for years, and it is not over"),
+            Some("en")
+        );
+        assert_eq!(
+            detect_language("Ich glaube, das ist nicht die Antwort, die wir auf Nostr wollen"),
+            Some("de")
+        );
+        assert_eq!(
+            detect_language("Hoy es un buen día para aprender algo nuevo con los amigos"),
+            Some("es")
+        );
+        assert_eq!(
+            detect_language("今日はとても良い天気ですね。散歩に行きましょう"),
+            Some("ja")
+        );
+        assert_eq!(
+            detect_language("Сегодня отличная погода для прогулки"),
+            Some("ru")
+        );
+
+        // links and hashtags aren't words
+        assert_eq!(
+            detect_language("https://example.com/some/long/path #nostr #bitcoin gm"),
+            None
+        );
+        assert_eq!(detect_language("gm"), None);
+    }
+
+    #[test]
+    fn test_parse_libretranslate() {
+        let translation = parse_libretranslate_response(
+            br#"{"translatedText": "hello world", "detectedLanguage": {"confidence": 90, "language": "de"}}"#,
+        )
+        .unwrap();
+        assert_eq!(translation.text, "hello world");
+        assert_eq!(translation.source.as_deref(), Some("de"));
+
+        assert_eq!(
+            parse_libretranslate_error(br#"{"error": "Invalid API key"}"#).as_deref(),
+            Some("Invalid API key")
+        );
+        assert!(parse_libretranslate_response(b"<html>").is_err());
+    }
+
+    /// Stands in for a translation server, answering right away
+    struct Shouty;
+
+    impl Translator for Shouty {
+        fn translate(&self, request: TranslationRequest, on_done: TranslationCallback) {
+            if request.text.is_empty() {
+                on_done(Err("nothing to translate".to_owned()));
+                return;
+            }
+
+            on_done(Ok(Translation {
+                text: request.text.to_uppercase(),
+                source: request.source,
+            }));
+        }
+    }
+
+    #[test]
+    fn test_translations_cache_by_note() {
+        let ctx = egui::Context::default();
+        let mut translations = Translations::default();
+        assert!(!translations.is_enabled());
+
+        translations.request(&ctx, &[1; 32], "hallo", Some("de"), "en");
+        assert_eq!(translations.get(&[1; 32]), None);
+
+        translations.set_translator(Some(Arc::new(Shouty)));
+        translations.request(&ctx, &[1; 32], "hallo", Some("de"), "en");
+        translations.request(&ctx, &[2; 32], "", None, "en");
+        assert_eq!(translations.get(&[1; 32]), Some(&TranslationState::Pending));
+
+        translations.poll();
+        assert_eq!(
+            translations.get(&[1; 32]),
+            Some(&TranslationState::Done(Translation {
+                text: "HALLO".to_owned(),
+                source: Some("de".to_owned()),
+            }))
+        );
+        assert!(matches!(
+            translations.get(&[2; 32]),
+            Some(TranslationState::Failed(_))
+        ));
+
+        // a new backend gets to try the failed ones again
+        translations.set_translator(Some(Arc::new(Shouty)));
+        assert!(translations.get(&[1; 32]).is_some());
+        assert_eq!(translations.get(&[2; 32]), None);
+    }
+}
@@ -192,6 +192,7 @@ impl UnknownIds {
         match unk_id {
             UnknownId::Pubkey(pk) => self.add_pubkey_if_missing(ndb, txn, pk),
             UnknownId::Id(note_id) => self.add_note_id_if_missing(ndb, txn, note_id.bytes()),
+            UnknownId::Address(address) => self.add_address_if_missing(ndb, txn, address),
         }
     }
 
@@ -222,12 +223,74 @@ impl UnknownIds {
         self.ids.entry(unknown_id).or_default();
         self.mark_updated();
     }
+
+    pub fn add_address_if_missing(&mut self, ndb: &Ndb, txn: &Transaction, address: &NoteAddress) {
+        // we already have a version of this note, skip
+        if address.get_note(ndb, txn).is_some() {
+            return;
+        }
+
+        let unknown_id = UnknownId::Address(address.clone());
+        if self.ids.contains_key(&unknown_id) {
+            return;
+        }
+        self.ids.entry(unknown_id).or_default();
+        self.mark_updated();
+    }
+}
+
+/// The address of a replaceable note: its kind, author and `d` tag. This
+/// is what naddr mentions point to, since the note's id changes with every
+/// edit.
+#[derive(Hash, Clone, PartialEq, Eq, Debug)]
+pub struct NoteAddress {
+    pub kind: u32,
+    pub pubkey: Pubkey,
+    pub identifier: String,
 }
 
-#[derive(Hash, Clone, Copy, PartialEq, Eq, Debug)]
+impl NoteAddress {
+    pub fn new(kind: u32, pubkey: Pubkey, identifier: String) -> Self {
+        Self {
+            kind,
+            pubkey,
+            identifier,
+        }
+    }
+
+    /// Parse an `a` tag coordinate, eg. `30030:<pubkey hex>:<d tag>`
+    pub fn parse(coordinate: &str) -> Option<Self> {
+        let mut parts = coordinate.splitn(3, ':');
+        let kind = parts.next()?.parse().ok()?;
+        let pubkey = Pubkey::from_hex(parts.next()?).ok()?;
+        let identifier = parts.next()?.to_owned();
+        Some(Self::new(kind, pubkey, identifier))
+    }
+
+    pub fn filter(&self) -> Filter {
+        Filter::new()
+            .kinds([self.kind as u64])
+            .authors([self.pubkey.bytes()])
+            .tags([self.identifier.as_str()], 'd')
+            .limit(1)
+            .build()
+    }
+
+    /// The latest version of the note that we have
+    pub fn get_note<'a>(&self, ndb: &Ndb, txn: &'a Transaction) -> Option<Note<'a>> {
+        ndb.query(txn, &[self.filter()], 1)
+            .ok()?
+            .into_iter()
+            .next()
+            .map(|qr| qr.note)
+    }
+}
+
+#[derive(Hash, Clone, PartialEq, Eq, Debug)]
 pub enum UnknownId {
     Pubkey(Pubkey),
     Id(NoteId),
+    Address(NoteAddress),
 }
 
 impl UnknownId {
@@ -244,6 +307,13 @@ impl UnknownId {
             _ => None,
         }
     }
+
+    pub fn is_address(&self) -> Option<&NoteAddress> {
+        match self {
+            UnknownId::Address(address) => Some(address),
+            _ => None,
+        }
+    }
 }
 
 /// Look for missing notes in various parts of notes that we see:
@@ -337,6 +407,22 @@ pub fn get_unknown_note_ids<'a>(
                     }
                 }
             }
+            Mention::Addr(addr) => {
+                let address = NoteAddress::new(
+                    addr.kind(),
+                    Pubkey::new(*addr.pubkey()),
+                    addr.identifier().to_owned(),
+                );
+                if address.get_note(ndb, txn).is_none() {
+                    let relays = addr
+                        .relays_iter()
+                        .filter_map(|s| RelayUrl::parse(s).ok())
+                        .collect::<HashSet<RelayUrl>>();
+                    ids.entry(UnknownId::Address(address))
+                        .or_default()
+                        .extend(relays);
+                }
+            }
             Mention::Note(note) => match ndb.get_note_by_id(txn, note.id()) {
                 Err(_) => {
                     ids.entry(UnknownId::Id(NoteId::new(*note.id())))
@@ -381,5 +467,10 @@ fn get_unknown_ids_filter(ids: &[&UnknownId]) -> Option<Vec<Filter>> {
         filters.push(Filter::new().ids(note_ids).build());
     }
 
+    filters.extend(
+        ids.iter()
+            .flat_map(|id| id.is_address().map(NoteAddress::filter)),
+    );
+
     Some(filters)
 }
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 use tracing::trace;
 use url::Url;
 
-use crate::{Error, MediaCacheType};
+use crate::{media::video, Error, MediaCacheType};
 
 const FILE_NAME: &str = "urls.bin";
 const SAVE_INTERVAL: Duration = Duration::from_secs(60);
@@ -531,13 +531,20 @@ impl SupportedMimeType {
 fn mime_to_cache_type(mime: &Mime) -> MediaCacheType {
     if *mime == mime_guess::mime::IMAGE_GIF {
         MediaCacheType::Gif
+    } else if mime.type_() == mime_guess::mime::VIDEO {
+        MediaCacheType::Video
+    } else if mime.type_() == mime_guess::mime::AUDIO {
+        MediaCacheType::Audio
     } else {
         MediaCacheType::Image
     }
 }
 
 fn is_mime_supported(mime: &mime_guess::Mime) -> bool {
-    mime.type_() == mime_guess::mime::IMAGE
+    let type_ = mime.type_();
+    type_ == mime_guess::mime::IMAGE
+        || (video::playback_supported()
+            && (type_ == mime_guess::mime::VIDEO || type_ == mime_guess::mime::AUDIO))
 }
 
 #[profiling::function]
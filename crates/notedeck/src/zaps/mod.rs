@@ -7,6 +7,8 @@ pub use cache::{
     AnyZapState, NoteZapTarget, NoteZapTargetOwned, ZapTarget, ZapTargetOwned, ZappingError, Zaps,
 };
 
+pub use networking::generate_endpoint_url;
+
 pub use default_zap::{
     get_current_default_msats, DefaultZapError, DefaultZapMsats, PendingDefaultZapState,
     UserZapMsats,
@@ -345,7 +345,7 @@ async fn fetch_ln_invoice(req: &Url) -> Result<LNInvoice, ZapError> {
     tokio::task::block_in_place(|| promise.block_and_take())
 }
 
-fn generate_endpoint_url(lud16: &str) -> Result<Url, ZapError> {
+pub fn generate_endpoint_url(lud16: &str) -> Result<Url, ZapError> {
     let (user, domain, use_http) = {
         let mut split = lud16.split('@');
         let user = split
@@ -7,8 +7,8 @@ use bitflags::bitflags;
 #[cfg(not(target_os = "ios"))]
 use eframe::CreationContext;
 use egui::{
-    vec2, Color32, CornerRadius, Label, Layout, Margin, Rect, RichText, Sense, ThemePreference, Ui,
-    Widget,
+    vec2, Color32, CornerRadius, Label, Layout, Margin, Rect, RichText, Sense, TextBuffer,
+    ThemePreference, Ui, Widget,
 };
 use egui_extras::{Size, StripBuilder};
 use egui_nav::RouteResponse;
@@ -18,6 +18,7 @@ use notedeck::fonts::get_font_size;
 use notedeck::name::get_display_name;
 use notedeck::ui::is_compiled_as_mobile;
 use notedeck::AppResponse;
+use notedeck::DraggedNote;
 use notedeck::DrawerRouter;
 use notedeck::Error;
 use notedeck::SoftKeyboardContext;
@@ -297,6 +298,12 @@ impl Chrome {
                     .inner
             }
             ChromeRoute::App => {
+                if !matches!(self.apps[self.active as usize], NotedeckApp::Columns(_)) {
+                    if let Some(columns) = self.get_columns_app() {
+                        columns.update_in_background(app_ctx, ui.ctx());
+                    }
+                }
+
                 let resp = self.apps[self.active as usize].update(app_ctx, ui);
 
                 if let Some(action) = resp.action {
@@ -506,6 +513,37 @@ fn chrome_handle_app_action(
             chrome.toggle();
         }
 
+        AppAction::Compose { content, reply_to } => {
+            chrome.switch_to_columns();
+            let Some(columns) = chrome.get_columns_app() else {
+                return;
+            };
+
+            let (draft, route) = if let Some(reply_to) = reply_to {
+                (
+                    columns.drafts.reply_mut(reply_to.bytes()),
+                    notedeck_columns::Route::Reply(reply_to),
+                )
+            } else {
+                (
+                    columns.drafts.compose_mut(),
+                    notedeck_columns::Route::ComposeNote,
+                )
+            };
+
+            let Some(cols) = columns
+                .decks_cache
+                .active_columns_mut(ctx.i18n, ctx.accounts)
+            else {
+                tracing::error!("no active columns to compose a note in");
+                return;
+            };
+
+            draft.clear();
+            draft.buffer.insert_text(&content, 0);
+            cols.get_selected_router().route_to(route);
+        }
+
         AppAction::Note(note_action) => {
             chrome.switch_to_columns();
             let Some(columns) = chrome.get_columns_app() else {
@@ -883,6 +921,14 @@ fn topdown_sidebar(
                         chrome.active = i as i32;
                         chrome.nav.close();
                     }
+
+                    // hovering an app while dragging a note switches to
+                    // it, so that the note can be dropped there
+                    if resp.contains_pointer()
+                        && egui::DragAndDrop::has_payload_of_type::<DraggedNote>(ui.ctx())
+                    {
+                        chrome.active = i as i32;
+                    }
                 })
             });
     }
@@ -0,0 +1,433 @@
+//! Things we can do to the node, as opposed to just looking at it. Every
+//! action is shown as the exact commando call it makes and has to be
+//! confirmed before it runs.
+
+use crate::channels::Channels;
+use crate::event::LoadingState;
+use crate::rune::Rune;
+use crate::ui;
+use serde_json::{Value, json};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    OpenChannel {
+        /// node id of the peer
+        id: String,
+        amount_sat: u64,
+    },
+    CloseChannel {
+        /// short channel id
+        id: String,
+        unilateral: bool,
+    },
+    SetFees {
+        /// short channel id
+        id: String,
+        base_msat: u64,
+        ppm: u32,
+    },
+    CreateInvoice {
+        amount_msat: Option<u64>,
+        label: String,
+        description: String,
+    },
+    PayInvoice {
+        bolt11: String,
+    },
+}
+
+impl Action {
+    pub fn method(&self) -> &'static str {
+        match self {
+            Action::OpenChannel { .. } => "fundchannel",
+            Action::CloseChannel { .. } => "close",
+            Action::SetFees { .. } => "setchannel",
+            Action::CreateInvoice { .. } => "invoice",
+            Action::PayInvoice { .. } => "pay",
+        }
+    }
+
+    pub fn params(&self) -> Value {
+        match self {
+            Action::OpenChannel { id, amount_sat } => json!({"id": id, "amount": amount_sat}),
+
+            // 0 waits for the peer forever, anything else force closes once
+            // that many seconds have passed
+            Action::CloseChannel { id, unilateral } => json!({
+                "id": id,
+                "unilateraltimeout": if *unilateral { 1 } else { 0 },
+            }),
+
+            Action::SetFees { id, base_msat, ppm } => {
+                json!({"id": id, "feebase": base_msat, "feeppm": ppm})
+            }
+
+            Action::CreateInvoice {
+                amount_msat,
+                label,
+                description,
+            } => json!({
+                "amount_msat": amount_msat.map(Value::from).unwrap_or_else(|| json!("any")),
+                "label": label,
+                "description": description,
+            }),
+
+            Action::PayInvoice { bolt11 } => json!({"bolt11": bolt11}),
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Action::OpenChannel { .. } => "Open channel",
+            Action::CloseChannel {
+                unilateral: false, ..
+            } => "Close channel",
+            Action::CloseChannel {
+                unilateral: true, ..
+            } => "Force close channel",
+            Action::SetFees { .. } => "Set channel fees",
+            Action::CreateInvoice { .. } => "Create invoice",
+            Action::PayInvoice { .. } => "Pay invoice",
+        }
+    }
+}
+
+/// What came back from running an action
+pub struct ActionResult {
+    pub action: Action,
+    pub result: Result<Value, lnsocket::Error>,
+}
+
+#[derive(Default)]
+pub struct ActionForm {
+    open_id: String,
+    open_amount_sat: String,
+    channel_id: String,
+    fee_base_msat: String,
+    fee_ppm: String,
+    invoice_amount_sat: String,
+    invoice_description: String,
+    bolt11: String,
+    error: Option<String>,
+}
+
+fn parse_amount<T: std::str::FromStr>(field: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .replace([',', '_'], "")
+        .parse()
+        .map_err(|_| format!("invalid {field}: {value}"))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// A button that is disabled when the rune can't call `method`
+fn action_button(ui: &mut egui::Ui, rune: Option<&Rune>, method: &str, text: &str) -> bool {
+    let allowed = rune.is_none_or(|rune| rune.allows_method(method));
+    let resp = ui.add_enabled(allowed, egui::Button::new(text));
+    if allowed {
+        resp.clicked()
+    } else {
+        resp.on_disabled_hover_text(format!("Your rune doesn't allow {method}"));
+        false
+    }
+}
+
+/// Forms for each action. Returns the action to confirm when one is
+/// submitted.
+pub fn actions_ui(
+    ui: &mut egui::Ui,
+    form: &mut ActionForm,
+    rune: Option<&Rune>,
+    channels: &LoadingState<Channels, lnsocket::Error>,
+    last_result: Option<&ActionResult>,
+) -> Option<Action> {
+    let mut action: Option<Result<Action, String>> = None;
+
+    egui::CollapsingHeader::new("Actions").show(ui, |ui| {
+        ui.strong("Open channel");
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut form.open_id).hint_text("node id"));
+            ui.add(
+                egui::TextEdit::singleline(&mut form.open_amount_sat)
+                    .hint_text("amount (sat)")
+                    .desired_width(100.0),
+            );
+            if action_button(ui, rune, "fundchannel", "Open") {
+                action = Some(
+                    parse_amount("amount", &form.open_amount_sat).map(|amount_sat| {
+                        Action::OpenChannel {
+                            id: form.open_id.trim().to_string(),
+                            amount_sat,
+                        }
+                    }),
+                );
+            }
+        });
+
+        ui.add_space(8.0);
+        ui.strong("Channel");
+        ui.horizontal(|ui| {
+            let selected_text = if form.channel_id.is_empty() {
+                "Select a channel".to_string()
+            } else {
+                form.channel_id.clone()
+            };
+
+            egui::ComboBox::from_id_salt("clndash-action-channel")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    let LoadingState::Loaded(channels) = channels else {
+                        return;
+                    };
+
+                    for channel in &channels.channels {
+                        let id = &channel.original.short_channel_id;
+                        ui.selectable_value(&mut form.channel_id, id.clone(), id)
+                            .on_hover_text(format!(
+                                "Capacity: {} sats",
+                                ui::human_sat(channel.original.total_msat)
+                            ));
+                    }
+                });
+
+            let has_channel = !form.channel_id.is_empty();
+            ui.add_enabled_ui(has_channel, |ui| {
+                if action_button(ui, rune, "close", "Close") {
+                    action = Some(Ok(Action::CloseChannel {
+                        id: form.channel_id.clone(),
+                        unilateral: false,
+                    }));
+                }
+
+                if action_button(ui, rune, "close", "Force close") {
+                    action = Some(Ok(Action::CloseChannel {
+                        id: form.channel_id.clone(),
+                        unilateral: true,
+                    }));
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut form.fee_base_msat)
+                    .hint_text("base fee (msat)")
+                    .desired_width(120.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut form.fee_ppm)
+                    .hint_text("fee rate (ppm)")
+                    .desired_width(120.0),
+            );
+
+            ui.add_enabled_ui(!form.channel_id.is_empty(), |ui| {
+                if action_button(ui, rune, "setchannel", "Set fees") {
+                    action = Some(parse_fees(form));
+                }
+            });
+        });
+
+        ui.add_space(8.0);
+        ui.strong("Create invoice");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut form.invoice_amount_sat)
+                    .hint_text("amount (sat), empty for any")
+                    .desired_width(180.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut form.invoice_description).hint_text("description"),
+            );
+            if action_button(ui, rune, "invoice", "Create") {
+                action = Some(parse_invoice(form));
+            }
+        });
+
+        ui.add_space(8.0);
+        ui.strong("Pay invoice");
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut form.bolt11).hint_text("bolt11 invoice"));
+            if action_button(ui, rune, "pay", "Pay") {
+                action = Some(Ok(Action::PayInvoice {
+                    bolt11: form.bolt11.trim().to_string(),
+                }));
+            }
+        });
+
+        if let Some(error) = &form.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        if let Some(result) = last_result {
+            ui.add_space(8.0);
+            action_result_ui(ui, result);
+        }
+    });
+
+    match action? {
+        Ok(action) => {
+            form.error = None;
+            Some(action)
+        }
+        Err(err) => {
+            form.error = Some(err);
+            None
+        }
+    }
+}
+
+fn parse_fees(form: &ActionForm) -> Result<Action, String> {
+    Ok(Action::SetFees {
+        id: form.channel_id.clone(),
+        base_msat: parse_amount("base fee", &form.fee_base_msat)?,
+        ppm: parse_amount("fee rate", &form.fee_ppm)?,
+    })
+}
+
+fn parse_invoice(form: &ActionForm) -> Result<Action, String> {
+    let amount_msat = if form.invoice_amount_sat.trim().is_empty() {
+        None
+    } else {
+        let sats: u64 = parse_amount("amount", &form.invoice_amount_sat)?;
+        Some(sats * 1000)
+    };
+
+    Ok(Action::CreateInvoice {
+        amount_msat,
+        label: format!("clndash-{}", unix_now()),
+        description: form.invoice_description.trim().to_string(),
+    })
+}
+
+fn action_result_ui(ui: &mut egui::Ui, result: &ActionResult) {
+    match &result.result {
+        Ok(value) => {
+            ui.label(format!("{} succeeded", result.action.title()));
+
+            // a fresh invoice is only useful if you can copy it
+            if let Some(bolt11) = value["bolt11"].as_str() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Label::new(bolt11).truncate());
+                    if ui.button("Copy").clicked() {
+                        ui.ctx().copy_text(bolt11.to_owned());
+                    }
+                });
+            }
+        }
+
+        Err(err) => {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("{} failed: {err}", result.action.title()),
+            );
+        }
+    }
+}
+
+/// Shows the exact call an action will make. Returns `Some(true)` when
+/// confirmed and `Some(false)` when cancelled.
+pub fn confirm_ui(ctx: &egui::Context, action: &Action) -> Option<bool> {
+    let mut confirmed = None;
+
+    let resp = egui::Modal::new(egui::Id::new("clndash-confirm-action")).show(ctx, |ui| {
+        ui.set_max_width(420.0);
+        ui.heading(action.title());
+        ui.label("This will run the following commando call on your node:");
+
+        let params = serde_json::to_string_pretty(&action.params()).unwrap_or_default();
+        egui::Frame::new()
+            .fill(ui.visuals().extreme_bg_color)
+            .inner_margin(egui::Margin::same(8))
+            .corner_radius(4.0)
+            .show(ui, |ui| {
+                ui.monospace(action.method());
+                ui.add(egui::Label::new(egui::RichText::new(params).monospace()).wrap());
+            });
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui.button("Confirm").clicked() {
+                confirmed = Some(true);
+            }
+            if ui.button("Cancel").clicked() {
+                confirmed = Some(false);
+            }
+        });
+    });
+
+    if resp.should_close() && confirmed.is_none() {
+        return Some(false);
+    }
+
+    confirmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_calls() {
+        let close = Action::CloseChannel {
+            id: "1x2x3".to_string(),
+            unilateral: true,
+        };
+        assert_eq!(close.method(), "close");
+        assert_eq!(
+            close.params(),
+            json!({"id": "1x2x3", "unilateraltimeout": 1})
+        );
+
+        let invoice = Action::CreateInvoice {
+            amount_msat: None,
+            label: "l".to_string(),
+            description: "d".to_string(),
+        };
+        assert_eq!(invoice.params()["amount_msat"], "any");
+
+        let fees = Action::SetFees {
+            id: "1x2x3".to_string(),
+            base_msat: 1000,
+            ppm: 10,
+        };
+        assert_eq!(fees.method(), "setchannel");
+        assert_eq!(
+            fees.params(),
+            json!({"id": "1x2x3", "feebase": 1000, "feeppm": 10})
+        );
+    }
+
+    #[test]
+    fn test_parse_forms() {
+        let mut form = ActionForm {
+            channel_id: "1x2x3".to_string(),
+            fee_base_msat: "1,000".to_string(),
+            fee_ppm: "nope".to_string(),
+            invoice_amount_sat: "21".to_string(),
+            ..Default::default()
+        };
+
+        assert!(parse_fees(&form).is_err());
+        form.fee_ppm = "100".to_string();
+        assert_eq!(
+            parse_fees(&form),
+            Ok(Action::SetFees {
+                id: "1x2x3".to_string(),
+                base_msat: 1000,
+                ppm: 100,
+            })
+        );
+
+        let Ok(Action::CreateInvoice { amount_msat, .. }) = parse_invoice(&form) else {
+            panic!("expected an invoice");
+        };
+        assert_eq!(amount_msat, Some(21_000));
+    }
+}
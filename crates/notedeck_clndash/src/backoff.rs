@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(60 * 5);
+const MAX_EXPONENT: u32 = 16;
+
+/// Exponential backoff for reconnecting to a node after we lose it
+#[derive(Default)]
+pub struct Backoff {
+    attempts: u32,
+    next_attempt: Option<Instant>,
+}
+
+impl Backoff {
+    fn delay(attempts: u32) -> Duration {
+        BASE_DELAY
+            .saturating_mul(1 << attempts.min(MAX_EXPONENT))
+            .min(MAX_DELAY)
+    }
+
+    /// Schedule another attempt. Each attempt waits twice as long as the
+    /// last one, up to [`MAX_DELAY`].
+    pub fn schedule(&mut self, now: Instant) -> Duration {
+        let delay = Self::delay(self.attempts);
+        self.attempts = self.attempts.saturating_add(1);
+        self.next_attempt = Some(now + delay);
+        delay
+    }
+
+    /// We're connected again, or gave up on this node
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// How long until the next scheduled attempt, if there is one
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.next_attempt
+            .map(|next| next.saturating_duration_since(now))
+    }
+
+    /// Returns true once when the scheduled attempt is due
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        if self.next_attempt.is_some_and(|next| next <= now) {
+            self.next_attempt = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_until_max() {
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+
+        assert_eq!(backoff.schedule(now), Duration::from_secs(1));
+        assert_eq!(backoff.schedule(now), Duration::from_secs(2));
+        assert_eq!(backoff.schedule(now), Duration::from_secs(4));
+
+        for _ in 0..40 {
+            backoff.schedule(now);
+        }
+        assert_eq!(backoff.schedule(now), MAX_DELAY);
+
+        backoff.reset();
+        assert_eq!(backoff.remaining(now), None);
+        assert_eq!(backoff.schedule(now), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_take_due() {
+        let now = Instant::now();
+        let mut backoff = Backoff::default();
+        assert!(!backoff.take_due(now));
+
+        let delay = backoff.schedule(now);
+        assert!(!backoff.take_due(now));
+        assert_eq!(backoff.remaining(now), Some(delay));
+
+        assert!(backoff.take_due(now + delay));
+        // only once
+        assert!(!backoff.take_due(now + delay));
+    }
+}
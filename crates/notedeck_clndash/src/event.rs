@@ -1,6 +1,7 @@
+use crate::actions::{Action, ActionResult};
 use crate::channels::Channels;
+use crate::forwards::Forward;
 use crate::invoice::Invoice;
-use serde::Serialize;
 use serde_json::Value;
 
 pub enum ConnectionState {
@@ -44,18 +45,13 @@ impl<T, E> LoadingState<T, E> {
     */
 }
 
-#[derive(Serialize, Debug, Clone)]
-pub struct _WaitRequest {
-    pub indexname: String,
-    pub subsystem: String,
-    pub nextvalue: u64,
-}
-
 #[derive(Clone, Debug)]
 pub enum Request {
     GetInfo,
     ListPeerChannels,
     PaidInvoices(u32),
+    /// Run a confirmed action
+    Action(Action),
 }
 
 /// Responses from the socket
@@ -63,6 +59,11 @@ pub enum ClnResponse {
     GetInfo(Value),
     ListPeerChannels(Result<Channels, lnsocket::Error>),
     PaidInvoices(Result<Vec<Invoice>, lnsocket::Error>),
+    /// Streamed from `waitanyinvoice` as invoices get paid
+    InvoicePaid(Invoice),
+    /// Streamed as forwards settle
+    Forwards(Vec<Forward>),
+    Action(ActionResult),
 }
 
 pub enum Event {
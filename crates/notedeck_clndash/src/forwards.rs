@@ -0,0 +1,86 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// An HTLC we routed, from `listforwards`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Forward {
+    #[serde(default)]
+    pub fee_msat: Option<u64>,
+    pub status: String,
+    pub received_time: f64,
+    #[serde(default)]
+    pub resolved_time: Option<f64>,
+    #[serde(default)]
+    pub updated_index: Option<u64>,
+}
+
+impl Forward {
+    pub fn is_settled(&self) -> bool {
+        self.status == "settled"
+    }
+
+    /// When the forward resolved, in unix seconds
+    pub fn time(&self) -> f64 {
+        self.resolved_time.unwrap_or(self.received_time)
+    }
+}
+
+#[derive(Deserialize)]
+struct ListForwards {
+    forwards: Vec<Forward>,
+}
+
+/// Parse a `listforwards` response into the forwards that settled, and the
+/// highest `updated` index in the response so we know where to continue.
+pub fn parse_forwards(value: Value) -> Result<(Vec<Forward>, Option<u64>), lnsocket::Error> {
+    let list: ListForwards = serde_json::from_value(value).map_err(|_| lnsocket::Error::Json)?;
+
+    let max_index = list.forwards.iter().filter_map(|f| f.updated_index).max();
+    let settled = list
+        .forwards
+        .into_iter()
+        .filter(|f| f.is_settled())
+        .collect();
+
+    Ok((settled, max_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_forwards() {
+        let value = json!({"forwards": [
+            {
+                "in_channel": "1x1x1",
+                "out_channel": "2x2x2",
+                "in_msat": 100_500,
+                "out_msat": 100_000,
+                "fee_msat": 500,
+                "status": "settled",
+                "received_time": 1700000000.5,
+                "resolved_time": 1700000001.0,
+                "created_index": 1,
+                "updated_index": 3,
+            },
+            {
+                "in_channel": "1x1x1",
+                "in_msat": 2000,
+                "status": "local_failed",
+                "received_time": 1700000002.0,
+                "created_index": 2,
+                "updated_index": 4,
+            },
+        ]});
+
+        let (settled, max_index) = parse_forwards(value).unwrap();
+        assert_eq!(max_index, Some(4));
+        assert_eq!(settled.len(), 1);
+        assert_eq!(settled[0].fee_msat, Some(500));
+        assert_eq!(settled[0].time(), 1700000001.0);
+
+        assert!(parse_forwards(json!({"nope": []})).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Rolling time series of our balances and routing fees, and charts for
+//! them.
+
+use crate::channels::Channels;
+use crate::forwards::Forward;
+use crate::ui;
+use egui::{Color32, Stroke};
+use std::collections::VecDeque;
+
+const MAX_POINTS: usize = 2000;
+const CHART_HEIGHT: f32 = 140.0;
+
+/// Points sorted by time, in unix seconds. Old points are dropped once we
+/// have [`MAX_POINTS`].
+#[derive(Default)]
+pub struct Series {
+    points: VecDeque<[f64; 2]>,
+}
+
+impl Series {
+    pub fn push(&mut self, time: f64, value: f64) {
+        // keep points sorted, forwards can come in slightly out of order
+        let pos = self.points.partition_point(|p| p[0] <= time);
+        self.points.insert(pos, [time, value]);
+
+        while self.points.len() > MAX_POINTS {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn last(&self) -> Option<f64> {
+        self.points.back().map(|p| p[1])
+    }
+
+    fn bounds(&self) -> Option<([f64; 2], [f64; 2])> {
+        let first = self.points.front()?;
+        let last = self.points.back()?;
+        let (min_y, max_y) = self
+            .points
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(min, max), p| {
+                (min.min(p[1]), max.max(p[1]))
+            });
+
+        Some(([first[0], last[0]], [min_y, max_y]))
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    /// msats we can receive
+    pub inbound: Series,
+    /// msats we can send
+    pub outbound: Series,
+    /// total routing fees earned since we started watching, in msats
+    pub fees: Series,
+    fees_total_msat: u64,
+}
+
+impl History {
+    pub fn record_channels(&mut self, time: f64, channels: &Channels) {
+        self.inbound.push(time, channels.avail_in as f64);
+        self.outbound.push(time, channels.avail_out as f64);
+    }
+
+    pub fn record_forwards(&mut self, forwards: &[Forward]) {
+        let mut forwards: Vec<&Forward> = forwards.iter().filter(|f| f.is_settled()).collect();
+        forwards.sort_by(|a, b| a.time().total_cmp(&b.time()));
+
+        for forward in forwards {
+            self.fees_total_msat += forward.fee_msat.unwrap_or(0);
+            self.fees.push(forward.time(), self.fees_total_msat as f64);
+        }
+    }
+
+    pub fn fees_total_msat(&self) -> u64 {
+        self.fees_total_msat
+    }
+}
+
+pub fn history_ui(ui: &mut egui::Ui, history: &History) {
+    let out_color = Color32::from_rgb(84, 69, 201); // blue
+    let in_color = Color32::from_rgb(158, 56, 180); // purple
+    let fee_color = Color32::from_rgb(80, 180, 120); // green
+
+    ui.strong("Liquidity");
+    line_chart(
+        ui,
+        &[
+            ("outbound", out_color, &history.outbound),
+            ("inbound", in_color, &history.inbound),
+        ],
+    );
+
+    ui.add_space(8.0);
+
+    ui.strong(format!(
+        "Routing fees ({} earned)",
+        ui::human_verbose_sat(history.fees_total_msat() as i64)
+    ));
+    line_chart(ui, &[("fees", fee_color, &history.fees)]);
+
+    ui.add_space(8.0);
+}
+
+/// A minimal line chart. All lines share the same axes.
+fn line_chart(ui: &mut egui::Ui, lines: &[(&str, Color32, &Series)]) {
+    let bounds = lines
+        .iter()
+        .filter_map(|(_, _, series)| series.bounds())
+        .reduce(|(ax, ay), (bx, by)| {
+            (
+                [ax[0].min(bx[0]), ax[1].max(bx[1])],
+                [ay[0].min(by[0]), ay[1].max(by[1])],
+            )
+        });
+
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), CHART_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+
+    let enough_points = lines.iter().any(|(_, _, series)| series.points.len() >= 2);
+    let Some(([min_x, max_x], [min_y, max_y])) = bounds.filter(|_| enough_points) else {
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "collecting data...",
+            egui::FontId::proportional(12.0),
+            ui.visuals().weak_text_color(),
+        );
+        return;
+    };
+
+    // leave some room so flat lines aren't glued to the edges
+    let pad_y = ((max_y - min_y) * 0.1).max(1000.0);
+    let (min_y, max_y) = ((min_y - pad_y).max(0.0), max_y + pad_y);
+    let span_x = (max_x - min_x).max(1.0);
+    let span_y = (max_y - min_y).max(1.0);
+
+    let plot_rect = rect.shrink(8.0);
+    let to_screen = |p: &[f64; 2]| {
+        egui::pos2(
+            plot_rect.left() + ((p[0] - min_x) / span_x) as f32 * plot_rect.width(),
+            plot_rect.bottom() - ((p[1] - min_y) / span_y) as f32 * plot_rect.height(),
+        )
+    };
+
+    for (_, color, series) in lines {
+        let points: Vec<egui::Pos2> = series.points.iter().map(to_screen).collect();
+        painter.add(egui::Shape::line(points, Stroke::new(1.5, *color)));
+    }
+
+    let text_color = ui.visuals().weak_text_color();
+    let font = egui::FontId::proportional(11.0);
+    painter.text(
+        plot_rect.left_top(),
+        egui::Align2::LEFT_TOP,
+        ui::human_sat(max_y as i64),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        plot_rect.left_bottom(),
+        egui::Align2::LEFT_BOTTOM,
+        ui::human_sat(min_y as i64),
+        font,
+        text_color,
+    );
+
+    response.on_hover_ui_at_pointer(|ui| {
+        for (name, color, series) in lines {
+            if let Some(last) = series.last() {
+                ui.colored_label(
+                    *color,
+                    format!("{name}: {}", ui::human_verbose_sat(last as i64)),
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forward(time: f64, fee_msat: u64, status: &str) -> Forward {
+        Forward {
+            fee_msat: Some(fee_msat),
+            status: status.to_string(),
+            received_time: time,
+            resolved_time: Some(time),
+            updated_index: None,
+        }
+    }
+
+    #[test]
+    fn test_series_is_sorted_and_bounded() {
+        let mut series = Series::default();
+        series.push(2.0, 20.0);
+        series.push(1.0, 10.0);
+        series.push(3.0, 5.0);
+
+        let times: Vec<f64> = series.points.iter().map(|p| p[0]).collect();
+        assert_eq!(times, vec![1.0, 2.0, 3.0]);
+        assert_eq!(series.bounds(), Some(([1.0, 3.0], [5.0, 20.0])));
+
+        for i in 0..MAX_POINTS {
+            series.push(10.0 + i as f64, 1.0);
+        }
+        assert_eq!(series.points.len(), MAX_POINTS);
+        // the oldest points went first
+        assert_eq!(series.points.front().map(|p| p[0]), Some(10.0));
+    }
+
+    #[test]
+    fn test_fees_accumulate() {
+        let mut history = History::default();
+        history.record_forwards(&[
+            forward(2.0, 300, "settled"),
+            forward(1.0, 200, "settled"),
+            forward(3.0, 1000, "failed"),
+        ]);
+
+        assert_eq!(history.fees_total_msat(), 500);
+        let fees: Vec<[f64; 2]> = history.fees.points.iter().copied().collect();
+        assert_eq!(fees, vec![[1.0, 200.0], [2.0, 500.0]]);
+    }
+}
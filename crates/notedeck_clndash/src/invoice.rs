@@ -8,6 +8,9 @@ use std::collections::HashMap;
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Invoice {
     pub lastpay_index: Option<u64>,
+    pub pay_index: Option<u64>,
+    pub paid_at: Option<u64>,
+    pub amount_received_msat: Option<u64>,
     pub label: String,
     pub bolt11: Bolt11Invoice,
     pub payment_hash: String,
@@ -1,3 +1,4 @@
+use crate::actions::{Action, ActionForm, ActionResult};
 use crate::channels::Channel;
 use crate::channels::Channels;
 use crate::channels::ListPeerChannel;
@@ -6,31 +7,55 @@ use crate::event::ConnectionState;
 use crate::event::Event;
 use crate::event::LoadingState;
 use crate::event::Request;
+use crate::history::History;
 use crate::invoice::Invoice;
+use crate::nodes::{NodeForm, NodeStore, NodesAction, SavedNodes};
+use crate::rune::Rune;
 use crate::summary::Summary;
-use crate::watch::fetch_paid_invoices;
+use crate::transport::LnSocketTransport;
+use crate::zaps::ZapAnalytics;
 
-use lnsocket::bitcoin::secp256k1::{PublicKey, SecretKey, rand};
-use lnsocket::{CommandoClient, LNSocket};
+use backoff::Backoff;
 use nostrdb::Ndb;
 use notedeck::AppContext;
 use notedeck::AppResponse;
-use serde_json::json;
 use std::collections::HashMap;
-use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
 
+mod actions;
+mod backoff;
 mod channels;
 mod event;
+mod forwards;
+mod history;
 mod invoice;
+mod nodes;
+mod rune;
 mod summary;
+mod transport;
 mod ui;
 mod watch;
+mod zaps;
+
+/// How often we refresh channel balances while connected
+const CHANNEL_REFRESH: Duration = Duration::from_secs(30);
 
 #[derive(Default)]
 pub struct ClnDash {
     initialized: bool,
+    store: Option<NodeStore>,
+    nodes: SavedNodes,
+    node_form: NodeForm,
+    backoff: Backoff,
+    /// The rune of the node we're connected to, so we know which actions
+    /// to offer. `None` if we couldn't parse it.
+    rune: Option<Rune>,
+    action_form: ActionForm,
+    /// Waiting for confirmation
+    pending_action: Option<Action>,
+    last_action: Option<ActionResult>,
     connection_state: ConnectionState,
     summary: LoadingState<Summary, lnsocket::Error>,
     get_info: LoadingState<String, lnsocket::Error>,
@@ -38,6 +63,9 @@ pub struct ClnDash {
     invoices: LoadingState<Vec<Invoice>, lnsocket::Error>,
     channel: Option<CommChannel>,
     last_summary: Option<Summary>,
+    history: History,
+    zaps: ZapAnalytics,
+    last_channel_refresh: Option<Instant>,
     // invoice label to zapreq id
     invoice_zap_reqs: HashMap<String, [u8; 32]>,
 }
@@ -62,13 +90,27 @@ struct CommChannel {
 impl notedeck::App for ClnDash {
     fn update(&mut self, ctx: &mut AppContext<'_>, ui: &mut egui::Ui) -> AppResponse {
         if !self.initialized {
-            self.connection_state = ConnectionState::Connecting;
+            let store = NodeStore::new(ctx.path);
+            self.nodes = store.load();
+            self.store = Some(store);
+
+            if let Some(selected) = self.nodes.selected {
+                self.connect(selected);
+            } else {
+                self.connection_state = ConnectionState::Dead("no node selected".to_string());
+            }
 
-            self.setup_connection();
             self.initialized = true;
         }
 
+        let now = Instant::now();
+        self.reconnect_if_due(now);
         self.process_events(ctx.ndb);
+        self.refresh_channels_if_due(ui, now);
+
+        if let Some(remaining) = self.backoff.remaining(now) {
+            ui.ctx().request_repaint_after(remaining);
+        }
 
         self.show(ui, ctx);
 
@@ -78,110 +120,199 @@ impl notedeck::App for ClnDash {
 
 impl ClnDash {
     fn show(&mut self, ui: &mut egui::Ui, ctx: &mut AppContext) {
+        let mut nodes_action = None;
+        let mut action = None;
+
         egui::Frame::new()
             .inner_margin(egui::Margin::same(20))
             .show(ui, |ui| {
                 egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui::connection_state_ui(ui, &self.connection_state);
+                    nodes_action = nodes::nodes_ui(ui, &self.nodes, &mut self.node_form);
+                    ui::connection_state_ui(
+                        ui,
+                        &self.connection_state,
+                        self.backoff.remaining(Instant::now()),
+                    );
                     crate::summary::summary_ui(ui, self.last_summary.as_ref(), &self.summary);
+                    crate::history::history_ui(ui, &self.history);
+                    if let ConnectionState::Active = self.connection_state {
+                        action = actions::actions_ui(
+                            ui,
+                            &mut self.action_form,
+                            self.rune.as_ref(),
+                            &self.channels,
+                            self.last_action.as_ref(),
+                        );
+                    }
                     crate::invoice::invoices_ui(ui, &self.invoice_zap_reqs, ctx, &self.invoices);
+                    crate::zaps::zaps_ui(ui, ctx.ndb, &self.zaps);
                     crate::channels::channels_ui(ui, &self.channels);
                     crate::ui::get_info_ui(ui, &self.get_info);
                 });
             });
+
+        if let Some(action) = nodes_action {
+            self.handle_nodes_action(action);
+        }
+
+        if action.is_some() {
+            self.pending_action = action;
+        }
+
+        self.confirm_pending_action(ui.ctx());
     }
 
-    fn setup_connection(&mut self) {
-        let (req_tx, mut req_rx) = unbounded_channel::<Request>();
-        let (event_tx, event_rx) = unbounded_channel::<Event>();
-        self.channel = Some(CommChannel { req_tx, event_rx });
+    fn confirm_pending_action(&mut self, ctx: &egui::Context) {
+        let Some(action) = &self.pending_action else {
+            return;
+        };
 
-        tokio::spawn(async move {
-            let key = SecretKey::new(&mut rand::thread_rng());
-            let their_pubkey = PublicKey::from_str(&std::env::var("CLNDASH_ID").unwrap_or(
-                "03f3c108ccd536b8526841f0a5c58212bb9e6584a1eb493080e7c1cc34f82dad71".to_string(),
-            ))
-            .unwrap();
-
-            let host = std::env::var("CLNDASH_HOST").unwrap_or("ln.damus.io:9735".to_string());
-            let lnsocket = match LNSocket::connect_and_init(key, their_pubkey, &host).await {
-                Err(err) => {
-                    let _ = event_tx.send(Event::Ended {
-                        reason: err.to_string(),
-                    });
-                    return;
+        let Some(confirmed) = actions::confirm_ui(ctx, action) else {
+            return;
+        };
+
+        let Some(action) = self.pending_action.take() else {
+            return;
+        };
+
+        if !confirmed {
+            return;
+        }
+
+        match &self.channel {
+            Some(channel) if channel.req_tx.send(Request::Action(action.clone())).is_ok() => {}
+            _ => {
+                self.last_action = Some(ActionResult {
+                    action,
+                    result: Err(lnsocket::Error::Io(std::io::ErrorKind::NotConnected)),
+                });
+            }
+        }
+    }
+
+    fn handle_nodes_action(&mut self, action: NodesAction) {
+        let Some(store) = &self.store else {
+            return;
+        };
+
+        match action {
+            NodesAction::Connect(ind) => {
+                self.backoff.reset();
+                self.connect(ind);
+            }
+
+            NodesAction::Remove(ind) => {
+                let was_selected = self.nodes.selected == Some(ind);
+                let removed = self.nodes.remove(ind);
+                if let Some(Err(err)) = removed.as_ref().map(|node| store.remove_rune(node)) {
+                    tracing::error!("could not remove rune: {err}");
                 }
 
-                Ok(lnsocket) => {
-                    let _ = event_tx.send(Event::Connected);
-                    lnsocket
+                if was_selected {
+                    self.backoff.reset();
+                    self.channel = None;
+                    self.connection_state = ConnectionState::Dead("no node selected".to_string());
                 }
-            };
-
-            let rune = std::env::var("CLNDASH_RUNE").unwrap_or(
-                "Vns1Zxvidr4J8pP2ZCg3Wjp2SyGyyf5RHgvFG8L36yM9MzMmbWV0aG9kPWdldGluZm8=".to_string(),
-            );
-            let commando = Arc::new(CommandoClient::spawn(lnsocket, &rune));
-
-            loop {
-                match req_rx.recv().await {
-                    None => {
-                        let _ = event_tx.send(Event::Ended {
-                            reason: "channel dead?".to_string(),
-                        });
-                        break;
-                    }
+            }
 
-                    Some(req) => {
-                        tracing::debug!("calling {req:?}");
-                        match req {
-                            Request::GetInfo => {
-                                let event_tx = event_tx.clone();
-                                let commando = commando.clone();
-                                tokio::spawn(async move {
-                                    match commando.call("getinfo", json!({})).await {
-                                        Ok(v) => {
-                                            let _ = event_tx
-                                                .send(Event::Response(ClnResponse::GetInfo(v)));
-                                        }
-                                        Err(err) => {
-                                            tracing::error!("get_info error {}", err);
-                                        }
-                                    }
-                                });
-                            }
+            NodesAction::Add { node, rune } => {
+                if let Err(err) = store.set_rune(&node, &rune) {
+                    self.connection_state =
+                        ConnectionState::Dead(format!("could not save rune: {err}"));
+                    return;
+                }
 
-                            Request::PaidInvoices(n) => {
-                                let event_tx = event_tx.clone();
-                                let commando = commando.clone();
-                                tokio::spawn(async move {
-                                    let invoices = fetch_paid_invoices(commando, n).await;
-                                    let _ = event_tx
-                                        .send(Event::Response(ClnResponse::PaidInvoices(invoices)));
-                                });
-                            }
+                let ind = self.nodes.add(node);
+                self.backoff.reset();
+                self.connect(ind);
+            }
+        }
 
-                            Request::ListPeerChannels => {
-                                let event_tx = event_tx.clone();
-                                let commando = commando.clone();
-                                tokio::spawn(async move {
-                                    let peer_channels =
-                                        commando.call("listpeerchannels", json!({})).await;
-                                    let channels = peer_channels.map(|v| {
-                                        let peer_channels: Vec<ListPeerChannel> =
-                                            serde_json::from_value(v["channels"].clone()).unwrap();
-                                        to_channels(peer_channels)
-                                    });
-                                    let _ = event_tx.send(Event::Response(
-                                        ClnResponse::ListPeerChannels(channels),
-                                    ));
-                                });
-                            }
-                        }
-                    }
-                }
+        if let Some(store) = &self.store {
+            store.save(&self.nodes);
+        }
+    }
+
+    /// Drop whatever we're connected to and connect to the node at `ind`
+    fn connect(&mut self, ind: usize) {
+        self.nodes.selected = Some(ind);
+        self.channel = None;
+        self.summary = LoadingState::Loading;
+        self.get_info = LoadingState::Loading;
+        self.channels = LoadingState::Loading;
+        self.invoices = LoadingState::Loading;
+        self.last_summary = None;
+        self.history = History::default();
+        self.last_channel_refresh = None;
+        self.invoice_zap_reqs.clear();
+        self.zaps = ZapAnalytics::default();
+        self.rune = None;
+        self.pending_action = None;
+        self.last_action = None;
+
+        let Some(node) = self.nodes.selected().cloned() else {
+            return;
+        };
+
+        let rune = match self.store.as_ref().map(|store| store.rune(&node)) {
+            Some(Ok(Some(rune))) => rune,
+            Some(Ok(None)) | None => {
+                self.backoff.reset();
+                self.connection_state =
+                    ConnectionState::Dead(format!("no rune saved for {}", node.display_name()));
+                return;
+            }
+            Some(Err(err)) => {
+                self.backoff.reset();
+                self.connection_state =
+                    ConnectionState::Dead(format!("could not read rune: {err}"));
+                return;
             }
-        });
+        };
+
+        self.rune = Rune::parse(&rune);
+        self.connection_state = ConnectionState::Connecting;
+
+        let (req_tx, req_rx) = unbounded_channel::<Request>();
+        let (event_tx, event_rx) = unbounded_channel::<Event>();
+        self.channel = Some(CommChannel { req_tx, event_rx });
+
+        tokio::spawn(transport::run(
+            Arc::new(LnSocketTransport),
+            node,
+            rune,
+            req_rx,
+            event_tx,
+        ));
+    }
+
+    fn reconnect_if_due(&mut self, now: Instant) {
+        if !self.backoff.take_due(now) {
+            return;
+        }
+
+        if let Some(selected) = self.nodes.selected {
+            self.connect(selected);
+        }
+    }
+
+    /// Poll channel balances so the liquidity chart keeps moving, even
+    /// when nothing gets paid or forwarded
+    fn refresh_channels_if_due(&mut self, ui: &egui::Ui, now: Instant) {
+        let (ConnectionState::Active, Some(channel)) = (&self.connection_state, &self.channel)
+        else {
+            return;
+        };
+
+        let due = self
+            .last_channel_refresh
+            .is_none_or(|last| now.duration_since(last) >= CHANNEL_REFRESH);
+        if due {
+            let _ = channel.req_tx.send(Request::ListPeerChannels);
+            self.last_channel_refresh = Some(now);
+        }
+
+        ui.ctx().request_repaint_after(CHANNEL_REFRESH);
     }
 
     fn process_events(&mut self, ndb: &Ndb) {
@@ -193,10 +324,13 @@ impl ClnDash {
             match event {
                 Event::Ended { reason } => {
                     self.connection_state = ConnectionState::Dead(reason);
+                    self.backoff.schedule(Instant::now());
                 }
 
                 Event::Connected => {
                     self.connection_state = ConnectionState::Active;
+                    self.backoff.reset();
+                    self.last_channel_refresh = Some(Instant::now());
                     let _ = channel.req_tx.send(Request::GetInfo);
                     let _ = channel.req_tx.send(Request::ListPeerChannels);
                     let _ = channel.req_tx.send(Request::PaidInvoices(100));
@@ -208,6 +342,10 @@ impl ClnDash {
                             self.last_summary = Some(crate::summary::compute_summary(prev));
                         }
 
+                        if let Ok(chans) = &chans {
+                            self.history.record_channels(unix_now_secs(), chans);
+                        }
+
                         self.summary = match &chans {
                             Ok(chans) => {
                                 LoadingState::Loaded(crate::summary::compute_summary(chans))
@@ -228,27 +366,65 @@ impl ClnDash {
 
                         if let Ok(invoices) = &invoices {
                             for invoice in invoices {
-                                let zap_req_id: Option<ZapReqId> =
-                                    serde_json::from_str(&invoice.description).ok();
-                                if let Some(zap_req_id) = zap_req_id {
-                                    self.invoice_zap_reqs
-                                        .insert(invoice.label.clone(), zap_req_id.id);
-                                    let _ = ndb.process_event(&format!(
-                                        "[\"EVENT\",\"a\",{}]",
-                                        &invoice.description
-                                    ));
-                                }
+                                process_zap_request(ndb, &mut self.invoice_zap_reqs, invoice);
                             }
+                            self.zaps.add_invoices(invoices);
                         }
 
                         self.invoices = LoadingState::from_result(invoices);
                     }
+
+                    ClnResponse::InvoicePaid(invoice) => {
+                        process_zap_request(ndb, &mut self.invoice_zap_reqs, &invoice);
+                        self.zaps.add_invoices([&invoice]);
+
+                        if let LoadingState::Loaded(invoices) = &mut self.invoices {
+                            if !invoices.iter().any(|inv| inv.label == invoice.label) {
+                                invoices.insert(0, invoice);
+                            }
+                        } else {
+                            self.invoices = LoadingState::Loaded(vec![invoice]);
+                        }
+
+                        // our balance changed
+                        let _ = channel.req_tx.send(Request::ListPeerChannels);
+                    }
+
+                    ClnResponse::Action(result) => {
+                        if result.result.is_ok() {
+                            let _ = channel.req_tx.send(Request::ListPeerChannels);
+                        }
+                        self.last_action = Some(result);
+                    }
+
+                    ClnResponse::Forwards(forwards) => {
+                        self.history.record_forwards(&forwards);
+                        let _ = channel.req_tx.send(Request::ListPeerChannels);
+                    }
                 },
             }
         }
     }
 }
 
+/// Zap invoices have the zap request as their description. Remember which
+/// note it was for, and store the zap request so we can show it.
+fn process_zap_request(ndb: &Ndb, zap_reqs: &mut HashMap<String, [u8; 32]>, invoice: &Invoice) {
+    let Ok(zap_req_id) = serde_json::from_str::<ZapReqId>(&invoice.description) else {
+        return;
+    };
+
+    zap_reqs.insert(invoice.label.clone(), zap_req_id.id);
+    let _ = ndb.process_event(&format!("[\"EVENT\",\"a\",{}]", &invoice.description));
+}
+
+fn unix_now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
 fn to_channels(peer_channels: Vec<ListPeerChannel>) -> Channels {
     let mut avail_out: i64 = 0;
     let mut avail_in: i64 = 0;
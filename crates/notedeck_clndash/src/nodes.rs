@@ -0,0 +1,312 @@
+//! Saved lightning nodes and the ui for managing them.
+//!
+//! The node list lives in the settings directory, while runes are kept in
+//! the OS keyring since they grant access to the node.
+
+use lnsocket::bitcoin::secp256k1::PublicKey;
+use notedeck::storage::KeyringStore;
+use notedeck::{DataPath, DataPathType, Directory, storage};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+const NODES_FILE: &str = "clndash_nodes.json";
+const DEFAULT_PORT: u16 = 9735;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeConfig {
+    #[serde(default)]
+    pub label: Option<String>,
+    /// The node's pubkey, hex encoded
+    pub id: String,
+    /// host:port
+    pub host: String,
+}
+
+impl NodeConfig {
+    /// Parse a `pubkey@host[:port]` address
+    pub fn parse(label: &str, address: &str) -> Result<Self, String> {
+        let Some((id, host)) = address.trim().split_once('@') else {
+            return Err("expected an address like pubkey@host:port".to_string());
+        };
+
+        if PublicKey::from_str(id).is_err() {
+            return Err(format!("invalid node id {id}"));
+        }
+
+        if host.is_empty() {
+            return Err("missing host".to_string());
+        }
+
+        let host = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{host}:{DEFAULT_PORT}")
+        };
+
+        let label = label.trim();
+        Ok(NodeConfig {
+            label: (!label.is_empty()).then(|| label.to_string()),
+            id: id.to_lowercase(),
+            host,
+        })
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}@{}", self.id, self.host)
+    }
+
+    pub fn display_name(&self) -> String {
+        if let Some(label) = &self.label {
+            return label.clone();
+        }
+
+        let short_id = self.id.get(..8).unwrap_or(&self.id);
+        format!("{short_id}…@{}", self.host)
+    }
+
+    fn rune_key(&self) -> String {
+        format!("clndash:{}", self.address())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedNodes {
+    pub nodes: Vec<NodeConfig>,
+    pub selected: Option<usize>,
+}
+
+impl SavedNodes {
+    pub fn selected(&self) -> Option<&NodeConfig> {
+        self.nodes.get(self.selected?)
+    }
+
+    /// Add a node, replacing any node with the same address. Returns its
+    /// index.
+    pub fn add(&mut self, node: NodeConfig) -> usize {
+        if let Some(ind) = self
+            .nodes
+            .iter()
+            .position(|n| n.address() == node.address())
+        {
+            self.nodes[ind] = node;
+            ind
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    pub fn remove(&mut self, ind: usize) -> Option<NodeConfig> {
+        if ind >= self.nodes.len() {
+            return None;
+        }
+
+        let removed = self.nodes.remove(ind);
+        self.selected = match self.selected {
+            Some(selected) if selected == ind => None,
+            Some(selected) if selected > ind => Some(selected - 1),
+            selected => selected,
+        };
+
+        Some(removed)
+    }
+}
+
+/// Loads and saves nodes, and their runes
+pub struct NodeStore {
+    directory: Directory,
+    keyring: KeyringStore,
+}
+
+impl NodeStore {
+    pub fn new(path: &DataPath) -> Self {
+        Self {
+            directory: Directory::new(path.path(DataPathType::Setting)),
+            keyring: KeyringStore::default(),
+        }
+    }
+
+    pub fn load(&self) -> SavedNodes {
+        let Ok(contents) = self.directory.get_file(NODES_FILE.to_owned()) else {
+            return SavedNodes::default();
+        };
+
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            tracing::error!("could not parse {NODES_FILE}: {err}");
+            SavedNodes::default()
+        })
+    }
+
+    pub fn save(&self, nodes: &SavedNodes) {
+        let json = match serde_json::to_string(nodes) {
+            Ok(json) => json,
+            Err(err) => {
+                tracing::error!("could not serialize nodes: {err}");
+                return;
+            }
+        };
+
+        if let Err(err) =
+            storage::write_file(&self.directory.file_path, NODES_FILE.to_owned(), &json)
+        {
+            tracing::error!("could not save nodes: {err}");
+        }
+    }
+
+    pub fn rune(&self, node: &NodeConfig) -> notedeck::Result<Option<String>> {
+        self.keyring.get_named_secret(&node.rune_key())
+    }
+
+    pub fn set_rune(&self, node: &NodeConfig, rune: &str) -> notedeck::Result<()> {
+        self.keyring.store_named_secret(&node.rune_key(), rune)
+    }
+
+    pub fn remove_rune(&self, node: &NodeConfig) -> notedeck::Result<()> {
+        self.keyring.remove_named_secret(&node.rune_key())
+    }
+}
+
+/// State for the "add node" form
+#[derive(Default)]
+pub struct NodeForm {
+    label: String,
+    address: String,
+    rune: String,
+    error: Option<String>,
+}
+
+pub enum NodesAction {
+    Connect(usize),
+    Remove(usize),
+    Add { node: NodeConfig, rune: String },
+}
+
+pub fn nodes_ui(ui: &mut egui::Ui, nodes: &SavedNodes, form: &mut NodeForm) -> Option<NodesAction> {
+    let mut action = None;
+
+    if !nodes.nodes.is_empty() {
+        ui.horizontal(|ui| {
+            let selected_text = nodes
+                .selected()
+                .map(|node| node.display_name())
+                .unwrap_or_else(|| "Select a node".to_string());
+
+            egui::ComboBox::from_id_salt("clndash-node")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for (ind, node) in nodes.nodes.iter().enumerate() {
+                        let is_selected = nodes.selected == Some(ind);
+                        if ui
+                            .selectable_label(is_selected, node.display_name())
+                            .on_hover_text(node.address())
+                            .clicked()
+                            && !is_selected
+                        {
+                            action = Some(NodesAction::Connect(ind));
+                        }
+                    }
+                });
+
+            if let Some(selected) = nodes.selected {
+                if ui.button("Reconnect").clicked() {
+                    action = Some(NodesAction::Connect(selected));
+                }
+
+                if ui.button("Remove").clicked() {
+                    action = Some(NodesAction::Remove(selected));
+                }
+            }
+        });
+    }
+
+    egui::CollapsingHeader::new("Add node")
+        .default_open(nodes.nodes.is_empty())
+        .show(ui, |ui| {
+            ui.add(egui::TextEdit::singleline(&mut form.label).hint_text("Label (optional)"));
+            ui.add(egui::TextEdit::singleline(&mut form.address).hint_text("pubkey@host:port"));
+            ui.add(
+                egui::TextEdit::singleline(&mut form.rune)
+                    .password(true)
+                    .hint_text("Rune"),
+            );
+
+            if let Some(error) = &form.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+
+            if ui.button("Save").clicked() {
+                match NodeConfig::parse(&form.label, &form.address) {
+                    Ok(_) if form.rune.trim().is_empty() => {
+                        form.error = Some("a rune is required".to_string());
+                    }
+                    Ok(node) => {
+                        action = Some(NodesAction::Add {
+                            node,
+                            rune: form.rune.trim().to_string(),
+                        });
+                        *form = NodeForm::default();
+                    }
+                    Err(err) => form.error = Some(err),
+                }
+            }
+        });
+
+    action
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "03f3c108ccd536b8526841f0a5c58212bb9e6584a1eb493080e7c1cc34f82dad71";
+
+    #[test]
+    fn test_parse_address() {
+        let node = NodeConfig::parse("", &format!("{ID}@ln.damus.io")).unwrap();
+        assert_eq!(node.host, "ln.damus.io:9735");
+        assert_eq!(node.label, None);
+
+        let node = NodeConfig::parse(" damus ", &format!("{ID}@127.0.0.1:19735")).unwrap();
+        assert_eq!(node.host, "127.0.0.1:19735");
+        assert_eq!(node.display_name(), "damus");
+
+        assert!(NodeConfig::parse("", "ln.damus.io:9735").is_err());
+        assert!(NodeConfig::parse("", "abcd@ln.damus.io").is_err());
+        assert!(NodeConfig::parse("", &format!("{ID}@")).is_err());
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let a = NodeConfig::parse("a", &format!("{ID}@a.example")).unwrap();
+        let b = NodeConfig::parse("b", &format!("{ID}@b.example")).unwrap();
+
+        let mut nodes = SavedNodes::default();
+        assert_eq!(nodes.add(a.clone()), 0);
+        assert_eq!(nodes.add(b.clone()), 1);
+
+        // same address replaces the existing node
+        let renamed = NodeConfig::parse("a2", &format!("{ID}@a.example")).unwrap();
+        assert_eq!(nodes.add(renamed), 0);
+        assert_eq!(nodes.nodes.len(), 2);
+
+        nodes.selected = Some(1);
+        assert_eq!(nodes.remove(0).map(|n| n.host), Some(a.host));
+        assert_eq!(nodes.selected(), Some(&b));
+
+        nodes.remove(0);
+        assert_eq!(nodes.selected, None);
+        assert!(nodes.remove(0).is_none());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut nodes = SavedNodes::default();
+        nodes.add(NodeConfig::parse("", &format!("{ID}@ln.damus.io")).unwrap());
+        nodes.selected = Some(0);
+
+        let json = serde_json::to_string(&nodes).unwrap();
+        // runes never end up in the node list
+        assert!(!json.contains("rune"));
+        assert_eq!(serde_json::from_str::<SavedNodes>(&json).unwrap(), nodes);
+    }
+}
@@ -0,0 +1,180 @@
+//! Just enough of core lightning's rune format to tell which commando
+//! methods a rune lets us call. The node is what actually enforces the
+//! rune, this only keeps us from offering actions that are sure to fail.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+/// The first part of a rune is a sha256 state, the restrictions follow
+const HASH_LEN: usize = 32;
+
+struct Alternative {
+    field: String,
+    op: char,
+    value: String,
+}
+
+impl Alternative {
+    fn parse(s: &str) -> Option<Self> {
+        let field_len = s
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(s.len());
+        let (field, rest) = s.split_at(field_len);
+        let mut rest = rest.chars();
+        let op = rest.next()?;
+
+        Some(Self {
+            field: field.to_string(),
+            op,
+            value: rest.collect(),
+        })
+    }
+
+    /// Could this alternative pass when calling `method`? Alternatives on
+    /// anything other than the method depend on things we don't know
+    /// here, so we give them the benefit of the doubt.
+    fn may_allow(&self, method: &str) -> bool {
+        if self.field != "method" {
+            return true;
+        }
+
+        let value = self.value.as_str();
+        match self.op {
+            '=' => method == value,
+            '/' => method != value,
+            '^' => method.starts_with(value),
+            '$' => method.ends_with(value),
+            '~' => method.contains(value),
+            '{' => method < value,
+            '}' => method > value,
+            '#' => true,
+            // missing, or integer comparisons which never pass on a method
+            _ => false,
+        }
+    }
+}
+
+/// A rune's restrictions. Every restriction has to pass, and a restriction
+/// passes when any of its alternatives do.
+pub struct Rune {
+    restrictions: Vec<Vec<Alternative>>,
+}
+
+impl Rune {
+    pub fn parse(rune: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(rune.trim().trim_end_matches('='))
+            .ok()?;
+        let restrictions = std::str::from_utf8(bytes.get(HASH_LEN..)?).ok()?;
+
+        let mut parsed = vec![];
+        for restriction in split_unescaped(restrictions, '&') {
+            let alternatives = split_unescaped(&restriction, '|')
+                .iter()
+                .map(|alt| Alternative::parse(&unescape(alt)))
+                .collect::<Option<Vec<_>>>()?;
+            parsed.push(alternatives);
+        }
+
+        Some(Self {
+            restrictions: parsed,
+        })
+    }
+
+    pub fn allows_method(&self, method: &str) -> bool {
+        self.restrictions
+            .iter()
+            .all(|alternatives| alternatives.iter().any(|alt| alt.may_allow(method)))
+    }
+}
+
+/// Split on `sep`, leaving escapes in place so nested splits still see
+/// them
+fn split_unescaped(s: &str, sep: char) -> Vec<String> {
+    let mut parts = vec![];
+    let mut current = String::new();
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            current.push(c);
+            current.extend(chars.next());
+        } else if c == sep {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+
+    if !current.is_empty() || !parts.is_empty() {
+        parts.push(current);
+    }
+
+    parts
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            out.extend(chars.next());
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::URL_SAFE;
+
+    fn rune(restrictions: &str) -> String {
+        let mut bytes = vec![0u8; HASH_LEN];
+        bytes.extend_from_slice(restrictions.as_bytes());
+        URL_SAFE.encode(bytes)
+    }
+
+    #[test]
+    fn test_unrestricted_rune() {
+        let rune = Rune::parse(&rune("=0")).unwrap();
+        assert!(rune.allows_method("pay"));
+        assert!(rune.allows_method("fundchannel"));
+    }
+
+    #[test]
+    fn test_method_restrictions() {
+        let readonly = Rune::parse(&rune("=1&method^list|method^get|method=summary")).unwrap();
+        assert!(readonly.allows_method("listpeerchannels"));
+        assert!(readonly.allows_method("getinfo"));
+        assert!(!readonly.allows_method("pay"));
+
+        let no_pay = Rune::parse(&rune("=2&method/pay&method/fundchannel")).unwrap();
+        assert!(no_pay.allows_method("invoice"));
+        assert!(!no_pay.allows_method("pay"));
+        assert!(!no_pay.allows_method("fundchannel"));
+
+        // we can't know the params, so this might pass
+        let param = Rune::parse(&rune("=3&method=pay|pnum=0")).unwrap();
+        assert!(param.allows_method("close"));
+    }
+
+    #[test]
+    fn test_escapes() {
+        let rune = Rune::parse(&rune("=4&method=odd\\|name|method=pay")).unwrap();
+        assert!(rune.allows_method("odd|name"));
+        assert!(rune.allows_method("pay"));
+        assert!(!rune.allows_method("odd"));
+    }
+
+    #[test]
+    fn test_invalid_runes() {
+        assert!(Rune::parse("not a rune!").is_none());
+        // too short to have a hash
+        assert!(Rune::parse(&URL_SAFE.encode([0u8; 8])).is_none());
+    }
+}
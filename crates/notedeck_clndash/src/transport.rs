@@ -0,0 +1,310 @@
+//! How we talk to a node.
+//!
+//! The dashboard only needs commando calls, so the connection is hidden
+//! behind [`Transport`] and [`Commando`]. This lets the request handling
+//! run against a mock commando server in tests.
+
+use crate::actions::ActionResult;
+use crate::channels::ListPeerChannel;
+use crate::event::{ClnResponse, Event, Request};
+use crate::nodes::NodeConfig;
+use crate::watch::{fetch_paid_invoices, watch_forwards, watch_invoices};
+
+use lnsocket::bitcoin::secp256k1::{PublicKey, SecretKey, rand};
+use lnsocket::{CallOpts, CommandoClient, LNSocket};
+use serde_json::{Value, json};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+pub type CallResult = Result<Value, lnsocket::Error>;
+
+/// Something we can make commando calls on
+pub trait Commando: Send + Sync + 'static {
+    fn call(&self, method: &str, params: Value) -> impl Future<Output = CallResult> + Send;
+
+    /// Like [`Commando::call`], but only returns the fields in `filter`
+    fn call_filtered(
+        &self,
+        method: &str,
+        params: Value,
+        filter: Value,
+    ) -> impl Future<Output = CallResult> + Send;
+}
+
+impl Commando for CommandoClient {
+    fn call(&self, method: &str, params: Value) -> impl Future<Output = CallResult> + Send {
+        CommandoClient::call(self, method, params)
+    }
+
+    fn call_filtered(
+        &self,
+        method: &str,
+        params: Value,
+        filter: Value,
+    ) -> impl Future<Output = CallResult> + Send {
+        self.call_with_opts(method, params, CallOpts::default().filter(filter))
+    }
+}
+
+/// Opens commando connections to nodes
+pub trait Transport: Send + Sync + 'static {
+    type Client: Commando;
+
+    fn connect(
+        &self,
+        node: &NodeConfig,
+        rune: &str,
+    ) -> impl Future<Output = Result<Self::Client, lnsocket::Error>> + Send;
+}
+
+/// Connects over the lightning network protocol with lnsocket
+#[derive(Default, Clone, Copy)]
+pub struct LnSocketTransport;
+
+impl Transport for LnSocketTransport {
+    type Client = CommandoClient;
+
+    fn connect(
+        &self,
+        node: &NodeConfig,
+        rune: &str,
+    ) -> impl Future<Output = Result<Self::Client, lnsocket::Error>> + Send {
+        let their_pubkey = PublicKey::from_str(&node.id);
+        let host = node.host.clone();
+        let rune = rune.to_owned();
+
+        async move {
+            let their_pubkey =
+                their_pubkey.map_err(|_| lnsocket::Error::Io(std::io::ErrorKind::InvalidInput))?;
+            let key = SecretKey::new(&mut rand::thread_rng());
+            let lnsocket = LNSocket::connect_and_init(key, their_pubkey, &host).await?;
+            Ok(CommandoClient::spawn(lnsocket, &rune))
+        }
+    }
+}
+
+/// Connect to `node` and serve requests until the request channel is closed.
+/// Progress is reported back to the dashboard over `event_tx`.
+pub async fn run<T: Transport>(
+    transport: Arc<T>,
+    node: NodeConfig,
+    rune: String,
+    mut req_rx: UnboundedReceiver<Request>,
+    event_tx: UnboundedSender<Event>,
+) {
+    let commando = match transport.connect(&node, &rune).await {
+        Err(err) => {
+            let _ = event_tx.send(Event::Ended {
+                reason: err.to_string(),
+            });
+            return;
+        }
+
+        Ok(commando) => {
+            let _ = event_tx.send(Event::Connected);
+            Arc::new(commando)
+        }
+    };
+
+    // these stop once the dashboard stops listening for events
+    tokio::spawn(watch_invoices(commando.clone(), event_tx.clone()));
+    tokio::spawn(watch_forwards(commando.clone(), event_tx.clone()));
+
+    while let Some(req) = req_rx.recv().await {
+        tracing::debug!("calling {req:?}");
+        tokio::spawn(handle_request(commando.clone(), req, event_tx.clone()));
+    }
+
+    let _ = event_tx.send(Event::Ended {
+        reason: "channel dead?".to_string(),
+    });
+}
+
+async fn handle_request<C: Commando>(
+    commando: Arc<C>,
+    req: Request,
+    event_tx: UnboundedSender<Event>,
+) {
+    let resp = match req {
+        Request::GetInfo => match commando.call("getinfo", json!({})).await {
+            Ok(v) => ClnResponse::GetInfo(v),
+            Err(err) => {
+                tracing::error!("get_info error {}", err);
+                return;
+            }
+        },
+
+        Request::PaidInvoices(n) => {
+            ClnResponse::PaidInvoices(fetch_paid_invoices(commando, n).await)
+        }
+
+        Request::ListPeerChannels => {
+            let channels = commando
+                .call("listpeerchannels", json!({}))
+                .await
+                .and_then(|v| {
+                    let peer_channels: Vec<ListPeerChannel> =
+                        serde_json::from_value(v["channels"].clone())
+                            .map_err(|_| lnsocket::Error::Json)?;
+                    Ok(crate::to_channels(peer_channels))
+                });
+            ClnResponse::ListPeerChannels(channels)
+        }
+
+        Request::Action(action) => {
+            let result = commando.call(action.method(), action.params()).await;
+            if let Err(err) = &result {
+                tracing::error!("{} error: {err}", action.method());
+            }
+            ClnResponse::Action(ActionResult { action, result })
+        }
+    };
+
+    let _ = event_tx.send(Event::Response(resp));
+}
+
+#[cfg(test)]
+mod mock {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// A commando server with canned responses for each method
+    #[derive(Clone, Default)]
+    pub struct MockCommando {
+        pub responses: HashMap<String, Value>,
+    }
+
+    impl MockCommando {
+        pub fn respond(mut self, method: &str, response: Value) -> Self {
+            self.responses.insert(method.to_string(), response);
+            self
+        }
+    }
+
+    impl Commando for MockCommando {
+        fn call(&self, method: &str, _params: Value) -> impl Future<Output = CallResult> + Send {
+            let resp = self
+                .responses
+                .get(method)
+                .cloned()
+                .ok_or(lnsocket::Error::Io(std::io::ErrorKind::Unsupported));
+            std::future::ready(resp)
+        }
+
+        fn call_filtered(
+            &self,
+            method: &str,
+            params: Value,
+            _filter: Value,
+        ) -> impl Future<Output = CallResult> + Send {
+            self.call(method, params)
+        }
+    }
+
+    /// Hands out a [`MockCommando`], or refuses to connect when there
+    /// isn't one
+    #[derive(Default)]
+    pub struct MockTransport {
+        pub server: Option<MockCommando>,
+    }
+
+    impl Transport for MockTransport {
+        type Client = MockCommando;
+
+        fn connect(
+            &self,
+            _node: &NodeConfig,
+            _rune: &str,
+        ) -> impl Future<Output = Result<Self::Client, lnsocket::Error>> + Send {
+            std::future::ready(
+                self.server
+                    .clone()
+                    .ok_or(lnsocket::Error::Io(std::io::ErrorKind::ConnectionRefused)),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{MockCommando, MockTransport};
+    use super::*;
+    use crate::actions::Action;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn node() -> NodeConfig {
+        NodeConfig::parse(
+            "mock",
+            "03f3c108ccd536b8526841f0a5c58212bb9e6584a1eb493080e7c1cc34f82dad71@127.0.0.1",
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_against_mock_server() {
+        let server = MockCommando::default()
+            .respond("getinfo", json!({"alias": "mock"}))
+            .respond(
+                "listpeerchannels",
+                json!({"channels": [{
+                    "short_channel_id": "1x2x3",
+                    "our_reserve_msat": 1000,
+                    "to_us_msat": 6000,
+                    "total_msat": 10000,
+                    "their_reserve_msat": 1000,
+                }]}),
+            );
+        let transport = Arc::new(MockTransport {
+            server: Some(server),
+        });
+
+        let (req_tx, req_rx) = unbounded_channel();
+        let (event_tx, mut event_rx) = unbounded_channel();
+        tokio::spawn(run(transport, node(), "rune".to_string(), req_rx, event_tx));
+
+        assert!(matches!(event_rx.recv().await, Some(Event::Connected)));
+
+        req_tx.send(Request::GetInfo).unwrap();
+        let Some(Event::Response(ClnResponse::GetInfo(info))) = event_rx.recv().await else {
+            panic!("expected getinfo");
+        };
+        assert_eq!(info["alias"], "mock");
+
+        req_tx.send(Request::ListPeerChannels).unwrap();
+        let Some(Event::Response(ClnResponse::ListPeerChannels(Ok(channels)))) =
+            event_rx.recv().await
+        else {
+            panic!("expected channels");
+        };
+        assert_eq!(channels.channels.len(), 1);
+        assert_eq!(channels.avail_out, 5000);
+        assert_eq!(channels.avail_in, 3000);
+
+        // the rune doesn't allow this one
+        let pay = Action::PayInvoice {
+            bolt11: "lnbc1".to_string(),
+        };
+        req_tx.send(Request::Action(pay.clone())).unwrap();
+        let Some(Event::Response(ClnResponse::Action(result))) = event_rx.recv().await else {
+            panic!("expected an action result");
+        };
+        assert_eq!(result.action, pay);
+        assert!(result.result.is_err());
+
+        drop(req_tx);
+        assert!(matches!(event_rx.recv().await, Some(Event::Ended { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused() {
+        let transport = Arc::new(MockTransport::default());
+        let (_req_tx, req_rx) = unbounded_channel();
+        let (event_tx, mut event_rx) = unbounded_channel();
+
+        run(transport, node(), "rune".to_string(), req_rx, event_tx).await;
+
+        assert!(matches!(event_rx.recv().await, Some(Event::Ended { .. })));
+    }
+}
@@ -6,6 +6,7 @@ use egui::RichText;
 use egui::Widget;
 use notedeck::AppContext;
 use std::collections::HashMap;
+use std::time::Duration;
 
 pub fn note_hover_ui(
     ui: &mut egui::Ui,
@@ -54,6 +55,7 @@ pub fn note_hover_ui(
             clipboard: ctx.clipboard,
             i18n: ctx.i18n,
             global_wallet: ctx.global_wallet,
+            nip05_cache: ctx.nip05_cache,
         };
 
         let options = notedeck_ui::NoteOptions::default();
@@ -90,7 +92,11 @@ pub fn get_info_ui(ui: &mut egui::Ui, info: &LoadingState<String, lnsocket::Erro
     });
 }
 
-pub fn connection_state_ui(ui: &mut egui::Ui, state: &ConnectionState) {
+pub fn connection_state_ui(
+    ui: &mut egui::Ui,
+    state: &ConnectionState,
+    reconnect_in: Option<Duration>,
+) {
     match state {
         ConnectionState::Active => {
             ui.add(Label::new(RichText::new("Connected").color(Color32::GREEN)));
@@ -103,9 +109,15 @@ pub fn connection_state_ui(ui: &mut egui::Ui, state: &ConnectionState) {
         }
 
         ConnectionState::Dead(reason) => {
-            ui.add(Label::new(
-                RichText::new(format!("Disconnected: {reason}")).color(Color32::RED),
-            ));
+            let text = if let Some(reconnect_in) = reconnect_in {
+                format!(
+                    "Disconnected: {reason} (reconnecting in {}s)",
+                    reconnect_in.as_secs() + 1
+                )
+            } else {
+                format!("Disconnected: {reason}")
+            };
+            ui.add(Label::new(RichText::new(text).color(Color32::RED)));
         }
     }
 }
@@ -1,9 +1,16 @@
+use crate::backoff::Backoff;
+use crate::event::{ClnResponse, Event};
+use crate::forwards::parse_forwards;
 use crate::invoice::Invoice;
-use lnsocket::CallOpts;
-use lnsocket::CommandoClient;
+use crate::transport::{CallResult, Commando};
 use serde::Deserialize;
-use serde_json::json;
+use serde_json::{Value, json};
 use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// How many recent forwards to load when we start watching
+const FORWARD_BACKFILL: u64 = 100;
 
 #[derive(Deserialize)]
 struct UpdatedInvoicesResponse {
@@ -20,7 +27,7 @@ struct PayIndexScan {
     pay_index: Option<u64>,
 }
 
-async fn find_lastpay_index(commando: Arc<CommandoClient>) -> Result<Option<u64>, lnsocket::Error> {
+async fn find_lastpay_index<C: Commando>(commando: Arc<C>) -> Result<Option<u64>, lnsocket::Error> {
     const PAGE: u64 = 250;
     // 1) get the current updated tail
     let created_value = commando
@@ -42,7 +49,7 @@ async fn find_lastpay_index(commando: Arc<CommandoClient>) -> Result<Option<u64>
     loop {
         // 2) fetch a window (indexed by "updated")
         let val = commando
-            .call_with_opts(
+            .call_filtered(
                 "listinvoices",
                 json!({
                     "index": "updated",
@@ -50,9 +57,9 @@ async fn find_lastpay_index(commando: Arc<CommandoClient>) -> Result<Option<u64>
                     "limit": PAGE,
                 }),
                 // only fetch the one field we care about
-                CallOpts::default().filter(json!({
+                json!({
                     "invoices": [{"pay_index": true}]
-                })),
+                }),
             )
             .await?;
 
@@ -72,8 +79,8 @@ async fn find_lastpay_index(commando: Arc<CommandoClient>) -> Result<Option<u64>
     }
 }
 
-pub async fn fetch_paid_invoices(
-    commando: Arc<CommandoClient>,
+pub async fn fetch_paid_invoices<C: Commando>(
+    commando: Arc<C>,
     limit: u32,
 ) -> Result<Vec<Invoice>, lnsocket::Error> {
     use tokio::task::JoinSet;
@@ -114,85 +121,138 @@ pub async fn fetch_paid_invoices(
     Ok(results)
 }
 
-// wip watch subsystem
-/*
-async fn watch_subsystem(
-    commando: CommandoClient,
-    subsystem: WaitSubsystem,
-    index: WaitIndex,
-    event_tx: UnboundedSender<Event>,
-    mut cancel_rx: Receiver<()>,
-) {
-    // Step 1: Fetch current index value so we can back up ~20
-    let mut nextvalue: u64 = match commando
-        .call(
-            "wait",
-            serde_json::json!({
-                "indexname": index.as_str(),
-                "subsystem": subsystem.as_str(),
-                "nextvalue": 0
-            }),
-        )
-        .await
-    {
-        Ok(v) => {
-            // You showed the result has `updated` as the current highest index
-            let current = v.get("updated").and_then(|x| x.as_u64()).unwrap_or(0);
-            current.saturating_sub(20) // back up 20, clamp at 0
-        }
+/// Wait on `method`, giving up if the dashboard stops listening. Returns
+/// `None` when it did.
+async fn call_while_listening<C: Commando>(
+    commando: &C,
+    event_tx: &UnboundedSender<Event>,
+    method: &str,
+    params: Value,
+) -> Option<CallResult> {
+    tokio::select! {
+        _ = event_tx.closed() => None,
+        res = commando.call(method, params) => Some(res),
+    }
+}
+
+/// Wait before retrying a failed call, giving up if the dashboard stops
+/// listening. Returns false when it did.
+async fn retry_while_listening(event_tx: &UnboundedSender<Event>, backoff: &mut Backoff) -> bool {
+    let delay = backoff.schedule(Instant::now());
+    tokio::select! {
+        _ = event_tx.closed() => false,
+        _ = tokio::time::sleep(delay) => true,
+    }
+}
+
+/// Stream invoices as they get paid, starting after the last paid one
+pub async fn watch_invoices<C: Commando>(commando: Arc<C>, event_tx: UnboundedSender<Event>) {
+    let mut lastpay_index = match find_lastpay_index(commando.clone()).await {
+        Ok(index) => index.unwrap_or(0),
         Err(err) => {
-            tracing::warn!("initial wait(…nextvalue=0) failed: {}", err);
-            0
+            tracing::warn!("not watching invoices: {err}");
+            return;
         }
     };
 
+    let mut backoff = Backoff::default();
     loop {
-        // You can add a timeout to avoid hanging forever in weird network states.
-        let fut = commando.call(
-            "wait",
-            serde_json::to_value(WaitRequest {
-                indexname: "invoices".into(),
-                subsystem: "lightningd".into(),
-                nextvalue,
-            })
-            .unwrap(),
-        );
-
-        tokio::select! {
-            _ = &mut cancel_rx => {
-                // graceful shutdown
-                break;
+        let params = json!({ "lastpay_index": lastpay_index });
+        let Some(res) =
+            call_while_listening(commando.as_ref(), &event_tx, "waitanyinvoice", params).await
+        else {
+            return;
+        };
+
+        let invoice = res
+            .and_then(|v| serde_json::from_value::<Invoice>(v).map_err(|_| lnsocket::Error::Json));
+
+        match invoice {
+            Ok(invoice) => {
+                backoff.reset();
+                lastpay_index = invoice.pay_index.unwrap_or(lastpay_index + 1);
+                if event_tx
+                    .send(Event::Response(ClnResponse::InvoicePaid(invoice)))
+                    .is_err()
+                {
+                    return;
+                }
             }
 
-            res = fut => {
-                match res {
-                    Ok(v) => {
-                        // Typical shape: { "nextvalue": n, "invoicestatus": { ... } } (varies by plugin/index)
-                        // Adjust these lookups for your node’s actual wait payload.
-                        if let Some(nv) = v.get("nextvalue").and_then(|x| x.as_u64()) {
-                            nextvalue = nv + 1;
-                        } else {
-                            // Defensive: never get stuck — bump at least by 1
-                            nextvalue += 1;
-                        }
-
-                        // Inspect/route
-                        let kind = v.get("status").and_then(|s| s.as_str());
-                        let ev = match kind {
-                            Some("paid") => ClnResponse::Invoice(InvoiceEvent::Paid(v.clone())),
-                            Some("created") => ClnResponse::Invoice(InvoiceEvent::Created(v.clone())),
-                            _ => ClnResponse::Invoice(InvoiceEvent::Other(v.clone())),
-                        };
-                        let _ = event_tx.send(Event::Response(ev));
+            Err(err) => {
+                tracing::warn!("waitanyinvoice error: {err}");
+                // don't tight-loop on persistent errors
+                if !retry_while_listening(&event_tx, &mut backoff).await {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Stream forwards as they get updated, starting with the last
+/// [`FORWARD_BACKFILL`] so the fee chart has something to show.
+pub async fn watch_forwards<C: Commando>(commando: Arc<C>, event_tx: UnboundedSender<Event>) {
+    let wait_params = |nextvalue: u64| json!({"subsystem": "forwards", "indexname": "updated", "nextvalue": nextvalue});
+
+    let mut tail = match commando.call("wait", wait_params(0)).await {
+        Ok(v) => v["updated"].as_u64().unwrap_or(0),
+        Err(err) => {
+            tracing::warn!("not watching forwards: {err}");
+            return;
+        }
+    };
+
+    let mut start = tail.saturating_sub(FORWARD_BACKFILL).max(1);
+    let mut backoff = Backoff::default();
+
+    loop {
+        if start <= tail {
+            let forwards = commando
+                .call("listforwards", json!({"index": "updated", "start": start}))
+                .await
+                .and_then(parse_forwards);
+
+            match forwards {
+                Ok((forwards, max_index)) => {
+                    backoff.reset();
+                    start = max_index.unwrap_or(0).max(tail) + 1;
+                    if !forwards.is_empty()
+                        && event_tx
+                            .send(Event::Response(ClnResponse::Forwards(forwards)))
+                            .is_err()
+                    {
+                        return;
                     }
-                    Err(err) => {
-                        tracing::warn!("wait(invoices) error: {err}");
-                        // small backoff so we don't tight-loop on persistent errors
-                        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                }
+
+                Err(err) => {
+                    tracing::warn!("listforwards error: {err}");
+                    if !retry_while_listening(&event_tx, &mut backoff).await {
+                        return;
                     }
+                    continue;
+                }
+            }
+        }
+
+        let Some(res) =
+            call_while_listening(commando.as_ref(), &event_tx, "wait", wait_params(start)).await
+        else {
+            return;
+        };
+
+        match res {
+            Ok(v) => {
+                backoff.reset();
+                tail = v["updated"].as_u64().unwrap_or(start);
+            }
+            Err(err) => {
+                tracing::warn!("wait(forwards) error: {err}");
+                if !retry_while_listening(&event_tx, &mut backoff).await {
+                    return;
                 }
             }
         }
     }
 }
-*/
@@ -0,0 +1,392 @@
+//! Zap analytics from paid invoices. Zap invoices carry the zap request
+//! as their description, which tells us who zapped and which note.
+
+use crate::invoice::Invoice;
+use crate::ui;
+use egui::Color32;
+use nostrdb::{Ndb, Transaction};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+use std::path::Path;
+
+const SECS_PER_DAY: u64 = 60 * 60 * 24;
+const TOP_N: usize = 10;
+const DAYS_SHOWN: usize = 30;
+
+#[derive(Deserialize)]
+struct ZapRequest {
+    #[serde(with = "hex::serde")]
+    pubkey: [u8; 32],
+    tags: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZapRecord {
+    pub label: String,
+    /// unix seconds
+    pub paid_at: u64,
+    pub amount_msat: u64,
+    pub zapper: [u8; 32],
+    /// The note that was zapped, from the zap request's `e` tag
+    pub note: Option<[u8; 32]>,
+}
+
+impl ZapRecord {
+    pub fn from_invoice(invoice: &Invoice) -> Option<Self> {
+        let zap_req: ZapRequest = serde_json::from_str(&invoice.description).ok()?;
+
+        let note = zap_req
+            .tags
+            .iter()
+            .find(|tag| tag.first().map(String::as_str) == Some("e"))
+            .and_then(|tag| tag.get(1))
+            .and_then(|id| {
+                let mut bytes = [0u8; 32];
+                hex::decode_to_slice(id, &mut bytes).ok()?;
+                Some(bytes)
+            });
+
+        Some(ZapRecord {
+            label: invoice.label.clone(),
+            paid_at: invoice.paid_at?,
+            amount_msat: invoice.amount_received_msat.unwrap_or(invoice.amount_msat),
+            zapper: zap_req.pubkey,
+            note,
+        })
+    }
+
+    fn day(&self) -> u64 {
+        self.paid_at / SECS_PER_DAY
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    pub count: u64,
+    pub msat: u64,
+}
+
+impl Tally {
+    fn add(&mut self, msat: u64) {
+        self.count += 1;
+        self.msat += msat;
+    }
+}
+
+#[derive(Default)]
+pub struct ZapStats {
+    /// (unix day, tally), oldest first
+    pub per_day: Vec<(u64, Tally)>,
+    /// Sorted by amount, largest first
+    pub top_zappers: Vec<([u8; 32], Tally)>,
+    /// Sorted by amount, largest first
+    pub top_notes: Vec<([u8; 32], Tally)>,
+}
+
+fn top<K: Copy>(tallies: HashMap<K, Tally>) -> Vec<(K, Tally)> {
+    let mut top: Vec<(K, Tally)> = tallies.into_iter().collect();
+    top.sort_by(|a, b| b.1.msat.cmp(&a.1.msat).then(b.1.count.cmp(&a.1.count)));
+    top.truncate(TOP_N);
+    top
+}
+
+impl ZapStats {
+    pub fn compute(records: &[ZapRecord]) -> Self {
+        let mut per_day: HashMap<u64, Tally> = HashMap::new();
+        let mut zappers: HashMap<[u8; 32], Tally> = HashMap::new();
+        let mut notes: HashMap<[u8; 32], Tally> = HashMap::new();
+
+        for record in records {
+            per_day
+                .entry(record.day())
+                .or_default()
+                .add(record.amount_msat);
+            zappers
+                .entry(record.zapper)
+                .or_default()
+                .add(record.amount_msat);
+            if let Some(note) = record.note {
+                notes.entry(note).or_default().add(record.amount_msat);
+            }
+        }
+
+        let mut per_day: Vec<(u64, Tally)> = per_day.into_iter().collect();
+        per_day.sort_by_key(|(day, _)| *day);
+
+        ZapStats {
+            per_day,
+            top_zappers: top(zappers),
+            top_notes: top(notes),
+        }
+    }
+}
+
+/// Zaps we've seen, and stats over them
+#[derive(Default)]
+pub struct ZapAnalytics {
+    records: Vec<ZapRecord>,
+    labels: HashSet<String>,
+    stats: ZapStats,
+}
+
+impl ZapAnalytics {
+    pub fn add_invoices<'a>(&mut self, invoices: impl IntoIterator<Item = &'a Invoice>) {
+        let mut changed = false;
+
+        for invoice in invoices {
+            if self.labels.contains(&invoice.label) {
+                continue;
+            }
+
+            if let Some(record) = ZapRecord::from_invoice(invoice) {
+                self.labels.insert(record.label.clone());
+                self.records.push(record);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.records.sort_by_key(|r| r.paid_at);
+            self.stats = ZapStats::compute(&self.records);
+        }
+    }
+
+    #[cfg(any(test, target_os = "windows", target_os = "macos", target_os = "linux"))]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("paid_at,date,amount_msat,zapper,note_id,invoice_label\n");
+
+        for record in &self.records {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                record.paid_at,
+                format_date(record.paid_at),
+                record.amount_msat,
+                hex::encode(record.zapper),
+                record.note.map(hex::encode).unwrap_or_default(),
+                csv_field(&record.label),
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(any(test, target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn format_date(unix_secs: u64) -> String {
+    chrono::DateTime::from_timestamp(unix_secs as i64, 0)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn export_csv(path: &Path, csv: &str) {
+    if let Err(err) = std::fs::write(path, csv) {
+        tracing::error!("could not export zaps to {}: {err}", path.display());
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn export_button_ui(ui: &mut egui::Ui, analytics: &ZapAnalytics) {
+    if !ui.button("Export CSV").clicked() {
+        return;
+    }
+
+    // the dialog is opened here, waiting on it would block the ui
+    let dialog = rfd::AsyncFileDialog::new()
+        .set_file_name("zaps.csv")
+        .add_filter("CSV", &["csv"])
+        .save_file();
+    let csv = analytics.to_csv();
+    tokio::spawn(async move {
+        if let Some(file) = dialog.await {
+            export_csv(file.path(), &csv);
+        }
+    });
+}
+
+pub fn zaps_ui(ui: &mut egui::Ui, ndb: &Ndb, analytics: &ZapAnalytics) {
+    egui::CollapsingHeader::new("Zaps").show(ui, |ui| {
+        if analytics.records.is_empty() {
+            ui.label("no zaps yet...");
+            return;
+        }
+
+        let total: u64 = analytics.records.iter().map(|r| r.amount_msat).sum();
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} zaps, {}",
+                analytics.records.len(),
+                ui::human_verbose_sat(total as i64)
+            ));
+
+            #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+            export_button_ui(ui, analytics);
+        });
+
+        ui.add_space(4.0);
+        ui.strong("Per day");
+        per_day_ui(ui, &analytics.stats.per_day);
+
+        let Ok(txn) = Transaction::new(ndb) else {
+            return;
+        };
+
+        ui.add_space(8.0);
+        ui.strong("Top zappers");
+        for (pubkey, tally) in &analytics.stats.top_zappers {
+            let profile = ndb.get_profile_by_pubkey(&txn, pubkey).ok();
+            let name = notedeck::name::get_display_name(profile.as_ref());
+            let name = if profile.is_some() {
+                name.name().to_owned()
+            } else {
+                short_hex(pubkey)
+            };
+            tally_ui(ui, &name, tally);
+        }
+
+        ui.add_space(8.0);
+        ui.strong("Top zapped notes");
+        for (note_id, tally) in &analytics.stats.top_notes {
+            let content = ndb
+                .get_note_by_id(&txn, note_id)
+                .map(|note| note.content().to_owned())
+                .unwrap_or_else(|_| short_hex(note_id));
+            tally_ui(ui, &content, tally);
+        }
+    });
+}
+
+fn short_hex(bytes: &[u8; 32]) -> String {
+    format!("{}…", hex::encode(&bytes[..4]))
+}
+
+fn tally_ui(ui: &mut egui::Ui, name: &str, tally: &Tally) {
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} ({}x)",
+            ui::human_verbose_sat(tally.msat as i64),
+            tally.count
+        ));
+        ui.add(egui::Label::new(name).truncate());
+    });
+}
+
+/// Bars for the last [`DAYS_SHOWN`] days with zaps
+fn per_day_ui(ui: &mut egui::Ui, per_day: &[(u64, Tally)]) {
+    let days = &per_day[per_day.len().saturating_sub(DAYS_SHOWN)..];
+    let max_msat = days.iter().map(|(_, t)| t.msat).max().unwrap_or(1).max(1);
+    let bar_color = Color32::from_rgb(247, 147, 26); // orange
+
+    let height = 80.0;
+    let (rect, _) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), height),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+
+    let slot = rect.width() / DAYS_SHOWN as f32;
+    for (i, (day, tally)) in days.iter().enumerate() {
+        let bar_h = (tally.msat as f32 / max_msat as f32) * (height - 8.0);
+        let left = rect.left() + i as f32 * slot;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left + 1.0, rect.bottom() - bar_h.max(1.0)),
+            egui::pos2(left + slot - 1.0, rect.bottom()),
+        );
+        painter.rect_filled(bar, 1.0, bar_color);
+
+        let slot_rect = egui::Rect::from_x_y_ranges(left..=left + slot, rect.y_range());
+        ui.interact(
+            slot_rect,
+            ui.id().with(("zap-day", day)),
+            egui::Sense::hover(),
+        )
+        .on_hover_text_at_pointer(format!(
+            "{}\n{} zaps, {}",
+            format_date(day * SECS_PER_DAY),
+            tally.count,
+            ui::human_verbose_sat(tally.msat as i64)
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(paid_at: u64, sats: u64, zapper: u8, note: Option<u8>) -> ZapRecord {
+        ZapRecord {
+            label: format!("{paid_at}"),
+            paid_at,
+            amount_msat: sats * 1000,
+            zapper: [zapper; 32],
+            note: note.map(|n| [n; 32]),
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let records = vec![
+            record(10, 21, 1, Some(9)),
+            record(20, 100, 2, Some(9)),
+            record(SECS_PER_DAY + 5, 50, 1, None),
+        ];
+
+        let stats = ZapStats::compute(&records);
+
+        assert_eq!(
+            stats.per_day,
+            vec![
+                (
+                    0,
+                    Tally {
+                        count: 2,
+                        msat: 121_000
+                    }
+                ),
+                (
+                    1,
+                    Tally {
+                        count: 1,
+                        msat: 50_000
+                    }
+                )
+            ]
+        );
+
+        assert_eq!(stats.top_zappers[0].0, [2; 32]);
+        assert_eq!(stats.top_zappers[1].1.count, 2);
+        assert_eq!(stats.top_notes.len(), 1);
+        assert_eq!(stats.top_notes[0].1.msat, 121_000);
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut analytics = ZapAnalytics::default();
+        analytics.records.push(record(86400, 21, 1, None));
+        analytics.records[0].label = "a,b".to_string();
+
+        let csv = analytics.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("paid_at,date,amount_msat,zapper,note_id,invoice_label")
+        );
+        assert_eq!(
+            lines
+                .next()
+                .map(|l| l.split(',').take(3).collect::<Vec<_>>()),
+            Some(vec!["86400", "1970-01-02", "21000"])
+        );
+        assert!(csv.ends_with(",\"a,b\"\n"));
+    }
+}
@@ -16,9 +16,11 @@ use enostr::{FilledKeypair, NoteId, Pubkey, RelayPool};
 use nostrdb::{IngestMetadata, Ndb, NoteBuilder, NoteKey, Transaction};
 use notedeck::{
     get_wallet_for, is_future_timestamp,
-    note::{reaction_sent_id, ReactAction, ZapTargetAmount},
+    note::{reaction_sent_id, ReactAction, VoteAction, ZapTargetAmount},
+    poll::POLL_RESPONSE_KIND,
     unix_time_secs, Accounts, GlobalWallet, Images, MediaJobSender, NoteAction, NoteCache,
-    NoteZapTargetOwned, UnknownIds, ZapAction, ZapTarget, ZappingError, Zaps,
+    NoteContextSelection, NoteZapTargetOwned, RelayAction, UnknownIds, ZapAction, ZapTarget,
+    ZappingError, Zaps,
 };
 use notedeck_ui::media::MediaViewerFlags;
 use tracing::error;
@@ -90,6 +92,16 @@ fn execute_note_action(
                         true,
                     )
                 });
+                accounts.record_reaction(&react_action.content, react_action.emoji.as_ref());
+            } else {
+                router_action = Some(RouterAction::route_to(Route::accounts()));
+            }
+        }
+        NoteAction::Vote(vote_action) => {
+            if let Some(filled) = accounts.selected_filled() {
+                if let Err(err) = send_poll_vote(ndb, pool, filled, &vote_action) {
+                    tracing::error!("Failed to send poll vote: {err}");
+                }
             } else {
                 router_action = Some(RouterAction::route_to(Route::accounts()));
             }
@@ -131,6 +143,9 @@ fn execute_note_action(
                 make_new: preview,
             });
         }
+        NoteAction::AddRelay(url) => {
+            accounts.process_relay_action(ui.ctx(), pool, RelayAction::Add(url));
+        }
         NoteAction::Hashtag(htag) => {
             let kind = TimelineKind::Hashtag(vec![htag.clone()]);
             router_action = Some(RouterAction::route_to(Route::Timeline(kind.clone())));
@@ -189,6 +204,14 @@ fn execute_note_action(
         }
         NoteAction::Context(context) => match ndb.get_note_by_key(txn, context.note_key) {
             Err(err) => tracing::error!("{err}"),
+            Ok(note) if context.action == NoteContextSelection::Highlight => {
+                let route = if can_post {
+                    Route::Highlight(NoteId::new(*note.id()))
+                } else {
+                    Route::accounts()
+                };
+                router_action = Some(RouterAction::route_to(route));
+            }
             Ok(note) => {
                 context.action.process_selection(ui, &note, pool, txn);
             }
@@ -293,7 +316,15 @@ fn send_reaction_event(
     let target_kind = note.kind();
     let d_tag_value = find_addressable_d_tag(&note);
 
-    let mut builder = NoteBuilder::new().kind(7).content(reaction.content);
+    let mut builder = NoteBuilder::new().kind(7).content(&reaction.content);
+
+    if let Some(emoji) = &reaction.emoji {
+        builder = builder
+            .start_tag()
+            .tag_str("emoji")
+            .tag_str(&emoji.shortcode)
+            .tag_str(&emoji.url);
+    }
 
     builder = builder
         .start_tag()
@@ -347,6 +378,45 @@ fn send_reaction_event(
     Ok(())
 }
 
+/// Send a NIP-88 poll response. Our latest response is the one that counts,
+/// so changing a vote is just sending another one.
+fn send_poll_vote(
+    ndb: &mut Ndb,
+    pool: &mut RelayPool,
+    kp: FilledKeypair<'_>,
+    vote: &VoteAction,
+) -> Result<(), String> {
+    let mut builder = NoteBuilder::new()
+        .kind(POLL_RESPONSE_KIND)
+        .content("")
+        .start_tag()
+        .tag_str("e")
+        .tag_id(vote.poll_id.bytes());
+
+    for option in &vote.options {
+        builder = builder.start_tag().tag_str("response").tag_str(option);
+    }
+
+    let note = builder
+        .sign(&kp.secret_key.secret_bytes())
+        .build()
+        .ok_or_else(|| "failed to build poll response".to_owned())?;
+
+    let Ok(event) = &enostr::ClientMessage::event(&note) else {
+        return Err("failed to convert poll response into client message".to_owned());
+    };
+
+    let Ok(json) = event.to_json() else {
+        return Err("failed to serialize poll response to json".to_owned());
+    };
+
+    let _ = ndb.process_event_with(&json, IngestMetadata::new().client(true));
+
+    pool.send(event);
+
+    Ok(())
+}
+
 fn find_addressable_d_tag(note: &nostrdb::Note<'_>) -> Option<String> {
     for tag in note.tags() {
         if tag.count() < 2 {
@@ -167,6 +167,9 @@ fn try_process_event(
     };
 
     app_ctx.pool.keepalive_ping(wakeup);
+    for (relay, outcome) in app_ctx.pool.poll_relay_info() {
+        timeline::fetch_reconciled(&mut damus.subscriptions, app_ctx.pool, &relay, outcome);
+    }
 
     // NOTE: we don't use the while let loop due to borrow issues
     #[allow(clippy::while_let_loop)]
@@ -186,6 +189,7 @@ fn try_process_event(
 
                 timeline::send_initial_timeline_filters(
                     damus.options.contains(AppOptions::SinceOptimize),
+                    app_ctx.ndb,
                     &mut damus.timeline_cache,
                     &mut damus.subscriptions,
                     app_ctx.pool,
@@ -416,16 +420,35 @@ fn process_message(damus: &mut Damus, ctx: &mut AppContext<'_>, relay: &str, msg
             }
         }
         RelayMessage::Notice(msg) => warn!("Notice from {}: {}", relay, msg),
+        RelayMessage::NegMsg(sid, msg) => {
+            if let Some(outcome) = ctx.pool.neg_msg(relay, sid, msg) {
+                timeline::fetch_reconciled(&mut damus.subscriptions, ctx.pool, relay, outcome);
+            }
+        }
+        RelayMessage::NegErr(sid, reason) => {
+            warn!("{relay} couldn't reconcile {sid}: {reason}");
+            if let Some(outcome) = ctx.pool.neg_err(relay, sid) {
+                timeline::fetch_reconciled(&mut damus.subscriptions, ctx.pool, relay, outcome);
+            }
+        }
         RelayMessage::OK(cr) => info!("OK {:?}", cr),
         RelayMessage::Eose(sid) => {
-            if let Err(err) = handle_eose(
-                &damus.subscriptions,
-                &mut damus.timeline_cache,
-                ctx,
-                sid,
-                relay,
-            ) {
-                error!("error handling eose: {}", err);
+            // the relay's subscription may be shared by several of ours
+            for sid in ctx.pool.demux_eose(relay, sid) {
+                // another app's subscription, the pool keeps its EOSEs
+                if ctx.pool.eosed(&sid).is_some() {
+                    continue;
+                }
+
+                if let Err(err) = handle_eose(
+                    &damus.subscriptions,
+                    &mut damus.timeline_cache,
+                    ctx,
+                    &sid,
+                    relay,
+                ) {
+                    error!("error handling eose: {}", err);
+                }
             }
         }
     }
@@ -647,6 +670,13 @@ impl Damus {
         }
     }
 
+    /// Keep processing relay events while another app is in front. The
+    /// relay pool is shared, so this is also how notes and profiles other
+    /// apps ask for through `UnknownIds` get fetched.
+    pub fn update_in_background(&mut self, app_ctx: &mut AppContext<'_>, ctx: &egui::Context) {
+        update_damus(self, app_ctx, ctx);
+    }
+
     pub fn subscriptions(&mut self) -> &mut HashMap<String, SubKind> {
         &mut self.subscriptions.subs
     }
@@ -683,6 +713,8 @@ fn get_note_options(args: ColumnsArgs, settings_handler: &mut SettingsHandler) -
         NoteOptions::RepliesNewestFirst,
         settings_handler.show_replies_newest_first(),
     );
+    note_options.set_content_warnings(settings_handler.content_warnings());
+    note_options.set(NoteOptions::Markdown, settings_handler.markdown());
     note_options
 }
 
@@ -838,6 +870,7 @@ fn should_show_compose_button(decks: &DecksCache, accounts: &Accounts) -> bool {
                 TimelineKind::Universe => true,
                 TimelineKind::Generic(_) => true,
                 TimelineKind::Hashtag(_) => true,
+                TimelineKind::Highlights => true,
 
                 // no!
                 TimelineKind::Search(_) => false,
@@ -849,6 +882,7 @@ fn should_show_compose_button(decks: &DecksCache, accounts: &Accounts) -> bool {
         Route::Accounts(_) => false,
         Route::Reply(_) => false,
         Route::Quote(_) => false,
+        Route::Highlight(_) => false,
         Route::Relays => false,
         Route::Settings => false,
         Route::ComposeNote => false,
@@ -1,4 +1,5 @@
 use egui::text::LayoutJob;
+use notedeck::poll::{Poll, PollOption, PollType};
 use poll_promise::Promise;
 
 use crate::{
@@ -7,7 +8,7 @@ use crate::{
     ui::{note::PostType, search::FocusState},
     Error,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range};
 
 #[derive(Default)]
 pub struct Draft {
@@ -18,6 +19,71 @@ pub struct Draft {
     pub uploading_media: Vec<Promise<Result<Nip94Event, Error>>>, // promises that aren't ready yet
     pub upload_errors: Vec<String>,      // media upload errors to show the user
     pub focus_state: FocusState,
+    pub content_warning: Option<String>, // NIP-36 reason, when the note gets a content warning
+    pub poll: Option<PollDraft>,         // set when the note is a NIP-88 poll
+}
+
+/// The poll durations offered in the composer, in seconds
+pub const POLL_DURATIONS: &[u64] = &[60 * 60, 24 * 60 * 60, 3 * 24 * 60 * 60, 7 * 24 * 60 * 60];
+
+/// A poll being written in the composer
+pub struct PollDraft {
+    pub options: Vec<String>,
+    pub poll_type: PollType,
+    /// How long the poll takes votes for, in seconds. Forever if `None`
+    pub duration: Option<u64>,
+}
+
+impl Default for PollDraft {
+    fn default() -> Self {
+        Self {
+            options: vec![String::new(), String::new()],
+            poll_type: PollType::SingleChoice,
+            duration: Some(POLL_DURATIONS[1]),
+        }
+    }
+}
+
+impl PollDraft {
+    /// The poll to publish. Empty options are left out, and a poll needs at
+    /// least two.
+    pub fn to_poll(&self, now: u64) -> Option<Poll> {
+        let options: Vec<PollOption> = self
+            .options
+            .iter()
+            .map(|label| label.trim())
+            .filter(|label| !label.is_empty())
+            .enumerate()
+            .map(|(i, label)| PollOption {
+                id: i.to_string(),
+                label: label.to_owned(),
+            })
+            .collect();
+
+        if options.len() < 2 {
+            return None;
+        }
+
+        Some(Poll {
+            options,
+            poll_type: self.poll_type,
+            ends_at: self.duration.map(|duration| now + duration),
+        })
+    }
+}
+
+/// A NIP-84 highlight of part of a note being made
+#[derive(Default)]
+pub struct HighlightDraft {
+    pub selection: Option<Range<usize>>, // bytes of the note's content
+    pub comment: String,
+}
+
+impl HighlightDraft {
+    pub fn clear(&mut self) {
+        self.selection = None;
+        self.comment.clear();
+    }
 }
 
 pub struct MentionHint {
@@ -30,6 +96,7 @@ pub struct MentionHint {
 pub struct Drafts {
     replies: HashMap<[u8; 32], Draft>,
     quotes: HashMap<[u8; 32], Draft>,
+    highlights: HashMap<[u8; 32], HighlightDraft>,
     compose: Draft,
 }
 
@@ -53,6 +120,10 @@ impl Drafts {
     pub fn quote_mut(&mut self, id: &[u8; 32]) -> &mut Draft {
         self.quotes.entry(*id).or_default()
     }
+
+    pub fn highlight_mut(&mut self, id: &[u8; 32]) -> &mut HighlightDraft {
+        self.highlights.entry(*id).or_default()
+    }
 }
 
 impl Draft {
@@ -65,5 +136,7 @@ impl Draft {
         self.upload_errors = Vec::new();
         self.uploaded_media = Vec::new();
         self.uploading_media = Vec::new();
+        self.content_warning = None;
+        self.poll = None;
     }
 }
@@ -1,11 +1,10 @@
-use std::collections::HashMap;
 use std::str::FromStr;
 
 use crate::Error;
 use ehttp::{Request, Response};
 use enostr::{Keypair, Pubkey, SecretKey};
+use notedeck::nip05::{nip05_url, Nip05Result};
 use poll_promise::Promise;
-use serde::{Deserialize, Serialize};
 use tracing::error;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -27,12 +26,6 @@ impl std::fmt::Display for AcquireKeyError {
 
 impl std::error::Error for AcquireKeyError {}
 
-#[derive(Deserialize, Serialize)]
-pub struct Nip05Result {
-    pub names: HashMap<String, String>,
-    pub relays: Option<HashMap<String, Vec<String>>>,
-}
-
 fn parse_nip05_response(response: Response) -> Result<Nip05Result, Error> {
     serde_json::from_slice::<Nip05Result>(&response.bytes)
         .map_err(|e| Error::Generic(e.to_string()))
@@ -77,8 +70,7 @@ fn get_nip05_pubkey(id: &str) -> Promise<Result<Pubkey, Error>> {
         return promise;
     }
 
-    let url = format!("https://{host}/.well-known/nostr.json?name={user}");
-    let request = Request::get(url);
+    let request = Request::get(nip05_url(user, host));
 
     let cloned_user = user.to_string();
     ehttp::fetch(request, move |response: Result<Response, String>| {
@@ -20,8 +20,8 @@ use crate::{
         column::NavTitle,
         configure_deck::ConfigureDeckView,
         edit_deck::{EditDeckResponse, EditDeckView},
-        note::{custom_zap::CustomZapView, NewPostAction, PostAction, PostType},
-        profile::EditProfileView,
+        note::{custom_zap::CustomZapView, HighlightAction, NewPostAction, PostAction, PostType},
+        profile::{EditProfileState, EditProfileView},
         repost::RepostDecisionView,
         search::{FocusState, SearchView},
         settings::SettingsAction,
@@ -65,6 +65,7 @@ pub enum RenderNavAction {
     /// The response when the user interacts with a pfp in the nav header
     PfpClicked,
     PostAction(NewPostAction),
+    HighlightAction(HighlightAction),
     NoteAction(NoteAction),
     ProfileAction(ProfileAction),
     SwitchingAction(SwitchingAction),
@@ -365,7 +366,7 @@ fn handle_navigating_edit_profile(ndb: &Ndb, accounts: &Accounts, app: &mut Damu
     };
 
     let txn = Transaction::new(ndb).expect("txn");
-    app.view_state.pubkey_to_profile_state.insert(pk, {
+    let state = {
         let filter = Filter::new_with_capacity(1)
             .kinds([0])
             .authors([pk.bytes()])
@@ -384,7 +385,11 @@ fn handle_navigating_edit_profile(ndb: &Ndb, accounts: &Accounts, app: &mut Damu
         } else {
             ProfileState::default()
         }
-    });
+    };
+
+    app.view_state
+        .pubkey_to_profile_state
+        .insert(pk, EditProfileState::new(state));
 }
 
 fn handle_navigating_timeline(
@@ -532,6 +537,15 @@ fn process_render_nav_action(
 
             Some(RouterAction::GoBack)
         }
+        RenderNavAction::HighlightAction(highlight_action) => {
+            let txn = Transaction::new(ctx.ndb).expect("txn");
+            match highlight_action.execute(ctx.ndb, &txn, ctx.pool, &mut app.drafts) {
+                Err(err) => tracing::error!("Error executing highlight action: {err}"),
+                Ok(_) => tracing::debug!("Highlight action executed"),
+            }
+
+            Some(RouterAction::GoBack)
+        }
         RenderNavAction::NoteAction(note_action) => {
             let txn = Transaction::new(ctx.ndb).expect("txn");
 
@@ -590,6 +604,7 @@ fn process_render_nav_action(
             ctx.settings,
             ctx.i18n,
             ctx.img_cache,
+            ctx.note_cache,
             ui.ctx(),
             ctx.accounts,
         ),
@@ -639,6 +654,7 @@ fn render_nav_body(
         clipboard: ctx.clipboard,
         i18n: ctx.i18n,
         global_wallet: ctx.global_wallet,
+        nip05_cache: ctx.nip05_cache,
     };
     match top {
         Route::Timeline(kind) => {
@@ -702,17 +718,26 @@ fn render_nav_body(
                 }
             })
         }
-        Route::Relays => RelayView::new(ctx.pool, &mut app.view_state.id_string_map, ctx.i18n)
-            .ui(ui)
-            .map_output(RenderNavAction::RelayAction),
-
-        Route::Settings => SettingsView::new(
-            ctx.settings.get_settings_mut(),
-            &mut note_context,
-            &mut app.note_options,
+        Route::Relays => RelayView::new(
+            ctx.pool,
+            &ctx.accounts.get_selected_account().data,
+            &mut app.view_state.id_string_map,
+            ctx.i18n,
         )
         .ui(ui)
-        .map_output(RenderNavAction::SettingsAction),
+        .map_output(RenderNavAction::RelayAction),
+
+        Route::Settings => {
+            let translation_api_key = ctx.settings.translation_api_key().to_owned();
+            SettingsView::new(
+                ctx.settings.get_settings_mut(),
+                translation_api_key,
+                &mut note_context,
+                &mut app.note_options,
+            )
+            .ui(ui)
+            .map_output(RenderNavAction::SettingsAction)
+        }
 
         Route::Reply(id) => {
             let txn = if let Ok(txn) = Transaction::new(ctx.ndb) {
@@ -794,6 +819,28 @@ fn render_nav_body(
 
             response.map_output_maybe(|o| Some(o.action?.into()))
         }
+        Route::Highlight(id) => {
+            let txn = Transaction::new(ctx.ndb).expect("txn");
+
+            let Ok(note) = ctx.ndb.get_note_by_id(&txn, id.bytes()) else {
+                ui.label(tr!(
+                    note_context.i18n,
+                    "Highlight of unknown note",
+                    "Error message when the note being highlighted cannot be found"
+                ));
+                return BodyResponse::none();
+            };
+
+            let Some(poster) = ctx.accounts.selected_filled() else {
+                return BodyResponse::none();
+            };
+
+            let draft = app.drafts.highlight_mut(note.id());
+
+            crate::ui::note::HighlightView::new(&mut note_context, poster, draft, &note, col)
+                .show(ui)
+                .map_output(RenderNavAction::HighlightAction)
+        }
         Route::ComposeNote => {
             let Some(kp) = ctx.accounts.get_selected_account().key.to_full() else {
                 return BodyResponse::none();
@@ -933,6 +980,8 @@ fn render_nav_body(
                 ctx.img_cache,
                 ctx.clipboard,
                 ctx.media_jobs.sender(),
+                ctx.nip05_cache,
+                kp,
             )
             .ui(ui)
             .map_output_maybe(|save| {
@@ -942,7 +991,7 @@ fn render_nav_body(
                         .get(kp.pubkey)
                         .map(|state| {
                             RenderNavAction::ProfileAction(ProfileAction::SaveChanges(
-                                SaveProfileChanges::new(kp.to_full(), state.clone()),
+                                SaveProfileChanges::new(kp.to_full(), state.to_publish()),
                             ))
                         })
                 } else {
@@ -1069,6 +1118,7 @@ fn render_nav_body(
                     &target.zap_recipient,
                     default_msats,
                     ctx.media_jobs.sender(),
+                    ctx.nip05_cache,
                 )
                 .ui(ui),
             )
@@ -5,6 +5,8 @@ use egui::{
 };
 use enostr::{FullKeypair, Pubkey};
 use nostrdb::{Note, NoteBuilder, NoteReply};
+use notedeck::emoji::CustomEmoji;
+use notedeck::poll::{Poll, POLL_KIND};
 use std::{
     any::TypeId,
     collections::{BTreeMap, HashMap, HashSet},
@@ -20,6 +22,12 @@ pub struct NewPost {
     pub account: FullKeypair,
     pub media: Vec<Nip94Event>,
     pub mentions: Vec<Pubkey>,
+    /// Custom emoji used in the content
+    pub emojis: Vec<CustomEmoji>,
+    /// The reason for a NIP-36 content warning, if the note has one
+    pub content_warning: Option<String>,
+    /// Set when the note is a NIP-88 poll
+    pub poll: Option<Poll>,
 }
 
 fn client_variant() -> &'static str {
@@ -34,6 +42,30 @@ fn client_variant() -> &'static str {
     }
 }
 
+fn add_poll_tags<'a>(mut builder: NoteBuilder<'a>, poll: &Poll) -> NoteBuilder<'a> {
+    for option in &poll.options {
+        builder = builder
+            .start_tag()
+            .tag_str("option")
+            .tag_str(&option.id)
+            .tag_str(&option.label);
+    }
+
+    builder = builder
+        .start_tag()
+        .tag_str("polltype")
+        .tag_str(poll.poll_type.as_str());
+
+    if let Some(ends_at) = poll.ends_at {
+        builder = builder
+            .start_tag()
+            .tag_str("endsAt")
+            .tag_str(&ends_at.to_string());
+    }
+
+    builder
+}
+
 fn add_client_tag(builder: NoteBuilder<'_>) -> NoteBuilder<'_> {
     builder
         .start_tag()
@@ -47,22 +79,41 @@ impl NewPost {
         account: enostr::FullKeypair,
         media: Vec<Nip94Event>,
         mentions: Vec<Pubkey>,
+        emojis: Vec<CustomEmoji>,
     ) -> Self {
         NewPost {
             content,
             account,
             media,
             mentions,
+            emojis,
+            content_warning: None,
+            poll: None,
         }
     }
 
+    pub fn with_content_warning(mut self, reason: Option<String>) -> Self {
+        self.content_warning = reason;
+        self
+    }
+
+    pub fn with_poll(mut self, poll: Option<Poll>) -> Self {
+        self.poll = poll;
+        self
+    }
+
     /// creates a NoteBuilder with all the shared data between note, reply & quote reply
     fn builder_with_shared_tags<'a>(&self, mut content: String) -> NoteBuilder<'a> {
         append_urls(&mut content, &self.media);
 
-        let mut builder = NoteBuilder::new().kind(1).content(&content);
+        let kind = if self.poll.is_some() { POLL_KIND } else { 1 };
+        let mut builder = NoteBuilder::new().kind(kind).content(&content);
         builder = add_client_tag(builder);
 
+        if let Some(poll) = &self.poll {
+            builder = add_poll_tags(builder, poll);
+        }
+
         for hashtag in Self::extract_hashtags(&self.content) {
             builder = builder.start_tag().tag_str("t").tag_str(&hashtag);
         }
@@ -75,6 +126,21 @@ impl NewPost {
             builder = add_mention_tags(builder, &self.mentions);
         }
 
+        for emoji in &self.emojis {
+            builder = builder
+                .start_tag()
+                .tag_str("emoji")
+                .tag_str(&emoji.shortcode)
+                .tag_str(&emoji.url);
+        }
+
+        if let Some(reason) = &self.content_warning {
+            builder = builder.start_tag().tag_str("content-warning");
+            if !reason.trim().is_empty() {
+                builder = builder.tag_str(reason.trim());
+            }
+        }
+
         builder
     }
 
@@ -1268,7 +1334,7 @@ mod tests {
 
         let out = buf.output();
         let kp = FullKeypair::generate();
-        let post = NewPost::new(out.text, kp.clone(), Vec::new(), out.mentions);
+        let post = NewPost::new(out.text, kp.clone(), Vec::new(), out.mentions, Vec::new());
         let note = post.to_note(&kp.pubkey);
 
         let mut tags_iter = note.tags().iter();
@@ -1298,7 +1364,7 @@ mod tests {
 
         let out = buf.output();
         let kp = FullKeypair::generate();
-        let post = NewPost::new(out.text, kp.clone(), Vec::new(), out.mentions);
+        let post = NewPost::new(out.text, kp.clone(), Vec::new(), out.mentions, Vec::new());
         let note = post.to_note(&kp.pubkey);
 
         let mut tags_iter = note.tags().iter();
@@ -1327,7 +1393,7 @@ mod tests {
 
         let out = buf.output();
         let kp = FullKeypair::generate();
-        let post = NewPost::new(out.text, kp.clone(), Vec::new(), out.mentions);
+        let post = NewPost::new(out.text, kp.clone(), Vec::new(), out.mentions, Vec::new());
         let note = post.to_note(&kp.pubkey);
 
         let mut tags_iter = note.tags().iter();
@@ -1335,4 +1401,74 @@ mod tests {
         assert!(tags_iter.next().is_none());
         assert_eq!(note.content(), "test @jb55 test");
     }
+
+    #[test]
+    fn note_content_warning() {
+        let kp = FullKeypair::generate();
+        let post = NewPost::new(
+            "spoilers".to_owned(),
+            kp.clone(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        )
+        .with_content_warning(Some(" ending of the movie ".to_owned()));
+        let note = post.to_note(&kp.pubkey);
+
+        let mut tags_iter = note.tags().iter();
+        tags_iter.next(); //ignore the first one, the client tag
+        let tag = tags_iter.next().unwrap();
+        assert_eq!(tag.count(), 2);
+        assert_eq!(tag.get(0).unwrap().str().unwrap(), "content-warning");
+        assert_eq!(tag.get(1).unwrap().str().unwrap(), "ending of the movie");
+        assert_eq!(
+            notedeck::note::content_warning(&note),
+            Some("ending of the movie")
+        );
+
+        let post = NewPost::new(
+            "spoilers".to_owned(),
+            kp.clone(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        )
+        .with_content_warning(Some(String::new()));
+        let note = post.to_note(&kp.pubkey);
+        assert_eq!(notedeck::note::content_warning(&note), Some(""));
+    }
+
+    #[test]
+    fn note_poll() {
+        use notedeck::poll::{PollOption, PollType};
+
+        let kp = FullKeypair::generate();
+        let poll = Poll {
+            options: vec![
+                PollOption {
+                    id: "0".to_owned(),
+                    label: "yes".to_owned(),
+                },
+                PollOption {
+                    id: "1".to_owned(),
+                    label: "no".to_owned(),
+                },
+            ],
+            poll_type: PollType::MultipleChoice,
+            ends_at: Some(1000),
+        };
+        let post = NewPost::new(
+            "should we?".to_owned(),
+            kp.clone(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        )
+        .with_poll(Some(poll.clone()));
+        let note = post.to_note(&kp.pubkey);
+
+        assert_eq!(note.kind(), POLL_KIND);
+        assert_eq!(note.content(), "should we?");
+        assert_eq!(Poll::from_note(&note), Some(poll));
+    }
 }
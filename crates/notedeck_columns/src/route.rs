@@ -19,6 +19,7 @@ pub enum Route {
     Accounts(AccountsRoute),
     Reply(NoteId),
     Quote(NoteId),
+    Highlight(NoteId),
     RepostDecision(NoteId),
     Relays,
     Settings,
@@ -106,6 +107,10 @@ impl Route {
                 writer.write_token("quote");
                 writer.write_token(&note_id.hex());
             }
+            Route::Highlight(note_id) => {
+                writer.write_token("highlight");
+                writer.write_token(&note_id.hex());
+            }
             Route::EditDeck(ind) => {
                 writer.write_token("deck");
                 writer.write_token("edit");
@@ -213,6 +218,12 @@ impl Route {
                         Ok(Route::Quote(NoteId::new(tokenator::parse_hex_id(p)?)))
                     })
                 },
+                |p| {
+                    p.parse_all(|p| {
+                        p.parse_token("highlight")?;
+                        Ok(Route::Highlight(NoteId::new(tokenator::parse_hex_id(p)?)))
+                    })
+                },
                 |p| {
                     p.parse_all(|p| {
                         p.parse_token("reply")?;
@@ -301,6 +312,11 @@ impl Route {
             Route::Quote(_id) => {
                 ColumnTitle::formatted(tr!(i18n, "Quote", "Column title for quote composition"))
             }
+            Route::Highlight(_id) => ColumnTitle::formatted(tr!(
+                i18n,
+                "Highlight",
+                "Column title for highlight composition"
+            )),
             Route::Relays => {
                 ColumnTitle::formatted(tr!(i18n, "Relays", "Column title for relay management"))
             }
@@ -626,6 +642,9 @@ impl fmt::Display for Route {
                 TimelineKind::Universe => {
                     write!(f, "{}", tr!("Universe", "Display name for universe feed"))
                 }
+                TimelineKind::Highlights => {
+                    write!(f, "{}", tr!("Highlights", "Display name for highlights feed"))
+                }
                 TimelineKind::Generic(_) => {
                     write!(f, "{}", tr!("Custom", "Display name for custom timelines"))
                 }
@@ -649,6 +668,11 @@ impl fmt::Display for Route {
             Route::Quote(_id) => {
                 write!(f, "{}", tr!("Quote", "Display name for quote composition"))
             }
+            Route::Highlight(_id) => write!(
+                f,
+                "{}",
+                tr!("Highlight", "Display name for highlight composition")
+            ),
             Route::Relays => write!(f, "{}", tr!("Relays", "Display name for relay management")),
             Route::Settings => write!(f, "{}", tr!("Settings", "Display name for settings management")),
             Route::Accounts(amr) => match amr {
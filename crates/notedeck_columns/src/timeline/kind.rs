@@ -4,6 +4,8 @@ use crate::timeline::{Timeline, TimelineTab};
 use enostr::{Filter, NoteId, Pubkey};
 use nostrdb::{Ndb, Transaction};
 use notedeck::filter::{NdbQueryPackage, ValidKind};
+use notedeck::highlight::HIGHLIGHT_KIND;
+use notedeck::poll::POLL_KIND;
 use notedeck::{
     contacts::{contacts_filter, hybrid_contacts_filter},
     filter::{self, default_limit, default_remote_limit, HybridFilter},
@@ -215,6 +217,9 @@ pub enum TimelineKind {
     Generic(u64),
 
     Hashtag(Vec<String>),
+
+    /// NIP-84 highlights
+    Highlights,
 }
 
 const NOTIFS_TOKEN_DEPRECATED: &str = "notifs";
@@ -315,6 +320,7 @@ impl TimelineKind {
             TimelineKind::Universe => None,
             TimelineKind::Generic(_) => None,
             TimelineKind::Hashtag(_ht) => None,
+            TimelineKind::Highlights => None,
             TimelineKind::Search(query) => query.author(),
         }
     }
@@ -330,10 +336,28 @@ impl TimelineKind {
             TimelineKind::Universe => true,
             TimelineKind::Generic(_) => true,
             TimelineKind::Hashtag(_ht) => true,
+            TimelineKind::Highlights => true,
             TimelineKind::Search(_q) => true,
         }
     }
 
+    /// Timelines we try to hold in full, where it pays to reconcile
+    /// (NIP-77) with relays instead of refetching what we have
+    pub fn can_reconcile(&self) -> bool {
+        match self {
+            TimelineKind::List(_list_kind) => true,
+            TimelineKind::Notifications(_pk_src) => true,
+            TimelineKind::Profile(_pk_src) => true,
+
+            TimelineKind::Algo(_) => false,
+            TimelineKind::Universe => false,
+            TimelineKind::Generic(_) => false,
+            TimelineKind::Hashtag(_ht) => false,
+            TimelineKind::Highlights => false,
+            TimelineKind::Search(_q) => false,
+        }
+    }
+
     // NOTE!!: if you just added a TimelineKind enum, make sure to update
     //         the parser below as well
     pub fn serialize_tokens(&self, writer: &mut TokenWriter) {
@@ -363,6 +387,9 @@ impl TimelineKind {
                 writer.write_token("hashtag");
                 writer.write_token(&ht.join(" "));
             }
+            TimelineKind::Highlights => {
+                writer.write_token("highlights");
+            }
         }
     }
 
@@ -423,6 +450,10 @@ impl TimelineKind {
                             .collect(),
                     ))
                 },
+                |p| {
+                    p.parse_token("highlights")?;
+                    Ok(TimelineKind::Highlights)
+                },
                 |p| {
                     p.parse_token("search")?;
                     let search_query = SearchQuery::parse_from_tokens(p)?;
@@ -467,6 +498,8 @@ impl TimelineKind {
 
             TimelineKind::Universe => FilterState::ready(universe_filter()),
 
+            TimelineKind::Highlights => FilterState::ready(highlights_filter()),
+
             TimelineKind::List(list_k) => match list_k {
                 ListKind::Contact(pubkey) => contact_filter_state(txn, ndb, pubkey),
             },
@@ -586,6 +619,12 @@ impl TimelineKind {
 
             TimelineKind::Hashtag(hashtag) => Some(Timeline::hashtag(hashtag)),
 
+            TimelineKind::Highlights => Some(Timeline::new(
+                TimelineKind::Highlights,
+                FilterState::ready(highlights_filter()),
+                TimelineTab::only_notes_and_replies(),
+            )),
+
             TimelineKind::List(ListKind::Contact(pk)) => Some(Timeline::new(
                 TimelineKind::contact_list(pk),
                 contact_filter_state(txn, ndb, &pk),
@@ -622,6 +661,9 @@ impl TimelineKind {
                 ColumnTitle::formatted(tr!(i18n, "Custom", "Column title for custom timelines"))
             }
             TimelineKind::Hashtag(hashtag) => ColumnTitle::formatted(hashtag.join(" ").to_string()),
+            TimelineKind::Highlights => {
+                ColumnTitle::formatted(tr!(i18n, "Highlights", "Column title for highlights feed"))
+            }
         }
     }
 }
@@ -753,11 +795,19 @@ fn profile_filter(pk: &[u8; 32]) -> HybridFilter {
                 .build()],
             kind: ValidKind::Six,
         },
+        NdbQueryPackage {
+            filters: vec![Filter::new()
+                .authors([pk])
+                .kinds([POLL_KIND as u64])
+                .limit(default_limit())
+                .build()],
+            kind: ValidKind::Poll,
+        },
     ];
 
     let remote = vec![Filter::new()
         .authors([pk])
-        .kinds([1, 6, 0, 3])
+        .kinds([1, 6, POLL_KIND as u64, 0, 3])
         .limit(default_remote_limit())
         .build()];
 
@@ -769,5 +819,15 @@ fn search_filter(s: &SearchQuery) -> Vec<Filter> {
 }
 
 fn universe_filter() -> Vec<Filter> {
-    vec![Filter::new().kinds([1]).limit(default_limit()).build()]
+    vec![Filter::new()
+        .kinds([1, POLL_KIND as u64])
+        .limit(default_limit())
+        .build()]
+}
+
+fn highlights_filter() -> Vec<Filter> {
+    vec![Filter::new()
+        .kinds([HIGHLIGHT_KIND as u64])
+        .limit(default_limit())
+        .build()]
 }
@@ -6,6 +6,8 @@ use crate::{
     Result,
 };
 
+use notedeck::highlight::HIGHLIGHT_KIND;
+use notedeck::poll::POLL_KIND;
 use notedeck::{
     contacts::hybrid_contacts_filter,
     filter::{self, HybridFilter},
@@ -14,7 +16,8 @@ use notedeck::{
 };
 
 use egui_virtual_list::VirtualList;
-use enostr::{PoolRelay, Pubkey, RelayPool};
+use enostr::relay::negentropy::Item;
+use enostr::{ClientMessage, NegOutcome, PoolRelay, Pubkey, RelayPool};
 use nostrdb::{Filter, Ndb, Note, NoteKey, Transaction};
 use std::rc::Rc;
 use std::{cell::RefCell, collections::HashSet};
@@ -73,7 +76,7 @@ impl ViewFilter {
     }
 
     fn notes_and_replies(_cache: &CachedNote, note: &Note) -> bool {
-        note.kind() == 1 || note.kind() == 6
+        matches!(note.kind(), 1 | 6 | POLL_KIND | HIGHLIGHT_KIND)
     }
 
     fn mentions_only(cache: &CachedNote, note: &Note) -> bool {
@@ -579,7 +582,7 @@ pub fn setup_new_timeline(
     }
 
     for relay in &mut pool.relays {
-        send_initial_timeline_filter(since_optimize, subs, relay, timeline, accounts);
+        send_initial_timeline_filter(since_optimize, ndb, txn, subs, relay, timeline, accounts);
     }
     timeline.subscription.increment();
 }
@@ -590,6 +593,7 @@ pub fn setup_new_timeline(
 /// setup_new_timeline.
 pub fn send_initial_timeline_filters(
     since_optimize: bool,
+    ndb: &Ndb,
     timeline_cache: &mut TimelineCache,
     subs: &mut Subscriptions,
     pool: &mut RelayPool,
@@ -598,9 +602,10 @@ pub fn send_initial_timeline_filters(
 ) -> Option<()> {
     info!("Sending initial filters to {}", relay_id);
     let relay = &mut pool.relays.iter_mut().find(|r| r.url() == relay_id)?;
+    let txn = Transaction::new(ndb).ok()?;
 
     for (_kind, timeline) in timeline_cache {
-        send_initial_timeline_filter(since_optimize, subs, relay, timeline, accounts);
+        send_initial_timeline_filter(since_optimize, ndb, &txn, subs, relay, timeline, accounts);
     }
 
     Some(())
@@ -608,6 +613,8 @@ pub fn send_initial_timeline_filters(
 
 pub fn send_initial_timeline_filter(
     can_since_optimize: bool,
+    ndb: &Ndb,
+    txn: &Transaction,
     subs: &mut Subscriptions,
     relay: &mut PoolRelay,
     timeline: &mut Timeline,
@@ -632,6 +639,16 @@ pub fn send_initial_timeline_filter(
 
         FilterState::Ready(filter) => {
             let filter = filter.to_owned();
+
+            // relays that speak negentropy can tell us exactly which of
+            // the notes we don't have, so the subscription only needs to
+            // cover new ones. This usually runs before we know whether the
+            // relay does, reconciliations it can't do fall back to a REQ
+            let reconcile = can_since_optimize
+                && timeline.kind.can_reconcile()
+                && relay.supports_negentropy()
+                && !timeline.all_or_any_entries().is_empty();
+
             let new_filters: Vec<Filter> = filter.remote().to_owned().into_iter().map(|f| {
                 // limit the size of remote filters
                 let default_limit = filter::default_remote_limit();
@@ -650,7 +667,8 @@ pub fn send_initial_timeline_filter(
                 // and seeing what its limit is. If we have less
                 // notes than the limit, we might want to backfill
                 // older notes
-                if can_since_optimize && filter::should_since_optimize(lim, entries.len()) {
+                let reconciling = reconcile && open_reconciliation(ndb, txn, relay, &filter, lim);
+                if reconciling || (can_since_optimize && filter::should_since_optimize(lim, entries.len())) {
                     filter = filter::since_optimize_filter(filter, entries.latest());
                 } else {
                     warn!("Skipping since optimization for {:?}: number of local notes is less than limit, attempting to backfill.", &timeline.kind);
@@ -675,6 +693,81 @@ pub fn send_initial_timeline_filter(
     }
 }
 
+/// Keeps lookups of missing notes within relays' message limits
+const MAX_IDS_PER_FILTER: usize = 100;
+
+/// Reconcile the notes we hold for a remote filter with a relay. The
+/// notes it finds missing get fetched in [`fetch_reconciled`].
+fn open_reconciliation(
+    ndb: &Ndb,
+    txn: &Transaction,
+    relay: &mut PoolRelay,
+    filter: &Filter,
+    limit: u64,
+) -> bool {
+    let items: Vec<Item> = match ndb.query(txn, std::slice::from_ref(filter), limit as i32) {
+        Ok(results) => results
+            .iter()
+            .map(|qr| (qr.note.created_at(), *qr.note.id()))
+            .collect(),
+        Err(err) => {
+            error!("open_reconciliation: query failed: {err}");
+            return false;
+        }
+    };
+
+    // with nothing to reconcile, the usual REQ does the job
+    let Some(filter) = reconciliation_filter(filter, &items) else {
+        return false;
+    };
+
+    match relay.neg_open(subscriptions::new_sub_id(), filter, items) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("error reconciling with {}: {err}", relay.url());
+            false
+        }
+    }
+}
+
+/// The filter to reconcile `items` under. We only hold the newest notes
+/// for a filter, so it's bounded to the range they cover, otherwise the
+/// relay would report all of the older history as missing.
+fn reconciliation_filter(filter: &Filter, items: &[Item]) -> Option<Filter> {
+    let oldest = items.iter().map(|(created_at, _)| *created_at).min()?;
+    Some(filter.clone().since_mut(oldest))
+}
+
+/// Fetch what a reconciliation with a relay turned up, or the whole filter
+/// if the relay couldn't reconcile
+pub fn fetch_reconciled(
+    subs: &mut Subscriptions,
+    pool: &mut RelayPool,
+    relay_url: &str,
+    outcome: NegOutcome,
+) {
+    let filters: Vec<Filter> = match outcome {
+        NegOutcome::Missing(ids) => ids
+            .chunks(MAX_IDS_PER_FILTER)
+            .map(|ids| {
+                Filter::new()
+                    .ids(ids.iter())
+                    .limit(ids.len() as u64)
+                    .build()
+            })
+            .collect(),
+        NegOutcome::Fallback(filter) => vec![filter],
+    };
+
+    if filters.is_empty() {
+        return;
+    }
+
+    let sub_id = subscriptions::new_sub_id();
+    subs.subs.insert(sub_id.clone(), SubKind::OneShot);
+    pool.send_to(&ClientMessage::req(sub_id, filters), relay_url);
+}
+
 pub fn fetch_contact_list(subs: &mut Subscriptions, timeline: &mut Timeline, accounts: &Accounts) {
     if timeline.filter.get_any_ready().is_some() {
         return;
@@ -885,3 +978,65 @@ pub fn is_timeline_ready(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::{NegSessions, Negentropy};
+
+    fn item(created_at: u64, n: u8) -> Item {
+        (created_at, [n; 32])
+    }
+
+    fn since(filter: &Filter) -> Option<u64> {
+        let json: serde_json::Value = serde_json::from_str(&filter.json().unwrap()).unwrap();
+        json["since"].as_u64()
+    }
+
+    #[test]
+    fn test_reconciliation_filter_bounds_to_local_window() {
+        let filter = Filter::new().kinds([1]).limit(3).build();
+
+        // the relay has older history than the notes we keep locally
+        let relay_items: Vec<Item> = (1..=10).map(|n| item(n as u64 * 10, n)).collect();
+        let local = vec![item(80, 8), item(100, 10)];
+
+        let bounded = reconciliation_filter(&filter, &local).unwrap();
+        assert_eq!(since(&bounded), Some(80));
+
+        // the relay only reconciles what matches the bounded filter
+        let since = since(&bounded).unwrap();
+        let mut relay = Negentropy::new(
+            relay_items
+                .into_iter()
+                .filter(|(created_at, _)| *created_at >= since)
+                .collect(),
+        );
+
+        let mut sessions = NegSessions::default();
+        let ClientMessage::NegOpen { message, .. } =
+            sessions.open("neg".to_owned(), bounded, local)
+        else {
+            panic!("expected NEG-OPEN");
+        };
+
+        let mut message = message;
+        let missing = loop {
+            let reply = hex::encode(relay.respond(&hex::decode(&message).unwrap()).unwrap());
+            match sessions.message("neg", &reply) {
+                Some((ClientMessage::NegMsg { message: next, .. }, None)) => message = next,
+                Some((_, Some(NegOutcome::Missing(missing)))) => break missing,
+                _ => panic!("expected reconciliation to finish"),
+            }
+        };
+
+        // only the note inside our window is missing, not older history
+        assert_eq!(missing, vec![[9; 32]]);
+    }
+
+    #[test]
+    fn test_reconciliation_filter_without_notes() {
+        let filter = Filter::new().kinds([1]).build();
+        assert!(reconciliation_filter(&filter, &[]).is_none());
+    }
+}
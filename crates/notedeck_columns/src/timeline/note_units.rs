@@ -371,6 +371,7 @@ mod tests {
                     reaction: "+".to_owned(),
                     sender: self.random_sender(),
                     sender_profilekey: None,
+                    emoji: None,
                 },
             }))
         }
@@ -675,4 +676,43 @@ mod tests {
         builder.aeq(2, Expect::Single(&single2));
         builder.aeq(3, Expect::Single(&single1));
     }
+
+    #[test]
+    fn test_reaction_counts() {
+        let mut builder = UnitBuilder::default();
+        let reacted_to = builder.new_noteref();
+
+        let frag = |builder: &mut UnitBuilder, content: &str| ReactionFragment {
+            noteref_reacted_to: reacted_to,
+            reaction_note_ref: builder.new_noteref(),
+            reaction: Reaction {
+                reaction: content.to_owned(),
+                sender: builder.random_sender(),
+                sender_profilekey: None,
+                emoji: None,
+            },
+        };
+
+        let mut unit: ReactionUnit = frag(&mut builder, "🔥").into();
+        for content in ["+", "🔥", "🫡", "🔥"] {
+            frag(&mut builder, content).fold_into(&mut unit);
+        }
+
+        let counts: Vec<(String, usize)> = unit
+            .counts(|_| true)
+            .into_iter()
+            .map(|c| (c.content, c.count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("🔥".to_owned(), 3),
+                ("❤️".to_owned(), 1),
+                ("🫡".to_owned(), 1)
+            ]
+        );
+
+        let without_fire = unit.counts(|r| r.reaction != "🔥");
+        assert_eq!(without_fire.len(), 2);
+    }
 }
@@ -26,6 +26,7 @@ pub fn render_timeline_route(
         | TimelineKind::Algo(_)
         | TimelineKind::Notifications(_)
         | TimelineKind::Universe
+        | TimelineKind::Highlights
         | TimelineKind::Hashtag(_)
         | TimelineKind::Generic(_) => {
             let resp =
@@ -2,6 +2,8 @@ use std::collections::HashSet;
 
 use enostr::Pubkey;
 use nostrdb::{Ndb, Note, NoteKey, Transaction};
+use notedeck::highlight::HIGHLIGHT_KIND;
+use notedeck::poll::POLL_KIND;
 use notedeck::NoteRef;
 use notedeck_ui::note::get_reposted_note;
 
@@ -117,7 +119,7 @@ fn to_fragment<'a>(
     txn: &Transaction,
 ) -> Option<NoteUnitFragmentResponse<'a>> {
     match payload.note.kind() {
-        1 => Some(NoteUnitFragmentResponse {
+        1 | POLL_KIND | HIGHLIGHT_KIND => Some(NoteUnitFragmentResponse {
             fragment: NoteUnitFragment::Single(NoteRef {
                 key: payload.key,
                 created_at: payload.note.created_at(),
@@ -182,6 +184,7 @@ fn to_reaction<'a>(
                 reaction: reaction.to_string(),
                 sender: Pubkey::new(*payload.note.pubkey()),
                 sender_profilekey,
+                emoji: notedeck::emoji::reaction_emoji(&payload.note),
             },
         },
         pk: payload.note.pubkey(),
@@ -2,6 +2,8 @@ use std::collections::{BTreeMap, HashSet};
 
 use enostr::Pubkey;
 use nostrdb::ProfileKey;
+use notedeck::emoji::CustomEmoji;
+use notedeck::reaction::{group_reactions, ReactionCount};
 use notedeck::NoteRef;
 
 use crate::timeline::note_units::{CompositeKey, CompositeType, UnitKey};
@@ -128,6 +130,17 @@ impl ReactionUnit {
             .map(|(r, _)| r)
             .unwrap_or(&self.note_reacted_to)
     }
+
+    /// The reactions grouped by emoji, most common first. `include` can
+    /// leave some out, eg. the ones from muted users.
+    pub fn counts(&self, include: impl Fn(&Reaction) -> bool) -> Vec<ReactionCount> {
+        group_reactions(
+            self.reactions
+                .values()
+                .filter(|r| include(r))
+                .map(|r| (r.reaction.as_str(), r.emoji.as_ref())),
+        )
+    }
 }
 
 impl From<ReactionFragment> for ReactionUnit {
@@ -277,6 +290,8 @@ pub struct Reaction {
     pub reaction: String, // can't use char because some emojis are 'grapheme clusters'
     pub sender: Pubkey,
     pub sender_profilekey: Option<ProfileKey>,
+    /// Set when the reaction is a NIP-30 custom emoji
+    pub emoji: Option<CustomEmoji>,
 }
 
 /// Represents a singular repost
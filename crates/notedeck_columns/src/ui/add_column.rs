@@ -55,6 +55,7 @@ pub enum AlgoOption {
 #[derive(Clone, Debug)]
 enum AddColumnOption {
     Universe,
+    Highlights,
     UndecidedNotification,
     ExternalNotification,
     Algo(AlgoOption),
@@ -146,6 +147,7 @@ impl AddColumnOption {
         match self {
             AddColumnOption::Algo(algo_option) => AddColumnResponse::Algo(algo_option),
             AddColumnOption::Universe => AddColumnResponse::Timeline(TimelineKind::Universe),
+            AddColumnOption::Highlights => AddColumnResponse::Timeline(TimelineKind::Highlights),
             AddColumnOption::Notification(pubkey) => AddColumnResponse::Timeline(
                 TimelineKind::Notifications(*pubkey.as_pubkey(&cur_account.key.pubkey)),
             ),
@@ -520,6 +522,16 @@ impl<'a> AddColumnView<'a> {
             icon: app_images::hashtag_image(),
             option: AddColumnOption::UndecidedHashtag,
         });
+        vec.push(ColumnOptionData {
+            title: tr!(self.i18n, "Highlights", "Title for highlights column"),
+            description: tr!(
+                self.i18n,
+                "See the passages people highlight in notes, articles and around the web",
+                "Description for highlights column"
+            ),
+            icon: app_images::edit_dark_image(),
+            option: AddColumnOption::Highlights,
+        });
         vec.push(ColumnOptionData {
             title: tr!(self.i18n, "Individual", "Title for individual user column"),
             description: tr!(
@@ -476,6 +476,7 @@ impl<'a> NavTitle<'a> {
                 }
 
                 TimelineKind::Universe
+                | TimelineKind::Highlights
                 | TimelineKind::Algo(_)
                 | TimelineKind::Notifications(_)
                 | TimelineKind::Generic(_)
@@ -483,6 +484,7 @@ impl<'a> NavTitle<'a> {
             },
             Route::Reply(_) => None,
             Route::Quote(_) => None,
+            Route::Highlight(_) => None,
             Route::Accounts(_as) => None,
             Route::ComposeNote => None,
             Route::AddColumn(_add_col_route) => None,
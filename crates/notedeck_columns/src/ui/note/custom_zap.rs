@@ -6,7 +6,7 @@ use enostr::Pubkey;
 use nostrdb::{Ndb, ProfileRecord, Transaction};
 use notedeck::{
     fonts::get_font_size, get_profile_url, name::get_display_name, tr, Images, Localization,
-    MediaJobSender, NotedeckTextStyle,
+    MediaJobSender, Nip05Cache, Nip05Status, NotedeckTextStyle,
 };
 use notedeck_ui::{
     app_images, colors, profile::display_name_widget, widgets::styled_button_toggleable,
@@ -21,10 +21,12 @@ pub struct CustomZapView<'a> {
     default_msats: u64,
     i18n: &'a mut Localization,
     jobs: &'a MediaJobSender,
+    nip05_cache: &'a mut Nip05Cache,
 }
 
 #[allow(clippy::new_without_default)]
 impl<'a> CustomZapView<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         i18n: &'a mut Localization,
         images: &'a mut Images,
@@ -33,6 +35,7 @@ impl<'a> CustomZapView<'a> {
         target_pubkey: &'a Pubkey,
         default_msats: u64,
         jobs: &'a MediaJobSender,
+        nip05_cache: &'a mut Nip05Cache,
     ) -> Self {
         Self {
             target_pubkey,
@@ -42,6 +45,7 @@ impl<'a> CustomZapView<'a> {
             default_msats,
             i18n,
             jobs,
+            nip05_cache,
         }
     }
 
@@ -62,7 +66,8 @@ impl<'a> CustomZapView<'a> {
             .get_profile_by_pubkey(self.txn, self.target_pubkey.bytes())
             .ok();
         let profile = profile.as_ref();
-        show_profile(ui, self.images, self.jobs, profile);
+        let nip05_status = self.nip05_cache.profile_status(self.target_pubkey, profile);
+        show_profile(ui, self.i18n, self.images, self.jobs, profile, nip05_status);
 
         ui.add_space(8.0);
 
@@ -172,9 +177,11 @@ fn show_title(ui: &mut egui::Ui, i18n: &mut Localization) {
 
 fn show_profile(
     ui: &mut egui::Ui,
+    i18n: &mut Localization,
     images: &mut Images,
     jobs: &MediaJobSender,
     profile: Option<&ProfileRecord>,
+    nip05_status: Option<Nip05Status>,
 ) {
     let max_size = 24.0;
     ui.allocate_ui_with_layout(
@@ -183,7 +190,11 @@ fn show_profile(
         |ui| {
             ui.add(&mut ProfilePic::new(images, jobs, get_profile_url(profile)).size(max_size));
             ui.vertical(|ui| {
-                ui.add(display_name_widget(&get_display_name(profile), false));
+                ui.add(display_name_widget(
+                    &get_display_name(profile),
+                    nip05_status.map(|status| (i18n, status)),
+                    false,
+                ));
             });
         },
     );
@@ -0,0 +1,200 @@
+use std::ops::Range;
+
+use crate::{
+    draft::{Drafts, HighlightDraft},
+    nav::BodyResponse,
+    Result,
+};
+
+use egui::{text::CCursorRange, Frame, RichText, ScrollArea, TextEdit};
+use enostr::{FilledKeypair, FullKeypair, RelayPool};
+use nostrdb::{IngestMetadata, Ndb, Note, Transaction};
+use notedeck::{highlight::NewHighlight, tr, ui::richtext_small, NoteContext, NotedeckTextStyle};
+
+pub struct HighlightAction {
+    account: FullKeypair,
+    highlight: NewHighlight,
+}
+
+impl HighlightAction {
+    pub fn execute(
+        &self,
+        ndb: &Ndb,
+        txn: &Transaction,
+        pool: &mut RelayPool,
+        drafts: &mut Drafts,
+    ) -> Result<()> {
+        let seckey = self.account.secret_key.to_secret_bytes();
+        let source = ndb.get_note_by_id(txn, self.highlight.source.bytes())?;
+        let note = self
+            .highlight
+            .to_note(&seckey, &source)
+            .ok_or_else(|| "failed to build highlight".to_owned())?;
+
+        let event = enostr::ClientMessage::event(&note)?;
+
+        // show our own highlight right away, rather than once a relay
+        // sends it back
+        let _ = ndb.process_event_with(&event.to_json()?, IngestMetadata::new().client(true));
+
+        pool.send(&event);
+        drafts.highlight_mut(source.id()).clear();
+
+        Ok(())
+    }
+}
+
+/// Pick a part of a note to highlight, and optionally say something about it
+pub struct HighlightView<'a, 'd> {
+    note_context: &'a mut NoteContext<'d>,
+    poster: FilledKeypair<'a>,
+    draft: &'a mut HighlightDraft,
+    note: &'a Note<'a>,
+    scroll_id: egui::Id,
+}
+
+impl<'a, 'd> HighlightView<'a, 'd> {
+    pub fn new(
+        note_context: &'a mut NoteContext<'d>,
+        poster: FilledKeypair<'a>,
+        draft: &'a mut HighlightDraft,
+        note: &'a Note<'a>,
+        col: usize,
+    ) -> Self {
+        HighlightView {
+            note_context,
+            poster,
+            draft,
+            note,
+            scroll_id: HighlightView::scroll_id(col, note.id()),
+        }
+    }
+
+    fn id(col: usize, note_id: &[u8; 32]) -> egui::Id {
+        egui::Id::new(("highlight", col, note_id))
+    }
+
+    pub fn scroll_id(col: usize, note_id: &[u8; 32]) -> egui::Id {
+        HighlightView::id(col, note_id).with("scroll")
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> BodyResponse<HighlightAction> {
+        let scroll_out = ScrollArea::vertical()
+            .id_salt(self.scroll_id)
+            .show(ui, |ui| {
+                Frame::new()
+                    .inner_margin(12.0)
+                    .show(ui, |ui| self.show_internal(ui))
+                    .inner
+            });
+
+        BodyResponse::scroll(scroll_out)
+    }
+
+    fn show_internal(&mut self, ui: &mut egui::Ui) -> Option<HighlightAction> {
+        let content = self.note.content();
+
+        ui.label(richtext_small(tr!(
+            self.note_context.i18n,
+            "Select the part of the note you want to highlight",
+            "Instructions above a note's text when highlighting part of it"
+        )));
+
+        // the note's text, read only, so that a part of it can be selected
+        let out = Frame::group(ui.style())
+            .show(ui, |ui| {
+                let mut text = content;
+                TextEdit::multiline(&mut text)
+                    .font(NotedeckTextStyle::NoteBody.text_style())
+                    .desired_width(f32::INFINITY)
+                    .frame(false)
+                    .show(ui)
+            })
+            .inner;
+
+        // clicking elsewhere, like into the comment, shouldn't lose what
+        // was picked
+        if let Some(range) = out.state.cursor.char_range() {
+            let selection = byte_range(content, &range);
+            if !selection.is_empty() {
+                self.draft.selection = Some(selection);
+            }
+        }
+
+        let selected = self
+            .draft
+            .selection
+            .clone()
+            .and_then(|selection| content.get(selection))
+            .map(str::trim)
+            .filter(|text| !text.is_empty());
+
+        if let Some(selected) = selected {
+            ui.add_space(8.0);
+            ui.label(richtext_small(tr!(
+                self.note_context.i18n,
+                "Highlighting",
+                "Label above the text that is about to be highlighted"
+            )));
+            ui.label(
+                RichText::new(selected)
+                    .italics()
+                    .text_style(NotedeckTextStyle::NoteBody.text_style()),
+            );
+        }
+
+        ui.add_space(8.0);
+        ui.add(
+            TextEdit::multiline(&mut self.draft.comment)
+                .hint_text(
+                    RichText::new(tr!(
+                        self.note_context.i18n,
+                        "Add a comment (optional)",
+                        "Placeholder for the comment on a highlight"
+                    ))
+                    .weak(),
+                )
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        );
+
+        let highlight = self.draft.selection.clone().and_then(|selection| {
+            NewHighlight::new(self.note, selection, self.draft.comment.clone())
+        });
+
+        ui.add_space(8.0);
+        let clicked = ui
+            .add_enabled(
+                highlight.is_some(),
+                egui::Button::new(tr!(
+                    self.note_context.i18n,
+                    "Highlight",
+                    "Button to publish a highlight of the selected text"
+                )),
+            )
+            .clicked();
+
+        let highlight = highlight.filter(|_| clicked)?;
+        Some(HighlightAction {
+            account: self.poster.to_full(),
+            highlight,
+        })
+    }
+}
+
+/// Text edits select by characters, we slice by bytes
+fn byte_range(text: &str, range: &CCursorRange) -> Range<usize> {
+    let (start, end) = if range.primary.index <= range.secondary.index {
+        (range.primary.index, range.secondary.index)
+    } else {
+        (range.secondary.index, range.primary.index)
+    };
+
+    let byte = |index: usize| {
+        text.char_indices()
+            .nth(index)
+            .map_or(text.len(), |(byte, _)| byte)
+    };
+
+    byte(start)..byte(end)
+}
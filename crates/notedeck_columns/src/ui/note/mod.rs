@@ -1,8 +1,10 @@
 pub mod custom_zap;
+pub mod highlight;
 pub mod post;
 pub mod quote_repost;
 pub mod reply;
 
+pub use highlight::{HighlightAction, HighlightView};
 pub use post::{NewPostAction, PostAction, PostResponse, PostType, PostView};
 pub use quote_repost::QuoteRepostView;
 pub use reply::PostReplyView;
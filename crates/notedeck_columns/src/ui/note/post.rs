@@ -1,4 +1,4 @@
-use crate::draft::{Draft, Drafts, MentionHint};
+use crate::draft::{Draft, Drafts, MentionHint, PollDraft, POLL_DURATIONS};
 use crate::media_upload::nostrbuild_nip96_upload;
 use crate::nav::BodyResponse;
 use crate::post::{downcast_post_buffer, MentionType, NewPost};
@@ -13,14 +13,17 @@ use egui::{
 };
 use enostr::{FilledKeypair, FullKeypair, NoteId, Pubkey, RelayPool};
 use nostrdb::{Ndb, Transaction};
+use notedeck::emoji::used_emojis;
 use notedeck::media::latest::LatestImageTex;
 use notedeck::media::AnimationMode;
 #[cfg(target_os = "android")]
 use notedeck::platform::android::try_open_file_picker;
 use notedeck::platform::get_next_selected_file;
+use notedeck::poll::PollType;
 use notedeck::PixelDimensions;
 use notedeck::{
-    name::get_display_name, supported_mime_hosted_at_url, tr, Localization, NoteAction, NoteContext,
+    name::get_display_name, supported_mime_hosted_at_url, tr, tr_plural, unix_time_secs,
+    Localization, NoteAction, NoteContext,
 };
 use notedeck_ui::{
     app_images,
@@ -471,8 +474,10 @@ impl<'a, 'd> PostView<'a, 'd> {
 
         self.transfer_uploads(ui);
         self.show_upload_errors(ui);
+        self.show_content_warning_reason(ui);
+        self.show_poll_editor(ui);
 
-        let post_action = ui.horizontal(|ui| self.input_buttons(ui)).inner;
+        let post_action = ui.horizontal(|ui| self.input_buttons(txn, ui)).inner;
 
         let action = note_response
             .and_then(|nr| nr.action.map(PostAction::QuotedNoteAction))
@@ -488,33 +493,53 @@ impl<'a, 'd> PostView<'a, 'd> {
         })
     }
 
-    fn input_buttons(&mut self, ui: &mut egui::Ui) -> Option<NewPostAction> {
+    fn input_buttons(&mut self, txn: &Transaction, ui: &mut egui::Ui) -> Option<NewPostAction> {
         ui.with_layout(egui::Layout::left_to_right(egui::Align::BOTTOM), |ui| {
             self.show_upload_media_button(ui);
+            self.show_emoji_button(txn, ui);
+            self.show_content_warning_button(ui);
+            if matches!(self.post_type, PostType::New) {
+                self.show_poll_button(ui);
+            }
         });
 
+        // a poll needs at least two options
+        let poll = self
+            .draft
+            .poll
+            .as_ref()
+            .map(|poll| poll.to_poll(unix_time_secs()));
+        let can_post = !self.draft.buffer.is_empty() && poll.as_ref().is_none_or(Option::is_some);
+
         ui.with_layout(egui::Layout::right_to_left(egui::Align::BOTTOM), |ui| {
             let post_button_clicked = ui
-                .add_sized(
-                    [91.0, 32.0],
-                    post_button(self.note_context.i18n, !self.draft.buffer.is_empty()),
-                )
+                .add_sized([91.0, 32.0], post_button(self.note_context.i18n, can_post))
                 .clicked();
 
             let shortcut_pressed = ui.input(|i| {
                 (i.modifiers.ctrl || i.modifiers.command) && i.key_pressed(egui::Key::Enter)
             });
 
-            if post_button_clicked
-                || (!self.draft.buffer.is_empty() && shortcut_pressed && self.focused(ui))
-            {
+            if post_button_clicked || (can_post && shortcut_pressed && self.focused(ui)) {
                 let output = self.draft.buffer.output();
+                let available = self.note_context.accounts.custom_emojis(
+                    self.note_context.ndb,
+                    txn,
+                    self.note_context.unknown_ids,
+                );
+                let emojis = used_emojis(&output.text, &available)
+                    .into_iter()
+                    .cloned()
+                    .collect();
                 let new_post = NewPost::new(
                     output.text,
                     self.poster.to_full(),
                     self.draft.uploaded_media.clone(),
                     output.mentions,
-                );
+                    emojis,
+                )
+                .with_content_warning(self.draft.content_warning.clone())
+                .with_poll(poll.flatten());
                 Some(NewPostAction::new(self.post_type.clone(), new_post))
             } else {
                 None
@@ -590,6 +615,196 @@ impl<'a, 'd> PostView<'a, 'd> {
         }
     }
 
+    fn show_emoji_button(&mut self, txn: &Transaction, ui: &mut egui::Ui) {
+        let resp = ui
+            .add(
+                egui::Button::new(egui::RichText::new("☺").size(16.0))
+                    .min_size(egui::vec2(32.0, 32.0))
+                    .corner_radius(8.0),
+            )
+            .on_hover_text(tr!(
+                self.note_context.i18n,
+                "Insert custom emoji",
+                "Hover text for the custom emoji button in the note composer"
+            ));
+
+        let popup_id = ui.id().with("custom-emoji-popup");
+        if resp.clicked() {
+            ui.memory_mut(|m| m.toggle_popup(popup_id));
+        }
+
+        let picked = egui::popup_below_widget(
+            ui,
+            popup_id,
+            &resp,
+            egui::PopupCloseBehavior::CloseOnClickOutside,
+            |ui| {
+                ui.set_min_width(200.0);
+                let emojis = self.note_context.accounts.custom_emojis(
+                    self.note_context.ndb,
+                    txn,
+                    self.note_context.unknown_ids,
+                );
+                notedeck_ui::emoji::custom_emoji_picker(
+                    ui,
+                    self.note_context.i18n,
+                    self.note_context.img_cache,
+                    self.note_context.jobs,
+                    &emojis,
+                )
+            },
+        )
+        .flatten();
+
+        if let Some(emoji) = picked {
+            let buffer = &mut self.draft.buffer;
+            let end = buffer.as_str().chars().count();
+            let code = if buffer.as_str().ends_with(char::is_whitespace) || end == 0 {
+                emoji.code()
+            } else {
+                format!(" {}", emoji.code())
+            };
+            buffer.insert_text(&code, end);
+            ui.memory_mut(|m| m.close_popup());
+        }
+    }
+
+    fn show_content_warning_button(&mut self, ui: &mut egui::Ui) {
+        let enabled = self.draft.content_warning.is_some();
+        let resp = ui
+            .add_sized(
+                [32.0, 32.0],
+                egui::SelectableLabel::new(enabled, egui::RichText::new("⚠").size(16.0)),
+            )
+            .on_hover_text(tr!(
+                self.note_context.i18n,
+                "Add content warning",
+                "Hover text for the button that adds a content warning to a note"
+            ));
+
+        if resp.clicked() {
+            self.draft.content_warning = (!enabled).then(String::new);
+        }
+    }
+
+    /// Where the reason for the note's content warning is written, when it
+    /// has one
+    fn show_content_warning_reason(&mut self, ui: &mut egui::Ui) {
+        let Some(reason) = &mut self.draft.content_warning else {
+            return;
+        };
+
+        ui.add(
+            TextEdit::singleline(reason)
+                .hint_text(tr!(
+                    self.note_context.i18n,
+                    "Content warning reason (optional)",
+                    "Placeholder for the reason of a note's content warning in the composer"
+                ))
+                .desired_width(f32::INFINITY),
+        );
+    }
+
+    fn show_poll_button(&mut self, ui: &mut egui::Ui) {
+        let enabled = self.draft.poll.is_some();
+        let resp = ui
+            .add_sized(
+                [32.0, 32.0],
+                egui::SelectableLabel::new(enabled, egui::RichText::new("📊").size(16.0)),
+            )
+            .on_hover_text(tr!(
+                self.note_context.i18n,
+                "Add poll",
+                "Hover text for the button that turns a note into a poll"
+            ));
+
+        if resp.clicked() {
+            self.draft.poll = (!enabled).then(PollDraft::default);
+        }
+    }
+
+    /// The options of the poll the note is, if it is one, and how it takes
+    /// votes. The note's text is the question.
+    fn show_poll_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(poll) = &mut self.draft.poll else {
+            return;
+        };
+        let i18n = &mut *self.note_context.i18n;
+
+        let mut to_remove = None;
+        let removable = poll.options.len() > 2;
+        for (i, option) in poll.options.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let hint = tr!(
+                    i18n,
+                    "Option {number}",
+                    "Placeholder for a poll option in the composer",
+                    number = i + 1
+                );
+                let width = ui.available_width() - if removable { 28.0 } else { 0.0 };
+                ui.add(
+                    TextEdit::singleline(option)
+                        .hint_text(hint)
+                        .desired_width(width),
+                );
+
+                if removable && ui.small_button("✕").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+        }
+
+        if let Some(i) = to_remove {
+            poll.options.remove(i);
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            if ui
+                .button(tr!(
+                    i18n,
+                    "Add option",
+                    "Button to add another option to a poll in the composer"
+                ))
+                .clicked()
+            {
+                poll.options.push(String::new());
+            }
+
+            let mut multiple = poll.poll_type == PollType::MultipleChoice;
+            if ui
+                .checkbox(
+                    &mut multiple,
+                    tr!(
+                        i18n,
+                        "Multiple choice",
+                        "Checkbox to let voters pick more than one option of a poll"
+                    ),
+                )
+                .changed()
+            {
+                poll.poll_type = if multiple {
+                    PollType::MultipleChoice
+                } else {
+                    PollType::SingleChoice
+                };
+            }
+
+            ui.label(tr!(
+                i18n,
+                "Ends in",
+                "Label for how long a poll in the composer takes votes"
+            ));
+            egui::ComboBox::from_id_salt("poll-duration")
+                .selected_text(poll_duration_label(i18n, poll.duration))
+                .show_ui(ui, |ui| {
+                    for duration in POLL_DURATIONS.iter().copied().map(Some).chain([None]) {
+                        let label = poll_duration_label(i18n, duration);
+                        ui.selectable_value(&mut poll.duration, duration, label);
+                    }
+                });
+        });
+    }
+
     fn transfer_uploads(&mut self, ui: &mut egui::Ui) {
         let mut indexes_to_remove = Vec::new();
         for (i, promise) in self.draft.uploading_media.iter().enumerate() {
@@ -689,6 +904,36 @@ fn render_post_view_media(
     }
 }
 
+fn poll_duration_label(i18n: &mut Localization, duration: Option<u64>) -> String {
+    const HOUR: u64 = 60 * 60;
+    const DAY: u64 = 24 * HOUR;
+
+    match duration {
+        None => tr!(i18n, "Never", "A poll that never stops taking votes"),
+        Some(secs) if secs % (7 * DAY) == 0 => tr_plural!(
+            i18n,
+            "{count} week",
+            "{count} weeks",
+            "How long a poll takes votes, in weeks",
+            secs / (7 * DAY),
+        ),
+        Some(secs) if secs % DAY == 0 => tr_plural!(
+            i18n,
+            "{count} day",
+            "{count} days",
+            "How long a poll takes votes, in days",
+            secs / DAY,
+        ),
+        Some(secs) => tr_plural!(
+            i18n,
+            "{count} hour",
+            "{count} hours",
+            "How long a poll takes votes, in hours",
+            secs / HOUR,
+        ),
+    }
+}
+
 fn post_button<'a>(i18n: &'a mut Localization, interactive: bool) -> impl egui::Widget + 'a {
     move |ui: &mut egui::Ui| {
         let button = egui::Button::new(tr!(i18n, "Post now", "Button label to post a note"));
@@ -883,6 +1128,7 @@ mod preview {
                 unknown_ids: app.unknown_ids,
                 clipboard: app.clipboard,
                 i18n: app.i18n,
+                nip05_cache: app.nip05_cache,
             };
 
             PostView::new(
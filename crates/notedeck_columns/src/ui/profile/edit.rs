@@ -1,31 +1,202 @@
 use core::f32;
 
 use egui::{vec2, Button, CornerRadius, Layout, Margin, RichText, ScrollArea, TextEdit};
-use enostr::ProfileState;
+use enostr::{FilledKeypair, ProfileState};
 use notedeck::{
-    profile::unwrap_profile_url, tr, Clipboard, Images, Localization, MediaJobSender,
-    NotedeckTextStyle,
+    generate_endpoint_url, platform::file::SelectedMedia, profile::unwrap_profile_url, tr,
+    Clipboard, Images, Localization, MediaJobSender, Nip05Cache, Nip05Status, NotedeckTextStyle,
 };
 use notedeck_ui::context_menu::{input_context, PasteBehavior};
 use notedeck_ui::{profile::banner, ProfilePic};
+use poll_promise::Promise;
+use tracing::error;
 
+use crate::media_upload::{nostrbuild_nip96_upload, Nip94Event};
 use crate::nav::BodyResponse;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageField {
+    Picture,
+    Banner,
+}
+
+impl ImageField {
+    fn key(&self) -> &'static str {
+        match self {
+            ImageField::Picture => "picture",
+            ImageField::Banner => "banner",
+        }
+    }
+}
+
+/// The profile being edited, along with what it looked like before so we
+/// can show what changed
+pub struct EditProfileState {
+    pub state: ProfileState,
+    original: ProfileState,
+    uploads: Vec<(ImageField, Promise<Result<Nip94Event, notedeck::Error>>)>,
+    upload_errors: Vec<String>,
+    /// Which field the next file from the platform picker is for
+    awaiting_file: Option<ImageField>,
+    /// Showing the changes before publishing
+    reviewing: bool,
+}
+
+impl EditProfileState {
+    pub fn new(state: ProfileState) -> Self {
+        Self {
+            original: state.clone(),
+            state,
+            uploads: Vec::new(),
+            upload_errors: Vec::new(),
+            awaiting_file: None,
+            reviewing: false,
+        }
+    }
+
+    /// The profile we should publish
+    pub fn to_publish(&self) -> ProfileState {
+        self.state.without_new_empty_fields(&self.original)
+    }
+
+    fn is_uploading(&self, field: ImageField) -> bool {
+        self.uploads.iter().any(|(f, _)| *f == field)
+    }
+
+    fn start_upload(
+        &mut self,
+        field: ImageField,
+        media: Result<SelectedMedia, notedeck::Error>,
+        seckey: [u8; 32],
+    ) {
+        match media {
+            Ok(media) => self
+                .uploads
+                .push((field, nostrbuild_nip96_upload(seckey, media))),
+            Err(err) => {
+                error!("{err}");
+                self.upload_errors.push(err.to_string());
+            }
+        }
+    }
+
+    fn poll_uploads(&mut self, seckey: [u8; 32]) {
+        if let Some(field) = self.awaiting_file {
+            while let Some(media) = notedeck::platform::get_next_selected_file() {
+                self.awaiting_file = None;
+                self.start_upload(field, media, seckey);
+            }
+        }
+
+        let mut i = 0;
+        while i < self.uploads.len() {
+            let Some(res) = self.uploads[i].1.ready() else {
+                i += 1;
+                continue;
+            };
+
+            let field = self.uploads[i].0;
+            let res = res
+                .as_ref()
+                .map(|media| media.url.clone())
+                .map_err(|err| err.to_string());
+            self.uploads.remove(i);
+
+            match res {
+                Ok(url) => *self.state.str_mut(field.key()) = url,
+                Err(err) => {
+                    error!("{err}");
+                    self.upload_errors.push(err);
+                }
+            }
+        }
+    }
+
+    fn has_errors(&self) -> bool {
+        self.state.value().as_object().is_some_and(|fields| {
+            fields.iter().any(|(key, value)| {
+                value
+                    .as_str()
+                    .is_some_and(|value| validate_field(key, value).is_some())
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFieldError {
+    InvalidUrl,
+    InvalidLud16,
+    InvalidNip05,
+}
+
+fn is_http_url(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|url| {
+        (url.scheme() == "https" || url.scheme() == "http") && url.host().is_some()
+    })
+}
+
+/// Check a profile field. Empty fields are always fine.
+pub fn validate_field(key: &str, value: &str) -> Option<ProfileFieldError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let valid = match key {
+        "picture" | "banner" => is_http_url(value),
+
+        // people tend to leave off the scheme here
+        "website" if !value.contains("://") => is_http_url(&format!("https://{value}")),
+        "website" => is_http_url(value),
+
+        "lud16" => {
+            let has_user = value
+                .split_once('@')
+                .is_some_and(|(user, _)| !user.is_empty());
+            has_user && !value.contains(char::is_whitespace) && generate_endpoint_url(value).is_ok()
+        }
+
+        "nip05" => value.split_once('@').is_some_and(|(user, domain)| {
+            !user.is_empty()
+                && domain.contains('.')
+                && !domain.contains('@')
+                && !value.contains(char::is_whitespace)
+        }),
+
+        _ => true,
+    };
+
+    if valid {
+        return None;
+    }
+
+    Some(match key {
+        "lud16" => ProfileFieldError::InvalidLud16,
+        "nip05" => ProfileFieldError::InvalidNip05,
+        _ => ProfileFieldError::InvalidUrl,
+    })
+}
+
 pub struct EditProfileView<'a> {
-    state: &'a mut ProfileState,
+    state: &'a mut EditProfileState,
     clipboard: &'a mut dyn Clipboard,
     img_cache: &'a mut Images,
     i18n: &'a mut Localization,
     jobs: &'a MediaJobSender,
+    nip05_cache: &'a mut Nip05Cache,
+    kp: FilledKeypair<'a>,
 }
 
 impl<'a> EditProfileView<'a> {
     pub fn new(
         i18n: &'a mut Localization,
-        state: &'a mut ProfileState,
+        state: &'a mut EditProfileState,
         img_cache: &'a mut Images,
         clipboard: &'a mut dyn Clipboard,
         jobs: &'a MediaJobSender,
+        nip05_cache: &'a mut Nip05Cache,
+        kp: FilledKeypair<'a>,
     ) -> Self {
         Self {
             i18n,
@@ -33,6 +204,8 @@ impl<'a> EditProfileView<'a> {
             img_cache,
             clipboard,
             jobs,
+            nip05_cache,
+            kp,
         }
     }
 
@@ -42,11 +215,20 @@ impl<'a> EditProfileView<'a> {
 
     // return true to save
     pub fn ui(&mut self, ui: &mut egui::Ui) -> BodyResponse<bool> {
+        self.state.poll_uploads(self.kp.secret_key.secret_bytes());
+        if !self.state.uploads.is_empty() {
+            ui.ctx().request_repaint();
+        }
+
         let scroll_out = ScrollArea::vertical()
             .id_salt(EditProfileView::scroll_id())
-            .stick_to_bottom(true)
+            .stick_to_bottom(!self.state.reviewing)
             .show(ui, |ui| {
-                banner(ui, self.state.banner(), 188.0);
+                if self.state.reviewing {
+                    return Some(self.review_ui(ui));
+                }
+
+                banner(ui, self.state.state.banner(), 188.0);
 
                 let padding = 24.0;
                 notedeck_ui::padding(padding, ui, |ui| {
@@ -55,34 +237,181 @@ impl<'a> EditProfileView<'a> {
 
                 ui.separator();
 
-                let mut save = false;
                 notedeck_ui::padding(padding, ui, |ui| {
+                    for error in &self.state.upload_errors {
+                        ui.colored_label(ui.visuals().warn_fg_color, error);
+                    }
+
                     ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui
-                            .add(
-                                button(
-                                    tr!(
-                                        self.i18n,
-                                        "Save changes",
-                                        "Button label to save profile changes"
-                                    )
-                                    .as_str(),
-                                    119.0,
+                        let has_errors = self.state.has_errors();
+                        let resp = ui.add_enabled(
+                            !has_errors,
+                            button(
+                                tr!(
+                                    self.i18n,
+                                    "Save changes",
+                                    "Button label to save profile changes"
                                 )
-                                .fill(notedeck_ui::colors::PINK),
+                                .as_str(),
+                                119.0,
                             )
-                            .clicked()
-                        {
-                            save = true;
+                            .fill(notedeck_ui::colors::PINK),
+                        );
+
+                        if has_errors {
+                            resp.on_disabled_hover_text(tr!(
+                                self.i18n,
+                                "Fix the highlighted fields first",
+                                "Hover text when profile fields are invalid"
+                            ));
+                        } else if resp.clicked() {
+                            self.state.reviewing = true;
                         }
                     });
                 });
 
-                Some(save)
+                Some(false)
             });
         BodyResponse::scroll(scroll_out)
     }
 
+    /// What's about to be published. Returns true to publish.
+    fn review_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut publish = false;
+        let to_publish = self.state.to_publish();
+        let changes = to_publish.diff(&self.state.original);
+
+        notedeck_ui::padding(24.0, ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(0.0, 8.0);
+            ui.add(label(
+                tr!(
+                    self.i18n,
+                    "Review changes",
+                    "Heading for the profile changes preview"
+                )
+                .as_str(),
+            ));
+
+            if changes.is_empty() {
+                ui.label(tr!(
+                    self.i18n,
+                    "Nothing changed",
+                    "Shown when there are no profile changes to publish"
+                ));
+            }
+
+            for change in &changes {
+                in_frame(ui, |ui| {
+                    ui.strong(change.key);
+                    if let Some(old) = change.old {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("- {}", display_value(old)),
+                        );
+                    }
+                    if let Some(new) = change.new {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(0x4c, 0xaf, 0x50),
+                            format!("+ {}", display_value(new)),
+                        );
+                    }
+                });
+            }
+
+            ui.separator();
+
+            ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                let publish_button = button(
+                    tr!(
+                        self.i18n,
+                        "Publish",
+                        "Button label to publish profile changes"
+                    )
+                    .as_str(),
+                    119.0,
+                )
+                .fill(notedeck_ui::colors::PINK);
+
+                if ui
+                    .add_enabled(!changes.is_empty(), publish_button)
+                    .clicked()
+                {
+                    publish = true;
+                }
+
+                ui.add_space(8.0);
+
+                if ui
+                    .add(button(
+                        tr!(
+                            self.i18n,
+                            "Keep editing",
+                            "Button label to go back to editing the profile"
+                        )
+                        .as_str(),
+                        119.0,
+                    ))
+                    .clicked()
+                {
+                    self.state.reviewing = false;
+                }
+            });
+        });
+
+        if publish {
+            self.state.reviewing = false;
+        }
+
+        publish
+    }
+
+    fn upload_button(&mut self, ui: &mut egui::Ui, field: ImageField) {
+        if self.state.is_uploading(field) {
+            ui.spinner();
+            return;
+        }
+
+        if !ui
+            .button(tr!(
+                self.i18n,
+                "Upload",
+                "Button label to upload a profile image"
+            ))
+            .clicked()
+        {
+            return;
+        }
+
+        #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+        {
+            if let Some(path) = rfd::FileDialog::new().pick_file() {
+                self.state.start_upload(
+                    field,
+                    SelectedMedia::from_path(path),
+                    self.kp.secret_key.secret_bytes(),
+                );
+            }
+        }
+
+        #[cfg(target_os = "android")]
+        {
+            self.state.awaiting_file = Some(field);
+            notedeck::platform::android::try_open_file_picker();
+        }
+    }
+
+    fn image_field(&mut self, ui: &mut egui::Ui, field: ImageField, title: String) {
+        in_frame(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(label(title.as_str()));
+                ui.add_space(8.0);
+                self.upload_button(ui, field);
+            });
+            multiline_textedit(ui, self.state.state.str_mut(field.key()), self.clipboard);
+            field_error_ui(ui, self.i18n, &self.state.state, field.key());
+        });
+    }
+
     fn inner(&mut self, ui: &mut egui::Ui, padding: f32) {
         ui.spacing_mut().item_spacing = egui::vec2(0.0, 16.0);
         let mut pfp_rect = ui.available_rect_before_wrap();
@@ -91,7 +420,7 @@ impl<'a> EditProfileView<'a> {
         pfp_rect.set_height(size);
         let pfp_rect = pfp_rect.translate(egui::vec2(0.0, -(padding + 2.0 + (size / 2.0))));
 
-        let pfp_url = unwrap_profile_url(self.state.picture());
+        let pfp_url = unwrap_profile_url(self.state.state.picture());
         ui.put(
             pfp_rect,
             &mut ProfilePic::new(self.img_cache, self.jobs, pfp_url)
@@ -108,47 +437,39 @@ impl<'a> EditProfileView<'a> {
                 )
                 .as_str(),
             ));
-            singleline_textedit(ui, self.state.str_mut("display_name"), self.clipboard);
+            singleline_textedit(ui, self.state.state.str_mut("display_name"), self.clipboard);
         });
 
         in_frame(ui, |ui| {
             ui.add(label(
                 tr!(self.i18n, "Username", "Profile username field label").as_str(),
             ));
-            singleline_textedit(ui, self.state.str_mut("name"), self.clipboard);
+            singleline_textedit(ui, self.state.state.str_mut("name"), self.clipboard);
         });
 
-        in_frame(ui, |ui| {
-            ui.add(label(
-                tr!(
-                    self.i18n,
-                    "Profile picture",
-                    "Profile picture URL field label"
-                )
-                .as_str(),
-            ));
-            multiline_textedit(ui, self.state.str_mut("picture"), self.clipboard);
-        });
+        let title = tr!(
+            self.i18n,
+            "Profile picture",
+            "Profile picture URL field label"
+        );
+        self.image_field(ui, ImageField::Picture, title);
 
-        in_frame(ui, |ui| {
-            ui.add(label(
-                tr!(self.i18n, "Banner", "Profile banner URL field label").as_str(),
-            ));
-            multiline_textedit(ui, self.state.str_mut("banner"), self.clipboard);
-        });
+        let title = tr!(self.i18n, "Banner", "Profile banner URL field label");
+        self.image_field(ui, ImageField::Banner, title);
 
         in_frame(ui, |ui| {
             ui.add(label(
                 tr!(self.i18n, "About", "Profile about/bio field label").as_str(),
             ));
-            multiline_textedit(ui, self.state.str_mut("about"), self.clipboard);
+            multiline_textedit(ui, self.state.state.str_mut("about"), self.clipboard);
         });
 
         in_frame(ui, |ui| {
             ui.add(label(
                 tr!(self.i18n, "Website", "Profile website field label").as_str(),
             ));
-            singleline_textedit(ui, self.state.str_mut("website"), self.clipboard);
+            singleline_textedit(ui, self.state.state.str_mut("website"), self.clipboard);
+            field_error_ui(ui, self.i18n, &self.state.state, "website");
         });
 
         in_frame(ui, |ui| {
@@ -160,7 +481,8 @@ impl<'a> EditProfileView<'a> {
                 )
                 .as_str(),
             ));
-            multiline_textedit(ui, self.state.str_mut("lud16"), self.clipboard);
+            multiline_textedit(ui, self.state.state.str_mut("lud16"), self.clipboard);
+            field_error_ui(ui, self.i18n, &self.state.state, "lud16");
         });
 
         in_frame(ui, |ui| {
@@ -173,9 +495,13 @@ impl<'a> EditProfileView<'a> {
                 .as_str(),
             ));
 
-            singleline_textedit(ui, self.state.str_mut("nip05"), self.clipboard);
+            let resp = singleline_textedit(ui, self.state.state.str_mut("nip05"), self.clipboard);
+
+            if field_error_ui(ui, self.i18n, &self.state.state, "nip05") {
+                return;
+            }
 
-            let Some(nip05) = self.state.nip05() else {
+            let Some(nip05) = self.state.state.nip05() else {
                 return;
             };
 
@@ -212,17 +538,115 @@ impl<'a> EditProfileView<'a> {
                     )
                 }),
             );
+
+            // don't look up every keystroke
+            if resp.has_focus() {
+                return;
+            }
+
+            let status = self.nip05_cache.status(self.kp.pubkey, nip05);
+            nip05_status_ui(ui, self.i18n, status, suffix);
         });
     }
 }
 
+fn display_value(value: &serde_json::Value) -> String {
+    match value.as_str() {
+        Some(s) => s.to_owned(),
+        None => value.to_string(),
+    }
+}
+
+/// Shows why a field is invalid. Returns true if it is.
+fn field_error_ui(
+    ui: &mut egui::Ui,
+    i18n: &mut Localization,
+    state: &ProfileState,
+    key: &str,
+) -> bool {
+    let Some(err) = state
+        .get_str(key)
+        .and_then(|value| validate_field(key, value))
+    else {
+        return false;
+    };
+
+    let text = match err {
+        ProfileFieldError::InvalidUrl => tr!(
+            i18n,
+            "This doesn't look like a valid http(s) link",
+            "Error for an invalid URL in a profile field"
+        ),
+        ProfileFieldError::InvalidLud16 => tr!(
+            i18n,
+            "Lightning addresses look like name@domain.com",
+            "Error for an invalid lightning address in the profile editor"
+        ),
+        ProfileFieldError::InvalidNip05 => tr!(
+            i18n,
+            "Nostr addresses look like name@domain.com",
+            "Error for an invalid NIP-05 identifier in the profile editor"
+        ),
+    };
+
+    ui.colored_label(ui.visuals().error_fg_color, text);
+    true
+}
+
+fn nip05_status_ui(ui: &mut egui::Ui, i18n: &mut Localization, status: Nip05Status, domain: &str) {
+    match status {
+        Nip05Status::Pending => {
+            ui.weak(tr!(
+                i18n,
+                "Checking {domain}...",
+                "Shown while checking a NIP-05 identifier",
+                domain = domain
+            ));
+        }
+        Nip05Status::Verified => {
+            ui.label(tr!(
+                i18n,
+                "✔ Verified by {domain}",
+                "Shown when a NIP-05 identifier points to this account",
+                domain = domain
+            ));
+        }
+        Nip05Status::Mismatch { .. } => {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                tr!(
+                    i18n,
+                    "{domain} doesn't point this name to your account yet",
+                    "Shown when a NIP-05 identifier points to another key or none",
+                    domain = domain
+                ),
+            );
+        }
+        Nip05Status::Unreachable => {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                tr!(
+                    i18n,
+                    "Couldn't reach {domain} to check this address",
+                    "Shown when the NIP-05 domain could not be fetched",
+                    domain = domain
+                ),
+            );
+        }
+    }
+}
+
 fn label(text: &str) -> impl egui::Widget + '_ {
     move |ui: &mut egui::Ui| -> egui::Response {
         ui.label(RichText::new(text).font(NotedeckTextStyle::Body.get_bolded_font(ui.ctx())))
     }
 }
 
-fn singleline_textedit(ui: &mut egui::Ui, data: &mut String, clipboard: &mut dyn Clipboard) {
+fn singleline_textedit(
+    ui: &mut egui::Ui,
+    data: &mut String,
+    clipboard: &mut dyn Clipboard,
+) -> egui::Response {
     let r = ui.add(
         TextEdit::singleline(data)
             .min_size(vec2(0.0, 40.0))
@@ -232,6 +656,7 @@ fn singleline_textedit(ui: &mut egui::Ui, data: &mut String, clipboard: &mut dyn
     );
 
     input_context(ui, &r, clipboard, data, PasteBehavior::Clear);
+    r
 }
 
 fn multiline_textedit(ui: &mut egui::Ui, data: &mut String, clipboard: &mut dyn Clipboard) {
@@ -259,3 +684,62 @@ fn button(text: &str, width: f32) -> egui::Button<'static> {
         .corner_radius(CornerRadius::same(8))
         .min_size(vec2(width, 40.0))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_urls() {
+        assert_eq!(validate_field("picture", ""), None);
+        assert_eq!(
+            validate_field("picture", "https://nostr.build/i/a.png"),
+            None
+        );
+        assert_eq!(
+            validate_field("banner", "ftp://example.com/a.png"),
+            Some(ProfileFieldError::InvalidUrl)
+        );
+        assert_eq!(
+            validate_field("picture", "not a url"),
+            Some(ProfileFieldError::InvalidUrl)
+        );
+
+        // the scheme is optional for websites
+        assert_eq!(validate_field("website", "damus.io"), None);
+        assert_eq!(
+            validate_field("website", "damus dot io"),
+            Some(ProfileFieldError::InvalidUrl)
+        );
+    }
+
+    #[test]
+    fn test_validate_addresses() {
+        assert_eq!(validate_field("lud16", "jb55@sendsats.lol"), None);
+        assert_eq!(
+            validate_field("lud16", "jb55"),
+            Some(ProfileFieldError::InvalidLud16)
+        );
+        assert_eq!(
+            validate_field("lud16", "@sendsats.lol"),
+            Some(ProfileFieldError::InvalidLud16)
+        );
+        assert_eq!(
+            validate_field("lud16", "jb55@localhost"),
+            Some(ProfileFieldError::InvalidLud16)
+        );
+
+        assert_eq!(validate_field("nip05", "_@jb55.com"), None);
+        assert_eq!(
+            validate_field("nip05", "jb55.com"),
+            Some(ProfileFieldError::InvalidNip05)
+        );
+        assert_eq!(
+            validate_field("nip05", "a@b@c.com"),
+            Some(ProfileFieldError::InvalidNip05)
+        );
+
+        // fields we don't check
+        assert_eq!(validate_field("about", "anything goes"), None);
+    }
+}
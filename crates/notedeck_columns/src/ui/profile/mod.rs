@@ -2,7 +2,7 @@ pub mod contacts_list;
 pub mod edit;
 
 pub use contacts_list::{ContactsListAction, ContactsListView};
-pub use edit::EditProfileView;
+pub use edit::{EditProfileState, EditProfileView};
 use egui::{vec2, Color32, CornerRadius, Layout, Rect, RichText, ScrollArea, Sense, Stroke};
 use enostr::Pubkey;
 use nostrdb::{ProfileRecord, Transaction};
@@ -256,7 +256,12 @@ fn profile_body(
 
             ui.add_space(18.0);
 
-            ui.add(display_name_widget(&get_display_name(profile), false));
+            let nip05_status = note_context.nip05_cache.profile_status(pubkey, profile);
+            ui.add(display_name_widget(
+                &get_display_name(profile),
+                nip05_status.map(|status| (&mut *note_context.i18n, status)),
+                false,
+            ));
 
             ui.add_space(8.0);
 
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use crate::nav::BodyResponse;
 use crate::ui::{Preview, PreviewConfig};
 use egui::{Align, Button, CornerRadius, Frame, Id, Layout, Margin, Rgba, RichText, Ui, Vec2};
-use enostr::{RelayPool, RelayStatus};
-use notedeck::{tr, Localization, NotedeckTextStyle, RelayAction};
+use enostr::{RelayInfoState, RelayPool, RelayStatus};
+use notedeck::{
+    tr, tr_plural, AccountData, Localization, NotedeckTextStyle, RelayAction, RelaySpec,
+};
 use notedeck_ui::app_images;
 use notedeck_ui::{colors::PINK, padding};
 use tracing::debug;
@@ -13,6 +15,8 @@ use super::widgets::styled_button;
 
 pub struct RelayView<'a> {
     pool: &'a RelayPool,
+    advertised: &'a BTreeSet<RelaySpec>,
+    dm_relays: &'a BTreeSet<String>,
     id_string_map: &'a mut HashMap<Id, String>,
     i18n: &'a mut Localization,
 }
@@ -40,14 +44,15 @@ impl RelayView<'_> {
                     .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
                     .auto_shrink([false; 2])
                     .show(ui, |ui| {
-                        let mut action = None;
-                        if let Some(relay_to_remove) = self.show_relays(ui) {
-                            action = Some(RelayAction::Remove(relay_to_remove));
-                        }
+                        let mut action = self.show_relays(ui);
                         ui.add_space(8.0);
                         if let Some(relay_to_add) = self.show_add_relay_ui(ui) {
                             action = Some(RelayAction::Add(relay_to_add));
                         }
+                        ui.add_space(24.0);
+                        if let Some(dm_action) = self.show_dm_relays(ui) {
+                            action = Some(dm_action);
+                        }
                         action
                     })
             })
@@ -64,11 +69,14 @@ impl RelayView<'_> {
 impl<'a> RelayView<'a> {
     pub fn new(
         pool: &'a RelayPool,
+        account: &'a AccountData,
         id_string_map: &'a mut HashMap<Id, String>,
         i18n: &'a mut Localization,
     ) -> Self {
         RelayView {
             pool,
+            advertised: account.advertised_relays(),
+            dm_relays: account.dm_relays(),
             id_string_map,
             i18n,
         }
@@ -78,9 +86,9 @@ impl<'a> RelayView<'a> {
         egui::CentralPanel::default().show(ui.ctx(), |ui| self.ui(ui));
     }
 
-    /// Show the current relays and return a relay the user selected to delete
-    fn show_relays(&mut self, ui: &mut Ui) -> Option<String> {
-        let mut relay_to_remove = None;
+    /// Show the current relays and return what the user did to one of them
+    fn show_relays(&mut self, ui: &mut Ui) -> Option<RelayAction> {
+        let mut relay_action = None;
         for (index, relay_info) in get_relay_infos(self.pool).iter().enumerate() {
             ui.add_space(8.0);
             ui.vertical_centered_justified(|ui| {
@@ -118,26 +126,111 @@ impl<'a> RelayView<'a> {
 
                         ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                             if ui.add(delete_button(ui.visuals().dark_mode)).clicked() {
-                                relay_to_remove = Some(relay_info.relay_url.to_string());
+                                relay_action =
+                                    Some(RelayAction::Remove(relay_info.relay_url.to_string()));
                             };
 
                             show_connection_status(ui, self.i18n, relay_info.status);
                         });
                     });
+
+                    let spec =
+                        self.advertised
+                            .get(&RelaySpec::new(relay_info.relay_url, false, false));
+                    if let Some(spec) = spec {
+                        if let Some(action) = usage_toggles(ui, self.i18n, spec) {
+                            relay_action = Some(action);
+                        }
+                    }
+
+                    if let Some(state) = self.pool.relay_info.get(relay_info.relay_url) {
+                        show_relay_info(ui, self.i18n, state);
+                    }
+                });
+            });
+        }
+        relay_action
+    }
+
+    /// The relays we ask others to send DMs to (kind 10050)
+    fn show_dm_relays(&mut self, ui: &mut Ui) -> Option<RelayAction> {
+        ui.label(
+            RichText::new(tr!(
+                self.i18n,
+                "DM inbox relays",
+                "Label for the list of relays to receive direct messages on"
+            ))
+            .text_style(NotedeckTextStyle::Heading3.text_style()),
+        );
+
+        let mut action = None;
+        if self.dm_relays.is_empty() {
+            ui.add_space(8.0);
+            ui.weak(tr!(
+                self.i18n,
+                "You haven't set any relays to receive direct messages on",
+                "Message shown when the DM inbox relay list is empty"
+            ));
+        }
+
+        for url in self.dm_relays {
+            ui.add_space(8.0);
+            ui.vertical_centered_justified(|ui| {
+                relay_frame(ui).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(url)
+                                .text_style(NotedeckTextStyle::Monospace.text_style()),
+                        );
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            if ui.add(delete_button(ui.visuals().dark_mode)).clicked() {
+                                action = Some(RelayAction::RemoveDmRelay(url.clone()));
+                            }
+                        });
+                    });
                 });
             });
         }
-        relay_to_remove
+
+        ui.add_space(8.0);
+        let id = ui.id().with("add-dm-relay");
+        let dm_relays = self.dm_relays;
+        let label = tr!(
+            self.i18n,
+            "Add DM relay",
+            "Button label to add a relay to receive direct messages on"
+        );
+        if let Some(url) = self.show_add_ui(ui, id, label, |url| {
+            // the dm relays we have are canonical, see `AccountRelayData`
+            is_valid_relay_url(url)
+                && !dm_relays.contains(&RelayPool::canonicalize_url(url.to_owned()))
+        }) {
+            action = Some(RelayAction::AddDmRelay(url));
+        }
+
+        action
     }
 
     const RELAY_PREFILL: &'static str = "wss://";
 
     fn show_add_relay_ui(&mut self, ui: &mut Ui) -> Option<String> {
         let id = ui.id().with("add-relay)");
+        let pool = self.pool;
+        let label = tr!(self.i18n, "Add relay", "Button label to add a relay");
+        self.show_add_ui(ui, id, label, |url| pool.is_valid_url(url))
+    }
+
+    fn show_add_ui(
+        &mut self,
+        ui: &mut Ui,
+        id: Id,
+        label: String,
+        is_valid: impl Fn(&str) -> bool,
+    ) -> Option<String> {
         match self.id_string_map.get(&id) {
             None => {
                 ui.with_layout(Layout::top_down(Align::Min), |ui| {
-                    let relay_button = add_relay_button(self.i18n);
+                    let relay_button = add_relay_button(label);
                     if ui.add(relay_button).clicked() {
                         debug!("add relay clicked");
                         self.id_string_map
@@ -148,20 +241,25 @@ impl<'a> RelayView<'a> {
             }
             Some(_) => {
                 ui.with_layout(Layout::top_down(Align::Min), |ui| {
-                    self.add_relay_entry(ui, id)
+                    self.add_relay_entry(ui, id, is_valid)
                 })
                 .inner
             }
         }
     }
 
-    pub fn add_relay_entry(&mut self, ui: &mut Ui, id: Id) -> Option<String> {
+    pub fn add_relay_entry(
+        &mut self,
+        ui: &mut Ui,
+        id: Id,
+        is_valid: impl Fn(&str) -> bool,
+    ) -> Option<String> {
         padding(16.0, ui, |ui| {
             let text_buffer = self
                 .id_string_map
                 .entry(id)
                 .or_insert_with(|| Self::RELAY_PREFILL.to_string());
-            let is_enabled = self.pool.is_valid_url(text_buffer);
+            let is_enabled = is_valid(text_buffer);
             let text_edit = egui::TextEdit::singleline(text_buffer)
                 .hint_text(
                     RichText::new(tr!(
@@ -193,10 +291,10 @@ impl<'a> RelayView<'a> {
     }
 }
 
-fn add_relay_button(i18n: &mut Localization) -> Button<'static> {
+fn add_relay_button(label: String) -> Button<'static> {
     Button::image_and_text(
         app_images::add_relay_image().fit_to_exact_size(Vec2::new(48.0, 48.0)),
-        RichText::new(tr!(i18n, "Add relay", "Button label to add a relay"))
+        RichText::new(label)
             .size(16.0)
             // TODO: this color should not be hard coded. Find some way to add it to the visuals
             .color(PINK),
@@ -212,6 +310,141 @@ fn add_relay_button2<'a>(i18n: &'a mut Localization, is_enabled: bool) -> impl e
     }
 }
 
+fn is_valid_relay_url(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "ws" | "wss"))
+}
+
+/// Read/write toggles for an advertised relay. A relay has to be used for
+/// at least one of them, so the last one left can't be turned off.
+fn usage_toggles(ui: &mut Ui, i18n: &mut Localization, spec: &RelaySpec) -> Option<RelayAction> {
+    let mut read = spec.is_readable();
+    let mut write = spec.is_writable();
+
+    let changed = ui
+        .horizontal(|ui| {
+            let read_changed = ui
+                .add_enabled(
+                    write,
+                    egui::Checkbox::new(&mut read, tr!(i18n, "Read", "Relay usage: read from")),
+                )
+                .on_disabled_hover_text(tr!(
+                    i18n,
+                    "A relay has to be used for reading or writing",
+                    "Hover text for a relay usage toggle that can't be turned off"
+                ))
+                .changed();
+            let write_changed = ui
+                .add_enabled(
+                    read,
+                    egui::Checkbox::new(&mut write, tr!(i18n, "Write", "Relay usage: write to")),
+                )
+                .on_disabled_hover_text(tr!(
+                    i18n,
+                    "A relay has to be used for reading or writing",
+                    "Hover text for a relay usage toggle that can't be turned off"
+                ))
+                .changed();
+            read_changed || write_changed
+        })
+        .inner;
+
+    changed.then(|| RelayAction::SetUsage {
+        url: spec.url.clone(),
+        read,
+        write,
+    })
+}
+
+/// What the relay told us about itself (NIP-11)
+fn show_relay_info(ui: &mut Ui, i18n: &mut Localization, state: &RelayInfoState) {
+    let RelayInfoState::Loaded(info) = state else {
+        return;
+    };
+
+    let limitation = info.limitation();
+    ui.horizontal_wrapped(|ui| {
+        if let Some(name) = &info.name {
+            let resp = ui.strong(name);
+            if let Some(description) = &info.description {
+                resp.on_hover_text(description);
+            }
+        }
+
+        if let Some(software) = &info.software {
+            let software = software.rsplit('/').next().unwrap_or(software);
+            let software = software.trim_end_matches(".git");
+            match &info.version {
+                Some(version) => ui.weak(format!("{software} {version}")),
+                None => ui.weak(software),
+            };
+        }
+
+        if limitation.payment_required() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                tr!(i18n, "Paid", "Badge for relays that require payment"),
+            );
+        }
+        if limitation.auth_required() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                tr!(
+                    i18n,
+                    "Auth required",
+                    "Badge for relays that require authentication"
+                ),
+            );
+        }
+    });
+
+    let mut limits = vec![];
+    if let Some(n) = limitation.max_subscriptions {
+        limits.push(tr_plural!(
+            i18n,
+            "{count} subscription",
+            "{count} subscriptions",
+            "Relay limit on open subscriptions",
+            n,
+        ));
+    }
+    if let Some(n) = limitation.max_filters {
+        limits.push(tr_plural!(
+            i18n,
+            "{count} filter",
+            "{count} filters",
+            "Relay limit on filters per subscription",
+            n,
+        ));
+    }
+    if let Some(n) = limitation.max_limit {
+        limits.push(tr_plural!(
+            i18n,
+            "{count} event per request",
+            "{count} events per request",
+            "Relay limit on events returned per request",
+            n,
+        ));
+    }
+    if let Some(n) = limitation.max_message_length {
+        limits.push(tr_plural!(
+            i18n,
+            "{count} byte per message",
+            "{count} bytes per message",
+            "Relay limit on message size",
+            n,
+        ));
+    }
+
+    if !limits.is_empty() {
+        ui.weak(tr!(
+            i18n,
+            "Limits: {limits}",
+            "Summary of a relay's limits",
+            limits = limits.join(", ")
+        ));
+    }
+}
+
 fn get_right_side_width(status: RelayStatus) -> f32 {
     match status {
         RelayStatus::Connected => 150.0,
@@ -310,7 +543,8 @@ mod preview {
         fn update(&mut self, app: &mut AppContext<'_>, ui: &mut egui::Ui) -> AppResponse {
             self.pool.try_recv();
             let mut id_string_map = HashMap::new();
-            RelayView::new(app.pool, &mut id_string_map, app.i18n).ui(ui);
+            let account = &app.accounts.get_selected_account().data;
+            RelayView::new(app.pool, account, &mut id_string_map, app.i18n).ui(ui);
             AppResponse::none()
         }
     }
@@ -6,9 +6,9 @@ use egui_extras::{Size, StripBuilder};
 use enostr::NoteId;
 use nostrdb::Transaction;
 use notedeck::{
-    tr, ui::richtext_small, Images, LanguageIdentifier, Localization, NoteContext,
-    NotedeckTextStyle, Settings, SettingsHandler, DEFAULT_MAX_HASHTAGS_PER_NOTE,
-    DEFAULT_NOTE_BODY_FONT_SIZE,
+    tr, ui::richtext_small, ContentWarningMode, Images, LanguageIdentifier, Localization,
+    NoteCache, NoteContext, NotedeckTextStyle, Settings, SettingsHandler,
+    DEFAULT_MAX_HASHTAGS_PER_NOTE, DEFAULT_NOTE_BODY_FONT_SIZE,
 };
 use notedeck_ui::{
     app_images::{copy_to_clipboard_dark_image, copy_to_clipboard_image},
@@ -36,18 +36,32 @@ pub enum SettingsAction {
     SetNoteBodyFontSize(f32),
     SetAnimateNavTransitions(bool),
     SetMaxHashtagsPerNote(usize),
+    SetContentWarnings(ContentWarningMode),
+    SetMarkdown(bool),
+    SetLinkPreviews(bool),
+    SetLinkPreviewDomains {
+        allowed: Vec<String>,
+        blocked: Vec<String>,
+    },
+    SetTranslationBackend {
+        endpoint: String,
+        api_key: String,
+    },
+    SetAutoTranslate(bool),
     OpenRelays,
     OpenCacheFolder,
     ClearCacheFolder,
 }
 
 impl SettingsAction {
+    #[allow(clippy::too_many_arguments)]
     pub fn process_settings_action<'a>(
         self,
         app: &mut Damus,
         settings: &'a mut SettingsHandler,
         i18n: &'a mut Localization,
         img_cache: &mut Images,
+        note_cache: &mut NoteCache,
         ctx: &egui::Context,
         accounts: &mut notedeck::Accounts,
     ) -> Option<RouterAction> {
@@ -100,6 +114,44 @@ impl SettingsAction {
                 settings.set_max_hashtags_per_note(value);
                 accounts.update_max_hashtags_per_note(value);
             }
+
+            Self::SetContentWarnings(mode) => {
+                app.note_options.set_content_warnings(mode);
+                settings.set_content_warnings(mode);
+            }
+
+            Self::SetMarkdown(value) => {
+                app.note_options.set(NoteOptions::Markdown, value);
+                settings.set_markdown(value);
+            }
+
+            Self::SetLinkPreviews(value) => {
+                settings.set_link_previews(value);
+                img_cache
+                    .link_previews
+                    .set_policy(settings.link_preview_policy());
+            }
+
+            Self::SetLinkPreviewDomains { allowed, blocked } => {
+                settings.set_link_preview_domains(allowed, blocked);
+                img_cache
+                    .link_previews
+                    .set_policy(settings.link_preview_policy());
+            }
+
+            Self::SetTranslationBackend { endpoint, api_key } => {
+                settings.set_translation_backend(endpoint, api_key);
+                note_cache
+                    .translations
+                    .set_config(&settings.translation_config());
+            }
+
+            Self::SetAutoTranslate(value) => {
+                settings.set_auto_translate(value);
+                note_cache
+                    .translations
+                    .set_config(&settings.translation_config());
+            }
         }
         route_action
     }
@@ -107,6 +159,8 @@ impl SettingsAction {
 
 pub struct SettingsView<'a> {
     settings: &'a mut Settings,
+    /// Kept in the keyring, not with the rest of the settings
+    translation_api_key: String,
     note_context: &'a mut NoteContext<'a>,
     note_options: &'a mut NoteOptions,
 }
@@ -133,11 +187,13 @@ where
 impl<'a> SettingsView<'a> {
     pub fn new(
         settings: &'a mut Settings,
+        translation_api_key: String,
         note_context: &'a mut NoteContext<'a>,
         note_options: &'a mut NoteOptions,
     ) -> Self {
         Self {
             settings,
+            translation_api_key,
             note_context,
             note_options,
         }
@@ -494,6 +550,77 @@ impl<'a> SettingsView<'a> {
                 }
             });
 
+            ui.horizontal_wrapped(|ui| {
+                ui.label(richtext_small(tr!(
+                    self.note_context.i18n,
+                    "Format notes with markdown:",
+                    "Label for rendering code, emphasis and quotes in notes, others settings section",
+                )));
+
+                if ui
+                    .toggle_value(
+                        &mut self.settings.markdown,
+                        RichText::new(tr!(
+                            self.note_context.i18n,
+                            "On",
+                            "Setting to turn on markdown formatting in notes"
+                        ))
+                        .text_style(NotedeckTextStyle::Small.text_style()),
+                    )
+                    .changed()
+                {
+                    action = Some(SettingsAction::SetMarkdown(self.settings.markdown));
+                }
+            });
+
+            ui.horizontal_wrapped(|ui| {
+                ui.label(richtext_small(tr!(
+                    self.note_context.i18n,
+                    "Content warnings:",
+                    "Label for what to do with notes that have a content warning, others settings section",
+                )));
+
+                let modes = [
+                    (
+                        ContentWarningMode::Ask,
+                        tr!(
+                            self.note_context.i18n,
+                            "Ask",
+                            "Setting to hide notes with a content warning until clicked"
+                        ),
+                    ),
+                    (
+                        ContentWarningMode::AlwaysShow,
+                        tr!(
+                            self.note_context.i18n,
+                            "Always show",
+                            "Setting to show notes with a content warning right away"
+                        ),
+                    ),
+                    (
+                        ContentWarningMode::AlwaysHide,
+                        tr!(
+                            self.note_context.i18n,
+                            "Always hide",
+                            "Setting to never show notes with a content warning"
+                        ),
+                    ),
+                ];
+
+                for (mode, label) in modes {
+                    if ui
+                        .selectable_value(
+                            &mut self.settings.content_warnings,
+                            mode,
+                            richtext_small(label),
+                        )
+                        .clicked()
+                    {
+                        action = Some(SettingsAction::SetContentWarnings(mode));
+                    }
+                }
+            });
+
             ui.horizontal_wrapped(|ui| {
                 ui.label(richtext_small(tr!(
                     self.note_context.i18n,
@@ -550,6 +677,198 @@ impl<'a> SettingsView<'a> {
         action
     }
 
+    fn link_previews_section(&mut self, ui: &mut egui::Ui) -> Option<SettingsAction> {
+        let mut action = None;
+
+        let title = tr!(
+            self.note_context.i18n,
+            "Link previews",
+            "Label for link previews settings section"
+        );
+        settings_group(ui, title, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(richtext_small(tr!(
+                    self.note_context.i18n,
+                    "Show link previews:",
+                    "Label for showing link previews, link previews settings section",
+                )));
+
+                if ui
+                    .toggle_value(
+                        &mut self.settings.link_previews,
+                        RichText::new(tr!(
+                            self.note_context.i18n,
+                            "On",
+                            "Setting to turn on fetching previews for links in notes"
+                        ))
+                        .text_style(NotedeckTextStyle::Small.text_style()),
+                    )
+                    .changed()
+                {
+                    action = Some(SettingsAction::SetLinkPreviews(self.settings.link_previews));
+                }
+            });
+
+            ui.label(
+                richtext_small(tr!(
+                    self.note_context.i18n,
+                    "Fetching a preview lets the linked site know you've seen the link.",
+                    "Privacy note for link previews, link previews settings section",
+                ))
+                .color(ui.visuals().gray_out(ui.visuals().text_color())),
+            );
+
+            if !self.settings.link_previews {
+                return;
+            }
+
+            let allowed = domains_edit(
+                ui,
+                "link-preview-allowed",
+                tr!(
+                    self.note_context.i18n,
+                    "Only preview these sites:",
+                    "Label for the domains link previews are limited to, link previews settings section",
+                ),
+                tr!(
+                    self.note_context.i18n,
+                    "All sites",
+                    "Hint shown when link previews aren't limited to any domains",
+                ),
+                &self.settings.link_preview_allowed_domains,
+            );
+
+            let blocked = domains_edit(
+                ui,
+                "link-preview-blocked",
+                tr!(
+                    self.note_context.i18n,
+                    "Never preview these sites:",
+                    "Label for the domains link previews are never fetched from, link previews settings section",
+                ),
+                tr!(
+                    self.note_context.i18n,
+                    "example.com, example.org",
+                    "Hint for the list of domains link previews are never fetched from",
+                ),
+                &self.settings.link_preview_blocked_domains,
+            );
+
+            if allowed.is_some() || blocked.is_some() {
+                action = Some(SettingsAction::SetLinkPreviewDomains {
+                    allowed: allowed
+                        .unwrap_or_else(|| self.settings.link_preview_allowed_domains.clone()),
+                    blocked: blocked
+                        .unwrap_or_else(|| self.settings.link_preview_blocked_domains.clone()),
+                });
+            }
+        });
+
+        action
+    }
+
+    fn translation_section(&mut self, ui: &mut egui::Ui) -> Option<SettingsAction> {
+        let mut action = None;
+
+        let title = tr!(
+            self.note_context.i18n,
+            "Translation",
+            "Label for note translation settings section"
+        );
+        settings_group(ui, title, |ui| {
+            let mut backend_edited = false;
+
+            // what's being typed, until it's saved
+            let api_key_id = egui::Id::new("translation-api-key");
+            let mut api_key = ui
+                .data_mut(|d| d.get_temp::<String>(api_key_id))
+                .unwrap_or_else(|| self.translation_api_key.clone());
+
+            ui.horizontal_wrapped(|ui| {
+                ui.label(richtext_small(tr!(
+                    self.note_context.i18n,
+                    "Translation server:",
+                    "Label for the LibreTranslate compatible server notes are translated with, translation settings section",
+                )));
+
+                backend_edited |= ui
+                    .add(
+                        TextEdit::singleline(&mut self.settings.translation_endpoint)
+                            .hint_text(richtext_small("https://libretranslate.com"))
+                            .desired_width(ui.available_width().min(300.0)),
+                    )
+                    .lost_focus();
+            });
+
+            ui.horizontal_wrapped(|ui| {
+                ui.label(richtext_small(tr!(
+                    self.note_context.i18n,
+                    "API key:",
+                    "Label for the translation server's API key, translation settings section",
+                )));
+
+                backend_edited |= ui
+                    .add(
+                        TextEdit::singleline(&mut api_key)
+                            .password(true)
+                            .hint_text(richtext_small(tr!(
+                                self.note_context.i18n,
+                                "Optional",
+                                "Hint for a setting that can be left empty",
+                            )))
+                            .desired_width(ui.available_width().min(300.0)),
+                    )
+                    .lost_focus();
+            });
+
+            if backend_edited {
+                action = Some(SettingsAction::SetTranslationBackend {
+                    endpoint: self.settings.translation_endpoint.trim().to_owned(),
+                    api_key: api_key.trim().to_owned(),
+                });
+            }
+            ui.data_mut(|d| d.insert_temp(api_key_id, api_key));
+
+            ui.label(
+                richtext_small(tr!(
+                    self.note_context.i18n,
+                    "Notes you translate are sent to this server. Leave it empty to turn translation off.",
+                    "Privacy note for note translation, translation settings section",
+                ))
+                .color(ui.visuals().gray_out(ui.visuals().text_color())),
+            );
+
+            if self.settings.translation_endpoint.trim().is_empty() {
+                return;
+            }
+
+            ui.horizontal_wrapped(|ui| {
+                ui.label(richtext_small(tr!(
+                    self.note_context.i18n,
+                    "Translate automatically:",
+                    "Label for translating notes in other languages without being asked, translation settings section",
+                )));
+
+                if ui
+                    .toggle_value(
+                        &mut self.settings.auto_translate,
+                        RichText::new(tr!(
+                            self.note_context.i18n,
+                            "On",
+                            "Setting to translate notes that aren't in the app's language automatically"
+                        ))
+                        .text_style(NotedeckTextStyle::Small.text_style()),
+                    )
+                    .changed()
+                {
+                    action = Some(SettingsAction::SetAutoTranslate(self.settings.auto_translate));
+                }
+            });
+        });
+
+        action
+    }
+
     fn keys_section(&mut self, ui: &mut egui::Ui) {
         let title = tr!(
             self.note_context.i18n,
@@ -739,6 +1058,18 @@ impl<'a> SettingsView<'a> {
                         action = Some(new_action);
                     }
 
+                    ui.add_space(5.0);
+
+                    if let Some(new_action) = self.link_previews_section(ui) {
+                        action = Some(new_action);
+                    }
+
+                    ui.add_space(5.0);
+
+                    if let Some(new_action) = self.translation_section(ui) {
+                        action = Some(new_action);
+                    }
+
                     ui.add_space(10.0);
 
                     if let Some(new_action) = self.manage_relays_section(ui) {
@@ -753,6 +1084,43 @@ impl<'a> SettingsView<'a> {
     }
 }
 
+/// A comma separated list of domains. Returns the new list once the user is
+/// done editing it.
+fn domains_edit(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    label: String,
+    hint: String,
+    domains: &[String],
+) -> Option<Vec<String>> {
+    let id = ui.id().with(id_salt);
+    let mut text = ui
+        .data_mut(|d| d.get_temp::<String>(id))
+        .unwrap_or_else(|| domains.join(", "));
+
+    let mut edited = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.label(richtext_small(label));
+
+        let resp = ui.add(
+            TextEdit::singleline(&mut text)
+                .hint_text(richtext_small(hint))
+                .desired_width(ui.available_width().min(300.0)),
+        );
+
+        if resp.lost_focus() {
+            let parsed = notedeck::link_preview::parse_domains(&text);
+            text = parsed.join(", ");
+            if parsed != domains {
+                edited = Some(parsed);
+            }
+        }
+    });
+
+    ui.data_mut(|d| d.insert_temp(id, text));
+    edited
+}
+
 pub fn format_size(size_bytes: u64) -> String {
     const KB: f64 = 1024.0;
     const MB: f64 = KB * 1024.0;
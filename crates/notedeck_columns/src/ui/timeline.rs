@@ -5,6 +5,7 @@ use enostr::Pubkey;
 use nostrdb::{Note, ProfileRecord, Transaction};
 use notedeck::fonts::get_font_size;
 use notedeck::name::get_display_name;
+use notedeck::reaction::{reaction_display, ReactionCount};
 use notedeck::ui::is_narrow;
 use notedeck::{tr_plural, Muted, NotedeckTextStyle};
 use notedeck_ui::app_images::{like_image_filled, repost_image};
@@ -702,6 +703,8 @@ fn render_reaction_cluster(
             .collect()
     };
 
+    let counts = reaction.counts(|r| !mute.is_pk_muted(r.sender.bytes()));
+
     render_composite_entry(
         ui,
         note_context,
@@ -709,6 +712,7 @@ fn render_reaction_cluster(
         underlying_note,
         profiles_to_show,
         CompositeType::Reaction,
+        &counts,
     )
 }
 
@@ -721,6 +725,7 @@ fn render_composite_entry(
     underlying_note: &nostrdb::Note<'_>,
     profiles_to_show: Vec<ProfileEntry>,
     composite_type: CompositeType,
+    reaction_counts: &[ReactionCount],
 ) -> RenderEntryResponse {
     let first_name = get_display_name(profiles_to_show.iter().find_map(|opt| opt.record.as_ref()))
         .name()
@@ -779,6 +784,21 @@ fn render_composite_entry(
                         action = Some(cur_action);
                     }
 
+                    // plain likes are already said by the icon
+                    let only_likes = matches!(reaction_counts, [only]
+                        if only.emoji.is_none() && only.content == reaction_display("+"));
+                    if !only_likes {
+                        for reaction in reaction_counts {
+                            notedeck_ui::note::reactions::reaction_count_ui(
+                                ui,
+                                note_context.img_cache,
+                                note_context.jobs,
+                                reaction,
+                                16.0,
+                            );
+                        }
+                    }
+
                     let description = composite_type.description(
                         note_context.i18n,
                         &first_name,
@@ -976,6 +996,7 @@ fn render_repost_cluster(
         underlying_note,
         profiles_to_show,
         CompositeType::Repost,
+        &[],
     )
 }
 
@@ -5,8 +5,8 @@ use notedeck_ui::nip51_set::Nip51SetUiCache;
 
 use crate::deck_state::DeckState;
 use crate::login_manager::AcquireKeyState;
+use crate::ui::profile::EditProfileState;
 use crate::ui::search::SearchQueryState;
-use enostr::ProfileState;
 use notedeck_ui::media::MediaViewerState;
 
 /// Various state for views
@@ -20,7 +20,7 @@ pub struct ViewState {
     pub id_state_map: HashMap<egui::Id, AcquireKeyState>,
     pub id_string_map: HashMap<egui::Id, String>,
     pub searches: HashMap<egui::Id, SearchQueryState>,
-    pub pubkey_to_profile_state: HashMap<Pubkey, ProfileState>,
+    pub pubkey_to_profile_state: HashMap<Pubkey, EditProfileState>,
 
     /// Keeps track of what urls we are actively viewing in the
     /// fullscreen media viewier, as well as any other state we want to
@@ -0,0 +1,248 @@
+use notedeck::{storage, DataPath, DataPathType, Directory};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Instant;
+use tracing::{debug, error};
+
+static AUDIT_FILE: &str = "dave_audit_log.json";
+
+/// The number of entries we keep around. Older entries are dropped.
+const MAX_ENTRIES: usize = 500;
+
+/// How a tool call was handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditDecision {
+    /// Allowed by the tool policy
+    Allowed,
+    /// The user approved the call
+    Approved,
+    /// Waiting for the user to approve or reject the call
+    Pending,
+    /// Denied by the tool policy
+    Denied,
+    /// The user rejected the call
+    Rejected,
+    /// The call was never run because the chat was closed
+    Cancelled,
+    /// The model sent a call we couldn't parse
+    Invalid,
+}
+
+/// A record of a single tool call made by the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub call_id: String,
+    pub tool: String,
+    pub arguments: String,
+    pub decision: AuditDecision,
+    /// When the call was received, in unix seconds
+    pub created_at: u64,
+    /// How long the tool took to run, in milliseconds
+    pub latency_ms: Option<u64>,
+    /// The size of the response sent back to the model, in bytes
+    pub result_size: Option<usize>,
+    pub error: Option<String>,
+}
+
+/// A persistent log of every tool call dave made, newest last
+pub struct AuditLog {
+    dir: Option<PathBuf>,
+    entries: Vec<AuditEntry>,
+    /// When calls that are currently running were started
+    running: HashMap<String, Instant>,
+}
+
+impl AuditLog {
+    /// An audit log that is only kept in memory
+    pub fn in_memory() -> Self {
+        Self {
+            dir: None,
+            entries: vec![],
+            running: HashMap::new(),
+        }
+    }
+
+    pub fn new(path: &DataPath) -> Self {
+        let dir = path.path(DataPathType::Setting);
+        let entries = match Directory::new(dir.clone()).get_file(AUDIT_FILE.to_owned()) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                error!("could not parse dave audit log: {e}");
+                vec![]
+            }),
+            Err(e) => {
+                debug!("no dave audit log loaded: {e}");
+                vec![]
+            }
+        };
+
+        Self {
+            dir: Some(dir),
+            entries,
+            running: HashMap::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Record a new tool call
+    pub fn record(
+        &mut self,
+        call_id: &str,
+        tool: &str,
+        arguments: String,
+        decision: AuditDecision,
+        now: u64,
+    ) {
+        self.entries.push(AuditEntry {
+            call_id: call_id.to_owned(),
+            tool: tool.to_owned(),
+            arguments,
+            decision,
+            created_at: now,
+            latency_ms: None,
+            result_size: None,
+            error: None,
+        });
+
+        if self.entries.len() > MAX_ENTRIES {
+            let excess = self.entries.len() - MAX_ENTRIES;
+            self.entries.drain(..excess);
+        }
+
+        self.save();
+    }
+
+    fn entry_mut(&mut self, call_id: &str) -> Option<&mut AuditEntry> {
+        self.entries.iter_mut().rev().find(|e| e.call_id == call_id)
+    }
+
+    /// Change the decision of a pending call
+    pub fn decide(&mut self, call_id: &str, decision: AuditDecision) {
+        if let Some(entry) = self.entry_mut(call_id) {
+            entry.decision = decision;
+            self.save();
+        }
+    }
+
+    /// The tool is about to run. Latency is measured from here.
+    pub fn start(&mut self, call_id: &str) {
+        self.running.insert(call_id.to_owned(), Instant::now());
+    }
+
+    /// The tool finished running and its response was added to the chat
+    pub fn finish(&mut self, call_id: &str, result_size: usize, error: Option<String>) {
+        let latency_ms = self
+            .running
+            .remove(call_id)
+            .map(|started| started.elapsed().as_millis() as u64);
+
+        let Some(entry) = self.entry_mut(call_id) else {
+            return;
+        };
+
+        entry.latency_ms = latency_ms;
+        entry.result_size = Some(result_size);
+        entry.error = error;
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+
+        let serialized = match serde_json::to_string(&self.entries) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("could not serialize dave audit log: {e}");
+                return;
+            }
+        };
+
+        if let Err(e) = storage::write_file(dir, AUDIT_FILE.to_owned(), &serialized) {
+            error!("could not write dave audit log: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_finish() {
+        let mut log = AuditLog::in_memory();
+        log.record(
+            "call1",
+            "relay_query",
+            r#"{"search":"bitcoin"}"#.to_string(),
+            AuditDecision::Pending,
+            10,
+        );
+        log.decide("call1", AuditDecision::Approved);
+        log.start("call1");
+        log.finish("call1", 42, None);
+
+        let entry = &log.entries()[0];
+        assert_eq!(entry.decision, AuditDecision::Approved);
+        assert_eq!(entry.result_size, Some(42));
+        assert!(entry.latency_ms.is_some());
+    }
+
+    #[test]
+    fn test_finish_without_start_has_no_latency() {
+        let mut log = AuditLog::in_memory();
+        log.record(
+            "call1",
+            "query",
+            "{}".to_string(),
+            AuditDecision::Denied,
+            10,
+        );
+        log.finish("call1", 10, Some("denied".to_string()));
+
+        let entry = &log.entries()[0];
+        assert_eq!(entry.latency_ms, None);
+        assert_eq!(entry.error.as_deref(), Some("denied"));
+    }
+
+    #[test]
+    fn test_entries_are_capped() {
+        let mut log = AuditLog::in_memory();
+        for i in 0..(MAX_ENTRIES + 10) {
+            log.record(
+                &i.to_string(),
+                "query",
+                "{}".to_string(),
+                AuditDecision::Allowed,
+                i as u64,
+            );
+        }
+
+        assert_eq!(log.entries().len(), MAX_ENTRIES);
+        assert_eq!(log.entries()[0].call_id, "10");
+    }
+
+    #[test]
+    fn test_persisted() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = DataPath::new(tmp.path());
+
+        let mut log = AuditLog::new(&path);
+        log.record(
+            "call1",
+            "thread",
+            "{}".to_string(),
+            AuditDecision::Allowed,
+            1,
+        );
+        log.finish("call1", 5, None);
+
+        let log = AuditLog::new(&path);
+        assert_eq!(log.entries().len(), 1);
+        assert_eq!(log.entries()[0].result_size, Some(5));
+    }
+}
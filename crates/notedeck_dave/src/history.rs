@@ -0,0 +1,282 @@
+use crate::messages::Message;
+use notedeck::{storage, DataPath, DataPathType, Directory};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::{debug, error};
+
+/// The directory (relative to the settings path) where conversations
+/// are stored, one json file per conversation
+static CONVERSATIONS_DIR: &str = "dave_conversations";
+
+/// Maximum number of characters used for auto-generated titles
+const MAX_TITLE_LEN: usize = 48;
+
+/// A saved dave conversation.
+///
+/// The system prompt is never stored, it is regenerated when the
+/// conversation is restored so that things like the current date are
+/// always up to date. Notes are referenced by id so that they are
+/// re-resolved from nostrdb when the conversation is loaded again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub messages: Vec<Message>,
+}
+
+impl Conversation {
+    pub fn new(id: String, now: u64) -> Self {
+        Self {
+            id,
+            title: String::new(),
+            created_at: now,
+            updated_at: now,
+            messages: vec![],
+        }
+    }
+
+    /// Update the conversation with the latest chat. Titles are
+    /// generated from the first user prompt unless one was already set
+    pub fn update(&mut self, chat: &[Message], now: u64) {
+        self.messages = chat
+            .iter()
+            .filter(|m| !matches!(m, Message::System(_)))
+            .cloned()
+            .collect();
+        self.updated_at = now;
+
+        if self.title.is_empty() {
+            if let Some(title) = self.first_prompt().map(generate_title) {
+                self.title = title;
+            }
+        }
+    }
+
+    pub fn first_prompt(&self) -> Option<&str> {
+        self.messages.iter().find_map(|m| {
+            if let Message::User(msg) = m {
+                Some(msg.as_str())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Whether there is anything worth saving in this conversation
+    pub fn is_empty(&self) -> bool {
+        self.first_prompt().is_none()
+    }
+
+    fn file_name(&self) -> String {
+        format!("{}.json", self.id)
+    }
+}
+
+/// Generate a conversation title from the first prompt of the user.
+/// Long prompts are cut at a word boundary.
+pub fn generate_title(prompt: &str) -> String {
+    let prompt = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if prompt.chars().count() <= MAX_TITLE_LEN {
+        return prompt;
+    }
+
+    let mut title = String::new();
+    for word in prompt.split(' ') {
+        let len = title.chars().count() + word.chars().count() + 1;
+        if len > MAX_TITLE_LEN {
+            break;
+        }
+
+        if !title.is_empty() {
+            title.push(' ');
+        }
+        title.push_str(word);
+    }
+
+    // a single very long word, just cut it
+    if title.is_empty() {
+        title = prompt.chars().take(MAX_TITLE_LEN).collect();
+    }
+
+    title.push('…');
+    title
+}
+
+/// All of the conversations that have been saved to disk, most
+/// recently updated first
+pub struct ConversationHistory {
+    dir: PathBuf,
+    conversations: Vec<Conversation>,
+}
+
+impl ConversationHistory {
+    pub fn new(path: &DataPath) -> Self {
+        let dir = path.path(DataPathType::Setting).join(CONVERSATIONS_DIR);
+        let mut history = Self {
+            dir,
+            conversations: vec![],
+        };
+        history.load();
+        history
+    }
+
+    fn load(&mut self) {
+        let files = match Directory::new(self.dir.clone()).get_files() {
+            Ok(files) => files,
+            Err(e) => {
+                debug!("no dave conversations loaded: {e}");
+                return;
+            }
+        };
+
+        self.conversations = files
+            .into_iter()
+            .filter_map(
+                |(name, contents)| match serde_json::from_str::<Conversation>(&contents) {
+                    Ok(conversation) => Some(conversation),
+                    Err(e) => {
+                        error!("could not parse dave conversation {name}: {e}");
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.conversations
+            .sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    }
+
+    pub fn conversations(&self) -> &[Conversation] {
+        &self.conversations
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Conversation> {
+        self.conversations.iter().find(|c| c.id == id)
+    }
+
+    /// Insert or update a conversation and persist it to disk
+    pub fn save(&mut self, conversation: &Conversation) {
+        if conversation.is_empty() {
+            return;
+        }
+
+        if let Some(existing) = self
+            .conversations
+            .iter_mut()
+            .find(|c| c.id == conversation.id)
+        {
+            *existing = conversation.clone();
+        } else {
+            self.conversations.push(conversation.clone());
+        }
+        self.sort();
+
+        let serialized = match serde_json::to_string(conversation) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("could not serialize dave conversation: {e}");
+                return;
+            }
+        };
+
+        if let Err(e) = storage::write_file(&self.dir, conversation.file_name(), &serialized) {
+            error!("could not write dave conversation {}: {e}", conversation.id);
+        }
+    }
+
+    pub fn rename(&mut self, id: &str, title: String) {
+        let Some(conversation) = self.conversations.iter_mut().find(|c| c.id == id) else {
+            return;
+        };
+
+        conversation.title = title;
+        let conversation = conversation.clone();
+        self.save(&conversation);
+    }
+
+    pub fn delete(&mut self, id: &str) {
+        let Some(index) = self.conversations.iter().position(|c| c.id == id) else {
+            return;
+        };
+
+        let conversation = self.conversations.remove(index);
+        if let Err(e) = storage::delete_file(&self.dir, conversation.file_name()) {
+            error!("could not delete dave conversation {id}: {e}");
+        }
+    }
+}
+
+/// Generate a new random conversation id
+pub fn new_conversation_id() -> String {
+    hex::encode(rand::random::<[u8; 8]>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_title() {
+        assert_eq!(
+            generate_title("  what did\n I miss today? "),
+            "what did I miss today?"
+        );
+    }
+
+    #[test]
+    fn test_long_title_cut_at_word() {
+        let title = generate_title(
+            "summarize all of the interesting notes about bitcoin and nostr from the last week",
+        );
+        assert_eq!(title, "summarize all of the interesting notes about…");
+        assert!(title.chars().count() <= MAX_TITLE_LEN + 1);
+    }
+
+    #[test]
+    fn test_conversation_update_skips_system() {
+        let mut conversation = Conversation::new("abc".to_string(), 1);
+        let chat = vec![
+            Message::System("system prompt".to_string()),
+            Message::User("hello dave".to_string()),
+            Message::Assistant("hi!".to_string()),
+        ];
+
+        conversation.update(&chat, 2);
+
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.title, "hello dave");
+        assert_eq!(conversation.updated_at, 2);
+
+        // titles are only generated once
+        conversation.update(&[Message::User("another".to_string())], 3);
+        assert_eq!(conversation.title, "hello dave");
+    }
+
+    #[test]
+    fn test_conversation_roundtrip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = DataPath::new(tmp.path());
+
+        let mut history = ConversationHistory::new(&path);
+        assert!(history.conversations().is_empty());
+
+        let mut conversation = Conversation::new(new_conversation_id(), 10);
+        conversation.update(&[Message::User("hello dave".to_string())], 10);
+        history.save(&conversation);
+        history.rename(&conversation.id, "greetings".to_string());
+
+        let loaded = ConversationHistory::new(&path);
+        assert_eq!(loaded.conversations().len(), 1);
+        assert_eq!(loaded.conversations()[0].title, "greetings");
+
+        history.delete(&conversation.id);
+        assert!(ConversationHistory::new(&path).conversations().is_empty());
+    }
+}
@@ -4,38 +4,46 @@ use async_openai::{
     Client,
 };
 use chrono::{Duration, Local};
-use enostr::KeypairUnowned;
+use enostr::{KeypairUnowned, RelayPool};
 use futures::StreamExt;
-use nostrdb::Transaction;
-use notedeck::{AppAction, AppContext, AppResponse};
-use std::collections::HashMap;
+use nostrdb::{Ndb, Transaction};
+use notedeck::{unix_time_secs, AppAction, AppContext, AppResponse};
+use std::collections::{HashMap, HashSet};
 use std::string::ToString;
-use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::sync::Arc;
+use std::time::Duration as StdDuration;
 
 #[cfg(not(target_os = "ios"))]
 use egui_wgpu::RenderState;
 
+pub use audit::{AuditDecision, AuditEntry, AuditLog};
 #[cfg(not(target_os = "ios"))]
 pub use avatar::DaveAvatar;
 pub use config::ModelConfig;
+pub use history::{Conversation, ConversationHistory};
 pub use messages::{DaveApiResponse, Message};
+pub use policy::{ToolPermission, ToolPolicy, ToolPolicyStore};
 #[cfg(not(target_os = "ios"))]
 pub use quaternion::Quaternion;
 pub use tools::{
-    PartialToolCall, QueryCall, QueryResponse, Tool, ToolCall, ToolCalls, ToolResponse,
-    ToolResponses,
+    DraftNoteCall, PartialToolCall, ProfileLookupCall, ProfileLookupResponse, QueryCall,
+    QueryResponse, RelayQueryCall, RemoteQueries, RemoteQuery, ThreadCall, ThreadResponse, Tool,
+    ToolCall, ToolCalls, ToolResponse, ToolResponses,
 };
-pub use ui::{DaveAction, DaveResponse, DaveUi};
+pub use ui::{AuditPanel, DaveAction, DaveResponse, DaveUi, HistoryPanel, HistoryPanelState};
 #[cfg(not(target_os = "ios"))]
 pub use vec3::Vec3;
 
+mod audit;
 #[cfg(not(target_os = "ios"))]
 mod avatar;
 mod config;
+mod history;
 #[cfg(not(target_os = "ios"))]
 pub(crate) mod mesh;
 mod messages;
+mod policy;
 #[cfg(not(target_os = "ios"))]
 mod quaternion;
 mod tools;
@@ -52,7 +60,19 @@ pub struct Dave {
     tools: Arc<HashMap<String, Tool>>,
     client: async_openai::Client<OpenAIConfig>,
     incoming_tokens: Option<Receiver<DaveApiResponse>>,
+    /// Relay queries that are waiting on relays to respond
+    remote_queries: RemoteQueries,
+    /// Tool calls that are waiting for the user to approve them
+    pending_approvals: Vec<ToolCall>,
+    tool_policy: ToolPolicyStore,
+    audit_log: AuditLog,
+    show_audit: bool,
     model_config: ModelConfig,
+    /// The conversation the current chat is saved to
+    conversation: Conversation,
+    /// Saved conversations, loaded lazily once we have a data path
+    history: Option<ConversationHistory>,
+    history_panel: HistoryPanelState,
 }
 
 /// Calculate an anonymous user_id from a keypair
@@ -115,10 +135,18 @@ You are an AI agent for the nostr protocol called Dave, created by Damus. nostr
             client,
             avatar,
             incoming_tokens: None,
+            remote_queries: RemoteQueries::default(),
+            pending_approvals: vec![],
+            tool_policy: ToolPolicyStore::in_memory(),
+            audit_log: AuditLog::in_memory(),
+            show_audit: false,
             tools: Arc::new(tools),
             input,
             model_config,
             chat: vec![],
+            conversation: Conversation::new(history::new_conversation_id(), unix_time_secs()),
+            history: None,
+            history_panel: HistoryPanelState::default(),
         }
     }
 
@@ -136,10 +164,18 @@ You are an AI agent for the nostr protocol called Dave, created by Damus. nostr
         Dave {
             client,
             incoming_tokens: None,
+            remote_queries: RemoteQueries::default(),
+            pending_approvals: vec![],
+            tool_policy: ToolPolicyStore::in_memory(),
+            audit_log: AuditLog::in_memory(),
+            show_audit: false,
             tools: Arc::new(tools),
             input,
             model_config,
             chat: vec![],
+            conversation: Conversation::new(history::new_conversation_id(), unix_time_secs()),
+            history: None,
+            history_panel: HistoryPanelState::default(),
         }
     }
 
@@ -153,7 +189,18 @@ You are an AI agent for the nostr protocol called Dave, created by Damus. nostr
             return should_send;
         };
 
-        while let Ok(res) = recvr.try_recv() {
+        let mut stream_ended = false;
+        let mut tool_calls: Vec<ToolCall> = vec![];
+        loop {
+            let res = match recvr.try_recv() {
+                Ok(res) => res,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    stream_ended = true;
+                    break;
+                }
+            };
+
             #[cfg(not(target_os = "ios"))]
             if let Some(avatar) = &mut self.avatar {
                 avatar.random_nudge();
@@ -170,45 +217,238 @@ You are an AI agent for the nostr protocol called Dave, created by Damus. nostr
                 DaveApiResponse::ToolCalls(toolcalls) => {
                     tracing::info!("got tool calls: {:?}", toolcalls);
                     self.chat.push(Message::ToolCalls(toolcalls.clone()));
+                    tool_calls.extend(toolcalls);
+                }
+            }
+        }
 
-                    let txn = Transaction::new(app_ctx.ndb).unwrap();
-                    for call in &toolcalls {
-                        // execute toolcall
-                        match call.calls() {
-                            ToolCalls::PresentNotes(present) => {
-                                self.chat.push(Message::ToolResponse(ToolResponse::new(
-                                    call.id().to_owned(),
-                                    ToolResponses::PresentNotes(present.note_ids.len() as i32),
-                                )));
-
-                                should_send = true;
-                            }
+        for call in tool_calls {
+            should_send = true;
+            self.handle_tool_call(app_ctx, call);
+        }
 
-                            ToolCalls::Invalid(invalid) => {
-                                should_send = true;
+        // the response stream is done, this is a good time to save
+        if stream_ended {
+            self.incoming_tokens = None;
+            self.save_conversation();
+        }
 
-                                self.chat.push(Message::tool_error(
-                                    call.id().to_string(),
-                                    invalid.error.clone(),
-                                ));
-                            }
+        // we can only respond once every tool call has a response
+        should_send && !self.tool_calls_pending()
+    }
 
-                            ToolCalls::Query(search_call) => {
-                                should_send = true;
+    /// Are there tool calls that don't have a response yet?
+    fn tool_calls_pending(&self) -> bool {
+        !self.remote_queries.is_empty() || !self.pending_approvals.is_empty()
+    }
 
-                                let resp = search_call.execute(&txn, app_ctx.ndb);
-                                self.chat.push(Message::ToolResponse(ToolResponse::new(
-                                    call.id().to_owned(),
-                                    ToolResponses::Query(resp),
-                                )))
-                            }
+    /// Check a tool call against the tool policy, running it, denying
+    /// it or asking the user for approval
+    fn handle_tool_call(&mut self, app_ctx: &AppContext, call: ToolCall) {
+        let now = unix_time_secs();
+
+        if let ToolCalls::Invalid(invalid) = call.calls() {
+            self.audit_log.record(
+                call.id(),
+                invalid.name.as_deref().unwrap_or("unknown"),
+                invalid.arguments.clone().unwrap_or_default(),
+                AuditDecision::Invalid,
+                now,
+            );
+            self.respond(
+                app_ctx.ndb,
+                ToolResponse::error(call.id().to_owned(), invalid.error.clone()),
+            );
+            return;
+        }
+
+        let tool_name = call.calls().name();
+        let permission = self
+            .tools
+            .get(tool_name)
+            .map(|tool| self.tool_policy.policy().permission(tool))
+            .unwrap_or(ToolPermission::Ask);
+
+        let arguments = call.calls().arguments();
+        match permission {
+            ToolPermission::Allow => {
+                self.audit_log
+                    .record(call.id(), tool_name, arguments, AuditDecision::Allowed, now);
+                self.execute_tool_call(app_ctx, &call);
+            }
+
+            ToolPermission::Ask => {
+                self.audit_log
+                    .record(call.id(), tool_name, arguments, AuditDecision::Pending, now);
+                self.pending_approvals.push(call);
+            }
+
+            ToolPermission::Deny => {
+                self.audit_log
+                    .record(call.id(), tool_name, arguments, AuditDecision::Denied, now);
+                self.respond(
+                    app_ctx.ndb,
+                    ToolResponse::error(
+                        call.id().to_owned(),
+                        format!("the user does not allow dave to use the {tool_name} tool"),
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Run a tool call that is allowed to run
+    fn execute_tool_call(&mut self, app_ctx: &AppContext, call: &ToolCall) {
+        self.audit_log.start(call.id());
+
+        let resp = {
+            let txn = Transaction::new(app_ctx.ndb).unwrap();
+            match call.calls() {
+                ToolCalls::PresentNotes(present) => {
+                    Some(ToolResponses::PresentNotes(present.note_ids.len() as i32))
+                }
+
+                ToolCalls::Invalid(invalid) => Some(ToolResponses::Error(invalid.error.clone())),
+
+                ToolCalls::Query(search_call) => {
+                    Some(ToolResponses::Query(search_call.execute(&txn, app_ctx.ndb)))
+                }
+
+                ToolCalls::ProfileLookup(lookup) => Some(ToolResponses::ProfileLookup(
+                    lookup.execute(&txn, app_ctx.ndb),
+                )),
+
+                ToolCalls::Thread(thread) => {
+                    Some(ToolResponses::Thread(thread.execute(&txn, app_ctx.ndb)))
+                }
+
+                ToolCalls::RelayQuery(relay_call) => {
+                    match relay_call.execute_local(&txn, app_ctx.ndb) {
+                        Some(resp) => Some(ToolResponses::RelayQuery(resp)),
+                        None => {
+                            self.remote_queries
+                                .start(call.id().to_owned(), relay_call.clone());
+                            None
                         }
                     }
                 }
+
+                // drafts are shown to the user, we never publish
+                // anything on their behalf
+                ToolCalls::DraftNote(_draft) => Some(ToolResponses::DraftNote),
             }
+        };
+
+        if let Some(resp) = resp {
+            self.respond(app_ctx.ndb, ToolResponse::new(call.id().to_owned(), resp));
         }
+    }
+
+    /// Add a tool response to the chat and record it in the audit log
+    fn respond(&mut self, ndb: &Ndb, resp: ToolResponse) {
+        let result_size = {
+            let txn = Transaction::new(ndb).unwrap();
+            resp.responses().format_for_dave(&txn, ndb).len()
+        };
+
+        let error = match resp.responses() {
+            ToolResponses::Error(err) => Some(err.clone()),
+            _ => None,
+        };
+
+        self.audit_log.finish(resp.id(), result_size, error);
+        self.chat.push(Message::ToolResponse(resp));
+    }
 
-        should_send
+    /// The user approved or rejected a pending tool call. Returns true
+    /// when every tool call has a response and we can reply to the ai
+    fn handle_tool_approval(
+        &mut self,
+        app_ctx: &AppContext,
+        call_id: &str,
+        approved: bool,
+        remember: bool,
+    ) -> bool {
+        let Some(index) = self
+            .pending_approvals
+            .iter()
+            .position(|c| c.id() == call_id)
+        else {
+            return false;
+        };
+
+        let call = self.pending_approvals.remove(index);
+        let tool_name = call.calls().name();
+
+        if remember {
+            let permission = if approved {
+                ToolPermission::Allow
+            } else {
+                ToolPermission::Deny
+            };
+            self.tool_policy.set(tool_name, permission);
+        }
+
+        if approved {
+            self.audit_log.decide(call_id, AuditDecision::Approved);
+            self.execute_tool_call(app_ctx, &call);
+        } else {
+            self.audit_log.decide(call_id, AuditDecision::Rejected);
+            self.respond(
+                app_ctx.ndb,
+                ToolResponse::error(
+                    call_id.to_owned(),
+                    format!("the user rejected this call to the {tool_name} tool"),
+                ),
+            );
+        }
+
+        !self.tool_calls_pending()
+    }
+
+    /// Give every tool call that is still waiting a response so that
+    /// the chat stays valid when it is saved and restored later
+    fn cancel_pending_tool_calls(&mut self, ndb: &Ndb) {
+        for call in std::mem::take(&mut self.pending_approvals) {
+            self.audit_log.decide(call.id(), AuditDecision::Cancelled);
+            self.respond(
+                ndb,
+                ToolResponse::error(call.id().to_owned(), "cancelled by the user".to_owned()),
+            );
+        }
+
+        for query in self.remote_queries.cancel() {
+            self.respond(
+                ndb,
+                ToolResponse::error(
+                    query.call_id().to_owned(),
+                    "cancelled by the user".to_owned(),
+                ),
+            );
+        }
+    }
+
+    /// Poll relay queries that are in flight. Returns true when the
+    /// last one finished and we can send the responses back to the ai
+    fn poll_remote_queries(&mut self, ndb: &Ndb, pool: &mut RelayPool) -> bool {
+        let finished = self.remote_queries.poll(pool);
+        if finished.is_empty() {
+            return false;
+        }
+
+        for query in finished {
+            let call_id = query.call_id().to_owned();
+            let resp = {
+                let txn = Transaction::new(ndb).unwrap();
+                query.finish(&txn, ndb)
+            };
+            self.respond(
+                ndb,
+                ToolResponse::new(call_id, ToolResponses::RelayQuery(resp)),
+            );
+        }
+
+        !self.tool_calls_pending()
     }
 
     fn ui(&mut self, app_ctx: &mut AppContext, ui: &mut egui::Ui) -> DaveResponse {
@@ -221,17 +461,151 @@ You are an AI agent for the nostr protocol called Dave, created by Damus. nostr
         DaveResponse::default()
             */
 
-        DaveUi::new(self.model_config.trial, &self.chat, &mut self.input).ui(app_ctx, ui)
+        let mut history_response = DaveResponse::default();
+        if self.history_panel.open {
+            if let Some(history) = &self.history {
+                history_response = egui::SidePanel::left("dave-history-panel")
+                    .resizable(false)
+                    .default_width(240.0)
+                    .show_inside(ui, |ui| {
+                        HistoryPanel::new(
+                            history,
+                            &mut self.history_panel,
+                            &self.conversation.id,
+                            app_ctx.i18n,
+                        )
+                        .ui(ui)
+                    })
+                    .inner;
+            }
+        }
+
+        let mut audit_response = DaveResponse::default();
+        if self.show_audit {
+            audit_response = egui::SidePanel::right("dave-audit-panel")
+                .resizable(false)
+                .default_width(320.0)
+                .show_inside(ui, |ui| {
+                    AuditPanel::new(
+                        &self.tools,
+                        self.tool_policy.policy(),
+                        &self.audit_log,
+                        app_ctx.i18n,
+                    )
+                    .ui(ui)
+                })
+                .inner;
+        }
+
+        DaveUi::new(self.model_config.trial, &self.chat, &mut self.input)
+            .pending_approvals(&self.pending_approvals)
+            .ui(app_ctx, ui)
+            .or(history_response)
+            .or(audit_response)
     }
 
-    fn handle_new_chat(&mut self) {
+    /// Save the current chat to the conversation history
+    fn save_conversation(&mut self) {
+        let Some(history) = &mut self.history else {
+            return;
+        };
+
+        self.conversation.update(&self.chat, unix_time_secs());
+        history.save(&self.conversation);
+    }
+
+    fn reset_chat(&mut self) {
         self.chat = vec![];
         self.input.clear();
+        self.incoming_tokens = None;
+        self.remote_queries.cancel();
+        self.pending_approvals.clear();
+    }
+
+    fn handle_new_chat(&mut self, ndb: &Ndb) {
+        self.cancel_pending_tool_calls(ndb);
+        self.save_conversation();
+        self.reset_chat();
+        self.conversation = Conversation::new(history::new_conversation_id(), unix_time_secs());
+    }
+
+    /// Restore a saved conversation. The system prompt is regenerated
+    /// and notes referenced by tool calls are resolved again from
+    /// nostrdb when rendering and when sending the context to the ai.
+    fn handle_load_conversation(&mut self, ndb: &Ndb, id: &str) {
+        if self.conversation.id == id {
+            return;
+        }
+
+        let Some(conversation) = self.history.as_ref().and_then(|h| h.get(id)).cloned() else {
+            return;
+        };
+
+        self.cancel_pending_tool_calls(ndb);
+        self.save_conversation();
+        self.reset_chat();
+        self.chat.push(Dave::system_prompt());
+        self.chat.extend(conversation.messages.iter().cloned());
+        self.conversation = conversation;
+        self.close_unanswered_tool_calls();
+    }
+
+    /// Tool calls that were waiting for approval when the app was
+    /// closed never got a response. Tell the ai they were cancelled.
+    fn close_unanswered_tool_calls(&mut self) {
+        let answered: HashSet<&str> = self
+            .chat
+            .iter()
+            .filter_map(|m| match m {
+                Message::ToolResponse(resp) => Some(resp.id()),
+                _ => None,
+            })
+            .collect();
+
+        let unanswered: Vec<String> = self
+            .chat
+            .iter()
+            .filter_map(|m| match m {
+                Message::ToolCalls(calls) => Some(calls),
+                _ => None,
+            })
+            .flatten()
+            .filter(|call| !answered.contains(call.id()))
+            .map(|call| call.id().to_owned())
+            .collect();
+
+        for id in unanswered {
+            self.chat
+                .push(Message::tool_error(id, "cancelled by the user".to_owned()));
+        }
+    }
+
+    fn handle_delete_conversation(&mut self, ndb: &Ndb, id: &str) {
+        if let Some(history) = &mut self.history {
+            history.delete(id);
+        }
+
+        if self.conversation.id == id {
+            self.cancel_pending_tool_calls(ndb);
+            self.reset_chat();
+            self.conversation = Conversation::new(history::new_conversation_id(), unix_time_secs());
+        }
+    }
+
+    fn handle_rename_conversation(&mut self, id: &str, title: String) {
+        if self.conversation.id == id {
+            self.conversation.title = title.clone();
+        }
+
+        if let Some(history) = &mut self.history {
+            history.rename(id, title);
+        }
     }
 
     /// Handle a user send action triggered by the ui
     fn handle_user_send(&mut self, app_ctx: &AppContext, ui: &egui::Ui) {
         self.chat.push(Message::User(self.input.clone()));
+        self.save_conversation();
         self.send_user_message(app_ctx, ui.ctx());
         self.input.clear();
     }
@@ -374,13 +748,30 @@ impl notedeck::App for Dave {
     fn update(&mut self, ctx: &mut AppContext<'_>, ui: &mut egui::Ui) -> AppResponse {
         let mut app_action: Option<AppAction> = None;
 
+        // load everything we keep in the data path
+        if self.history.is_none() {
+            self.history = Some(ConversationHistory::new(ctx.path));
+            self.tool_policy = ToolPolicyStore::new(ctx.path);
+            self.audit_log = AuditLog::new(ctx.path);
+        }
+
         // always insert system prompt if we have no context
         if self.chat.is_empty() {
             self.chat.push(Dave::system_prompt());
         }
 
         //update_dave(self, ctx, ui.ctx());
-        let should_send = self.process_events(ctx);
+        let mut should_send = self.process_events(ctx);
+        if self.poll_remote_queries(ctx.ndb, ctx.pool) {
+            should_send = true;
+        }
+
+        if !self.remote_queries.is_empty() {
+            // make sure we wake up to check for timeouts
+            ui.ctx()
+                .request_repaint_after(StdDuration::from_millis(250));
+        }
+
         if let Some(action) = self.ui(ctx, ui).action {
             match action {
                 DaveAction::ToggleChrome => {
@@ -389,8 +780,41 @@ impl notedeck::App for Dave {
                 DaveAction::Note(n) => {
                     app_action = Some(AppAction::Note(n));
                 }
+                DaveAction::OpenDraft(draft) => {
+                    app_action = Some(AppAction::Compose {
+                        content: draft.content,
+                        reply_to: draft.reply_to,
+                    });
+                }
                 DaveAction::NewChat => {
-                    self.handle_new_chat();
+                    self.handle_new_chat(ctx.ndb);
+                }
+                DaveAction::ToggleHistory => {
+                    self.history_panel.open = !self.history_panel.open;
+                }
+                DaveAction::ToggleAudit => {
+                    self.show_audit = !self.show_audit;
+                }
+                DaveAction::ToolApproval {
+                    call_id,
+                    approved,
+                    remember,
+                } => {
+                    if self.handle_tool_approval(ctx, &call_id, approved, remember) {
+                        should_send = true;
+                    }
+                }
+                DaveAction::SetToolPermission { tool, permission } => {
+                    self.tool_policy.set(&tool, permission);
+                }
+                DaveAction::LoadConversation(id) => {
+                    self.handle_load_conversation(ctx.ndb, &id);
+                }
+                DaveAction::DeleteConversation(id) => {
+                    self.handle_delete_conversation(ctx.ndb, &id);
+                }
+                DaveAction::RenameConversation { id, title } => {
+                    self.handle_rename_conversation(&id, title);
                 }
                 DaveAction::Send => {
                     self.handle_user_send(ctx, ui);
@@ -1,8 +1,9 @@
 use crate::tools::{ToolCall, ToolResponse};
 use async_openai::types::*;
 use nostrdb::{Ndb, Transaction};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
     System(String),
     Error(String),
@@ -0,0 +1,173 @@
+use crate::tools::Tool;
+use notedeck::{storage, DataPath, DataPathType, Directory};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, error};
+
+static POLICY_FILE: &str = "dave_tool_policy.json";
+
+/// What dave is allowed to do when the model calls a tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToolPermission {
+    /// Run the tool without asking
+    Allow,
+    /// Ask the user before running the tool
+    Ask,
+    /// Never run the tool. The model is told the call was denied
+    Deny,
+}
+
+impl ToolPermission {
+    pub const ALL: [ToolPermission; 3] = [
+        ToolPermission::Allow,
+        ToolPermission::Ask,
+        ToolPermission::Deny,
+    ];
+
+    /// The permission a tool gets when the user hasn't picked one.
+    /// Tools with side effects need approval.
+    pub fn default_for(tool: &Tool) -> Self {
+        if tool.has_side_effects() {
+            ToolPermission::Ask
+        } else {
+            ToolPermission::Allow
+        }
+    }
+}
+
+/// Per-tool permissions chosen by the user. Tools without an entry
+/// use [`ToolPermission::default_for`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ToolPolicy {
+    permissions: BTreeMap<String, ToolPermission>,
+}
+
+impl ToolPolicy {
+    pub fn permission(&self, tool: &Tool) -> ToolPermission {
+        self.permissions
+            .get(tool.name())
+            .copied()
+            .unwrap_or_else(|| ToolPermission::default_for(tool))
+    }
+
+    pub fn set(&mut self, tool: &str, permission: ToolPermission) {
+        self.permissions.insert(tool.to_owned(), permission);
+    }
+}
+
+/// A [`ToolPolicy`] that is persisted in the settings directory
+pub struct ToolPolicyStore {
+    dir: Option<PathBuf>,
+    policy: ToolPolicy,
+}
+
+impl ToolPolicyStore {
+    /// A policy store that is only kept in memory
+    pub fn in_memory() -> Self {
+        Self {
+            dir: None,
+            policy: ToolPolicy::default(),
+        }
+    }
+
+    pub fn new(path: &DataPath) -> Self {
+        let dir = path.path(DataPathType::Setting);
+        let policy = Self::load(&dir).unwrap_or_default();
+        Self {
+            dir: Some(dir),
+            policy,
+        }
+    }
+
+    fn load(dir: &Path) -> Option<ToolPolicy> {
+        let contents = match Directory::new(dir.to_path_buf()).get_file(POLICY_FILE.to_owned()) {
+            Ok(contents) => contents,
+            Err(e) => {
+                debug!("no dave tool policy loaded: {e}");
+                return None;
+            }
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(policy) => Some(policy),
+            Err(e) => {
+                error!("could not parse dave tool policy: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn policy(&self) -> &ToolPolicy {
+        &self.policy
+    }
+
+    pub fn set(&mut self, tool: &str, permission: ToolPermission) {
+        self.policy.set(tool, permission);
+
+        let Some(dir) = &self.dir else {
+            return;
+        };
+
+        let serialized = match serde_json::to_string(&self.policy) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("could not serialize dave tool policy: {e}");
+                return;
+            }
+        };
+
+        if let Err(e) = storage::write_file(dir, POLICY_FILE.to_owned(), &serialized) {
+            error!("could not write dave tool policy: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::dave_tools;
+
+    fn tool(name: &str) -> Tool {
+        dave_tools()
+            .into_iter()
+            .find(|t| t.name() == name)
+            .expect("tool")
+    }
+
+    #[test]
+    fn test_defaults() {
+        let policy = ToolPolicy::default();
+        assert_eq!(policy.permission(&tool("query")), ToolPermission::Allow);
+        assert_eq!(policy.permission(&tool("relay_query")), ToolPermission::Ask);
+        assert_eq!(policy.permission(&tool("draft_note")), ToolPermission::Ask);
+    }
+
+    #[test]
+    fn test_override() {
+        let mut policy = ToolPolicy::default();
+        policy.set("relay_query", ToolPermission::Allow);
+        policy.set("query", ToolPermission::Deny);
+
+        assert_eq!(
+            policy.permission(&tool("relay_query")),
+            ToolPermission::Allow
+        );
+        assert_eq!(policy.permission(&tool("query")), ToolPermission::Deny);
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = DataPath::new(tmp.path());
+
+        let mut store = ToolPolicyStore::new(&path);
+        store.set("thread", ToolPermission::Deny);
+
+        let store = ToolPolicyStore::new(&path);
+        assert_eq!(
+            store.policy().permission(&tool("thread")),
+            ToolPermission::Deny
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use super::{ArgType, Tool, ToolArg, ToolCallError, ToolCalls};
+use enostr::NoteId;
+use serde::{Deserialize, Serialize};
+
+/// A note drafted by dave. Drafts are never published automatically,
+/// the user has to open them in the composer and post them.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DraftNoteCall {
+    pub content: String,
+    pub reply_to: Option<NoteId>,
+}
+
+impl DraftNoteCall {
+    pub fn parse(args: &str) -> Result<ToolCalls, ToolCallError> {
+        let call = serde_json::from_str::<DraftNoteCall>(args)
+            .map_err(|e| ToolCallError::ArgParseFailure(format!("{args}, error: {e}")))?;
+
+        if call.content.trim().is_empty() {
+            return Err(ToolCallError::ArgParseFailure(
+                "draft content is empty".to_string(),
+            ));
+        }
+
+        Ok(ToolCalls::DraftNote(call))
+    }
+}
+
+pub(super) fn draft_note_tool() -> Tool {
+    Tool {
+        name: "draft_note",
+        parse_call: DraftNoteCall::parse,
+        side_effects: true,
+        description: "Draft a note for the user. The draft is shown to the user, who can open it in the composer to edit and publish it. Drafts are never published automatically.",
+        arguments: vec![
+            ToolArg {
+                name: "content",
+                typ: ArgType::String,
+                required: true,
+                default: None,
+                description: "The content of the note",
+            },
+
+            ToolArg {
+                name: "reply_to",
+                typ: ArgType::String,
+                required: false,
+                default: None,
+                description: "The hex note id of the note this draft replies to, if any",
+            },
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_draft() {
+        let Ok(ToolCalls::DraftNote(draft)) = DraftNoteCall::parse(r#"{"content": "gm nostr"}"#)
+        else {
+            panic!("expected a draft");
+        };
+
+        assert_eq!(draft.content, "gm nostr");
+        assert!(draft.reply_to.is_none());
+    }
+
+    #[test]
+    fn test_parse_reply_draft() {
+        let id = "0000000000000000000000000000000000000000000000000000000000000001";
+        let Ok(ToolCalls::DraftNote(draft)) =
+            DraftNoteCall::parse(&format!(r#"{{"content": "nice", "reply_to": "{id}"}}"#))
+        else {
+            panic!("expected a draft");
+        };
+
+        assert_eq!(draft.reply_to, Some(NoteId::from_hex(id).unwrap()));
+    }
+
+    #[test]
+    fn test_empty_draft_is_invalid() {
+        assert!(DraftNoteCall::parse(r#"{"content": "  "}"#).is_err());
+        assert!(DraftNoteCall::parse(r#"{}"#).is_err());
+    }
+}
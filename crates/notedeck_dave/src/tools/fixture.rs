@@ -0,0 +1,120 @@
+//! A small nostrdb fixture for testing dave's tools
+
+use enostr::{ClientMessage, FullKeypair, NoteId, Pubkey};
+use nostrdb::{Config, Filter, IngestMetadata, Ndb, NoteBuilder};
+use tempfile::TempDir;
+
+pub struct Fixture {
+    pub ndb: Ndb,
+    pub alice: Pubkey,
+    pub bob: Pubkey,
+    /// A root note by alice
+    pub root: NoteId,
+    /// bob's reply to `root`
+    pub reply: NoteId,
+    /// alice's reply to bob's `reply`
+    pub nested_reply: NoteId,
+    _tmp: TempDir,
+}
+
+fn ingest(ndb: &Ndb, kp: &FullKeypair, builder: NoteBuilder) -> NoteId {
+    let note = builder
+        .sign(&kp.secret_key.secret_bytes())
+        .build()
+        .expect("note");
+
+    let json = ClientMessage::event(&note)
+        .expect("event")
+        .to_json()
+        .expect("json");
+
+    ndb.process_event_with(&json, IngestMetadata::new().client(true))
+        .expect("ingest");
+
+    NoteId::new(*note.id())
+}
+
+fn profile(ndb: &Ndb, kp: &FullKeypair, name: &str, nip05: &str) {
+    let content = format!(r#"{{"name":"{name}","nip05":"{nip05}","about":"hi i'm {name}"}}"#);
+    ingest(
+        ndb,
+        kp,
+        NoteBuilder::new().kind(0).content(&content).created_at(1),
+    );
+}
+
+fn reply(root: &NoteId, reply_to: Option<&NoteId>, content: &str, at: u64) -> NoteBuilder<'static> {
+    let builder = NoteBuilder::new()
+        .kind(1)
+        .content(content)
+        .created_at(at)
+        .start_tag()
+        .tag_str("e")
+        .tag_str(&root.hex())
+        .tag_str("")
+        .tag_str("root");
+
+    if let Some(reply_to) = reply_to {
+        builder
+            .start_tag()
+            .tag_str("e")
+            .tag_str(&reply_to.hex())
+            .tag_str("")
+            .tag_str("reply")
+    } else {
+        builder
+    }
+}
+
+impl Fixture {
+    pub async fn new() -> Self {
+        let tmp = TempDir::new().unwrap();
+        let ndb = Ndb::new(tmp.path().to_str().unwrap(), &Config::new()).unwrap();
+
+        let alice_kp = FullKeypair::generate();
+        let bob_kp = FullKeypair::generate();
+
+        let sub = ndb
+            .subscribe(&[Filter::new().kinds([0, 1]).build()])
+            .unwrap();
+
+        profile(&ndb, &alice_kp, "alice", "alice@example.com");
+        profile(&ndb, &bob_kp, "bob", "bob@example.com");
+
+        let root = ingest(
+            &ndb,
+            &alice_kp,
+            NoteBuilder::new()
+                .kind(1)
+                .content("what is everyone reading this week?")
+                .created_at(100),
+        );
+        let reply_id = ingest(
+            &ndb,
+            &bob_kp,
+            reply(&root, None, "the bitcoin whitepaper", 200),
+        );
+        let nested_reply = ingest(
+            &ndb,
+            &alice_kp,
+            reply(&root, Some(&reply_id), "a classic", 300),
+        );
+
+        // wait for everything to be ingested
+        let total = 5;
+        let mut count = 0;
+        while count < total {
+            count += ndb.wait_for_notes(sub, total).await.unwrap().len() as u32;
+        }
+
+        Fixture {
+            ndb,
+            alice: alice_kp.pubkey,
+            bob: bob_kp.pubkey,
+            root,
+            reply: reply_id,
+            nested_reply,
+            _tmp: tmp,
+        }
+    }
+}
@@ -1,11 +1,23 @@
 use async_openai::types::*;
 use chrono::DateTime;
 use enostr::{NoteId, Pubkey};
-use nostrdb::{Ndb, Note, NoteKey, Transaction};
+use nostrdb::{Ndb, Note, Transaction};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::{collections::HashMap, fmt};
 
+mod draft;
+#[cfg(test)]
+mod fixture;
+mod profile;
+mod relay;
+mod thread;
+
+pub use draft::DraftNoteCall;
+pub use profile::{ProfileLookupCall, ProfileLookupResponse};
+pub use relay::{RelayQueryCall, RemoteQueries, RemoteQuery};
+pub use thread::{ThreadCall, ThreadResponse};
+
 /// A tool
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
@@ -83,10 +95,21 @@ impl PartialToolCall {
     }
 }
 
-/// The query response from nostrdb for a given context
+/// The query response from nostrdb for a given context. Notes are
+/// stored by id so that responses can be persisted and re-resolved
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryResponse {
-    notes: Vec<u64>,
+    notes: Vec<NoteId>,
+}
+
+impl QueryResponse {
+    pub fn new(notes: Vec<NoteId>) -> Self {
+        Self { notes }
+    }
+
+    pub fn notes(&self) -> &[NoteId] {
+        &self.notes
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +117,10 @@ pub enum ToolResponses {
     Error(String),
     Query(QueryResponse),
     PresentNotes(i32),
+    ProfileLookup(ProfileLookupResponse),
+    Thread(ThreadResponse),
+    RelayQuery(QueryResponse),
+    DraftNote,
 }
 
 #[derive(Debug, Clone)]
@@ -142,6 +169,10 @@ pub struct InvalidToolCall {
 pub enum ToolCalls {
     Query(QueryCall),
     PresentNotes(PresentNotesCall),
+    ProfileLookup(ProfileLookupCall),
+    Thread(ThreadCall),
+    RelayQuery(RelayQueryCall),
+    DraftNote(DraftNoteCall),
     Invalid(InvalidToolCall),
 }
 
@@ -153,19 +184,28 @@ impl ToolCalls {
         }
     }
 
-    fn name(&self) -> &'static str {
+    /// The name of the tool this call is for
+    pub fn name(&self) -> &'static str {
         match self {
-            Self::Query(_) => "search",
+            Self::Query(_) => "query",
             Self::Invalid(_) => "error",
-            Self::PresentNotes(_) => "present",
+            Self::PresentNotes(_) => "present_notes",
+            Self::ProfileLookup(_) => "profile_lookup",
+            Self::Thread(_) => "thread",
+            Self::RelayQuery(_) => "relay_query",
+            Self::DraftNote(_) => "draft_note",
         }
     }
 
-    fn arguments(&self) -> String {
+    pub fn arguments(&self) -> String {
         match self {
             Self::Query(search) => serde_json::to_string(search).unwrap(),
             Self::Invalid(partial) => serde_json::to_string(partial).unwrap(),
             Self::PresentNotes(call) => serde_json::to_string(&call.to_simple()).unwrap(),
+            Self::ProfileLookup(call) => serde_json::to_string(call).unwrap(),
+            Self::Thread(call) => serde_json::to_string(call).unwrap(),
+            Self::RelayQuery(call) => serde_json::to_string(call).unwrap(),
+            Self::DraftNote(call) => serde_json::to_string(call).unwrap(),
         }
     }
 }
@@ -225,6 +265,9 @@ pub struct Tool {
     name: &'static str,
     description: &'static str,
     arguments: Vec<ToolArg>,
+    /// Whether the tool does anything outside of reading nostrdb, such
+    /// as talking to relays. These tools need approval by default.
+    side_effects: bool,
 }
 
 impl Tool {
@@ -232,6 +275,10 @@ impl Tool {
         self.name
     }
 
+    pub fn has_side_effects(&self) -> bool {
+        self.side_effects
+    }
+
     pub fn to_function_object(&self) -> FunctionObject {
         let required_args = self
             .arguments
@@ -458,7 +505,10 @@ impl QueryCall {
     pub fn execute(&self, txn: &Transaction, ndb: &Ndb) -> QueryResponse {
         let notes = {
             if let Ok(results) = ndb.query(txn, &[self.to_filter()], self.limit() as i32) {
-                results.into_iter().map(|r| r.note_key.as_u64()).collect()
+                results
+                    .into_iter()
+                    .map(|r| NoteId::new(*r.note.id()))
+                    .collect()
             } else {
                 vec![]
             }
@@ -488,6 +538,48 @@ struct SimpleNote {
     note_kind: u64, // todo: add replying to
 }
 
+/// Convert a note in nostrdb to the simplified format we present
+/// to the ai
+fn simple_note(txn: &Transaction, ndb: &Ndb, note_id: &NoteId) -> Option<SimpleNote> {
+    let Ok(note) = ndb.get_note_by_id(txn, note_id.bytes()) else {
+        return None;
+    };
+
+    let name = ndb
+        .get_profile_by_pubkey(txn, note.pubkey())
+        .ok()
+        .and_then(|p| p.record().profile())
+        .and_then(|p| p.name().or_else(|| p.display_name()))
+        .unwrap_or("Anonymous")
+        .to_string();
+
+    let content = note.content().to_owned();
+    let pubkey = hex::encode(note.pubkey());
+    let note_kind = note.kind() as u64;
+    let note_id = hex::encode(note.id());
+
+    let created_at = {
+        let datetime = DateTime::from_timestamp(note.created_at() as i64, 0).unwrap();
+        datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+    };
+
+    Some(SimpleNote {
+        note_id,
+        pubkey,
+        name,
+        content,
+        created_at,
+        note_kind,
+    })
+}
+
+fn simple_notes(txn: &Transaction, ndb: &Ndb, note_ids: &[NoteId]) -> Vec<SimpleNote> {
+    note_ids
+        .iter()
+        .filter_map(|note_id| simple_note(txn, ndb, note_id))
+        .collect()
+}
+
 /// Take the result of a tool response and present it to the ai so that
 /// it can interepret it and take further action
 fn format_tool_response_for_ai(txn: &Transaction, ndb: &Ndb, resp: &ToolResponses) -> String {
@@ -496,46 +588,29 @@ fn format_tool_response_for_ai(txn: &Transaction, ndb: &Ndb, resp: &ToolResponse
         ToolResponses::Error(s) => format!("error: {}", &s),
 
         ToolResponses::Query(search_r) => {
-            let simple_notes: Vec<SimpleNote> = search_r
-                .notes
-                .iter()
-                .filter_map(|nkey| {
-                    let Ok(note) = ndb.get_note_by_key(txn, NoteKey::new(*nkey)) else {
-                        return None;
-                    };
-
-                    let name = ndb
-                        .get_profile_by_pubkey(txn, note.pubkey())
-                        .ok()
-                        .and_then(|p| p.record().profile())
-                        .and_then(|p| p.name().or_else(|| p.display_name()))
-                        .unwrap_or("Anonymous")
-                        .to_string();
-
-                    let content = note.content().to_owned();
-                    let pubkey = hex::encode(note.pubkey());
-                    let note_kind = note.kind() as u64;
-                    let note_id = hex::encode(note.id());
-
-                    let created_at = {
-                        let datetime =
-                            DateTime::from_timestamp(note.created_at() as i64, 0).unwrap();
-                        datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-                    };
-
-                    Some(SimpleNote {
-                        note_id,
-                        pubkey,
-                        name,
-                        content,
-                        created_at,
-                        note_kind,
-                    })
-                })
-                .collect();
-
+            let simple_notes = simple_notes(txn, ndb, search_r.notes());
             serde_json::to_string(&json!({"search_results": simple_notes})).unwrap()
         }
+
+        ToolResponses::RelayQuery(search_r) => {
+            let simple_notes = simple_notes(txn, ndb, search_r.notes());
+            serde_json::to_string(&json!({"relay_results": simple_notes})).unwrap()
+        }
+
+        ToolResponses::Thread(thread) => {
+            let simple_notes = simple_notes(txn, ndb, thread.notes());
+            serde_json::to_string(&json!({
+                "root": thread.root().map(|id| id.hex()),
+                "thread": simple_notes
+            }))
+            .unwrap()
+        }
+
+        ToolResponses::ProfileLookup(lookup) => lookup.format_for_dave(txn, ndb),
+
+        ToolResponses::DraftNote => {
+            "draft shown to the user. It will only be published if the user opens it in the composer and posts it".to_string()
+        }
     }
 }
 
@@ -551,6 +626,7 @@ fn present_tool() -> Tool {
     Tool {
         name: "present_notes",
         parse_call: PresentNotesCall::parse,
+        side_effects: false,
         description: "A tool for presenting notes to the user for display. Should be called at the end of a response so that the UI can present the notes referred to in the previous message.",
         arguments: vec![
             ToolArg {
@@ -568,6 +644,7 @@ fn query_tool() -> Tool {
     Tool {
         name: "query",
         parse_call: QueryCall::parse,
+        side_effects: false,
         description: "Note query functionality. Used for finding notes using full-text search terms, scoped by different contexts. You can use a combination of limit, since, and until to pull notes from any time range.",
         arguments: vec![
             ToolArg {
@@ -632,5 +709,12 @@ se, you can query for kind 0 profiles with the search argument.",
 }
 
 pub fn dave_tools() -> Vec<Tool> {
-    vec![query_tool(), present_tool()]
+    vec![
+        query_tool(),
+        present_tool(),
+        profile::profile_lookup_tool(),
+        thread::thread_tool(),
+        relay::relay_query_tool(),
+        draft::draft_note_tool(),
+    ]
 }
@@ -0,0 +1,231 @@
+use super::{ArgType, Tool, ToolArg, ToolCallError, ToolCalls};
+use enostr::Pubkey;
+use nostrdb::{Ndb, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Look up profiles in nostrdb by name or nip05 identifier
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProfileLookupCall {
+    pub name: Option<String>,
+    pub nip05: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// The pubkeys of the profiles that matched a lookup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileLookupResponse {
+    profiles: Vec<Pubkey>,
+}
+
+/// A simple profile format for use when formatting tool responses
+#[derive(Debug, Serialize)]
+struct SimpleProfile {
+    pubkey: String,
+    name: Option<String>,
+    display_name: Option<String>,
+    nip05: Option<String>,
+    about: Option<String>,
+}
+
+/// Normalize a nip05 identifier for comparison. `_@domain.com` is the
+/// same identifier as `domain.com`
+fn normalize_nip05(nip05: &str) -> String {
+    let nip05 = nip05.trim().to_lowercase();
+    match nip05.strip_prefix("_@") {
+        Some(domain) => domain.to_owned(),
+        None => nip05,
+    }
+}
+
+fn nip05_matches(claimed: &str, wanted: &str) -> bool {
+    normalize_nip05(claimed) == normalize_nip05(wanted)
+}
+
+impl ProfileLookupCall {
+    fn limit(&self) -> u32 {
+        self.limit.unwrap_or(5).clamp(1, 20)
+    }
+
+    /// The term we use for nostrdb's profile search when looking up by
+    /// nip05. The profile search index only covers names, so we search
+    /// by the local part (or the domain for root identifiers) and then
+    /// check the nip05 field of the results.
+    fn nip05_search_term(nip05: &str) -> String {
+        let nip05 = normalize_nip05(nip05);
+        match nip05.split_once('@') {
+            Some((local, _domain)) => local.to_owned(),
+            None => nip05.split('.').next().unwrap_or_default().to_owned(),
+        }
+    }
+
+    pub fn execute(&self, txn: &Transaction, ndb: &Ndb) -> ProfileLookupResponse {
+        let limit = self.limit();
+        let mut profiles: Vec<Pubkey> = vec![];
+
+        if let Some(nip05) = &self.nip05 {
+            let term = Self::nip05_search_term(nip05);
+            for pk in ndb.search_profile(txn, &term, 50).unwrap_or_default() {
+                let claimed = ndb
+                    .get_profile_by_pubkey(txn, pk)
+                    .ok()
+                    .and_then(|p| p.record().profile())
+                    .and_then(|p| p.nip05());
+
+                if claimed.is_some_and(|claimed| nip05_matches(claimed, nip05)) {
+                    profiles.push(Pubkey::new(*pk));
+                }
+            }
+        }
+
+        if let Some(name) = &self.name {
+            for pk in ndb.search_profile(txn, name, limit).unwrap_or_default() {
+                let pk = Pubkey::new(*pk);
+                if !profiles.contains(&pk) {
+                    profiles.push(pk);
+                }
+            }
+        }
+
+        profiles.truncate(limit as usize);
+        ProfileLookupResponse { profiles }
+    }
+
+    pub fn parse(args: &str) -> Result<ToolCalls, ToolCallError> {
+        let call = serde_json::from_str::<ProfileLookupCall>(args)
+            .map_err(|e| ToolCallError::ArgParseFailure(format!("{args}, error: {e}")))?;
+
+        if call.name.is_none() && call.nip05.is_none() {
+            return Err(ToolCallError::ArgParseFailure(
+                "either name or nip05 is required".to_string(),
+            ));
+        }
+
+        Ok(ToolCalls::ProfileLookup(call))
+    }
+}
+
+impl ProfileLookupResponse {
+    pub fn profiles(&self) -> &[Pubkey] {
+        &self.profiles
+    }
+
+    pub fn format_for_dave(&self, txn: &Transaction, ndb: &Ndb) -> String {
+        let profiles: Vec<SimpleProfile> = self
+            .profiles
+            .iter()
+            .map(|pk| {
+                let profile = ndb
+                    .get_profile_by_pubkey(txn, pk.bytes())
+                    .ok()
+                    .and_then(|p| p.record().profile());
+
+                SimpleProfile {
+                    pubkey: pk.hex(),
+                    name: profile.and_then(|p| p.name()).map(str::to_owned),
+                    display_name: profile.and_then(|p| p.display_name()).map(str::to_owned),
+                    nip05: profile.and_then(|p| p.nip05()).map(str::to_owned),
+                    about: profile.and_then(|p| p.about()).map(str::to_owned),
+                }
+            })
+            .collect();
+
+        serde_json::to_string(&json!({ "profiles": profiles })).unwrap()
+    }
+}
+
+pub(super) fn profile_lookup_tool() -> Tool {
+    Tool {
+        name: "profile_lookup",
+        parse_call: ProfileLookupCall::parse,
+        side_effects: false,
+        description: "Look up user profiles by name or nip05 identifier (name@domain.com). Returns pubkeys that can be used as the author in other queries.",
+        arguments: vec![
+            ToolArg {
+                name: "name",
+                typ: ArgType::String,
+                required: false,
+                default: None,
+                description: "A name or display name to search for",
+            },
+
+            ToolArg {
+                name: "nip05",
+                typ: ArgType::String,
+                required: false,
+                default: None,
+                description: "A nip05 identifier, eg: jb55@jb55.com. Only profiles claiming exactly this identifier are returned.",
+            },
+
+            ToolArg {
+                name: "limit",
+                typ: ArgType::Number,
+                required: false,
+                default: Some(Value::Number(serde_json::Number::from(5))),
+                description: "The maximum number of profiles to return",
+            },
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::fixture::Fixture;
+
+    #[test]
+    fn test_nip05_matches() {
+        assert!(nip05_matches("Bob@Example.com", "bob@example.com"));
+        assert!(nip05_matches("_@example.com", "example.com"));
+        assert!(!nip05_matches("bob@example.com", "bob@evil.com"));
+    }
+
+    #[test]
+    fn test_parse_requires_name_or_nip05() {
+        assert!(ProfileLookupCall::parse("{}").is_err());
+        assert!(matches!(
+            ProfileLookupCall::parse(r#"{"name": "alice"}"#),
+            Ok(ToolCalls::ProfileLookup(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_by_name() {
+        let fixture = Fixture::new().await;
+        let txn = Transaction::new(&fixture.ndb).unwrap();
+
+        let call = ProfileLookupCall {
+            name: Some("alice".to_string()),
+            nip05: None,
+            limit: None,
+        };
+
+        let resp = call.execute(&txn, &fixture.ndb);
+        assert_eq!(resp.profiles(), &[fixture.alice]);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_by_nip05() {
+        let fixture = Fixture::new().await;
+        let txn = Transaction::new(&fixture.ndb).unwrap();
+
+        let call = ProfileLookupCall {
+            name: None,
+            nip05: Some("BOB@example.com".to_string()),
+            limit: None,
+        };
+
+        let resp = call.execute(&txn, &fixture.ndb);
+        assert_eq!(resp.profiles(), &[fixture.bob]);
+
+        // bob's name but the wrong domain
+        let call = ProfileLookupCall {
+            name: None,
+            nip05: Some("bob@evil.com".to_string()),
+            limit: None,
+        };
+
+        let resp = call.execute(&txn, &fixture.ndb);
+        assert!(resp.profiles().is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+use super::{ArgType, QueryCall, QueryResponse, Tool, ToolArg, ToolCallError, ToolCalls};
+use enostr::{PoolRelay, RelayPool, RelayStatus};
+use nostrdb::{Ndb, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+const MAX_TIMEOUT_SECS: u64 = 30;
+
+/// Query relays for notes when we don't have any local results
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RelayQueryCall {
+    #[serde(flatten)]
+    pub query: QueryCall,
+    /// How long to wait for relays, in seconds
+    pub timeout: Option<u64>,
+}
+
+impl RelayQueryCall {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.timeout
+                .unwrap_or(DEFAULT_TIMEOUT_SECS)
+                .clamp(1, MAX_TIMEOUT_SECS),
+        )
+    }
+
+    /// Check nostrdb first. We only go to relays if we don't have
+    /// anything locally.
+    pub fn execute_local(&self, txn: &Transaction, ndb: &Ndb) -> Option<QueryResponse> {
+        let resp = self.query.execute(txn, ndb);
+        if resp.notes().is_empty() {
+            None
+        } else {
+            Some(resp)
+        }
+    }
+
+    /// The filter sent to relays. This is the same as the local query
+    /// without the local-only reply filtering.
+    fn remote_filter(&self) -> nostrdb::Filter {
+        let query = &self.query;
+        let mut filter = nostrdb::Filter::new()
+            .limit(query.limit())
+            .kinds([query.kind.unwrap_or(1)]);
+
+        if let Some(author) = &query.author {
+            filter = filter.authors([author.bytes()]);
+        }
+
+        if let Some(search) = &query.search {
+            filter = filter.search(search);
+        }
+
+        if let Some(until) = query.until {
+            filter = filter.until(until);
+        }
+
+        if let Some(since) = query.since {
+            filter = filter.since(since);
+        }
+
+        filter.build()
+    }
+
+    pub fn parse(args: &str) -> Result<ToolCalls, ToolCallError> {
+        match serde_json::from_str::<RelayQueryCall>(args) {
+            Ok(call) => Ok(ToolCalls::RelayQuery(call)),
+            Err(e) => Err(ToolCallError::ArgParseFailure(format!(
+                "{args}, error: {e}"
+            ))),
+        }
+    }
+}
+
+/// A REQ that is in flight for a [`RelayQueryCall`]. The app reading the
+/// relay pool ingests the notes as they come in, and the local query is
+/// re-run when all relays have sent EOSE or when the timeout is reached.
+pub struct RemoteQuery {
+    call_id: String,
+    call: RelayQueryCall,
+    subid: String,
+    /// The relays we sent the REQ to, None until it's sent
+    relays: Option<BTreeSet<String>>,
+    started: Instant,
+}
+
+impl RemoteQuery {
+    fn new(call_id: String, call: RelayQueryCall) -> Self {
+        Self {
+            subid: format!("dave-{call_id}"),
+            call_id,
+            call,
+            relays: None,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn call_id(&self) -> &str {
+        &self.call_id
+    }
+
+    fn timed_out(&self) -> bool {
+        self.started.elapsed() >= self.call.timeout()
+    }
+
+    /// Relays that went away since we sent the REQ won't answer, so we
+    /// don't wait on them
+    fn done(&self, pool: &RelayPool) -> bool {
+        let Some(relays) = &self.relays else {
+            return false;
+        };
+        let eosed = pool.eosed(&self.subid);
+
+        self.timed_out()
+            || relays.iter().all(|url| {
+                eosed.is_some_and(|eosed| eosed.contains(url)) || !is_connected(pool, url)
+            })
+    }
+
+    /// Run the query against nostrdb, which now contains whatever the
+    /// relays sent us
+    pub fn finish(self, txn: &Transaction, ndb: &Ndb) -> QueryResponse {
+        self.call.query.execute(txn, ndb)
+    }
+}
+
+fn is_connected(pool: &RelayPool, url: &str) -> bool {
+    pool.relays.iter().any(|relay| {
+        matches!(relay, PoolRelay::Websocket(_))
+            && relay.url() == url
+            && matches!(relay.status(), RelayStatus::Connected)
+    })
+}
+
+/// The relay queries in flight.
+///
+/// Queries are subscriptions on the shared relay pool with ids of our own.
+/// Another app reads the pool's events and ingests the notes, the pool
+/// keeps the EOSEs for our subscriptions so we know when they're done.
+/// Subscriptions are opened and closed in [`RemoteQueries::poll`], where
+/// we have the pool.
+#[derive(Default)]
+pub struct RemoteQueries {
+    queries: Vec<RemoteQuery>,
+    /// Subscriptions of cancelled queries that still need closing
+    closing: Vec<String>,
+}
+
+impl RemoteQueries {
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// Query the user's relays, the REQ goes out on the next poll
+    pub fn start(&mut self, call_id: String, call: RelayQueryCall) {
+        self.queries.push(RemoteQuery::new(call_id, call));
+    }
+
+    /// Send the REQs of new queries and close the subscriptions of
+    /// cancelled ones. Returns the queries that are finished, their
+    /// subscriptions are closed.
+    pub fn poll(&mut self, pool: &mut RelayPool) -> Vec<RemoteQuery> {
+        for subid in self.closing.drain(..) {
+            pool.unsubscribe(subid.clone());
+            pool.unwatch_eose(&subid);
+        }
+
+        for query in &mut self.queries {
+            if query.relays.is_some() {
+                continue;
+            }
+
+            let relays: BTreeSet<String> = pool
+                .relays
+                .iter()
+                .map(|relay| relay.url())
+                .filter(|url| is_connected(pool, url))
+                .map(str::to_owned)
+                .collect();
+
+            pool.watch_eose(query.subid.clone());
+            pool.subscribe(query.subid.clone(), vec![query.call.remote_filter()]);
+            query.relays = Some(relays);
+        }
+
+        let (finished, pending) = std::mem::take(&mut self.queries)
+            .into_iter()
+            .partition(|query| query.done(pool));
+        self.queries = pending;
+
+        for query in &finished {
+            pool.unsubscribe(query.subid.clone());
+            pool.unwatch_eose(&query.subid);
+        }
+
+        finished
+    }
+
+    /// Stop all queries, their subscriptions are closed on the next poll
+    pub fn cancel(&mut self) -> Vec<RemoteQuery> {
+        let cancelled = std::mem::take(&mut self.queries);
+        self.closing.extend(
+            cancelled
+                .iter()
+                .filter(|query| query.relays.is_some())
+                .map(|query| query.subid.clone()),
+        );
+        cancelled
+    }
+}
+
+pub(super) fn relay_query_tool() -> Tool {
+    Tool {
+        name: "relay_query",
+        parse_call: RelayQueryCall::parse,
+        side_effects: true,
+        description: "Query remote relays for notes. Only use this when the local query tool returned no results. Takes the same arguments as query, plus a timeout. Local results are returned immediately if there are any.",
+        arguments: vec![
+            ToolArg {
+                name: "search",
+                typ: ArgType::String,
+                required: false,
+                default: None,
+                description: "A fulltext search query. Not all relays support search.",
+            },
+
+            ToolArg {
+                name: "limit",
+                typ: ArgType::Number,
+                required: true,
+                default: Some(Value::Number(serde_json::Number::from(50))),
+                description: "The number of results to return.",
+            },
+
+            ToolArg {
+                name: "since",
+                typ: ArgType::Number,
+                required: false,
+                default: None,
+                description: "Only pull notes after this unix timestamp",
+            },
+
+            ToolArg {
+                name: "until",
+                typ: ArgType::Number,
+                required: false,
+                default: None,
+                description: "Only pull notes up until this unix timestamp",
+            },
+
+            ToolArg {
+                name: "author",
+                typ: ArgType::String,
+                required: false,
+                default: None,
+                description: "An author *pubkey* to constrain the query on. Use profile_lookup to find pubkeys.",
+            },
+
+            ToolArg {
+                name: "kind",
+                typ: ArgType::Number,
+                required: false,
+                default: Some(Value::Number(serde_json::Number::from(1))),
+                description: "The kind of note, see the query tool for a list of kinds",
+            },
+
+            ToolArg {
+                name: "timeout",
+                typ: ArgType::Number,
+                required: false,
+                default: Some(Value::Number(serde_json::Number::from(DEFAULT_TIMEOUT_SECS))),
+                description: "How many seconds to wait for relays to respond (max 30)",
+            },
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::fixture::Fixture;
+
+    fn call(args: &str) -> RelayQueryCall {
+        match RelayQueryCall::parse(args) {
+            Ok(ToolCalls::RelayQuery(call)) => call,
+            other => panic!("unexpected parse result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_flattened_query() {
+        let call = call(r#"{"search": "bitcoin", "limit": 10, "timeout": 3}"#);
+        assert_eq!(call.query.search(), Some("bitcoin"));
+        assert_eq!(call.query.limit, Some(10));
+        assert_eq!(call.timeout(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_timeout_clamped() {
+        assert_eq!(
+            call(r#"{"limit": 10}"#).timeout(),
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            call(r#"{"limit": 10, "timeout": 600}"#).timeout(),
+            Duration::from_secs(MAX_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            call(r#"{"limit": 10, "timeout": 0}"#).timeout(),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_results_skip_relays() {
+        let fixture = Fixture::new().await;
+        let txn = Transaction::new(&fixture.ndb).unwrap();
+
+        let found = call(r#"{"search": "reading", "limit": 10}"#);
+        let resp = found.execute_local(&txn, &fixture.ndb).unwrap();
+        assert_eq!(resp.notes(), &[fixture.root]);
+
+        let missing = call(r#"{"search": "zzzunknownzzz", "limit": 10}"#);
+        assert!(missing.execute_local(&txn, &fixture.ndb).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_no_relays_finishes_immediately() {
+        let fixture = Fixture::new().await;
+        let mut pool = RelayPool::new();
+        let mut remote = RemoteQueries::default();
+        remote.start(
+            "call".to_string(),
+            call(r#"{"search": "zzzunknownzzz", "limit": 10}"#),
+        );
+
+        let finished = remote.poll(&mut pool);
+        assert_eq!(finished.len(), 1);
+        assert!(remote.is_empty());
+
+        let txn = Transaction::new(&fixture.ndb).unwrap();
+        let query = finished.into_iter().next().unwrap();
+        assert_eq!(query.call_id(), "call");
+        assert!(query.finish(&txn, &fixture.ndb).notes().is_empty());
+    }
+}
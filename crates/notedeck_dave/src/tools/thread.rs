@@ -0,0 +1,166 @@
+use super::{ArgType, Tool, ToolArg, ToolCallError, ToolCalls};
+use enostr::NoteId;
+use nostrdb::{Ndb, NoteReply, Transaction};
+use serde::{Deserialize, Serialize};
+
+/// The maximum number of notes returned for a thread
+const MAX_THREAD_NOTES: i32 = 200;
+
+/// Fetch the full thread that a note is a part of
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ThreadCall {
+    pub note_id: NoteId,
+}
+
+/// The notes in a thread, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadResponse {
+    root: Option<NoteId>,
+    notes: Vec<NoteId>,
+}
+
+impl ThreadResponse {
+    /// The root of the thread, if we have it
+    pub fn root(&self) -> Option<&NoteId> {
+        self.root.as_ref()
+    }
+
+    pub fn notes(&self) -> &[NoteId] {
+        &self.notes
+    }
+}
+
+impl ThreadCall {
+    /// Find the root id of the thread the note belongs to
+    fn root_id(&self, txn: &Transaction, ndb: &Ndb) -> Option<NoteId> {
+        let note = ndb.get_note_by_id(txn, self.note_id.bytes()).ok()?;
+
+        match NoteReply::new(note.tags()).root() {
+            Some(root) => Some(NoteId::new(*root.id)),
+            None => Some(self.note_id),
+        }
+    }
+
+    pub fn execute(&self, txn: &Transaction, ndb: &Ndb) -> ThreadResponse {
+        let Some(root_id) = self.root_id(txn, ndb) else {
+            return ThreadResponse {
+                root: None,
+                notes: vec![],
+            };
+        };
+
+        let filter = nostrdb::Filter::new()
+            .kinds([1])
+            .event(root_id.bytes())
+            .build();
+
+        let mut replies: Vec<(u64, NoteId)> = ndb
+            .query(txn, &[filter], MAX_THREAD_NOTES)
+            .map(|results| {
+                results
+                    .into_iter()
+                    .map(|r| (r.note.created_at(), NoteId::new(*r.note.id())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        replies.sort_by_key(|(created_at, _)| *created_at);
+
+        let has_root = ndb.get_note_by_id(txn, root_id.bytes()).is_ok();
+
+        let mut notes: Vec<NoteId> = Vec::with_capacity(replies.len() + 1);
+        if has_root {
+            notes.push(root_id);
+        }
+        notes.extend(replies.into_iter().map(|(_, id)| id));
+
+        ThreadResponse {
+            root: has_root.then_some(root_id),
+            notes,
+        }
+    }
+
+    pub fn parse(args: &str) -> Result<ToolCalls, ToolCallError> {
+        match serde_json::from_str::<ThreadCall>(args) {
+            Ok(call) => Ok(ToolCalls::Thread(call)),
+            Err(e) => Err(ToolCallError::ArgParseFailure(format!(
+                "{args}, error: {e}"
+            ))),
+        }
+    }
+}
+
+pub(super) fn thread_tool() -> Tool {
+    Tool {
+        name: "thread",
+        parse_call: ThreadCall::parse,
+        side_effects: false,
+        description: "Fetch the full thread a note belongs to, starting with the root note followed by all of the replies, oldest first.",
+        arguments: vec![ToolArg {
+            name: "note_id",
+            typ: ArgType::String,
+            required: true,
+            default: None,
+            description: "The hex note id of any note in the thread",
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::fixture::Fixture;
+
+    #[tokio::test]
+    async fn test_thread_from_reply() {
+        let fixture = Fixture::new().await;
+        let txn = Transaction::new(&fixture.ndb).unwrap();
+
+        // fetching from a nested reply returns the whole thread
+        let resp = ThreadCall {
+            note_id: fixture.nested_reply,
+        }
+        .execute(&txn, &fixture.ndb);
+
+        assert_eq!(resp.root(), Some(&fixture.root));
+        assert_eq!(
+            resp.notes(),
+            &[fixture.root, fixture.reply, fixture.nested_reply]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_thread_from_root() {
+        let fixture = Fixture::new().await;
+        let txn = Transaction::new(&fixture.ndb).unwrap();
+
+        let resp = ThreadCall {
+            note_id: fixture.root,
+        }
+        .execute(&txn, &fixture.ndb);
+
+        assert_eq!(resp.notes().len(), 3);
+        assert_eq!(resp.notes()[0], fixture.root);
+    }
+
+    #[tokio::test]
+    async fn test_thread_unknown_note() {
+        let fixture = Fixture::new().await;
+        let txn = Transaction::new(&fixture.ndb).unwrap();
+
+        let resp = ThreadCall {
+            note_id: NoteId::new([0; 32]),
+        }
+        .execute(&txn, &fixture.ndb);
+
+        assert!(resp.root().is_none());
+        assert!(resp.notes().is_empty());
+    }
+
+    #[test]
+    fn test_parse() {
+        let id = "0000000000000000000000000000000000000000000000000000000000000001";
+        let parsed = ThreadCall::parse(&format!(r#"{{"note_id": "{id}"}}"#));
+        assert!(matches!(parsed, Ok(ToolCalls::Thread(_))));
+        assert!(ThreadCall::parse(r#"{"note_id": "nope"}"#).is_err());
+    }
+}
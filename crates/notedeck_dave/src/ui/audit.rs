@@ -0,0 +1,194 @@
+use crate::audit::{AuditDecision, AuditEntry, AuditLog};
+use crate::policy::{ToolPermission, ToolPolicy};
+use crate::tools::Tool;
+use crate::ui::{DaveAction, DaveResponse};
+use egui::RichText;
+use notedeck::{time_ago_since, tr, Localization};
+use std::collections::HashMap;
+
+/// A sidebar with per-tool permissions and the log of every tool call
+/// dave has made
+pub struct AuditPanel<'a> {
+    tools: &'a HashMap<String, Tool>,
+    policy: &'a ToolPolicy,
+    log: &'a AuditLog,
+    i18n: &'a mut Localization,
+}
+
+impl<'a> AuditPanel<'a> {
+    pub fn new(
+        tools: &'a HashMap<String, Tool>,
+        policy: &'a ToolPolicy,
+        log: &'a AuditLog,
+        i18n: &'a mut Localization,
+    ) -> Self {
+        Self {
+            tools,
+            policy,
+            log,
+            i18n,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> DaveResponse {
+        let mut action: Option<DaveAction> = None;
+
+        ui.add_space(60.0);
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                action = self.permissions_ui(ui);
+
+                ui.add_space(16.0);
+                ui.separator();
+                self.log_ui(ui);
+            });
+
+        DaveResponse { action }
+    }
+
+    fn permissions_ui(&mut self, ui: &mut egui::Ui) -> Option<DaveAction> {
+        let mut action: Option<DaveAction> = None;
+
+        ui.label(
+            RichText::new(tr!(
+                self.i18n,
+                "Tool permissions",
+                "Heading for the list of Dave tool permissions"
+            ))
+            .strong(),
+        );
+        ui.add_space(8.0);
+
+        let mut tools: Vec<&Tool> = self.tools.values().collect();
+        tools.sort_by_key(|t| t.name());
+
+        for tool in tools {
+            let current = self.policy.permission(tool);
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(tool.name()).monospace());
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    // right to left, so reverse the order
+                    for permission in ToolPermission::ALL.iter().rev() {
+                        let label = permission_label(self.i18n, *permission);
+                        if ui.selectable_label(current == *permission, label).clicked()
+                            && current != *permission
+                        {
+                            action = Some(DaveAction::SetToolPermission {
+                                tool: tool.name().to_owned(),
+                                permission: *permission,
+                            });
+                        }
+                    }
+                });
+            });
+        }
+
+        action
+    }
+
+    fn log_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(
+            RichText::new(tr!(
+                self.i18n,
+                "Audit log",
+                "Heading for the log of tool calls made by Dave"
+            ))
+            .strong(),
+        );
+        ui.add_space(8.0);
+
+        let log = self.log;
+        if log.entries().is_empty() {
+            ui.label(
+                RichText::new(tr!(
+                    self.i18n,
+                    "No tool calls yet",
+                    "Shown when Dave has not made any tool calls"
+                ))
+                .weak(),
+            );
+            return;
+        }
+
+        for entry in log.entries().iter().rev() {
+            self.entry_ui(entry, ui);
+            ui.add_space(4.0);
+        }
+    }
+
+    fn entry_ui(&mut self, entry: &AuditEntry, ui: &mut egui::Ui) {
+        egui::Frame::new()
+            .inner_margin(8.0)
+            .corner_radius(8.0)
+            .stroke(ui.visuals().noninteractive().bg_stroke)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(&entry.tool).monospace().strong());
+                    ui.label(decision_label(self.i18n, entry.decision));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.label(RichText::new(time_ago_since(self.i18n, entry.created_at)).weak());
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    if let Some(latency) = entry.latency_ms {
+                        ui.label(RichText::new(format!("{latency} ms")).weak());
+                    }
+
+                    if let Some(size) = entry.result_size {
+                        ui.label(RichText::new(format!("{size} bytes")).weak());
+                    }
+                });
+
+                if let Some(err) = &entry.error {
+                    ui.label(RichText::new(err).color(ui.visuals().warn_fg_color));
+                }
+
+                egui::CollapsingHeader::new(tr!(
+                    self.i18n,
+                    "Arguments",
+                    "Label to expand the arguments of a Dave tool call in the audit log"
+                ))
+                .id_salt(("dave-audit-args", &entry.call_id))
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::Label::new(RichText::new(&entry.arguments).monospace())
+                            .wrap_mode(egui::TextWrapMode::Wrap),
+                    );
+                });
+            });
+    }
+}
+
+fn permission_label(i18n: &mut Localization, permission: ToolPermission) -> String {
+    match permission {
+        ToolPermission::Allow => tr!(
+            i18n,
+            "Allow",
+            "Permission to always allow a Dave tool to run"
+        ),
+        ToolPermission::Ask => tr!(i18n, "Ask", "Permission to ask before a Dave tool runs"),
+        ToolPermission::Deny => tr!(i18n, "Deny", "Permission to never run a Dave tool"),
+    }
+}
+
+fn decision_label(i18n: &mut Localization, decision: AuditDecision) -> String {
+    match decision {
+        AuditDecision::Allowed => tr!(i18n, "allowed", "Dave tool call was allowed by policy"),
+        AuditDecision::Approved => tr!(i18n, "approved", "Dave tool call was approved by the user"),
+        AuditDecision::Pending => tr!(
+            i18n,
+            "waiting for approval",
+            "Dave tool call is waiting for approval"
+        ),
+        AuditDecision::Denied => tr!(i18n, "denied", "Dave tool call was denied by policy"),
+        AuditDecision::Rejected => tr!(i18n, "rejected", "Dave tool call was rejected by the user"),
+        AuditDecision::Cancelled => tr!(
+            i18n,
+            "cancelled",
+            "Dave tool call was cancelled before it ran"
+        ),
+        AuditDecision::Invalid => tr!(i18n, "invalid", "Dave tool call could not be parsed"),
+    }
+}
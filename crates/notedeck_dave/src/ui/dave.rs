@@ -1,6 +1,10 @@
 use crate::{
     messages::Message,
-    tools::{PresentNotesCall, QueryCall, ToolCall, ToolCalls, ToolResponse},
+    policy::ToolPermission,
+    tools::{
+        DraftNoteCall, PresentNotesCall, ProfileLookupCall, QueryCall, ThreadCall, ToolCall,
+        ToolCalls, ToolResponse,
+    },
 };
 use egui::{Align, Key, KeyboardShortcut, Layout, Modifiers};
 use nostrdb::{Ndb, Transaction};
@@ -14,6 +18,8 @@ pub struct DaveUi<'a> {
     chat: &'a [Message],
     trial: bool,
     input: &'a mut String,
+    /// Tool calls waiting for the user to approve them
+    pending_approvals: &'a [ToolCall],
 }
 
 /// The response the app generates. The response contains an optional
@@ -30,11 +36,7 @@ impl DaveResponse {
         }
     }
 
-    fn note(action: NoteAction) -> DaveResponse {
-        Self::new(DaveAction::Note(action))
-    }
-
-    fn or(self, r: DaveResponse) -> DaveResponse {
+    pub(crate) fn or(self, r: DaveResponse) -> DaveResponse {
         DaveResponse {
             action: self.action.or(r.action),
         }
@@ -59,12 +61,46 @@ pub enum DaveAction {
     Send,
     NewChat,
     ToggleChrome,
+    /// Open a note drafted by dave in the composer
+    OpenDraft(DraftNoteCall),
+    /// Show or hide the conversation history sidebar
+    ToggleHistory,
+    /// Show or hide the tool permissions and audit log sidebar
+    ToggleAudit,
+    /// The user approved or rejected a tool call. When `remember` is
+    /// set, the decision is used for every future call of that tool.
+    ToolApproval {
+        call_id: String,
+        approved: bool,
+        remember: bool,
+    },
+    SetToolPermission {
+        tool: String,
+        permission: ToolPermission,
+    },
+    /// Restore a previously saved conversation
+    LoadConversation(String),
+    DeleteConversation(String),
+    RenameConversation {
+        id: String,
+        title: String,
+    },
     Note(NoteAction),
 }
 
 impl<'a> DaveUi<'a> {
     pub fn new(trial: bool, chat: &'a [Message], input: &'a mut String) -> Self {
-        DaveUi { trial, chat, input }
+        DaveUi {
+            trial,
+            chat,
+            input,
+            pending_approvals: &[],
+        }
+    }
+
+    pub fn pending_approvals(mut self, pending_approvals: &'a [ToolCall]) -> Self {
+        self.pending_approvals = pending_approvals;
+        self
     }
 
     fn chat_margin(ctx: &egui::Context) -> i8 {
@@ -107,7 +143,7 @@ impl<'a> DaveUi<'a> {
                         .show(ui, |ui| self.inputbox(app_ctx.i18n, ui))
                         .inner;
 
-                    let note_action = egui::ScrollArea::vertical()
+                    let chat_action = egui::ScrollArea::vertical()
                         .stick_to_bottom(true)
                         .auto_shrink([false; 2])
                         .show(ui, |ui| {
@@ -119,8 +155,8 @@ impl<'a> DaveUi<'a> {
                         })
                         .inner;
 
-                    if let Some(action) = note_action {
-                        DaveResponse::note(action)
+                    if let Some(action) = chat_action {
+                        DaveResponse::new(action)
                     } else {
                         r
                     }
@@ -147,8 +183,8 @@ impl<'a> DaveUi<'a> {
     }
 
     /// Render a chat message (user, assistant, tool call/response, etc)
-    fn render_chat(&self, ctx: &mut AppContext, ui: &mut egui::Ui) -> Option<NoteAction> {
-        let mut action: Option<NoteAction> = None;
+    fn render_chat(&self, ctx: &mut AppContext, ui: &mut egui::Ui) -> Option<DaveAction> {
+        let mut action: Option<DaveAction> = None;
         for message in self.chat {
             let r = match message {
                 Message::Error(err) => {
@@ -172,7 +208,9 @@ impl<'a> DaveUi<'a> {
                     // have a debug option to show this
                     None
                 }
-                Message::ToolCalls(toolcalls) => Self::tool_calls_ui(ctx, toolcalls, ui),
+                Message::ToolCalls(toolcalls) => {
+                    Self::tool_calls_ui(ctx, toolcalls, self.pending_approvals, ui)
+                }
             };
 
             if r.is_some() {
@@ -218,6 +256,7 @@ impl<'a> DaveUi<'a> {
             clipboard: ctx.clipboard,
             i18n: ctx.i18n,
             global_wallet: ctx.global_wallet,
+            nip05_cache: ctx.nip05_cache,
         };
 
         let txn = Transaction::new(note_context.ndb).unwrap();
@@ -267,17 +306,17 @@ impl<'a> DaveUi<'a> {
     fn tool_calls_ui(
         ctx: &mut AppContext,
         toolcalls: &[ToolCall],
+        pending_approvals: &[ToolCall],
         ui: &mut egui::Ui,
-    ) -> Option<NoteAction> {
-        let mut note_action: Option<NoteAction> = None;
+    ) -> Option<DaveAction> {
+        let mut action: Option<DaveAction> = None;
 
         ui.vertical(|ui| {
             for call in toolcalls {
                 match call.calls() {
                     ToolCalls::PresentNotes(call) => {
-                        let r = Self::present_notes_ui(ctx, call, ui);
-                        if r.is_some() {
-                            note_action = r;
+                        if let Some(r) = Self::present_notes_ui(ctx, call, ui) {
+                            action = Some(DaveAction::Note(r));
                         }
                     }
                     ToolCalls::Invalid(err) => {
@@ -292,11 +331,50 @@ impl<'a> DaveUi<'a> {
                             },
                         );
                     }
+                    ToolCalls::RelayQuery(relay_call) => {
+                        ui.allocate_ui_with_layout(
+                            egui::vec2(ui.available_size().x, 32.0),
+                            Layout::left_to_right(Align::Center),
+                            |ui| {
+                                Self::search_call_ui(ctx, &relay_call.query, ui);
+                                pill_label(
+                                    "relays",
+                                    &relay_call.timeout().as_secs().to_string(),
+                                    ui,
+                                );
+                            },
+                        );
+                    }
+                    ToolCalls::ProfileLookup(lookup) => {
+                        ui.allocate_ui_with_layout(
+                            egui::vec2(ui.available_size().x, 32.0),
+                            Layout::left_to_right(Align::Center),
+                            |ui| profile_lookup_ui(lookup, ui),
+                        );
+                    }
+                    ToolCalls::Thread(thread) => {
+                        ui.allocate_ui_with_layout(
+                            egui::vec2(ui.available_size().x, 32.0),
+                            Layout::left_to_right(Align::Center),
+                            |ui| thread_call_ui(thread, ui),
+                        );
+                    }
+                    ToolCalls::DraftNote(draft) => {
+                        if let Some(r) = draft_note_ui(ctx.i18n, draft, ui) {
+                            action = Some(r);
+                        }
+                    }
+                }
+
+                if pending_approvals.iter().any(|p| p.id() == call.id()) {
+                    if let Some(r) = tool_approval_ui(ctx.i18n, call, ui) {
+                        action = Some(r);
+                    }
                 }
             }
         });
 
-        note_action
+        action
     }
 
     fn inputbox(&mut self, i18n: &mut Localization, ui: &mut egui::Ui) -> DaveResponse {
@@ -368,17 +446,31 @@ impl<'a> DaveUi<'a> {
 }
 
 fn new_chat_button() -> impl egui::Widget {
+    top_button("new-chat-button", app_images::new_message_image())
+}
+
+fn history_button() -> impl egui::Widget {
+    top_button("dave-history-button", app_images::columns_image())
+}
+
+fn audit_button(dark_mode: bool) -> impl egui::Widget {
+    let img = if dark_mode {
+        app_images::settings_dark_image()
+    } else {
+        app_images::settings_light_image()
+    };
+    top_button("dave-audit-button", img)
+}
+
+fn top_button(id: &'static str, img: egui::Image<'static>) -> impl egui::Widget {
     move |ui: &mut egui::Ui| {
         let img_size = 24.0;
         let max_size = 32.0;
 
-        let img = app_images::new_message_image().max_width(img_size);
+        let img = img.max_width(img_size);
 
-        let helper = notedeck_ui::anim::AnimationHelper::new(
-            ui,
-            "new-chat-button",
-            egui::vec2(max_size, max_size),
-        );
+        let helper =
+            notedeck_ui::anim::AnimationHelper::new(ui, id, egui::vec2(max_size, max_size));
 
         let cur_img_size = helper.scale_1d_pos(img_size);
         img.paint_at(
@@ -441,6 +533,139 @@ fn query_call_ui(
     }
 }
 
+fn profile_lookup_ui(lookup: &ProfileLookupCall, ui: &mut egui::Ui) {
+    ui.spacing_mut().item_spacing.x = 8.0;
+    ui.add(search_icon(16.0, 16.0));
+    ui.add_space(8.0);
+
+    if let Some(name) = lookup.name.as_ref() {
+        pill_label("profile", name, ui);
+    }
+
+    if let Some(nip05) = lookup.nip05.as_ref() {
+        pill_label("nip05", nip05, ui);
+    }
+}
+
+fn thread_call_ui(thread: &ThreadCall, ui: &mut egui::Ui) {
+    ui.spacing_mut().item_spacing.x = 8.0;
+    ui.add(search_icon(16.0, 16.0));
+    ui.add_space(8.0);
+
+    let bech = thread
+        .note_id
+        .to_bech()
+        .unwrap_or_else(|| thread.note_id.hex());
+    pill_label("thread", &bech[..bech.len().min(16)], ui);
+}
+
+/// Drafts are only shown to the user. They have to explicitly open it
+/// in the composer to publish it.
+fn draft_note_ui(
+    i18n: &mut Localization,
+    draft: &DraftNoteCall,
+    ui: &mut egui::Ui,
+) -> Option<DaveAction> {
+    egui::Frame::new()
+        .inner_margin(10.0)
+        .corner_radius(10.0)
+        .stroke(ui.visuals().noninteractive().bg_stroke)
+        .show(ui, |ui| {
+            ui.label(
+                egui::RichText::new(tr!(
+                    i18n,
+                    "Draft note",
+                    "Heading for a note drafted by the Dave AI assistant"
+                ))
+                .weak(),
+            );
+            ui.add_space(4.0);
+            ui.add(egui::Label::new(draft.content.as_str()).wrap_mode(egui::TextWrapMode::Wrap));
+            ui.add_space(8.0);
+
+            let open = ui
+                .button(tr!(
+                    i18n,
+                    "Open in composer",
+                    "Button to open a note drafted by Dave in the note composer"
+                ))
+                .clicked();
+
+            open.then(|| DaveAction::OpenDraft(draft.clone()))
+        })
+        .inner
+}
+
+/// Ask the user if dave is allowed to run a tool call. The arguments
+/// are shown so that the user knows exactly what will happen.
+fn tool_approval_ui(
+    i18n: &mut Localization,
+    call: &ToolCall,
+    ui: &mut egui::Ui,
+) -> Option<DaveAction> {
+    let tool = call.calls().name();
+    let mut action: Option<DaveAction> = None;
+
+    egui::Frame::new()
+        .inner_margin(10.0)
+        .corner_radius(10.0)
+        .stroke(egui::Stroke::new(1.0, ui.visuals().warn_fg_color))
+        .show(ui, |ui| {
+            ui.label(tr!(
+                i18n,
+                "Dave wants to use the {tool} tool",
+                "Prompt asking the user to approve a Dave tool call",
+                tool = tool
+            ));
+            ui.add(
+                egui::Label::new(
+                    egui::RichText::new(call.calls().arguments())
+                        .monospace()
+                        .weak(),
+                )
+                .wrap_mode(egui::TextWrapMode::Wrap),
+            );
+            ui.add_space(8.0);
+
+            ui.horizontal(|ui| {
+                let approval = |approved: bool, remember: bool| {
+                    Some(DaveAction::ToolApproval {
+                        call_id: call.id().to_owned(),
+                        approved,
+                        remember,
+                    })
+                };
+
+                if ui
+                    .button(tr!(i18n, "Allow", "Button to allow a Dave tool call once"))
+                    .clicked()
+                {
+                    action = approval(true, false);
+                }
+
+                if ui
+                    .button(tr!(
+                        i18n,
+                        "Always allow",
+                        "Button to always allow calls to a Dave tool"
+                    ))
+                    .clicked()
+                {
+                    action = approval(true, true);
+                }
+
+                if ui
+                    .button(tr!(i18n, "Deny", "Button to deny a Dave tool call"))
+                    .clicked()
+                {
+                    action = approval(false, false);
+                }
+            });
+        });
+
+    action
+}
+
 fn pill_label(name: &str, value: &str, ui: &mut egui::Ui) {
     pill_label_ui(
         name,
@@ -510,6 +735,20 @@ fn top_buttons_ui(app_ctx: &mut AppContext, ui: &mut egui::Ui) -> Option<DaveAct
         action = Some(DaveAction::NewChat);
     }
 
+    rect = rect.translate(egui::vec2(30.0, 0.0));
+    let r = ui.put(rect, history_button());
+
+    if r.clicked() {
+        action = Some(DaveAction::ToggleHistory);
+    }
+
+    rect = rect.translate(egui::vec2(30.0, 0.0));
+    let r = ui.put(rect, audit_button(ui.visuals().dark_mode));
+
+    if r.clicked() {
+        action = Some(DaveAction::ToggleAudit);
+    }
+
     action
 }
 
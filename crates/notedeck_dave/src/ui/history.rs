@@ -0,0 +1,206 @@
+use crate::history::ConversationHistory;
+use crate::ui::{DaveAction, DaveResponse};
+use egui::{Align, Layout, RichText};
+use notedeck::{time_ago_since, tr, Localization};
+
+/// State for the conversation history sidebar that needs to live
+/// across frames
+#[derive(Default)]
+pub struct HistoryPanelState {
+    pub open: bool,
+    renaming: Option<Renaming>,
+}
+
+struct Renaming {
+    id: String,
+    title: String,
+}
+
+/// The sidebar listing past conversations
+pub struct HistoryPanel<'a> {
+    history: &'a ConversationHistory,
+    state: &'a mut HistoryPanelState,
+    selected: &'a str,
+    i18n: &'a mut Localization,
+}
+
+impl<'a> HistoryPanel<'a> {
+    pub fn new(
+        history: &'a ConversationHistory,
+        state: &'a mut HistoryPanelState,
+        selected: &'a str,
+        i18n: &'a mut Localization,
+    ) -> Self {
+        Self {
+            history,
+            state,
+            selected,
+            i18n,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> DaveResponse {
+        let mut action: Option<DaveAction> = None;
+
+        ui.add_space(60.0);
+        ui.label(
+            RichText::new(tr!(
+                self.i18n,
+                "Conversations",
+                "Heading for the list of past Dave conversations"
+            ))
+            .strong(),
+        );
+        ui.add_space(8.0);
+
+        let history = self.history;
+        if history.conversations().is_empty() {
+            ui.label(
+                RichText::new(tr!(
+                    self.i18n,
+                    "No saved conversations yet",
+                    "Shown when there are no past Dave conversations"
+                ))
+                .weak(),
+            );
+            return DaveResponse::default();
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for conversation in history.conversations() {
+                    let is_renaming = self
+                        .state
+                        .renaming
+                        .as_ref()
+                        .is_some_and(|r| r.id == conversation.id);
+
+                    let r = if is_renaming {
+                        self.rename_ui(ui)
+                    } else {
+                        self.conversation_ui(
+                            &conversation.id,
+                            &conversation.title,
+                            conversation.updated_at,
+                            ui,
+                        )
+                    };
+
+                    if r.is_some() {
+                        action = r;
+                    }
+                }
+            });
+
+        DaveResponse { action }
+    }
+
+    fn conversation_ui(
+        &mut self,
+        id: &str,
+        title: &str,
+        updated_at: u64,
+        ui: &mut egui::Ui,
+    ) -> Option<DaveAction> {
+        let mut action: Option<DaveAction> = None;
+
+        let title = if title.is_empty() {
+            tr!(
+                self.i18n,
+                "Untitled",
+                "Fallback title for a Dave conversation without a title"
+            )
+        } else {
+            title.to_owned()
+        };
+
+        let selected = id == self.selected;
+        let resp = ui
+            .vertical(|ui| {
+                let resp = ui.selectable_label(selected, title.as_str());
+
+                ui.label(
+                    RichText::new(time_ago_since(self.i18n, updated_at))
+                        .small()
+                        .weak(),
+                );
+                ui.add_space(4.0);
+
+                resp
+            })
+            .inner;
+
+        if resp.clicked() {
+            action = Some(DaveAction::LoadConversation(id.to_owned()));
+        }
+
+        resp.context_menu(|ui| {
+            if ui
+                .button(tr!(
+                    self.i18n,
+                    "Rename",
+                    "Context menu option to rename a Dave conversation"
+                ))
+                .clicked()
+            {
+                self.state.renaming = Some(Renaming {
+                    id: id.to_owned(),
+                    title: title.clone(),
+                });
+                ui.close_menu();
+            }
+
+            if ui
+                .button(tr!(
+                    self.i18n,
+                    "Delete",
+                    "Context menu option to delete a Dave conversation"
+                ))
+                .clicked()
+            {
+                action = Some(DaveAction::DeleteConversation(id.to_owned()));
+                ui.close_menu();
+            }
+        });
+
+        action
+    }
+
+    fn rename_ui(&mut self, ui: &mut egui::Ui) -> Option<DaveAction> {
+        let renaming = self.state.renaming.as_mut()?;
+
+        let (done, cancel) = ui
+            .with_layout(Layout::left_to_right(Align::Center), |ui| {
+                let r = ui.add(
+                    egui::TextEdit::singleline(&mut renaming.title).desired_width(f32::INFINITY),
+                );
+                r.request_focus();
+
+                let done = r.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                let cancel = ui.input(|i| i.key_pressed(egui::Key::Escape));
+                (done, cancel)
+            })
+            .inner;
+
+        if cancel {
+            self.state.renaming = None;
+            return None;
+        }
+
+        if !done {
+            return None;
+        }
+
+        let renaming = self.state.renaming.take()?;
+        let title = renaming.title.trim().to_owned();
+        if title.is_empty() {
+            return None;
+        }
+
+        Some(DaveAction::RenameConversation {
+            id: renaming.id,
+            title,
+        })
+    }
+}
@@ -1,3 +1,7 @@
+mod audit;
 mod dave;
+mod history;
 
+pub use audit::AuditPanel;
 pub use dave::{DaveAction, DaveResponse, DaveUi};
+pub use history::{HistoryPanel, HistoryPanelState};
@@ -0,0 +1,443 @@
+//! A [JSON Canvas](https://jsoncanvas.org/spec/1.0/) document model.
+//!
+//! Fields we don't know about are kept in `extra` so that files written
+//! by other apps survive a load/save round trip.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Canvas {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<Node>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edges: Vec<Edge>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The type of a node. Unknown types are preserved as-is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum NodeKind {
+    Text,
+    File,
+    Link,
+    Group,
+    Other(String),
+}
+
+impl From<String> for NodeKind {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "text" => NodeKind::Text,
+            "file" => NodeKind::File,
+            "link" => NodeKind::Link,
+            "group" => NodeKind::Group,
+            _ => NodeKind::Other(s),
+        }
+    }
+}
+
+impl From<NodeKind> for String {
+    fn from(kind: NodeKind) -> Self {
+        match kind {
+            NodeKind::Text => "text".to_string(),
+            NodeKind::File => "file".to_string(),
+            NodeKind::Link => "link".to_string(),
+            NodeKind::Group => "group".to_string(),
+            NodeKind::Other(s) => s,
+        }
+    }
+}
+
+/// A canvas node. The fields that only apply to some node types are
+/// optional, see the spec for which ones belong to which type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Node {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: NodeKind,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    /// text nodes: markdown text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    /// file nodes: the path to the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// file nodes: a heading or block inside the file, starting with #
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subpath: Option<String>,
+
+    /// link nodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// group nodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(
+        rename = "backgroundStyle",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub background_style: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Top, Side::Right, Side::Bottom, Side::Left];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum End {
+    None,
+    Arrow,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Edge {
+    pub id: String,
+    pub from_node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_side: Option<Side>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_end: Option<End>,
+    pub to_node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_side: Option<Side>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_end: Option<End>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Edge {
+    pub fn new(
+        id: String,
+        from_node: String,
+        from_side: Side,
+        to_node: String,
+        to_side: Side,
+    ) -> Self {
+        Edge {
+            id,
+            from_node,
+            from_side: Some(from_side),
+            from_end: None,
+            to_node,
+            to_side: Some(to_side),
+            to_end: None,
+            color: None,
+            label: None,
+            extra: Map::new(),
+        }
+    }
+
+    /// Edges have no arrow at the start unless specified
+    pub fn from_end(&self) -> End {
+        self.from_end.unwrap_or(End::None)
+    }
+
+    /// Edges have an arrow at the end unless specified
+    pub fn to_end(&self) -> End {
+        self.to_end.unwrap_or(End::Arrow)
+    }
+
+    pub fn connects(&self, node_id: &str) -> bool {
+        self.from_node == node_id || self.to_node == node_id
+    }
+}
+
+impl Node {
+    fn new(id: String, kind: NodeKind, x: i64, y: i64, width: i64, height: i64) -> Self {
+        Node {
+            id,
+            kind,
+            x,
+            y,
+            width,
+            height,
+            color: None,
+            text: None,
+            file: None,
+            subpath: None,
+            url: None,
+            label: None,
+            background: None,
+            background_style: None,
+            extra: Map::new(),
+        }
+    }
+
+    pub fn text(id: String, x: i64, y: i64, text: String) -> Self {
+        Node {
+            text: Some(text),
+            ..Node::new(id, NodeKind::Text, x, y, 250, 60)
+        }
+    }
+
+    pub fn link(id: String, x: i64, y: i64, url: String) -> Self {
+        Node {
+            url: Some(url),
+            ..Node::new(id, NodeKind::Link, x, y, 400, 80)
+        }
+    }
+
+    pub fn file(id: String, x: i64, y: i64, file: String) -> Self {
+        Node {
+            file: Some(file),
+            ..Node::new(id, NodeKind::File, x, y, 400, 80)
+        }
+    }
+
+    pub fn group(id: String, x: i64, y: i64, label: Option<String>) -> Self {
+        Node {
+            label,
+            ..Node::new(id, NodeKind::Group, x, y, 600, 400)
+        }
+    }
+}
+
+impl FromStr for Canvas {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl Canvas {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    pub fn node_mut(&mut self, id: &str) -> Option<&mut Node> {
+        self.nodes.iter_mut().find(|n| n.id == id)
+    }
+
+    pub fn edge(&self, id: &str) -> Option<&Edge> {
+        self.edges.iter().find(|e| e.id == id)
+    }
+
+    /// Generate an id that isn't used by any node or edge yet
+    pub fn new_id(&self) -> String {
+        loop {
+            let id = hex::encode(rand::random::<[u8; 8]>());
+            if self.node(&id).is_none() && self.edge(&id).is_none() {
+                return id;
+            }
+        }
+    }
+
+    pub fn add_node(&mut self, node: Node) {
+        self.nodes.push(node);
+    }
+
+    /// Remove a node along with every edge connected to it
+    pub fn remove_node(&mut self, id: &str) -> Option<Node> {
+        let index = self.nodes.iter().position(|n| n.id == id)?;
+        self.edges.retain(|e| !e.connects(id));
+        Some(self.nodes.remove(index))
+    }
+
+    /// Add an edge if both of its nodes exist
+    pub fn add_edge(&mut self, edge: Edge) -> bool {
+        if self.node(&edge.from_node).is_none() || self.node(&edge.to_node).is_none() {
+            return false;
+        }
+
+        self.edges.push(edge);
+        true
+    }
+
+    pub fn remove_edge(&mut self, id: &str) -> Option<Edge> {
+        let index = self.edges.iter().position(|e| e.id == id)?;
+        Some(self.edges.remove(index))
+    }
+
+    /// Move a node to the top of the z-order
+    pub fn raise_node(&mut self, id: &str) {
+        let Some(index) = self.nodes.iter().position(|n| n.id == id) else {
+            return;
+        };
+
+        let node = self.nodes.remove(index);
+        self.nodes.push(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A canvas using every part of the spec, plus a few fields that
+    /// aren't part of it
+    const SPEC_CANVAS: &str = r##"{
+      "nodes": [
+        {"id": "group1", "type": "group", "x": -100, "y": -100, "width": 800, "height": 600,
+         "label": "Research", "background": "bg.png", "backgroundStyle": "ratio", "color": "4"},
+        {"id": "text1", "type": "text", "x": 0, "y": 0, "width": 250, "height": 60,
+         "text": "# Hello\n\nsome **markdown**", "color": "#ff0000"},
+        {"id": "file1", "type": "file", "x": 300, "y": 0, "width": 400, "height": 400,
+         "file": "notes/idea.md", "subpath": "#Heading"},
+        {"id": "link1", "type": "link", "x": 0, "y": 200, "width": 400, "height": 80,
+         "url": "https://jsoncanvas.org", "customNodeField": {"nested": [1, 2, 3]}},
+        {"id": "future1", "type": "someFutureType", "x": 10, "y": 10, "width": 10, "height": 10,
+         "payload": "keep me"}
+      ],
+      "edges": [
+        {"id": "edge1", "fromNode": "text1", "fromSide": "right", "fromEnd": "arrow",
+         "toNode": "file1", "toSide": "left", "toEnd": "none", "color": "2", "label": "leads to"},
+        {"id": "edge2", "fromNode": "text1", "toNode": "link1", "customEdgeField": true}
+      ],
+      "customCanvasField": "also kept"
+    }"##;
+
+    fn roundtrip(json: &str) -> (Value, Value) {
+        let canvas: Canvas = json.parse().expect("parse");
+        let saved = canvas.to_json().expect("serialize");
+
+        let original: Value = serde_json::from_str(json).unwrap();
+        let saved: Value = serde_json::from_str(&saved).unwrap();
+        (original, saved)
+    }
+
+    #[test]
+    fn test_spec_roundtrip() {
+        let (original, saved) = roundtrip(SPEC_CANVAS);
+        assert_eq!(original, saved);
+    }
+
+    #[test]
+    fn test_demo_roundtrip() {
+        let (original, saved) = roundtrip(include_str!("../demo.canvas"));
+        assert_eq!(original, saved);
+    }
+
+    #[test]
+    fn test_empty_canvas() {
+        let (original, saved) = roundtrip("{}");
+        assert_eq!(original, saved);
+    }
+
+    #[test]
+    fn test_parsed_types() {
+        let canvas: Canvas = SPEC_CANVAS.parse().unwrap();
+
+        assert_eq!(canvas.node("group1").unwrap().kind, NodeKind::Group);
+        assert_eq!(
+            canvas.node("future1").unwrap().kind,
+            NodeKind::Other("someFutureType".to_string())
+        );
+
+        let edge = canvas.edge("edge2").unwrap();
+        assert_eq!(edge.from_side, None);
+        assert_eq!(edge.from_end(), End::None);
+        assert_eq!(edge.to_end(), End::Arrow);
+
+        let edge = canvas.edge("edge1").unwrap();
+        assert_eq!(edge.from_side, Some(Side::Right));
+        assert_eq!(edge.from_end(), End::Arrow);
+        assert_eq!(edge.to_end(), End::None);
+    }
+
+    #[test]
+    fn test_remove_node_removes_edges() {
+        let mut canvas: Canvas = SPEC_CANVAS.parse().unwrap();
+
+        assert!(canvas.remove_node("text1").is_some());
+        assert!(canvas.node("text1").is_none());
+        assert!(canvas.edges.is_empty());
+        assert!(canvas.remove_node("text1").is_none());
+    }
+
+    #[test]
+    fn test_add_edge_requires_nodes() {
+        let mut canvas = Canvas::default();
+        let a = canvas.new_id();
+        canvas.add_node(Node::text(a.clone(), 0, 0, "a".to_string()));
+        let b = canvas.new_id();
+        canvas.add_node(Node::link(
+            b.clone(),
+            500,
+            0,
+            "https://damus.io".to_string(),
+        ));
+
+        let missing = Edge::new(
+            canvas.new_id(),
+            a.clone(),
+            Side::Right,
+            "nope".to_string(),
+            Side::Left,
+        );
+        assert!(!canvas.add_edge(missing));
+
+        let edge = Edge::new(canvas.new_id(), a, Side::Right, b, Side::Left);
+        assert!(canvas.add_edge(edge));
+        assert_eq!(canvas.edges.len(), 1);
+    }
+
+    #[test]
+    fn test_new_nodes_roundtrip() {
+        let mut canvas = Canvas::default();
+        canvas.add_node(Node::group(
+            "g".to_string(),
+            0,
+            0,
+            Some("label".to_string()),
+        ));
+        canvas.add_node(Node::file("f".to_string(), 0, 0, "a.md".to_string()));
+
+        let json = canvas.to_json().unwrap();
+        let parsed: Canvas = json.parse().unwrap();
+        assert_eq!(parsed, canvas);
+
+        // optional fields that aren't set are not written
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let file = &value["nodes"][1];
+        assert!(file.get("subpath").is_none());
+        assert!(file.get("text").is_none());
+        assert!(value.get("edges").is_none());
+    }
+
+    #[test]
+    fn test_raise_node() {
+        let mut canvas: Canvas = SPEC_CANVAS.parse().unwrap();
+        canvas.raise_node("text1");
+        assert_eq!(canvas.nodes.last().unwrap().id, "text1");
+    }
+}
@@ -0,0 +1,92 @@
+//! Nostr content embedded in a canvas.
+//!
+//! Embeds are stored as regular link nodes with `nostr:` urls, so other
+//! JSON Canvas apps still see a link they can open.
+
+use enostr::{NoteId, Pubkey};
+use nostr::nips::nip19::{FromBech32, Nip19, Nip19Event, ToBech32};
+
+const NOSTR_SCHEME: &str = "nostr:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Embed {
+    Note { id: NoteId },
+    Profile { pubkey: Pubkey },
+}
+
+impl Embed {
+    /// Parse a `nostr:` url. Supports nevent, note, nprofile and npub.
+    /// Relay hints are ignored, canvas files can come from anywhere and
+    /// embeds are fetched from the account's relays.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let bech = url.trim().strip_prefix(NOSTR_SCHEME)?;
+
+        match Nip19::from_bech32(bech).ok()? {
+            Nip19::EventId(id) => Some(Embed::Note {
+                id: NoteId::new(id.to_bytes()),
+            }),
+            Nip19::Event(event) => Some(Embed::Note {
+                id: NoteId::new(event.event_id.to_bytes()),
+            }),
+            Nip19::Pubkey(pk) => Some(Embed::Profile {
+                pubkey: Pubkey::new(pk.to_bytes()),
+            }),
+            Nip19::Profile(profile) => Some(Embed::Profile {
+                pubkey: Pubkey::new(profile.public_key.to_bytes()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// A `nostr:nevent` url for a note, with relay hints
+    pub fn note_url(id: &NoteId, relays: &[String]) -> Option<String> {
+        let event = Nip19Event::new(
+            nostr::event::EventId::from_byte_array(*id.bytes()),
+            relays.to_vec(),
+        );
+
+        let bech = event.to_bech32().ok().or_else(|| id.to_bech())?;
+        Some(format!("{NOSTR_SCHEME}{bech}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_url_round_trip() {
+        let id = NoteId::new([7; 32]);
+        let relays = vec!["wss://relay.damus.io".to_string()];
+
+        let url = Embed::note_url(&id, &relays).unwrap();
+        assert!(url.starts_with("nostr:nevent1"));
+
+        let Some(Embed::Note { id: parsed, .. }) = Embed::from_url(&url) else {
+            panic!("expected a note embed");
+        };
+        assert_eq!(parsed, id);
+    }
+
+    #[test]
+    fn test_parse_note_and_npub() {
+        let id = NoteId::new([1; 32]);
+        let url = format!("nostr:{}", id.to_bech().unwrap());
+        assert_eq!(Embed::from_url(&url), Some(Embed::Note { id }));
+
+        let pubkey =
+            Pubkey::from_hex("32e1827635450ebb3c5a7d12c1f8e7b2b514439ac10a67eef3d9fd9c5c68e245")
+                .unwrap();
+        let url = format!("nostr:{}", pubkey.npub().unwrap());
+        assert_eq!(Embed::from_url(&url), Some(Embed::Profile { pubkey }));
+    }
+
+    #[test]
+    fn test_not_an_embed() {
+        assert_eq!(Embed::from_url("https://damus.io"), None);
+        assert_eq!(Embed::from_url("nostr:garbage"), None);
+        // the nostr: prefix is required
+        let id = NoteId::new([1; 32]);
+        assert_eq!(Embed::from_url(&id.to_bech().unwrap()), None);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::canvas::Canvas;
+use notedeck::{Directory, Error, Result, storage};
+use std::path::Path;
+
+const CANVAS_EXT: &str = ".canvas";
+
+/// The `.canvas` files in the notebook directory, sorted by name
+pub fn list_canvases(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = Directory::new(dir.to_path_buf())
+        .get_file_names()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|name| name.ends_with(CANVAS_EXT))
+        .collect();
+
+    names.sort();
+    names
+}
+
+pub fn load_canvas(dir: &Path, name: &str) -> Result<Canvas> {
+    let contents = Directory::new(dir.to_path_buf()).get_file(name.to_owned())?;
+    Ok(contents.parse()?)
+}
+
+pub fn save_canvas(dir: &Path, name: &str, canvas: &Canvas) -> Result<()> {
+    if !name.ends_with(CANVAS_EXT) {
+        return Err(Error::Generic(format!("{name} is not a .canvas file")));
+    }
+
+    storage::write_file(dir, name.to_owned(), &canvas.to_json()?)
+}
+
+/// A file name based on `base` that isn't in `existing` yet, eg:
+/// `untitled.canvas`, `untitled 2.canvas`, ...
+pub fn unique_name(existing: &[String], base: &str) -> String {
+    let mut name = format!("{base}{CANVAS_EXT}");
+    let mut n = 2;
+    while existing.contains(&name) {
+        name = format!("{base} {n}{CANVAS_EXT}");
+        n += 1;
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Node;
+
+    #[test]
+    fn test_save_and_load() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+
+        let mut canvas = Canvas::default();
+        canvas.add_node(Node::text("a".to_string(), 10, 20, "hello".to_string()));
+
+        save_canvas(dir, "test.canvas", &canvas).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a canvas").unwrap();
+
+        assert_eq!(list_canvases(dir), vec!["test.canvas".to_string()]);
+        assert_eq!(load_canvas(dir, "test.canvas").unwrap(), canvas);
+        assert!(load_canvas(dir, "missing.canvas").is_err());
+        assert!(save_canvas(dir, "test.json", &canvas).is_err());
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let existing = vec![
+            "untitled.canvas".to_string(),
+            "untitled 2.canvas".to_string(),
+        ];
+        assert_eq!(unique_name(&[], "untitled"), "untitled.canvas");
+        assert_eq!(unique_name(&existing, "untitled"), "untitled 3.canvas");
+    }
+}
@@ -1,14 +1,75 @@
-use crate::ui::{edge_ui, node_ui};
-use egui::{Pos2, Rect};
-use jsoncanvas::JsonCanvas;
-use notedeck::{AppContext, AppResponse};
+use crate::canvas::{Canvas, Edge, Node, NodeKind, Side};
+use crate::embed::Embed;
+use crate::ui::{
+    NodeResponse, Toolbar, ToolbarAction, closest_side, connection_preview_ui, edge_ui, node_rect,
+    node_ui, side_point,
+};
+use crate::undo::UndoHistory;
+use egui::{DragAndDrop, Key, KeyboardShortcut, LayerId, Modifiers, Pos2, Rect, Vec2};
+use nostrdb::Transaction;
+use notedeck::{
+    AppAction, AppContext, AppResponse, DataPathType, DraggedNote, NoteAction, NoteContext,
+};
+use std::path::PathBuf;
 
+mod canvas;
+mod embed;
+mod files;
 mod ui;
+mod undo;
+
+/// Nodes can't be resized smaller than this
+const MIN_NODE_SIZE: i64 = 40;
+
+/// The size of a node created by dropping a note on the canvas
+const EMBED_NODE_WIDTH: i64 = 400;
+const EMBED_NODE_HEIGHT: i64 = 300;
+
+/// What is currently selected on the canvas
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selection {
+    Node(String),
+    Edge(String),
+}
+
+/// An edge that is being drawn from the side of a node
+struct Connecting {
+    from: String,
+    side: Side,
+    pos: Pos2,
+}
+
+/// A node that is being moved or resized
+struct Dragging {
+    /// Nodes that move along with the dragged node. These are the
+    /// nodes inside of a group when moving a group.
+    children: Vec<String>,
+    /// The part of the drag we haven't applied yet, since node
+    /// positions are integers
+    remainder: Vec2,
+}
 
 pub struct Notebook {
-    canvas: JsonCanvas,
+    canvas: Canvas,
     scene_rect: Rect,
     loaded: bool,
+    /// Where notebooks are stored, set on the first update
+    dir: Option<PathBuf>,
+    /// The file the canvas is saved to. None for the demo canvas
+    file: Option<String>,
+    files: Vec<String>,
+    /// Are there changes that haven't been saved?
+    dirty: bool,
+    undo: UndoHistory,
+    selected: Option<Selection>,
+    /// The node whose contents are being edited
+    editing: Option<String>,
+    /// The canvas from before the node being edited was changed, it goes
+    /// on the undo stack once there is an edit
+    edit_checkpoint: Option<Canvas>,
+    connecting: Option<Connecting>,
+    dragging: Option<Dragging>,
+    error: Option<String>,
 }
 
 impl Notebook {
@@ -23,38 +84,597 @@ impl Default for Notebook {
             canvas: demo_canvas(),
             scene_rect: Rect::from_min_max(Pos2::ZERO, Pos2::ZERO),
             loaded: false,
+            dir: None,
+            file: None,
+            files: vec![],
+            dirty: false,
+            undo: UndoHistory::default(),
+            selected: None,
+            editing: None,
+            edit_checkpoint: None,
+            connecting: None,
+            dragging: None,
+            error: None,
+        }
+    }
+}
+
+impl Notebook {
+    fn refresh_files(&mut self) {
+        if let Some(dir) = &self.dir {
+            self.files = files::list_canvases(dir);
+        }
+    }
+
+    /// Replace the canvas, forgetting everything about the old one
+    fn set_canvas(&mut self, canvas: Canvas, file: Option<String>) {
+        self.canvas = canvas;
+        self.file = file;
+        self.dirty = false;
+        self.undo.clear();
+        self.selected = None;
+        self.editing = None;
+        self.edit_checkpoint = None;
+        self.connecting = None;
+        self.dragging = None;
+        self.loaded = false;
+    }
+
+    /// Call before every change to the canvas
+    fn checkpoint(&mut self) {
+        self.undo.checkpoint(&self.canvas);
+        self.dirty = true;
+    }
+
+    fn save(&mut self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+
+        let file = self
+            .file
+            .get_or_insert_with(|| files::unique_name(&self.files, "untitled"));
+
+        match files::save_canvas(dir, file, &self.canvas) {
+            Ok(()) => {
+                self.dirty = false;
+                self.error = None;
+            }
+            Err(err) => {
+                tracing::error!("could not save notebook {file}: {err}");
+                self.error = Some(err.to_string());
+            }
+        }
+
+        self.refresh_files();
+    }
+
+    fn open(&mut self, name: String) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+
+        match files::load_canvas(dir, &name) {
+            Ok(canvas) => {
+                // don't lose changes to the canvas we're switching from
+                if self.dirty && self.file.is_some() {
+                    self.save();
+                }
+                self.error = None;
+                self.set_canvas(canvas, Some(name));
+            }
+            Err(err) => {
+                tracing::error!("could not open notebook {name}: {err}");
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    fn new_canvas(&mut self) {
+        if self.dirty && self.file.is_some() {
+            self.save();
+        }
+
+        let name = files::unique_name(&self.files, "untitled");
+        self.set_canvas(Canvas::default(), Some(name));
+        self.save();
+    }
+
+    fn add_node(&mut self, kind: NodeKind) {
+        let id = self.canvas.new_id();
+        let center = self.scene_rect.center();
+        let (x, y) = (center.x as i64, center.y as i64);
+
+        let mut node = match kind {
+            NodeKind::Text => Node::text(id.clone(), x, y, String::new()),
+            NodeKind::Link => Node::link(id.clone(), x, y, String::new()),
+            NodeKind::File => Node::file(id.clone(), x, y, String::new()),
+            NodeKind::Group => Node::group(id.clone(), x, y, None),
+            NodeKind::Other(_) => return,
+        };
+
+        // center the node in the view
+        node.x -= node.width / 2;
+        node.y -= node.height / 2;
+
+        self.checkpoint();
+        self.canvas.add_node(node);
+        self.selected = Some(Selection::Node(id.clone()));
+        self.editing = Some(id);
+        self.edit_checkpoint = None;
+    }
+
+    fn delete_selected(&mut self) {
+        let Some(selected) = self.selected.take() else {
+            return;
+        };
+
+        self.checkpoint();
+        match selected {
+            Selection::Node(id) => {
+                self.canvas.remove_node(&id);
+            }
+            Selection::Edge(id) => {
+                self.canvas.remove_edge(&id);
+            }
+        }
+        self.editing = None;
+    }
+
+    fn undo(&mut self) {
+        if self.undo.undo(&mut self.canvas) {
+            self.after_history_change();
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.undo.redo(&mut self.canvas) {
+            self.after_history_change();
+        }
+    }
+
+    fn after_history_change(&mut self) {
+        self.dirty = true;
+        self.editing = None;
+
+        let exists = match &self.selected {
+            Some(Selection::Node(id)) => self.canvas.node(id).is_some(),
+            Some(Selection::Edge(id)) => self.canvas.edge(id).is_some(),
+            None => true,
+        };
+
+        if !exists {
+            self.selected = None;
+        }
+    }
+
+    fn handle_toolbar_action(&mut self, action: ToolbarAction) {
+        match action {
+            ToolbarAction::New => self.new_canvas(),
+            ToolbarAction::Open(name) => self.open(name),
+            ToolbarAction::Save => self.save(),
+            ToolbarAction::Undo => self.undo(),
+            ToolbarAction::Redo => self.redo(),
+            ToolbarAction::AddNode(kind) => self.add_node(kind),
+            ToolbarAction::Delete => self.delete_selected(),
+        }
+    }
+
+    /// Keyboard shortcuts, only active when no text field has focus
+    fn shortcuts(&mut self, ui: &mut egui::Ui) -> Option<ToolbarAction> {
+        let save = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+        if ui.input_mut(|i| i.consume_shortcut(&save)) {
+            return Some(ToolbarAction::Save);
+        }
+
+        if ui.memory(|m| m.focused().is_some()) {
+            return None;
+        }
+
+        // check redo first, undo would match it too
+        let redo = KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z);
+        let redo_y = KeyboardShortcut::new(Modifiers::COMMAND, Key::Y);
+        let undo = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+
+        ui.input_mut(|i| {
+            if i.consume_shortcut(&redo) || i.consume_shortcut(&redo_y) {
+                Some(ToolbarAction::Redo)
+            } else if i.consume_shortcut(&undo) {
+                Some(ToolbarAction::Undo)
+            } else if i.key_pressed(Key::Delete) || i.key_pressed(Key::Backspace) {
+                Some(ToolbarAction::Delete)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn handle_node_response(&mut self, id: &str, resp: NodeResponse) -> bool {
+        let mut interacted = false;
+
+        if resp.edited {
+            if let Some(canvas) = self.edit_checkpoint.take() {
+                self.undo.checkpoint(&canvas);
+            }
+            self.dirty = true;
+        }
+
+        if resp.body.clicked() {
+            interacted = true;
+            self.selected = Some(Selection::Node(id.to_owned()));
+            if self.editing.as_deref() != Some(id) {
+                self.editing = None;
+            }
+        }
+
+        // only looking at a node's contents isn't a change
+        if resp.body.double_clicked() {
+            self.edit_checkpoint = Some(self.canvas.clone());
+            self.editing = Some(id.to_owned());
+        }
+
+        if resp.body.drag_started() {
+            interacted = true;
+            self.checkpoint();
+            self.selected = Some(Selection::Node(id.to_owned()));
+            self.editing = None;
+
+            let children = match self.canvas.node(id) {
+                Some(node) if node.kind == NodeKind::Group => {
+                    let group = node_rect(node);
+                    self.canvas
+                        .nodes
+                        .iter()
+                        .filter(|n| n.id != id && group.contains_rect(node_rect(n)))
+                        .map(|n| n.id.clone())
+                        .collect()
+                }
+                _ => vec![],
+            };
+
+            self.dragging = Some(Dragging {
+                children,
+                remainder: Vec2::ZERO,
+            });
+        }
+
+        let moved = if resp.body.dragged() {
+            self.drag_delta(resp.body.drag_delta())
+        } else {
+            None
+        };
+
+        if let Some((dx, dy)) = moved {
+            let children = self
+                .dragging
+                .as_ref()
+                .map(|d| d.children.clone())
+                .unwrap_or_default();
+
+            for node_id in std::iter::once(id.to_owned()).chain(children) {
+                if let Some(node) = self.canvas.node_mut(&node_id) {
+                    node.x += dx;
+                    node.y += dy;
+                }
+            }
+        }
+
+        if let Some(resize) = &resp.resize {
+            if resize.drag_started() {
+                interacted = true;
+                self.checkpoint();
+                self.dragging = Some(Dragging {
+                    children: vec![],
+                    remainder: Vec2::ZERO,
+                });
+            }
+
+            let resized = if resize.dragged() {
+                self.drag_delta(resize.drag_delta())
+            } else {
+                None
+            };
+
+            if let (Some((dw, dh)), Some(node)) = (resized, self.canvas.node_mut(id)) {
+                node.width = (node.width + dw).max(MIN_NODE_SIZE);
+                node.height = (node.height + dh).max(MIN_NODE_SIZE);
+            }
+        }
+
+        if resp.body.drag_stopped() || resp.resize.as_ref().is_some_and(|r| r.drag_stopped()) {
+            self.dragging = None;
+        }
+
+        for (side, handle) in &resp.sides {
+            if handle.drag_started() {
+                interacted = true;
+                if let Some(node) = self.canvas.node(id) {
+                    self.connecting = Some(Connecting {
+                        from: id.to_owned(),
+                        side: *side,
+                        pos: side_point(*side, node_rect(node)),
+                    });
+                }
+            }
+
+            let pointer = handle
+                .dragged()
+                .then(|| handle.interact_pointer_pos())
+                .flatten();
+            if let (Some(connecting), Some(pos)) = (&mut self.connecting, pointer) {
+                connecting.pos = pos;
+            }
+
+            if handle.drag_stopped() {
+                self.finish_connecting();
+            }
+        }
+
+        interacted
+    }
+
+    /// Turn a drag delta into whole canvas units, keeping track of the
+    /// fractional part for the next frame
+    fn drag_delta(&mut self, delta: Vec2) -> Option<(i64, i64)> {
+        let dragging = self.dragging.as_mut()?;
+        let total = dragging.remainder + delta;
+        let whole = Vec2::new(total.x.trunc(), total.y.trunc());
+        dragging.remainder = total - whole;
+
+        if whole == Vec2::ZERO {
+            None
+        } else {
+            Some((whole.x as i64, whole.y as i64))
         }
     }
+
+    /// The user let go of an edge they were drawing. If it was dropped
+    /// on another node, connect them.
+    fn finish_connecting(&mut self) {
+        let Some(connecting) = self.connecting.take() else {
+            return;
+        };
+
+        // the topmost node under the pointer, groups last
+        let target = self
+            .canvas
+            .nodes
+            .iter()
+            .rev()
+            .filter(|n| n.id != connecting.from)
+            .filter(|n| node_rect(n).contains(connecting.pos))
+            .min_by_key(|n| n.kind == NodeKind::Group)
+            .map(|n| (n.id.clone(), closest_side(node_rect(n), connecting.pos)));
+
+        let Some((to, to_side)) = target else {
+            return;
+        };
+
+        let edge = Edge::new(
+            self.canvas.new_id(),
+            connecting.from,
+            connecting.side,
+            to,
+            to_side,
+        );
+        let edge_id = edge.id.clone();
+
+        self.checkpoint();
+        self.canvas.add_edge(edge);
+        self.selected = Some(Selection::Edge(edge_id));
+    }
+
+    /// Opening a note or profile node shows it in columns
+    fn embed_action(&self, id: &str) -> Option<NoteAction> {
+        let url = self.canvas.node(id)?.url.as_deref()?;
+
+        match Embed::from_url(url)? {
+            Embed::Note { id, .. } => Some(NoteAction::note(id)),
+            Embed::Profile { pubkey, .. } => Some(NoteAction::Profile(pubkey)),
+        }
+    }
+
+    /// Notes dragged from a timeline can be dropped onto the canvas.
+    /// `visible` is the part of the canvas that is on screen, in canvas
+    /// coordinates.
+    fn handle_note_drop(&mut self, ui: &egui::Ui, scene_layer: Option<LayerId>, visible: Rect) {
+        let ctx = ui.ctx();
+        if !DragAndDrop::has_payload_of_type::<DraggedNote>(ctx) {
+            return;
+        }
+
+        let (Some(pointer), Some(from_global)) = (
+            ctx.pointer_latest_pos(),
+            scene_layer.and_then(|layer| ctx.layer_transform_from_global(layer)),
+        ) else {
+            return;
+        };
+
+        // the pointer is in screen coordinates, the canvas has its own
+        let pos = from_global * pointer;
+        if !visible.contains(pos) {
+            return;
+        }
+
+        // show that the note can be dropped here
+        ui.painter().rect_stroke(
+            from_global.inverse() * visible,
+            egui::CornerRadius::ZERO,
+            egui::Stroke::new(2.0, ui.visuals().selection.stroke.color),
+            egui::StrokeKind::Inside,
+        );
+
+        if !ui.input(|i| i.pointer.any_released()) {
+            return;
+        }
+
+        let Some(dropped) = DragAndDrop::take_payload::<DraggedNote>(ctx) else {
+            return;
+        };
+
+        let Some(url) = Embed::note_url(&dropped.note_id, &dropped.relays) else {
+            return;
+        };
+
+        let mut node = Node::link(self.canvas.new_id(), pos.x as i64, pos.y as i64, url);
+        node.width = EMBED_NODE_WIDTH;
+        node.height = EMBED_NODE_HEIGHT;
+        node.x -= node.width / 2;
+        node.y -= node.height / 2;
+
+        let id = node.id.clone();
+        self.checkpoint();
+        self.canvas.add_node(node);
+        self.selected = Some(Selection::Node(id));
+        self.editing = None;
+    }
 }
 
 impl notedeck::App for Notebook {
-    fn update(&mut self, _ctx: &mut AppContext<'_>, ui: &mut egui::Ui) -> AppResponse {
-        //let app_action: Option<AppAction> = None;
+    fn update(&mut self, ctx: &mut AppContext<'_>, ui: &mut egui::Ui) -> AppResponse {
+        if self.dir.is_none() {
+            self.dir = Some(ctx.path.path(DataPathType::Notebook));
+            self.refresh_files();
+        }
+
+        let toolbar = Toolbar {
+            file: self.file.as_deref(),
+            files: &self.files,
+            dirty: self.dirty,
+            can_undo: self.undo.can_undo(),
+            can_redo: self.undo.can_redo(),
+            has_selection: self.selected.is_some(),
+            error: self.error.as_deref(),
+        };
+
+        if let Some(action) = toolbar.ui(ctx.i18n, ui).or_else(|| self.shortcuts(ui)) {
+            self.handle_toolbar_action(action);
+        }
+
+        let txn = Transaction::new(ctx.ndb).unwrap();
+
+        if ui.input(|i| i.key_pressed(Key::Escape)) {
+            self.editing = None;
+        }
 
         if !self.loaded {
             self.scene_rect = ui.available_rect_before_wrap();
             self.loaded = true;
         }
 
-        egui::Scene::new().show(ui, &mut self.scene_rect, |ui| {
-            // render nodes
-            for (_node_id, node) in self.canvas.get_nodes().iter() {
-                let _resp = node_ui(ui, node);
+        let selected = self.selected.clone();
+        let editing = self.editing.clone();
+        let mut node_responses: Vec<(String, NodeResponse)> = vec![];
+        let mut edge_clicked: Option<String> = None;
+        let mut scene_layer: Option<LayerId> = None;
+        let mut visible = Rect::NOTHING;
+
+        let mut note_context = NoteContext {
+            ndb: ctx.ndb,
+            accounts: ctx.accounts,
+            img_cache: ctx.img_cache,
+            note_cache: ctx.note_cache,
+            zaps: ctx.zaps,
+            pool: ctx.pool,
+            jobs: ctx.media_jobs.sender(),
+            unknown_ids: ctx.unknown_ids,
+            clipboard: ctx.clipboard,
+            i18n: ctx.i18n,
+            global_wallet: ctx.global_wallet,
+            nip05_cache: ctx.nip05_cache,
+        };
+
+        let scene = egui::Scene::new().show(ui, &mut self.scene_rect, |ui| {
+            scene_layer = Some(ui.layer_id());
+            visible = ui.clip_rect();
+            let is_selected = |id: &str| selected == Some(Selection::Node(id.to_owned()));
+            let is_editing = |id: &str| editing.as_deref() == Some(id);
+
+            // groups go behind everything else
+            for node in self
+                .canvas
+                .nodes
+                .iter_mut()
+                .filter(|n| n.kind == NodeKind::Group)
+            {
+                let resp = node_ui(
+                    ui,
+                    &mut note_context,
+                    &txn,
+                    node,
+                    is_selected(&node.id),
+                    is_editing(&node.id),
+                );
+                node_responses.push((node.id.clone(), resp));
             }
 
             // render edges
-            for (_edge_id, edge) in self.canvas.get_edges().iter() {
-                let _resp = edge_ui(ui, self.canvas.get_nodes(), edge);
+            for edge in &self.canvas.edges {
+                let edge_selected = selected == Some(Selection::Edge(edge.id.clone()));
+                if edge_ui(ui, &self.canvas.nodes, edge, edge_selected)
+                    .is_some_and(|resp| resp.clicked())
+                {
+                    edge_clicked = Some(edge.id.clone());
+                }
+            }
+
+            // render nodes
+            for node in self
+                .canvas
+                .nodes
+                .iter_mut()
+                .filter(|n| n.kind != NodeKind::Group)
+            {
+                let resp = node_ui(
+                    ui,
+                    &mut note_context,
+                    &txn,
+                    node,
+                    is_selected(&node.id),
+                    is_editing(&node.id),
+                );
+                node_responses.push((node.id.clone(), resp));
+            }
+
+            let preview = self.connecting.as_ref().and_then(|connecting| {
+                let node = self.canvas.node(&connecting.from)?;
+                Some((connecting, side_point(connecting.side, node_rect(node))))
+            });
+            if let Some((connecting, from)) = preview {
+                connection_preview_ui(ui, from, connecting.side, connecting.pos);
             }
         });
 
-        AppResponse::none()
+        let mut note_action: Option<NoteAction> = None;
+        let mut interacted = false;
+        for (id, resp) in node_responses {
+            if resp.body.clicked() && self.editing.as_deref() != Some(id.as_str()) {
+                note_action = note_action.or_else(|| self.embed_action(&id));
+            }
+            interacted |= self.handle_node_response(&id, resp);
+        }
+
+        if let Some(edge_id) = edge_clicked {
+            interacted = true;
+            self.selected = Some(Selection::Edge(edge_id));
+            self.editing = None;
+        }
+
+        // clicking on the background clears the selection
+        if !interacted && scene.response.clicked() {
+            self.selected = None;
+            self.editing = None;
+        }
+
+        self.handle_note_drop(ui, scene_layer, visible);
+
+        AppResponse::action(note_action.map(AppAction::Note))
     }
 }
 
-fn demo_canvas() -> JsonCanvas {
+fn demo_canvas() -> Canvas {
     let demo_json: String = include_str!("../demo.canvas").to_string();
 
-    let canvas: JsonCanvas = demo_json.parse().unwrap_or_else(|_| JsonCanvas::default());
+    let canvas: Canvas = demo_json.parse().unwrap_or_else(|_| Canvas::default());
     canvas
 }
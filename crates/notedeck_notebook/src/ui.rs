@@ -1,13 +1,21 @@
-use egui::{Align, Label, Pos2, Rect, Shape, Stroke, TextWrapMode, epaint::CubicBezierShape, vec2};
-use jsoncanvas::{
-    FileNode, GroupNode, LinkNode, Node, NodeId, TextNode,
-    edge::{Edge, Side},
-    node::GenericNode,
+use crate::canvas::{Edge, End, Node, NodeKind, Side};
+use crate::embed::Embed;
+use egui::{
+    Align, Color32, CursorIcon, Label, Pos2, Rect, Sense, Shape, Stroke, TextWrapMode,
+    epaint::CubicBezierShape, vec2,
 };
-use std::collections::HashMap;
+use nostrdb::Transaction;
+use notedeck::{Localization, NoteContext, tr};
+use notedeck_ui::{NoteOptions, NoteView, ProfilePreview};
 use std::ops::Neg;
 
-fn node_rect(node: &GenericNode) -> Rect {
+/// Radius of the handles used to draw edges from a node side
+const SIDE_HANDLE_RADIUS: f32 = 6.0;
+
+/// Size of the resize handle in the bottom right corner of a node
+const RESIZE_HANDLE_SIZE: f32 = 14.0;
+
+pub fn node_rect(node: &Node) -> Rect {
     let x = node.x as f32;
     let y = node.y as f32;
     let width = node.width as f32;
@@ -19,9 +27,7 @@ fn node_rect(node: &GenericNode) -> Rect {
     Rect::from_min_max(min, max)
 }
 
-fn side_point(side: &Side, node: &GenericNode) -> Pos2 {
-    let rect = node_rect(node);
-
+pub fn side_point(side: Side, rect: Rect) -> Pos2 {
     match side {
         Side::Top => rect.center_top(),
         Side::Left => rect.left_center(),
@@ -30,8 +36,36 @@ fn side_point(side: &Side, node: &GenericNode) -> Pos2 {
     }
 }
 
+/// The side of `rect` closest to `pos`
+pub fn closest_side(rect: Rect, pos: Pos2) -> Side {
+    Side::ALL
+        .into_iter()
+        .min_by(|a, b| {
+            let da = side_point(*a, rect).distance_sq(pos);
+            let db = side_point(*b, rect).distance_sq(pos);
+            da.total_cmp(&db)
+        })
+        .unwrap_or(Side::Right)
+}
+
+/// Edges don't have to specify sides. Pick the ones that face each other.
+fn facing_sides(from: Rect, to: Rect) -> (Side, Side) {
+    let d = to.center() - from.center();
+    if d.x.abs() > d.y.abs() {
+        if d.x > 0.0 {
+            (Side::Right, Side::Left)
+        } else {
+            (Side::Left, Side::Right)
+        }
+    } else if d.y > 0.0 {
+        (Side::Bottom, Side::Top)
+    } else {
+        (Side::Top, Side::Bottom)
+    }
+}
+
 /// a unit vector pointing outward from the given side
-fn side_tangent(side: &Side) -> egui::Vec2 {
+fn side_tangent(side: Side) -> egui::Vec2 {
     match side {
         Side::Top => vec2(0.0, -1.0),
         Side::Bottom => vec2(0.0, 1.0),
@@ -40,25 +74,20 @@ fn side_tangent(side: &Side) -> egui::Vec2 {
     }
 }
 
-pub fn edge_ui(
-    ui: &mut egui::Ui,
-    nodes: &HashMap<NodeId, Node>,
-    edge: &Edge,
-) -> Option<egui::Response> {
-    let from_node = nodes.get(edge.from_node())?;
-    let to_node = nodes.get(edge.to_node())?;
-    let to_side = edge.to_side()?;
-    let from_side = edge.from_side()?;
-
-    // anchor from-side
-    let p0 = side_point(from_side, from_node.node());
-
-    // anchor b
-    let to_anchor = side_point(to_side, to_node.node());
-
-    // to-point is slightly offset to accomidate arrow
-    let p3 = to_anchor + side_tangent(to_side) * 2.0;
+/// The preset colors from the JSON Canvas spec, or a hex color
+pub fn canvas_color(color: &str) -> Option<Color32> {
+    match color {
+        "1" => Some(Color32::from_rgb(0xfb, 0x46, 0x4c)), // red
+        "2" => Some(Color32::from_rgb(0xe9, 0x97, 0x3f)), // orange
+        "3" => Some(Color32::from_rgb(0xe0, 0xde, 0x71)), // yellow
+        "4" => Some(Color32::from_rgb(0x44, 0xcf, 0x6e)), // green
+        "5" => Some(Color32::from_rgb(0x53, 0xdf, 0xdd)), // cyan
+        "6" => Some(Color32::from_rgb(0xa8, 0x82, 0xff)), // purple
+        hex => Color32::from_hex(hex).ok(),
+    }
+}
 
+fn bezier_points(p0: Pos2, from_side: Side, p3: Pos2, to_side: Side) -> [Pos2; 4] {
     // bend debug
     //let bend = debug_slider(ui, ui.id().with("bend"), p3, 0.25, 0.0..=1.0);
     let bend = 0.28;
@@ -73,14 +102,100 @@ pub fn edge_ui(
     // c2 = anchor B + (inward tangent)  * d
     let c2 = p3 - side_tangent(to_side).neg() * d;
 
-    let color = ui.visuals().noninteractive().bg_stroke.color;
+    [p0, c1, c2, p3]
+}
+
+/// Render an edge. The returned response is for a small handle in the
+/// middle of the edge, which is used to select it.
+pub fn edge_ui(
+    ui: &mut egui::Ui,
+    nodes: &[Node],
+    edge: &Edge,
+    selected: bool,
+) -> Option<egui::Response> {
+    let from_rect = node_rect(nodes.iter().find(|n| n.id == edge.from_node)?);
+    let to_rect = node_rect(nodes.iter().find(|n| n.id == edge.to_node)?);
+
+    let (default_from, default_to) = facing_sides(from_rect, to_rect);
+    let from_side = edge.from_side.unwrap_or(default_from);
+    let to_side = edge.to_side.unwrap_or(default_to);
+
+    // anchor from-side
+    let from_anchor = side_point(from_side, from_rect);
+
+    // anchor b
+    let to_anchor = side_point(to_side, to_rect);
+
+    // points are slightly offset to accomidate arrows
+    let p0 = from_anchor + side_tangent(from_side) * 2.0;
+    let p3 = to_anchor + side_tangent(to_side) * 2.0;
+
+    let color = if selected {
+        ui.visuals().selection.stroke.color
+    } else {
+        edge.color
+            .as_deref()
+            .and_then(canvas_color)
+            .unwrap_or(ui.visuals().noninteractive().bg_stroke.color)
+    };
     let stroke = egui::Stroke::new(4.0, color);
-    let bezier = CubicBezierShape::from_points_stroke([p0, c1, c2, p3], false, color, stroke);
+    let bezier = CubicBezierShape::from_points_stroke(
+        bezier_points(p0, from_side, p3, to_side),
+        false,
+        Color32::TRANSPARENT,
+        stroke,
+    );
+    let mid = bezier.sample(0.5);
 
     ui.painter().add(Shape::CubicBezier(bezier));
-    arrow_ui(ui, to_side, to_anchor, color);
 
-    None
+    if edge.to_end() == End::Arrow {
+        arrow_ui(ui, to_side, to_anchor, color);
+    }
+
+    if edge.from_end() == End::Arrow {
+        arrow_ui(ui, from_side, from_anchor, color);
+    }
+
+    let handle = Rect::from_center_size(mid, vec2(16.0, 16.0));
+    let resp = ui
+        .interact(handle, ui.id().with(("edge", &edge.id)), Sense::click())
+        .on_hover_cursor(CursorIcon::PointingHand);
+
+    if let Some(label) = &edge.label {
+        ui.painter().text(
+            mid,
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::FontId::proportional(14.0),
+            ui.visuals().text_color(),
+        );
+    } else if resp.hovered() || selected {
+        ui.painter().circle_filled(mid, 5.0, color);
+    }
+
+    Some(resp)
+}
+
+/// The edge that is being drawn while the user drags from a node side
+pub fn connection_preview_ui(ui: &mut egui::Ui, from: Pos2, from_side: Side, to: Pos2) {
+    // point the end back at where we came from
+    let to_side = match from_side {
+        Side::Top => Side::Bottom,
+        Side::Bottom => Side::Top,
+        Side::Left => Side::Right,
+        Side::Right => Side::Left,
+    };
+
+    let color = ui.visuals().selection.stroke.color;
+    let bezier = CubicBezierShape::from_points_stroke(
+        bezier_points(from, from_side, to, to_side),
+        false,
+        Color32::TRANSPARENT,
+        Stroke::new(4.0, color),
+    );
+
+    ui.painter().add(Shape::CubicBezier(bezier));
 }
 
 /// Paint a tiny triangular “arrow”.
@@ -89,7 +204,7 @@ pub fn edge_ui(
 /// * `side`  – which edge of the box we’re attaching to
 /// * `point` – the exact spot on that edge the arrow’s tip should touch
 /// * `fill`  – colour to fill the arrow with (usually your popup’s background)
-pub fn arrow_ui(ui: &mut egui::Ui, side: &Side, point: Pos2, fill: egui::Color32) {
+pub fn arrow_ui(ui: &mut egui::Ui, side: Side, point: Pos2, fill: egui::Color32) {
     let len: f32 = 12.0; // distance from tip to base
     let width: f32 = 16.0; // length of the base
     let stroke: f32 = 1.0; // length of the base
@@ -124,61 +239,451 @@ pub fn arrow_ui(ui: &mut egui::Ui, side: &Side, point: Pos2, fill: egui::Color32
     ));
 }
 
-pub fn node_ui(ui: &mut egui::Ui, node: &Node) -> egui::Response {
-    match node {
-        Node::Text(text_node) => text_node_ui(ui, text_node),
-        Node::File(file_node) => file_node_ui(ui, file_node),
-        Node::Link(link_node) => link_node_ui(ui, link_node),
-        Node::Group(group_node) => group_node_ui(ui, group_node),
+/// The interactive parts of a node
+pub struct NodeResponse {
+    /// The body of the node, used to select and move it
+    pub body: egui::Response,
+    /// The resize handle, only there when the node is selected
+    pub resize: Option<egui::Response>,
+    /// Handles used to draw edges from each side of the node
+    pub sides: Vec<(Side, egui::Response)>,
+    /// The contents of the node were edited
+    pub edited: bool,
+}
+
+pub fn node_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    node: &mut Node,
+    selected: bool,
+    editing: bool,
+) -> NodeResponse {
+    let rect = node_rect(node);
+    let hovered = ui.rect_contains_pointer(rect.expand(SIDE_HANDLE_RADIUS * 2.0));
+    let embed = node.url.as_deref().and_then(Embed::from_url);
+
+    node_frame_ui(ui, node, rect, selected);
+
+    let mut edited = false;
+    if !editing {
+        node_contents_ui(ui, node, rect);
+        if let (NodeKind::Link, Some(embed)) = (&node.kind, &embed) {
+            embed_ui(ui, note_context, txn, &node.id, embed, rect);
+        }
+    }
+
+    let body = ui.interact(
+        rect,
+        ui.id().with(("node", &node.id)),
+        Sense::click_and_drag(),
+    );
+
+    if editing {
+        edited = node_editor_ui(ui, node, rect);
+    } else if let (NodeKind::Link, Some(url), None) = (&node.kind, &node.url, &embed) {
+        // links go on top of the body so that they can be clicked
+        let inner = rect.shrink(16.0);
+        ui.put(
+            Rect::from_min_size(inner.min, vec2(inner.width(), 20.0)),
+            egui::Hyperlink::from_label_and_text(url.as_str(), url.as_str()),
+        );
+    }
+
+    let resize = selected.then(|| {
+        let handle = Rect::from_min_max(
+            rect.max - vec2(RESIZE_HANDLE_SIZE, RESIZE_HANDLE_SIZE),
+            rect.max,
+        );
+        let resp = ui
+            .interact(handle, ui.id().with(("resize", &node.id)), Sense::drag())
+            .on_hover_cursor(CursorIcon::ResizeNwSe);
+
+        let color = ui.visuals().selection.stroke.color;
+        ui.painter().line_segment(
+            [handle.right_top(), handle.left_bottom()],
+            Stroke::new(2.0, color),
+        );
+        resp
+    });
+
+    let mut sides = vec![];
+    if selected || hovered {
+        let color = ui.visuals().selection.stroke.color;
+        for side in Side::ALL {
+            let center = side_point(side, rect);
+            let handle = Rect::from_center_size(center, vec2(1.0, 1.0) * SIDE_HANDLE_RADIUS * 2.0);
+            let resp = ui
+                .interact(
+                    handle,
+                    ui.id().with(("side", &node.id, side)),
+                    Sense::drag(),
+                )
+                .on_hover_cursor(CursorIcon::Crosshair);
+
+            let radius = if resp.hovered() {
+                SIDE_HANDLE_RADIUS * 1.3
+            } else {
+                SIDE_HANDLE_RADIUS
+            };
+            ui.painter().circle(
+                center,
+                radius,
+                ui.visuals().extreme_bg_color,
+                Stroke::new(2.0, color),
+            );
+
+            sides.push((side, resp));
+        }
+    }
+
+    NodeResponse {
+        body,
+        resize,
+        sides,
+        edited,
+    }
+}
+
+fn node_frame_ui(ui: &mut egui::Ui, node: &Node, rect: Rect, selected: bool) {
+    let color = node.color.as_deref().and_then(canvas_color);
+
+    let stroke = if selected {
+        Stroke::new(3.0, ui.visuals().selection.stroke.color)
+    } else {
+        Stroke::new(
+            2.0,
+            color.unwrap_or(ui.visuals().noninteractive().bg_stroke.color),
+        )
+    };
+
+    let fill = match node.kind {
+        // groups are see-through so that their children are visible
+        NodeKind::Group => color
+            .unwrap_or(ui.visuals().noninteractive().weak_bg_fill)
+            .gamma_multiply(0.15),
+        _ => ui.visuals().noninteractive().weak_bg_fill,
+    };
+
+    ui.painter().rect(
+        rect,
+        egui::CornerRadius::same(10),
+        fill,
+        stroke,
+        egui::StrokeKind::Inside,
+    );
+
+    if let (NodeKind::Group, Some(label)) = (&node.kind, &node.label) {
+        ui.painter().text(
+            rect.left_top() - vec2(0.0, 4.0),
+            egui::Align2::LEFT_BOTTOM,
+            label,
+            egui::FontId::proportional(18.0),
+            ui.visuals().text_color(),
+        );
+    }
+}
+
+fn node_contents_ui(ui: &mut egui::Ui, node: &Node, rect: Rect) {
+    let inner = rect.shrink(16.0);
+
+    match &node.kind {
+        NodeKind::Text => {
+            let text = node.text.as_deref().unwrap_or_default();
+            ui.put(inner, |ui: &mut egui::Ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt(("node-scroll", &node.id))
+                    .show(ui, |ui| {
+                        ui.with_layout(egui::Layout::left_to_right(Align::Min), |ui| {
+                            ui.add(
+                                Label::new(text)
+                                    .wrap_mode(TextWrapMode::Wrap)
+                                    .selectable(false),
+                            )
+                        })
+                    })
+                    .inner
+                    .response
+            });
+        }
+
+        NodeKind::File => {
+            let mut label = node.file.clone().unwrap_or_default();
+            if let Some(subpath) = &node.subpath {
+                label.push_str(subpath);
+            }
+            ui.put(
+                inner,
+                Label::new(label)
+                    .wrap_mode(TextWrapMode::Wrap)
+                    .selectable(false),
+            );
+        }
+
+        // links and embeds are handled in node_ui
+        NodeKind::Link | NodeKind::Group => {}
+
+        NodeKind::Other(typ) => {
+            ui.put(
+                inner,
+                Label::new(egui::RichText::new(typ.as_str()).weak()).selectable(false),
+            );
+        }
     }
 }
 
-fn text_node_ui(ui: &mut egui::Ui, node: &TextNode) -> egui::Response {
-    node_box_ui(ui, node.node(), |ui| {
+/// A note or profile from a `nostr:` link node. These sit below the node
+/// body, so they aren't interactive. Clicking the node opens them instead.
+fn embed_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    node_id: &str,
+    embed: &Embed,
+    rect: Rect,
+) {
+    let inner = rect.shrink(8.0);
+
+    ui.put(inner, |ui: &mut egui::Ui| {
         egui::ScrollArea::vertical()
-            .show(ui, |ui| {
-                ui.with_layout(egui::Layout::left_to_right(Align::Min), |ui| {
-                    ui.add(Label::new(node.text()).wrap_mode(TextWrapMode::Wrap))
-                })
+            .id_salt(("embed-scroll", node_id))
+            .show(ui, |ui| match embed {
+                Embed::Note { id, .. } => match note_context.ndb.get_note_by_id(txn, id.bytes()) {
+                    Ok(note) => {
+                        NoteView::new(note_context, &note, NoteOptions::default())
+                            .preview_style()
+                            .options_button(false)
+                            .show(ui)
+                            .response
+                    }
+                    Err(_) => {
+                        note_context.unknown_ids.add_note_id_if_missing(
+                            note_context.ndb,
+                            txn,
+                            id.bytes(),
+                        );
+                        ui.weak(tr!(
+                            note_context.i18n,
+                            "Loading note...",
+                            "Shown in a notebook while an embedded note is being fetched"
+                        ))
+                    }
+                },
+                Embed::Profile { pubkey, .. } => {
+                    match note_context.ndb.get_profile_by_pubkey(txn, pubkey.bytes()) {
+                        Ok(profile) => {
+                            let nip05_status = note_context
+                                .nip05_cache
+                                .profile_status(pubkey, Some(&profile));
+                            ui.add(
+                                ProfilePreview::new(
+                                    &profile,
+                                    note_context.img_cache,
+                                    note_context.jobs,
+                                )
+                                .nip05_status(note_context.i18n, nip05_status),
+                            )
+                        }
+                        Err(_) => {
+                            note_context.unknown_ids.add_pubkey_if_missing(
+                                note_context.ndb,
+                                txn,
+                                pubkey.bytes(),
+                            );
+                            ui.weak(tr!(
+                                note_context.i18n,
+                                "Loading profile...",
+                                "Shown in a notebook while an embedded profile is being fetched"
+                            ))
+                        }
+                    }
+                }
             })
             .inner
-            .response
-    })
+    });
 }
 
-fn file_node_ui(ui: &mut egui::Ui, node: &FileNode) -> egui::Response {
-    node_box_ui(ui, node.node(), |ui| ui.label("file node"))
+/// Edit the main field of a node. Returns true if it was changed.
+fn node_editor_ui(ui: &mut egui::Ui, node: &mut Node, rect: Rect) -> bool {
+    let inner = rect.shrink(12.0);
+    let id = ui.id().with(("editor", &node.id));
+
+    let resp = match node.kind {
+        NodeKind::Text => {
+            let text = node.text.get_or_insert_with(String::new);
+            ui.put(
+                inner,
+                egui::TextEdit::multiline(text)
+                    .id(id)
+                    .frame(false)
+                    .desired_width(inner.width()),
+            )
+        }
+        NodeKind::Link => {
+            let url = node.url.get_or_insert_with(String::new);
+            ui.put(
+                Rect::from_min_size(inner.min, vec2(inner.width(), 24.0)),
+                egui::TextEdit::singleline(url).id(id).hint_text("https://"),
+            )
+        }
+        NodeKind::File => {
+            let file = node.file.get_or_insert_with(String::new);
+            ui.put(
+                Rect::from_min_size(inner.min, vec2(inner.width(), 24.0)),
+                egui::TextEdit::singleline(file).id(id),
+            )
+        }
+        NodeKind::Group => {
+            let label = node.label.get_or_insert_with(String::new);
+            // the label sits above the group
+            let label_rect =
+                Rect::from_min_size(rect.min - vec2(0.0, 28.0), vec2(rect.width(), 24.0));
+            ui.put(label_rect, egui::TextEdit::singleline(label).id(id))
+        }
+        NodeKind::Other(_) => return false,
+    };
+
+    // focus the editor when we start editing
+    if !resp.has_focus() && !resp.lost_focus() && ui.memory(|m| m.focused().is_none()) {
+        resp.request_focus();
+    }
+
+    resp.changed()
 }
 
-fn link_node_ui(ui: &mut egui::Ui, node: &LinkNode) -> egui::Response {
-    node_box_ui(ui, node.node(), |ui| ui.label("link node"))
+/// Actions from the notebook toolbar
+pub enum ToolbarAction {
+    New,
+    Open(String),
+    Save,
+    Undo,
+    Redo,
+    AddNode(NodeKind),
+    Delete,
 }
 
-fn group_node_ui(ui: &mut egui::Ui, node: &GroupNode) -> egui::Response {
-    node_box_ui(ui, node.node(), |ui| ui.label("group node"))
+pub struct Toolbar<'a> {
+    pub file: Option<&'a str>,
+    pub files: &'a [String],
+    pub dirty: bool,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub has_selection: bool,
+    pub error: Option<&'a str>,
 }
 
-fn node_box_ui(
-    ui: &mut egui::Ui,
-    node: &GenericNode,
-    contents: impl FnOnce(&mut egui::Ui) -> egui::Response,
-) -> egui::Response {
-    let pos = node_rect(node);
-
-    ui.put(pos, |ui: &mut egui::Ui| {
-        egui::Frame::default()
-            .fill(ui.visuals().noninteractive().weak_bg_fill)
-            .inner_margin(egui::Margin::same(16))
-            .corner_radius(egui::CornerRadius::same(10))
-            .stroke(egui::Stroke::new(
-                2.0,
-                ui.visuals().noninteractive().bg_stroke.color,
-            ))
-            .show(ui, |ui| {
-                let rect = ui.available_rect_before_wrap();
-                ui.allocate_at_least(ui.available_size(), egui::Sense::click());
-                ui.put(rect, contents);
-            })
-            .response
-    })
+impl Toolbar<'_> {
+    pub fn ui(&self, i18n: &mut Localization, ui: &mut egui::Ui) -> Option<ToolbarAction> {
+        let mut action: Option<ToolbarAction> = None;
+
+        ui.horizontal(|ui| {
+            let current = self.file.map(str::to_owned).unwrap_or_else(|| {
+                tr!(
+                    i18n,
+                    "Demo notebook",
+                    "Name of the demo notebook that isn't saved yet"
+                )
+            });
+            let current = if self.dirty {
+                format!("{current} *")
+            } else {
+                current
+            };
+
+            egui::ComboBox::from_id_salt("notebook-files")
+                .selected_text(current)
+                .show_ui(ui, |ui| {
+                    for file in self.files {
+                        if ui
+                            .selectable_label(self.file == Some(file.as_str()), file)
+                            .clicked()
+                        {
+                            action = Some(ToolbarAction::Open(file.clone()));
+                        }
+                    }
+                });
+
+            if ui
+                .button(tr!(i18n, "New", "Button to create a new notebook"))
+                .clicked()
+            {
+                action = Some(ToolbarAction::New);
+            }
+
+            if ui
+                .button(tr!(i18n, "Save", "Button to save the current notebook"))
+                .clicked()
+            {
+                action = Some(ToolbarAction::Save);
+            }
+
+            ui.separator();
+
+            let node_buttons = [
+                (
+                    NodeKind::Text,
+                    tr!(i18n, "Text", "Button to add a text node to a notebook"),
+                ),
+                (
+                    NodeKind::Link,
+                    tr!(i18n, "Link", "Button to add a link node to a notebook"),
+                ),
+                (
+                    NodeKind::File,
+                    tr!(i18n, "File", "Button to add a file node to a notebook"),
+                ),
+                (
+                    NodeKind::Group,
+                    tr!(i18n, "Group", "Button to add a group node to a notebook"),
+                ),
+            ];
+            for (kind, label) in node_buttons {
+                if ui.button(format!("+ {label}")).clicked() {
+                    action = Some(ToolbarAction::AddNode(kind));
+                }
+            }
+
+            ui.separator();
+
+            if ui
+                .add_enabled(
+                    self.can_undo,
+                    egui::Button::new(tr!(i18n, "Undo", "Button to undo the last notebook edit")),
+                )
+                .clicked()
+            {
+                action = Some(ToolbarAction::Undo);
+            }
+
+            if ui
+                .add_enabled(
+                    self.can_redo,
+                    egui::Button::new(tr!(i18n, "Redo", "Button to redo a notebook edit")),
+                )
+                .clicked()
+            {
+                action = Some(ToolbarAction::Redo);
+            }
+
+            if ui
+                .add_enabled(
+                    self.has_selection,
+                    egui::Button::new(tr!(
+                        i18n,
+                        "Delete",
+                        "Button to delete the selected notebook node or edge"
+                    )),
+                )
+                .clicked()
+            {
+                action = Some(ToolbarAction::Delete);
+            }
+
+            if let Some(err) = self.error {
+                ui.label(egui::RichText::new(err).color(ui.visuals().error_fg_color));
+            }
+        });
+
+        action
+    }
 }
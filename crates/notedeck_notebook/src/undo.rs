@@ -0,0 +1,111 @@
+use crate::canvas::Canvas;
+
+/// How many edits we remember
+const MAX_UNDO: usize = 100;
+
+/// Snapshot based undo/redo. Take a [`UndoHistory::checkpoint`] of the
+/// canvas right before changing it.
+#[derive(Default)]
+pub struct UndoHistory {
+    undo: Vec<Canvas>,
+    redo: Vec<Canvas>,
+}
+
+impl UndoHistory {
+    pub fn checkpoint(&mut self, canvas: &Canvas) {
+        self.undo.push(canvas.clone());
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Restore the last checkpoint. Returns false if there was nothing
+    /// to undo.
+    pub fn undo(&mut self, canvas: &mut Canvas) -> bool {
+        let Some(prev) = self.undo.pop() else {
+            return false;
+        };
+
+        self.redo.push(std::mem::replace(canvas, prev));
+        true
+    }
+
+    pub fn redo(&mut self, canvas: &mut Canvas) -> bool {
+        let Some(next) = self.redo.pop() else {
+            return false;
+        };
+
+        self.undo.push(std::mem::replace(canvas, next));
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Node;
+
+    fn canvas_with(texts: &[&str]) -> Canvas {
+        let mut canvas = Canvas::default();
+        for (i, text) in texts.iter().enumerate() {
+            canvas.add_node(Node::text(i.to_string(), 0, 0, text.to_string()));
+        }
+        canvas
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = UndoHistory::default();
+        let mut canvas = canvas_with(&["a"]);
+
+        history.checkpoint(&canvas);
+        canvas.add_node(Node::text("1".to_string(), 0, 0, "b".to_string()));
+        assert_eq!(canvas.nodes.len(), 2);
+
+        assert!(history.undo(&mut canvas));
+        assert_eq!(canvas, canvas_with(&["a"]));
+        assert!(!history.undo(&mut canvas));
+
+        assert!(history.redo(&mut canvas));
+        assert_eq!(canvas, canvas_with(&["a", "b"]));
+        assert!(!history.redo(&mut canvas));
+    }
+
+    #[test]
+    fn test_checkpoint_clears_redo() {
+        let mut history = UndoHistory::default();
+        let mut canvas = canvas_with(&["a"]);
+
+        history.checkpoint(&canvas);
+        canvas.remove_node("0");
+        history.undo(&mut canvas);
+        assert!(history.can_redo());
+
+        history.checkpoint(&canvas);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut history = UndoHistory::default();
+        let canvas = Canvas::default();
+        for _ in 0..(MAX_UNDO + 5) {
+            history.checkpoint(&canvas);
+        }
+        assert_eq!(history.undo.len(), MAX_UNDO);
+    }
+}
@@ -0,0 +1,103 @@
+//! Rendering NIP-30 custom emoji
+
+use egui::{Label, RichText, Sense};
+use notedeck::emoji::{split_emoji, CustomEmoji, EmojiText};
+use notedeck::media::AnimationMode;
+use notedeck::{tr, ImageType, Images, Localization, MediaJobSender};
+
+const PICKER_EMOJI_SIZE: f32 = 28.0;
+const PICKER_COLUMNS: usize = 8;
+
+/// A custom emoji at `size`. Shows its `:shortcode:` until the image loads.
+pub fn custom_emoji_ui(
+    ui: &mut egui::Ui,
+    images: &mut Images,
+    jobs: &MediaJobSender,
+    emoji: &CustomEmoji,
+    size: f32,
+) -> egui::Response {
+    let texture = images.latest_texture(
+        jobs,
+        ui,
+        &emoji.url,
+        ImageType::Content(None),
+        AnimationMode::Reactive,
+    );
+
+    let resp = match texture {
+        Some(texture) => ui.add(
+            egui::Image::new(texture)
+                .fit_to_exact_size(egui::vec2(size, size))
+                .maintain_aspect_ratio(true),
+        ),
+        None => ui.add(Label::new(RichText::new(emoji.code()).weak()).selectable(false)),
+    };
+
+    resp.on_hover_text(emoji.code())
+}
+
+/// Text with the custom emoji it uses drawn inline. Meant for wrapping
+/// horizontal layouts. `style` styles the plain text parts.
+pub fn emoji_text_ui(
+    ui: &mut egui::Ui,
+    images: &mut Images,
+    jobs: &MediaJobSender,
+    text: &str,
+    emojis: &[CustomEmoji],
+    style: impl Fn(&str) -> RichText,
+) {
+    for part in split_emoji(text, emojis) {
+        match part {
+            EmojiText::Text(text) => {
+                ui.add(Label::new(style(text)).wrap());
+            }
+            EmojiText::Emoji(emoji) => {
+                let size = ui.fonts(|fonts| style("").font_height(fonts, ui.style()));
+                custom_emoji_ui(ui, images, jobs, emoji, size);
+            }
+        }
+    }
+}
+
+/// A grid of custom emoji to pick from
+pub fn custom_emoji_picker(
+    ui: &mut egui::Ui,
+    i18n: &mut Localization,
+    images: &mut Images,
+    jobs: &MediaJobSender,
+    emojis: &[CustomEmoji],
+) -> Option<CustomEmoji> {
+    if emojis.is_empty() {
+        ui.weak(tr!(
+            i18n,
+            "No custom emoji yet. Add some to your emoji list in another client.",
+            "Shown in the custom emoji picker when the user has no custom emoji"
+        ));
+        return None;
+    }
+
+    let mut picked = None;
+    egui::ScrollArea::vertical()
+        .max_height(PICKER_EMOJI_SIZE * 6.0)
+        .show(ui, |ui| {
+            egui::Grid::new("custom-emoji-picker")
+                .spacing(egui::vec2(4.0, 4.0))
+                .show(ui, |ui| {
+                    for (i, emoji) in emojis.iter().enumerate() {
+                        let resp = custom_emoji_ui(ui, images, jobs, emoji, PICKER_EMOJI_SIZE)
+                            .interact(Sense::click())
+                            .on_hover_cursor(egui::CursorIcon::PointingHand);
+
+                        if resp.clicked() {
+                            picked = Some(emoji.clone());
+                        }
+
+                        if (i + 1) % PICKER_COLUMNS == 0 {
+                            ui.end_row();
+                        }
+                    }
+                });
+        });
+
+    picked
+}
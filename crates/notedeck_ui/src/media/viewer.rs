@@ -1,7 +1,9 @@
 use bitflags::bitflags;
-use egui::{emath::TSTransform, pos2, Color32, Rangef, Rect};
+use egui::{emath::TSTransform, pos2, Color32, Rangef, Rect, TextureHandle};
 use notedeck::media::{AnimationMode, MediaInfo, ViewMediaInfo};
-use notedeck::{ImageType, Images, MediaJobSender};
+use notedeck::{ImageType, Images, MediaCacheType, MediaJobSender, PlayerState};
+
+use crate::note::media::{format_position, paint_play_button};
 
 bitflags! {
     #[repr(transparent)]
@@ -191,12 +193,13 @@ impl<'a> MediaViewer<'a> {
         jobs: &MediaJobSender,
     ) -> Rect {
         // fetch image texture
-        let Some(texture) = images.latest_texture(
+        let Some(texture) = media_texture(
+            images,
             jobs,
             ui,
             &media.url,
-            ImageType::Content(None),
             AnimationMode::NoAnimation,
+            false,
         ) else {
             tracing::error!("could not get latest texture in first_image_rect");
             return Rect::ZERO;
@@ -230,15 +233,17 @@ impl<'a> MediaViewer<'a> {
             // fetch image texture
 
             // we want to continually redraw things in the gallery
-            let Some(texture) = images.latest_texture(
+            let Some(texture) = media_texture(
+                images,
                 jobs,
                 ui,
                 url,
-                ImageType::Content(None),
                 AnimationMode::Continuous { fps: None }, // media viewer has continuous rendering
+                true,
             ) else {
                 continue;
             };
+            let texture_id = texture.id();
 
             // the area the next image will be put in.
             let mut img_rect = ui.available_rect_before_wrap();
@@ -267,18 +272,111 @@ impl<'a> MediaViewer<'a> {
 
                 // Paint image
                 ui.painter().image(
-                    texture.id(),
+                    texture_id,
                     img_rect,
                     uv,
                     Color32::from_white_alpha((open_amount * 255.0) as u8),
                 );
 
+                if let Some((state, position)) = images.players.video_state(url) {
+                    video_controls(ui, images, url, img_rect, state, position);
+                }
+
                 ui.advance_cursor_after_rect(img_rect);
             }
         }
     }
 }
 
+/// The texture to show for some media. Videos play when `play` is set,
+/// showing their poster until the first frame is decoded.
+fn media_texture<'a>(
+    images: &'a mut Images,
+    jobs: &MediaJobSender,
+    ui: &mut egui::Ui,
+    url: &str,
+    animation_mode: AnimationMode,
+    play: bool,
+) -> Option<&'a TextureHandle> {
+    let Some(media) = images.get_renderable_media(url) else {
+        return images.latest_texture(jobs, ui, url, ImageType::Content(None), animation_mode);
+    };
+
+    if media.media_type == MediaCacheType::Video {
+        if play {
+            images.players.play_video(ui.ctx(), url);
+        }
+
+        if images.players.video_texture(url).is_some() {
+            return images.players.video_texture(url);
+        }
+    }
+
+    let (texture_url, _) = media.texture_source();
+    images.latest_texture(
+        jobs,
+        ui,
+        texture_url,
+        ImageType::Content(None),
+        animation_mode,
+    )
+}
+
+/// Click a video to pause or resume it. We show a ▶ while it isn't
+/// playing and how far into it we are.
+fn video_controls(
+    ui: &mut egui::Ui,
+    images: &mut Images,
+    url: &str,
+    rect: Rect,
+    state: PlayerState,
+    position: std::time::Duration,
+) {
+    let resp = ui
+        .interact(
+            rect,
+            ui.id().with(("video-controls", url)),
+            egui::Sense::click(),
+        )
+        .on_hover_cursor(egui::CursorIcon::PointingHand);
+    if resp.clicked() {
+        images.players.toggle_video(url);
+    }
+
+    let painter = ui.painter_at(rect);
+    let label_pos = rect.left_bottom() + egui::vec2(8.0, -8.0);
+    let font = egui::FontId::proportional(14.0);
+
+    match state {
+        PlayerState::Playing => {}
+        PlayerState::Loading => {
+            ui.put(
+                Rect::from_center_size(rect.center(), egui::vec2(32.0, 32.0)),
+                egui::Spinner::new(),
+            );
+        }
+        PlayerState::Paused | PlayerState::Ended => paint_play_button(ui, rect),
+        PlayerState::Failed(err) => {
+            painter.text(
+                label_pos,
+                egui::Align2::LEFT_BOTTOM,
+                err,
+                font,
+                Color32::WHITE,
+            );
+            return;
+        }
+    }
+
+    painter.text(
+        label_pos,
+        egui::Align2::LEFT_BOTTOM,
+        format_position(position),
+        font,
+        Color32::WHITE,
+    );
+}
+
 /// Helper: lerp a TSTransform (uniform scale + translation)
 fn lerp_ts(a: TSTransform, b: TSTransform, t: f32) -> TSTransform {
     let s = egui::lerp(a.scaling..=b.scaling, t);
@@ -1,13 +1,27 @@
-use super::media::image_carousel;
+use super::highlight::highlight_ui;
+use super::media::{audio_player, image_carousel};
+use super::poll::poll_ui;
+use super::translation::translation_ui;
 use crate::{
     note::{NoteAction, NoteOptions, NoteResponse, NoteView},
     secondary_label,
 };
 use egui::{Color32, Hyperlink, Label, RichText};
+use enostr::{NoteId, Pubkey, RelayPool};
 use nostrdb::{BlockType, Mention, Note, NoteKey, Transaction};
-use notedeck::Localization;
-use notedeck::RenderableMedia;
-use notedeck::{time_format, update_imeta_blurhashes, NoteCache, NoteContext, NotedeckTextStyle};
+use notedeck::highlight::HIGHLIGHT_KIND;
+use notedeck::markdown::MarkdownState;
+use notedeck::media::latest::{MediaRenderState, ObfuscatedTexture};
+use notedeck::note::{content_warning, event_tag};
+use notedeck::poll::POLL_KIND;
+use notedeck::{
+    media::AnimationMode, tr, ImageType, Localization, NoteAddress, ObfuscationType,
+    RenderableMedia,
+};
+use notedeck::{
+    time_format, update_imeta_blurhashes, update_imeta_media, MediaCacheType, NoteCache,
+    NoteContext, NotedeckTextStyle,
+};
 use tracing::warn;
 
 pub struct NoteContents<'a, 'd> {
@@ -76,7 +90,7 @@ pub fn render_note_preview(
 ) -> NoteResponse {
     let note = if let Ok(note) = note_context.ndb.get_note_by_id(txn, id) {
         // TODO: support other preview kinds
-        if note.kind() == 1 {
+        if matches!(note.kind(), 1 | POLL_KIND | HIGHLIGHT_KIND) {
             note
         } else {
             return NoteResponse::new(ui.colored_label(
@@ -107,6 +121,281 @@ pub fn render_note_preview(
         .show(ui)
 }
 
+/// Render an inline preview of an addressable note (naddr), like an
+/// article, a list or a live event
+fn render_address_preview(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    address: &NoteAddress,
+    bech32: &str,
+    parent: NoteKey,
+    note_options: NoteOptions,
+) -> NoteResponse {
+    let Some(note) = address.get_note(note_context.ndb, txn) else {
+        note_context
+            .unknown_ids
+            .add_address_if_missing(note_context.ndb, txn, address);
+
+        let naddr = abbreviate_bech32(bech32);
+        return NoteResponse::new(ui.weak(tr!(
+            note_context.i18n,
+            "Loading {naddr}…",
+            "Placeholder while a mentioned article, list or live event is fetched",
+            naddr = naddr
+        )));
+    };
+
+    if note.kind() == 1 {
+        return NoteView::new(note_context, &note, note_options)
+            .preview_style()
+            .parent(parent)
+            .show(ui);
+    }
+
+    let title = event_tag(&note, "title")
+        .or_else(|| event_tag(&note, "name"))
+        .unwrap_or(address.identifier.as_str());
+    let summary = event_tag(&note, "summary").or_else(|| event_tag(&note, "description"));
+    let kind_label = address_kind_label(note_context.i18n, note.kind());
+
+    let mut author_action = None;
+    let resp = egui::Frame::new()
+        .fill(ui.visuals().noninteractive().weak_bg_fill)
+        .inner_margin(egui::Margin::same(8))
+        .outer_margin(egui::Margin::symmetric(0, 8))
+        .corner_radius(egui::CornerRadius::same(10))
+        .stroke(egui::Stroke::new(
+            1.0,
+            ui.visuals().noninteractive().bg_stroke.color,
+        ))
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+
+            secondary_label(ui, kind_label);
+            ui.add(
+                Label::new(
+                    RichText::new(title)
+                        .strong()
+                        .text_style(NotedeckTextStyle::NoteBody.text_style()),
+                )
+                .wrap(),
+            );
+
+            if let Some(summary) = summary {
+                ui.add(Label::new(RichText::new(summary).weak()).truncate());
+            }
+
+            author_action = crate::Mention::new(
+                note_context.ndb,
+                note_context.img_cache,
+                note_context.jobs,
+                txn,
+                note.pubkey(),
+            )
+            .show(ui);
+        })
+        .response
+        .interact(egui::Sense::click())
+        .on_hover_cursor(egui::CursorIcon::PointingHand);
+
+    let action = author_action.or_else(|| {
+        resp.clicked()
+            .then(|| NoteAction::note(NoteId::new(*note.id())))
+    });
+
+    NoteResponse::new(resp).with_action(action)
+}
+
+/// An OpenGraph preview card for a link, once we have one. Clicking it
+/// opens the link.
+fn render_link_preview(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    url: &str,
+    note_options: NoteOptions,
+) {
+    let Some(preview) = note_context.img_cache.link_previews.get(ui.ctx(), url) else {
+        return;
+    };
+    let preview = preview.clone();
+
+    // the image comes from wherever the page says, so only load it for
+    // people we trust with media
+    let show_image = note_options.contains(NoteOptions::TrustMedia)
+        && !note_options.contains(NoteOptions::HideMedia);
+    let site_name = preview.site_name.clone().unwrap_or_else(|| {
+        url.trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("www.")
+            .split('/')
+            .next()
+            .unwrap_or(url)
+            .to_owned()
+    });
+
+    let resp = egui::Frame::new()
+        .fill(ui.visuals().noninteractive().weak_bg_fill)
+        .inner_margin(egui::Margin::same(8))
+        .outer_margin(egui::Margin::symmetric(0, 8))
+        .corner_radius(egui::CornerRadius::same(10))
+        .stroke(egui::Stroke::new(
+            1.0,
+            ui.visuals().noninteractive().bg_stroke.color,
+        ))
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+
+            ui.horizontal(|ui| {
+                let image = preview.image.as_deref().filter(|_| show_image);
+                if let Some(image) = image {
+                    link_preview_image(ui, note_context, image);
+                }
+
+                ui.vertical(|ui| {
+                    secondary_label(ui, &site_name);
+
+                    if let Some(title) = &preview.title {
+                        ui.add(
+                            Label::new(
+                                RichText::new(title)
+                                    .strong()
+                                    .text_style(NotedeckTextStyle::NoteBody.text_style()),
+                            )
+                            .truncate(),
+                        );
+                    }
+
+                    if let Some(description) = &preview.description {
+                        ui.add(Label::new(RichText::new(description).weak()).truncate());
+                    }
+                });
+            });
+        })
+        .response
+        .interact(egui::Sense::click())
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .on_hover_text(url);
+
+    if resp.clicked() {
+        ui.ctx().open_url(egui::OpenUrl::new_tab(url));
+    }
+}
+
+fn link_preview_image(ui: &mut egui::Ui, note_context: &mut NoteContext, url: &str) {
+    const SIZE: f32 = 64.0;
+
+    let texture = note_context.img_cache.latest_texture(
+        note_context.jobs,
+        ui,
+        url,
+        ImageType::Content(None),
+        AnimationMode::NoAnimation,
+    );
+
+    let Some(texture) = texture else {
+        // still loading, or not an image we can show
+        return;
+    };
+
+    ui.add(
+        egui::Image::new(texture)
+            .fit_to_exact_size(egui::vec2(SIZE, SIZE))
+            .maintain_aspect_ratio(true)
+            .corner_radius(6.0),
+    );
+}
+
+fn address_kind_label(i18n: &mut Localization, kind: u32) -> String {
+    match kind {
+        30023 => tr!(i18n, "Article", "Label for a long-form article"),
+        30024 => tr!(i18n, "Draft article", "Label for a draft long-form article"),
+        30000..=30005 | 30015 | 30030 | 39089 => {
+            tr!(
+                i18n,
+                "List",
+                "Label for a list of people, notes or other things"
+            )
+        }
+        30311 => tr!(i18n, "Live event", "Label for a live stream or live event"),
+        31922 | 31923 => tr!(i18n, "Calendar event", "Label for a calendar event"),
+        _ => tr!(
+            i18n,
+            "Kind {kind}",
+            "Label for an addressable note of a kind we don't know",
+            kind = kind
+        ),
+    }
+}
+
+/// A mention of a note, as a link, for when we aren't showing previews
+fn note_link_ui(ui: &mut egui::Ui, id: &[u8; 32], bech32: &str) -> Option<NoteAction> {
+    let resp = ui
+        .colored_label(
+            ui.visuals().hyperlink_color,
+            RichText::new(abbreviate_bech32(bech32))
+                .text_style(NotedeckTextStyle::NoteBody.text_style()),
+        )
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .on_hover_text(bech32);
+
+    resp.clicked().then(|| NoteAction::note(NoteId::new(*id)))
+}
+
+/// A mentioned relay (nrelay). Clicking it adds it to our relays if it
+/// isn't one of them already.
+fn relay_chip_ui(
+    ui: &mut egui::Ui,
+    i18n: &mut Localization,
+    pool: &RelayPool,
+    url: &str,
+) -> Option<NoteAction> {
+    let known = pool.has(&RelayPool::canonicalize_url(url.to_owned()));
+    let host = url
+        .trim_start_matches("wss://")
+        .trim_start_matches("ws://")
+        .trim_end_matches('/');
+
+    let label = if known {
+        format!("🔗 {host}")
+    } else {
+        format!("🔗 {host} +")
+    };
+
+    let resp = ui.add(
+        egui::Button::new(RichText::new(label).color(ui.visuals().hyperlink_color))
+            .small()
+            .corner_radius(8.0),
+    );
+
+    if known {
+        resp.on_hover_text(tr!(
+            i18n,
+            "{relay} is one of your relays",
+            "Tooltip for a mentioned relay we already use",
+            relay = url
+        ));
+        return None;
+    }
+
+    resp.on_hover_text(tr!(
+        i18n,
+        "Add {relay} to your relays",
+        "Tooltip for a mentioned relay that can be added to the user's relays",
+        relay = url
+    ))
+    .clicked()
+    .then(|| NoteAction::AddRelay(url.to_owned()))
+}
+
+/// note1abcdefgh…uvwxyz
+fn abbreviate_bech32(bech32: &str) -> String {
+    if bech32.len() <= 24 {
+        return bech32.to_owned();
+    }
+    format!("{}…{}", &bech32[..14], &bech32[bech32.len() - 6..])
+}
+
 /// Render note contents and surrounding info (client name, full date timestamp)
 fn render_note_contents(
     ui: &mut egui::Ui,
@@ -115,7 +404,35 @@ fn render_note_contents(
     note: &Note,
     options: NoteOptions,
 ) -> NoteResponse {
-    let response = render_undecorated_note_contents(ui, note_context, txn, note, options);
+    if let Some(reason) = content_warning(note) {
+        let revealed_id = egui::Id::new(("content-warning-revealed", note.id()));
+        let revealed = !options.contains(NoteOptions::HideContentWarned)
+            && (options.contains(NoteOptions::ShowContentWarned)
+                || ui.data(|d| d.get_temp(revealed_id)) == Some(true));
+
+        if !revealed {
+            let resp = content_warning_ui(ui, note_context, txn, note, reason, options);
+            if resp.clicked() && !options.contains(NoteOptions::HideContentWarned) {
+                ui.data_mut(|d| d.insert_temp(revealed_id, true));
+            }
+            return NoteResponse::new(resp);
+        }
+    }
+
+    let mut response = if note.kind() == HIGHLIGHT_KIND {
+        highlight_ui(ui, note_context, txn, note)
+    } else {
+        render_undecorated_note_contents(ui, note_context, txn, note, options)
+    };
+
+    if note.kind() == POLL_KIND {
+        let poll_action = poll_ui(ui, note_context, txn, note);
+        response.action = response.action.or(poll_action);
+    }
+
+    if !options.intersects(NoteOptions::IsPreview | NoteOptions::NotificationPreview) {
+        translation_ui(ui, note_context, note);
+    }
 
     ui.horizontal_wrapped(|ui| {
         note_bottom_metadata_ui(
@@ -130,6 +447,121 @@ fn render_note_contents(
     response
 }
 
+/// The banner a note with a content warning is hidden behind, with the
+/// blurhashes of its images. Clicking it reveals the note, unless the user
+/// always hides them.
+fn content_warning_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    note: &Note,
+    reason: &str,
+    options: NoteOptions,
+) -> egui::Response {
+    let can_reveal = !options.contains(NoteOptions::HideContentWarned);
+
+    let frame = egui::Frame::new()
+        .fill(ui.visuals().noninteractive().weak_bg_fill)
+        .inner_margin(egui::Margin::same(8))
+        .outer_margin(egui::Margin::symmetric(0, 4))
+        .corner_radius(egui::CornerRadius::same(10))
+        .stroke(egui::Stroke::new(
+            1.0,
+            ui.visuals().noninteractive().bg_stroke.color,
+        ))
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+
+            ui.label(
+                RichText::new(tr!(
+                    note_context.i18n,
+                    "⚠ Content warning",
+                    "Heading of the banner hiding a note with a content warning"
+                ))
+                .strong(),
+            );
+
+            if !reason.is_empty() {
+                ui.add(Label::new(RichText::new(reason).weak()).wrap());
+            }
+
+            if !options.contains(NoteOptions::Textmode) && !options.contains(NoteOptions::HideMedia)
+            {
+                content_warning_blurs(ui, note_context, txn, note);
+            }
+
+            if can_reveal {
+                secondary_label(
+                    ui,
+                    tr!(
+                        note_context.i18n,
+                        "Tap to show",
+                        "Hint to reveal a note hidden behind a content warning"
+                    ),
+                );
+            }
+        })
+        .response;
+
+    if can_reveal {
+        frame
+            .interact(egui::Sense::click())
+            .on_hover_cursor(egui::CursorIcon::PointingHand)
+    } else {
+        frame
+    }
+}
+
+/// The blurhashes of a hidden note's images, so it's clear there is media
+/// without showing any of it
+fn content_warning_blurs(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    note: &Note,
+) {
+    let Some(note_key) = note.key() else {
+        return;
+    };
+    let Ok(blocks) = note_context.ndb.get_blocks_by_key(txn, note_key) else {
+        return;
+    };
+
+    update_imeta_blurhashes(note, &mut note_context.img_cache.metadata);
+
+    let medias: Vec<RenderableMedia> = blocks
+        .iter(note)
+        .filter(|block| matches!(block.blocktype(), BlockType::Url))
+        .filter_map(|block| note_context.img_cache.get_renderable_media(block.as_str()))
+        .filter(|media| matches!(media.obfuscation_type, ObfuscationType::Blurhash(_)))
+        .collect();
+
+    if medias.is_empty() {
+        return;
+    }
+
+    let size = egui::vec2(96.0, 96.0);
+    ui.horizontal(|ui| {
+        for media in &medias {
+            let state = note_context.img_cache.untrusted_texture_loader().latest(
+                note_context.jobs,
+                ui,
+                &media.url,
+                &media.obfuscation_type,
+                size,
+            );
+
+            if let MediaRenderState::Obfuscated(ObfuscatedTexture::Blur(texture)) = state {
+                ui.add(
+                    egui::Image::new(texture)
+                        .fit_to_exact_size(size)
+                        .corner_radius(6.0),
+                );
+            }
+        }
+    });
+}
+
 /// Client name, full timestamp, etc
 fn note_bottom_metadata_ui(
     ui: &mut egui::Ui,
@@ -162,6 +594,9 @@ fn render_undecorated_note_contents<'a>(
     let selectable = options.contains(NoteOptions::SelectableText);
     let mut note_action: Option<NoteAction> = None;
     let mut inline_note: Option<(&[u8; 32], &str)> = None;
+    let mut inline_address: Option<(NoteAddress, &str)> = None;
+    let mut link_preview: Option<&str> = None;
+    let has_previews = options.contains(NoteOptions::HasNotePreviews);
     let hide_media = options.contains(NoteOptions::HideMedia);
     let link_color = ui.visuals().hyperlink_color;
 
@@ -175,6 +610,7 @@ fn render_undecorated_note_contents<'a>(
     }
 
     let mut supported_medias: Vec<RenderableMedia> = vec![];
+    let emojis = notedeck::emoji::note_emojis(note);
 
     let response = ui.horizontal_wrapped(|ui| {
         ui.spacing_mut().item_spacing.x = 1.0;
@@ -187,7 +623,12 @@ fn render_undecorated_note_contents<'a>(
             return;
         };
 
+        let mut markdown = MarkdownState::default();
         for block in blocks.iter(note) {
+            if !matches!(block.blocktype(), BlockType::Text) {
+                markdown.other_block();
+            }
+
             match block.blocktype() {
                 BlockType::MentionBech32 => match block.as_mention().unwrap() {
                     Mention::Profile(profile) => {
@@ -222,14 +663,62 @@ fn render_undecorated_note_contents<'a>(
                         }
                     }
 
-                    Mention::Note(note) if options.contains(NoteOptions::HasNotePreviews) => {
+                    Mention::Note(note) if has_previews => {
                         inline_note = Some((note.id(), block.as_str()));
                     }
 
-                    Mention::Event(note) if options.contains(NoteOptions::HasNotePreviews) => {
+                    Mention::Event(note) if has_previews => {
                         inline_note = Some((note.id(), block.as_str()));
                     }
 
+                    Mention::Note(note) => {
+                        if let Some(act) = note_link_ui(ui, note.id(), block.as_str()) {
+                            note_action = Some(act);
+                        }
+                    }
+
+                    Mention::Event(note) => {
+                        if let Some(act) = note_link_ui(ui, note.id(), block.as_str()) {
+                            note_action = Some(act);
+                        }
+                    }
+
+                    Mention::Addr(addr) => {
+                        profiling::scope!("addr-block");
+                        let address = NoteAddress::new(
+                            addr.kind(),
+                            Pubkey::new(*addr.pubkey()),
+                            addr.identifier().to_owned(),
+                        );
+
+                        if has_previews {
+                            inline_address = Some((address, block.as_str()));
+                        } else if let Some(note) = address.get_note(note_context.ndb, txn) {
+                            if let Some(act) = note_link_ui(ui, note.id(), block.as_str()) {
+                                note_action = Some(act);
+                            }
+                        } else {
+                            note_context.unknown_ids.add_address_if_missing(
+                                note_context.ndb,
+                                txn,
+                                &address,
+                            );
+                            ui.colored_label(
+                                link_color,
+                                RichText::new(abbreviate_bech32(block.as_str()))
+                                    .text_style(NotedeckTextStyle::NoteBody.text_style()),
+                            );
+                        }
+                    }
+
+                    Mention::Relay(relay) => {
+                        if let Some(act) =
+                            relay_chip_ui(ui, note_context.i18n, note_context.pool, relay.relay())
+                        {
+                            note_action = Some(act);
+                        }
+                    }
+
                     _ => {
                         ui.colored_label(
                             link_color,
@@ -266,6 +755,10 @@ fn render_undecorated_note_contents<'a>(
                             update_imeta_blurhashes(note, &mut note_context.img_cache.metadata);
                         }
 
+                        if !note_context.img_cache.media_metadata.contains_key(url) {
+                            update_imeta_media(note, &mut note_context.img_cache.media_metadata);
+                        }
+
                         let Some(media) = note_context.img_cache.get_renderable_media(url) else {
                             return false;
                         };
@@ -278,6 +771,9 @@ fn render_undecorated_note_contents<'a>(
                         if block.as_str().trim().is_empty() {
                             continue;
                         }
+                        if link_preview.is_none() {
+                            link_preview = Some(block.as_str());
+                        }
                         ui.add(Hyperlink::from_label_and_url(
                             RichText::new(block.as_str())
                                 .color(link_color)
@@ -321,14 +817,43 @@ fn render_undecorated_note_contents<'a>(
                             .selectable(selectable),
                         );
                     } else {
-                        let mut richtext = RichText::new(block_str)
-                            .text_style(NotedeckTextStyle::NoteBody.text_style());
+                        let body_text = |text: &str| {
+                            let richtext = RichText::new(text)
+                                .text_style(NotedeckTextStyle::NoteBody.text_style());
+
+                            if options.contains(NoteOptions::NotificationPreview) {
+                                richtext.color(egui::Color32::from_rgb(0x87, 0x87, 0x8D))
+                            } else {
+                                richtext
+                            }
+                        };
 
-                        if options.contains(NoteOptions::NotificationPreview) {
-                            richtext = richtext.color(egui::Color32::from_rgb(0x87, 0x87, 0x8D));
+                        if options.contains(NoteOptions::Markdown) {
+                            super::markdown::markdown_ui(
+                                ui,
+                                note_context,
+                                block_str,
+                                &mut markdown,
+                                &emojis,
+                                body_text,
+                                selectable,
+                            );
+                        } else if !emojis.is_empty() && block_str.contains(':') {
+                            crate::emoji::emoji_text_ui(
+                                ui,
+                                note_context.img_cache,
+                                note_context.jobs,
+                                block_str,
+                                &emojis,
+                                body_text,
+                            );
+                        } else {
+                            ui.add(
+                                Label::new(body_text(block_str))
+                                    .wrap()
+                                    .selectable(selectable),
+                            );
                         }
-
-                        ui.add(Label::new(richtext).wrap().selectable(selectable));
                     }
                     // don't render any more blocks
                     if truncate {
@@ -356,6 +881,35 @@ fn render_undecorated_note_contents<'a>(
             })
     });
 
+    let address_action = inline_address.and_then(|(address, bech32)| {
+        render_address_preview(ui, note_context, txn, &address, bech32, note_key, options).action
+    });
+
+    let shows_link_previews = !options.intersects(
+        NoteOptions::Textmode | NoteOptions::IsPreview | NoteOptions::NotificationPreview,
+    );
+    if let Some(url) = link_preview.filter(|_| shows_link_previews) {
+        render_link_preview(ui, note_context, url, options);
+    }
+
+    // audio has nothing to show in the carousel, it gets a player bar
+    let (audios, supported_medias): (Vec<RenderableMedia>, Vec<RenderableMedia>) = supported_medias
+        .into_iter()
+        .partition(|media| media.media_type == MediaCacheType::Audio);
+
+    if !audios.is_empty() && !options.contains(NoteOptions::Textmode) {
+        ui.add_space(2.0);
+        for audio in &audios {
+            audio_player(
+                ui,
+                &mut note_context.img_cache.players,
+                note_context.i18n,
+                &audio.url,
+                ui.available_width(),
+            );
+        }
+    }
+
     let mut media_action = None;
     if !supported_medias.is_empty() && !options.contains(NoteOptions::Textmode) {
         ui.add_space(2.0);
@@ -374,6 +928,7 @@ fn render_undecorated_note_contents<'a>(
     }
 
     let note_action = preview_note_action
+        .or(address_action)
         .or(note_action)
         .or(media_action.map(NoteAction::Media));
 
@@ -128,6 +128,17 @@ impl NoteContextButton {
                 context_selection = Some(NoteContextSelection::CopyNoteJSON);
                 ui.close_menu();
             }
+            if ui
+                .button(tr!(
+                    i18n,
+                    "Highlight",
+                    "Highlight a part of the note's text"
+                ))
+                .clicked()
+            {
+                context_selection = Some(NoteContextSelection::Highlight);
+                ui.close_menu();
+            }
             if ui
                 .button(tr!(
                     i18n,
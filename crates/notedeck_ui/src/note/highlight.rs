@@ -0,0 +1,188 @@
+use egui::{vec2, Hyperlink, Label, RichText, Sense};
+use enostr::NoteId;
+use nostrdb::{Note, Transaction};
+use notedeck::highlight::{Highlight, HighlightSource};
+use notedeck::note::{NoteAction, NoteContext};
+use notedeck::{tr, NotedeckTextStyle};
+
+use crate::{secondary_label, NoteResponse};
+
+const QUOTE_BAR_WIDTH: f32 = 3.0;
+
+/// A NIP-84 highlight: the highlighter's comment, the excerpt as a quote,
+/// and where it's from beneath it
+pub fn highlight_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    note: &Note,
+) -> NoteResponse {
+    let Some(highlight) = Highlight::from_note(note) else {
+        return NoteResponse::new(ui.weak(note.content()));
+    };
+
+    let mut action = None;
+    let response = ui
+        .vertical(|ui| {
+            if let Some(comment) = highlight.comment {
+                ui.add(
+                    Label::new(
+                        RichText::new(comment).text_style(NotedeckTextStyle::NoteBody.text_style()),
+                    )
+                    .wrap(),
+                );
+                ui.add_space(4.0);
+            }
+
+            excerpt_ui(ui, highlight.text);
+
+            ui.add_space(4.0);
+            action = attribution_ui(ui, note_context, txn, &highlight);
+        })
+        .response;
+
+    NoteResponse::new(response).with_action(action)
+}
+
+/// The highlighted text, with a bar in the highlight color down its side
+fn excerpt_ui(ui: &mut egui::Ui, text: &str) {
+    let bar_color = ui.visuals().selection.bg_fill;
+
+    let resp = ui.horizontal(|ui| {
+        ui.add_space(QUOTE_BAR_WIDTH + 8.0);
+        ui.add(
+            Label::new(
+                RichText::new(text)
+                    .italics()
+                    .text_style(NotedeckTextStyle::NoteBody.text_style()),
+            )
+            .wrap(),
+        );
+    });
+
+    let rect = resp.response.rect;
+    let bar = egui::Rect::from_min_size(rect.min, vec2(QUOTE_BAR_WIDTH, rect.height()));
+    ui.painter().rect_filled(bar, 1.0, bar_color);
+}
+
+/// Who wrote what was highlighted and where it's from
+fn attribution_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    highlight: &Highlight,
+) -> Option<NoteAction> {
+    let mut action = None;
+
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 4.0;
+        secondary_label(ui, "—");
+
+        if let Some(author) = highlight.author {
+            note_context
+                .unknown_ids
+                .add_pubkey_if_missing(note_context.ndb, txn, author);
+
+            action = crate::Mention::new(
+                note_context.ndb,
+                note_context.img_cache,
+                note_context.jobs,
+                txn,
+                author,
+            )
+            .size(12.0)
+            .show(ui);
+        }
+
+        let source_action = match &highlight.source {
+            None => None,
+            Some(HighlightSource::Url(url)) => {
+                ui.add(Hyperlink::from_label_and_url(
+                    RichText::new(url_host(url)).small(),
+                    *url,
+                ))
+                .on_hover_text(*url);
+                None
+            }
+            Some(HighlightSource::Note(id)) => {
+                note_context
+                    .unknown_ids
+                    .add_note_id_if_missing(note_context.ndb, txn, id);
+
+                let label = tr!(
+                    note_context.i18n,
+                    "from a note",
+                    "Where a highlight's text is from, when it's a note"
+                );
+                source_link_ui(ui, label).then(|| NoteAction::note(NoteId::new(**id)))
+            }
+            Some(HighlightSource::Address(address)) => {
+                match address.get_note(note_context.ndb, txn) {
+                    Some(article) => {
+                        let title = article
+                            .tags()
+                            .iter()
+                            .find(|tag| tag.get_str(0) == Some("title"))
+                            .and_then(|tag| tag.get_str(1))
+                            .filter(|title| !title.is_empty());
+
+                        let label = match title {
+                            Some(title) => tr!(
+                                note_context.i18n,
+                                "from {title}",
+                                "Where a highlight's text is from, when it's an article with a title",
+                                title = title
+                            ),
+                            None => tr!(
+                                note_context.i18n,
+                                "from an article",
+                                "Where a highlight's text is from, when it's an article without a title"
+                            ),
+                        };
+                        source_link_ui(ui, label)
+                            .then(|| NoteAction::note(NoteId::new(*article.id())))
+                    }
+                    None => {
+                        note_context.unknown_ids.add_address_if_missing(
+                            note_context.ndb,
+                            txn,
+                            address,
+                        );
+                        secondary_label(
+                            ui,
+                            tr!(
+                                note_context.i18n,
+                                "from an article",
+                                "Where a highlight's text is from, when it's an article without a title"
+                            ),
+                        );
+                        None
+                    }
+                }
+            }
+        };
+
+        action = action.or(source_action);
+    });
+
+    action
+}
+
+fn source_link_ui(ui: &mut egui::Ui, label: String) -> bool {
+    ui.add(
+        Label::new(
+            RichText::new(label)
+                .small()
+                .color(ui.visuals().hyperlink_color),
+        )
+        .sense(Sense::click())
+        .selectable(false),
+    )
+    .on_hover_cursor(egui::CursorIcon::PointingHand)
+    .clicked()
+}
+
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
@@ -0,0 +1,124 @@
+use egui::text::{LayoutJob, TextFormat};
+use egui::{vec2, Color32, FontFamily, Label, RichText, Sense};
+use notedeck::emoji::CustomEmoji;
+use notedeck::markdown::{parse, MarkdownState, Span, Style, Token};
+use notedeck::{NamedFontFamily, NoteContext, NotedeckTextStyle};
+
+/// A note's text block with markdown. Mentions, hashtags and links are
+/// their own blocks and are shown as usual in between.
+pub fn markdown_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    text: &str,
+    state: &mut MarkdownState,
+    emojis: &[CustomEmoji],
+    body_text: impl Fn(&str) -> RichText,
+    selectable: bool,
+) {
+    let quote_color = ui.visuals().weak_text_color();
+    let mut code: Vec<(&str, Token)> = Vec::new();
+
+    for span in parse(text, state) {
+        let (text, style) = match span {
+            Span::Code(text, token) => {
+                code.push((text, token));
+                continue;
+            }
+            Span::Quote => {
+                code_block_ui(ui, &mut code, selectable);
+                quote_bar_ui(ui, quote_color);
+                continue;
+            }
+            Span::Text(text, style) => (text, style),
+        };
+
+        code_block_ui(ui, &mut code, selectable);
+
+        let styled = |text: &str| styled_text(body_text(text), style, quote_color);
+        if !style.code && !emojis.is_empty() && text.contains(':') {
+            crate::emoji::emoji_text_ui(
+                ui,
+                note_context.img_cache,
+                note_context.jobs,
+                text,
+                emojis,
+                styled,
+            );
+        } else {
+            ui.add(Label::new(styled(text)).wrap().selectable(selectable));
+        }
+    }
+
+    // the code block may go on in the next text block, after a link, but
+    // that's shown on its own
+    code_block_ui(ui, &mut code, selectable);
+}
+
+fn styled_text(mut text: RichText, style: Style, quote_color: Color32) -> RichText {
+    if style.code {
+        text = text.code();
+    }
+    if style.bold {
+        text = text.family(FontFamily::Name(NamedFontFamily::Bold.as_str().into()));
+    }
+    if style.italic {
+        text = text.italics();
+    }
+    if style.quote {
+        text = text.color(quote_color);
+    }
+    text
+}
+
+fn quote_bar_ui(ui: &mut egui::Ui, color: Color32) {
+    let height = ui.text_style_height(&NotedeckTextStyle::NoteBody.text_style());
+    let (rect, _) = ui.allocate_exact_size(vec2(3.0, height), Sense::hover());
+    ui.painter().rect_filled(rect, 1.0, color);
+    ui.add_space(6.0);
+}
+
+/// A fenced code block on its own row, in monospace and highlighted
+fn code_block_ui<'a>(ui: &mut egui::Ui, code: &mut Vec<(&'a str, Token)>, selectable: bool) {
+    // the fence is on its own line, the newline before it isn't part of
+    // the code
+    if let Some((last, _)) = code.last_mut() {
+        let text: &'a str = *last;
+        *last = text.strip_suffix('\n').unwrap_or(text);
+    }
+    if code.iter().all(|(text, _)| text.is_empty()) {
+        code.clear();
+        return;
+    }
+
+    let font_id = NotedeckTextStyle::Monospace.get_font_id(ui.ctx());
+    let mut job = LayoutJob::default();
+    for (text, token) in code.drain(..) {
+        let format = TextFormat::simple(font_id.clone(), token_color(ui.visuals(), token));
+        job.append(text, 0.0, format);
+    }
+
+    ui.end_row();
+    egui::Frame::new()
+        .fill(ui.visuals().code_bg_color)
+        .corner_radius(4.0)
+        .inner_margin(6.0)
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.add(Label::new(job).wrap().selectable(selectable));
+        });
+    ui.end_row();
+}
+
+fn token_color(visuals: &egui::Visuals, token: Token) -> Color32 {
+    let dark = visuals.dark_mode;
+    match token {
+        Token::Plain => visuals.text_color(),
+        Token::Comment => visuals.weak_text_color(),
+        Token::Keyword if dark => Color32::from_rgb(0xC6, 0x78, 0xDD),
+        Token::Keyword => Color32::from_rgb(0xA6, 0x26, 0xA4),
+        Token::String if dark => Color32::from_rgb(0x98, 0xC3, 0x79),
+        Token::String => Color32::from_rgb(0x50, 0xA1, 0x4F),
+        Token::Number if dark => Color32::from_rgb(0xD1, 0x9A, 0x66),
+        Token::Number => Color32::from_rgb(0x98, 0x68, 0x01),
+    }
+}
@@ -1,13 +1,15 @@
 use bitflags::bitflags;
+use std::time::Duration;
+
 use egui::{
-    vec2, Button, Color32, Context, CornerRadius, FontId, Image, InnerResponse, Response,
-    TextureHandle, Vec2,
+    vec2, Button, Color32, Context, CornerRadius, FontId, Image, InnerResponse, Label, Response,
+    RichText, TextureHandle, Vec2,
 };
 use notedeck::media::latest::ObfuscatedTexture;
 use notedeck::MediaJobSender;
 use notedeck::{
     fonts::get_font_size, show_one_error_message, tr, Images, Localization, MediaAction,
-    MediaCacheType, NotedeckTextStyle, RenderableMedia,
+    MediaCacheType, MediaPlayers, NotedeckTextStyle, PlayerState, RenderableMedia,
 };
 
 use crate::NoteOptions;
@@ -58,13 +60,15 @@ pub fn image_carousel(
                         let mut media_action: Option<(usize, MediaUIAction)> = None;
 
                         for (i, media) in medias.iter().enumerate() {
+                            let (texture_url, texture_type) = media.texture_source();
+                            let trusted = note_options.contains(NoteOptions::TrustMedia)
+                                || img_cache.user_trusts_img(texture_url, texture_type);
                             let media_response = render_media(
                                 ui,
                                 img_cache,
                                 jobs,
                                 media,
-                                note_options.contains(NoteOptions::TrustMedia)
-                                    || img_cache.user_trusts_img(&media.url, media.media_type),
+                                trusted,
                                 i18n,
                                 size,
                                 if note_options.contains(NoteOptions::NoAnimations) {
@@ -95,7 +99,6 @@ pub fn image_carousel(
                                 medias,
                                 media_infos,
                                 i,
-                                img_cache,
                                 ImageType::Content(Some((size.x as u32, size.y as u32))),
                             );
                         }
@@ -122,17 +125,20 @@ pub fn render_media(
     animation_mode: Option<AnimationMode>,
     scale_flags: ScaledTextureFlags,
 ) -> InnerResponse<Option<MediaUIAction>> {
-    let RenderableMedia {
-        url,
-        media_type,
-        obfuscation_type: blur_type,
-    } = media;
+    if media.media_type == MediaCacheType::Audio {
+        let resp = audio_player(ui, &mut img_cache.players, i18n, &media.url, size.x);
+        return egui::InnerResponse::new(None, resp);
+    }
+
+    // videos show their poster
+    let (url, media_type) = media.texture_source();
+    let blur_type = &media.obfuscation_type;
 
     let animation_mode = animation_mode.unwrap_or_else(|| {
         // if animations aren't disabled, we cap it at 24fps for gifs in carousels
         let fps = match media_type {
             MediaCacheType::Gif => Some(24.0),
-            MediaCacheType::Image => None,
+            MediaCacheType::Image | MediaCacheType::Video | MediaCacheType::Audio => None,
         };
         AnimationMode::Continuous { fps }
     });
@@ -141,7 +147,7 @@ pub fn render_media(
             jobs,
             ui,
             url,
-            *media_type,
+            media_type,
             ImageType::Content(None),
             animation_mode,
             blur_type,
@@ -153,7 +159,118 @@ pub fn render_media(
             .latest(jobs, ui, url, blur_type, size)
     };
 
-    render_media_internal(ui, media_state, url, size, i18n, scale_flags)
+    let is_playable = media.media_type == MediaCacheType::Video
+        && matches!(
+            media_state,
+            MediaRenderState::ActualImage(_) | MediaRenderState::Transitioning { .. }
+        );
+
+    let resp = render_media_internal(ui, media_state, url, size, i18n, scale_flags);
+    if is_playable {
+        paint_play_button(ui, resp.response.rect);
+    }
+    resp
+}
+
+/// The ▶ drawn over a video's poster
+pub fn paint_play_button(ui: &egui::Ui, rect: egui::Rect) {
+    let radius = (rect.height().min(rect.width()) / 6.0).clamp(12.0, 32.0);
+    let center = rect.center();
+    let painter = ui.painter_at(rect);
+
+    painter.circle_filled(center, radius, Color32::from_black_alpha(160));
+
+    let side = radius * 0.9;
+    let left = center.x - side * 0.4;
+    painter.add(egui::Shape::convex_polygon(
+        vec![
+            egui::pos2(left, center.y - side / 2.0),
+            egui::pos2(left + side * 0.9, center.y),
+            egui::pos2(left, center.y + side / 2.0),
+        ],
+        Color32::WHITE,
+        egui::Stroke::NONE,
+    ));
+}
+
+/// A player bar for audio files, which have nothing to show
+pub fn audio_player(
+    ui: &mut egui::Ui,
+    players: &mut MediaPlayers,
+    i18n: &mut Localization,
+    url: &str,
+    width: f32,
+) -> Response {
+    let state = players.audio_state(url);
+    let playing = matches!(state, Some((PlayerState::Playing, _)));
+    if playing {
+        ui.ctx().request_repaint_after(Duration::from_millis(500));
+    }
+
+    let resp = egui::Frame::new()
+        .fill(ui.visuals().noninteractive().weak_bg_fill)
+        .inner_margin(egui::Margin::symmetric(8, 6))
+        .corner_radius(egui::CornerRadius::same(8))
+        .show(ui, |ui| {
+            ui.set_width(width.min(ui.available_width()) - 16.0);
+            ui.horizontal(|ui| {
+                let (icon, hover) = if playing {
+                    (
+                        "⏸",
+                        tr!(i18n, "Pause", "Hover text of the button pausing audio"),
+                    )
+                } else {
+                    (
+                        "▶",
+                        tr!(i18n, "Play", "Hover text of the button playing audio"),
+                    )
+                };
+
+                if ui
+                    .add(Button::new(RichText::new(icon).size(18.0)).frame(false))
+                    .on_hover_cursor(egui::CursorIcon::PointingHand)
+                    .on_hover_text(hover)
+                    .clicked()
+                {
+                    players.toggle_audio(url);
+                }
+
+                match &state {
+                    Some((PlayerState::Failed(err), _)) => {
+                        ui.add(Label::new(RichText::new(err).weak()).truncate());
+                    }
+                    Some((PlayerState::Playing | PlayerState::Paused, position)) => {
+                        ui.weak(format_position(*position));
+                    }
+                    _ => {}
+                }
+
+                ui.add(Label::new(media_file_name(url)).truncate());
+            });
+        })
+        .response;
+
+    copy_link(i18n, url, &resp);
+    resp
+}
+
+/// `m:ss` or `h:mm:ss`
+pub fn format_position(position: Duration) -> String {
+    let secs = position.as_secs();
+    let (hours, mins, secs) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{mins:02}:{secs:02}")
+    } else {
+        format!("{mins}:{secs:02}")
+    }
+}
+
+fn media_file_name(url: &str) -> &str {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.trim_end_matches('/').rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(url)
 }
 
 pub enum MediaUIAction {
@@ -169,9 +286,10 @@ impl MediaUIAction {
         medias: &[RenderableMedia],
         responses: Vec<MediaInfo>,
         selected: usize,
-        img_cache: &Images,
         img_type: ImageType,
     ) -> Option<MediaAction> {
+        // videos are loaded and transition as their poster
+        let (url, cache_type) = medias[selected].texture_source();
         match self {
             // We've clicked on some media, let's package up
             // all of the rendered media responses, and send
@@ -182,31 +300,24 @@ impl MediaUIAction {
                 medias: responses,
             })),
 
-            MediaUIAction::Unblur => {
-                let url = &medias[selected].url;
-                let cache = img_cache.get_cache(medias[selected].media_type);
-                let cache_type = cache.cache_type;
-                Some(MediaAction::FetchImage {
-                    url: url.to_owned(),
-                    cache_type,
-                })
-            }
+            MediaUIAction::Unblur => Some(MediaAction::FetchImage {
+                url: url.to_owned(),
+                cache_type,
+            }),
 
             MediaUIAction::Error => {
                 if !matches!(img_type, ImageType::Profile(_)) {
                     return None;
                 };
 
-                let cache = img_cache.get_cache(medias[selected].media_type);
-                let cache_type = cache.cache_type;
                 Some(MediaAction::FetchImage {
-                    url: medias[selected].url.to_owned(),
+                    url: url.to_owned(),
                     cache_type,
                 })
             }
             MediaUIAction::DoneLoading => Some(MediaAction::DoneLoading {
-                url: medias[selected].url.to_owned(),
-                cache_type: img_cache.get_cache(medias[selected].media_type).cache_type,
+                url: url.to_owned(),
+                cache_type,
             }),
         }
     }
@@ -1,15 +1,22 @@
 pub mod contents;
 pub mod context;
+pub mod highlight;
+pub mod markdown;
 pub mod media;
 pub mod options;
+pub mod poll;
+pub mod reactions;
 pub mod reply_description;
+pub mod translation;
 
 use crate::{app_images, secondary_label};
 use crate::{widgets::x_button, ProfilePic, ProfilePreview, PulseAlpha, Username};
 
 pub use contents::{render_note_preview, NoteContents};
 pub use context::NoteContextButton;
+use notedeck::highlight::HIGHLIGHT_KIND;
 use notedeck::note::{reaction_sent_id, ZapTargetAmount};
+use notedeck::reaction::DEFAULT_REACTION;
 use notedeck::ui::is_narrow;
 use notedeck::Accounts;
 use notedeck::GlobalWallet;
@@ -21,12 +28,13 @@ pub use options::NoteOptions;
 pub use reply_description::reply_desc;
 
 use egui::emath::{pos2, Vec2};
-use egui::{Id, Pos2, Rect, Response, Sense};
+use egui::{DragAndDrop, Id, Pos2, Rect, Response, Sense};
 use enostr::{KeypairUnowned, NoteId, Pubkey};
 use nostrdb::{Ndb, Note, NoteKey, ProfileRecord, Transaction};
 use notedeck::{
     note::{NoteAction, NoteContext, ReactAction, ZapAction},
-    tr, AnyZapState, ContextSelection, NoteZapTarget, NoteZapTargetOwned, ZapTarget, Zaps,
+    tr, AnyZapState, ContextSelection, DraggedNote, NoteZapTarget, NoteZapTargetOwned, ZapTarget,
+    Zaps,
 };
 
 pub struct NoteView<'a, 'd> {
@@ -285,8 +293,8 @@ impl<'a, 'd> NoteView<'a, 'd> {
             // so the calls must be separate
             Some(pic) => show_actual_pfp(
                 ui,
-                self.note_context.img_cache,
-                self.note_context.jobs,
+                self.note_context,
+                &Pubkey::new(*self.note.pubkey()),
                 pic,
                 pfp_size,
                 note_key,
@@ -342,18 +350,27 @@ impl<'a, 'd> NoteView<'a, 'd> {
     #[profiling::function]
     fn note_header(
         ui: &mut egui::Ui,
-        i18n: &mut Localization,
+        note_context: &mut NoteContext,
+        txn: &Transaction,
         note: &Note,
         profile: &Result<nostrdb::ProfileRecord<'_>, nostrdb::Error>,
         flags: NoteOptions,
     ) {
+        let emojis = profile
+            .as_ref()
+            .map(|profile| notedeck::emoji::profile_emojis(note_context.ndb, txn, profile))
+            .unwrap_or_default();
+
         let horiz_resp = ui
             .horizontal_wrapped(|ui| {
                 ui.spacing_mut().item_spacing.x = if is_narrow(ui.ctx()) { 1.0 } else { 2.0 };
-                let response = ui
-                    .add(Username::new(i18n, profile.as_ref().ok(), note.pubkey()).abbreviated(20));
+                let response = ui.add(
+                    Username::new(note_context.i18n, profile.as_ref().ok(), note.pubkey())
+                        .abbreviated(20)
+                        .emojis(&emojis, note_context.img_cache, note_context.jobs),
+                );
                 if !flags.contains(NoteOptions::FullCreatedDate) {
-                    return render_notetime(ui, i18n, note.created_at(), true);
+                    return render_notetime(ui, note_context.i18n, note.created_at(), true);
                 }
                 response
             })
@@ -400,7 +417,8 @@ impl<'a, 'd> NoteView<'a, 'd> {
                                 ui.horizontal_centered(|ui| {
                                     NoteView::note_header(
                                         ui,
-                                        self.note_context.i18n,
+                                        self.note_context,
+                                        txn,
                                         self.note,
                                         profile,
                                         self.flags,
@@ -417,7 +435,9 @@ impl<'a, 'd> NoteView<'a, 'd> {
                             .reply
                             .borrow(self.note.tags());
 
-                        if note_reply.reply().is_none() {
+                        // a highlight's `e` tag is where it's from, which
+                        // the highlight shows itself
+                        if note_reply.reply().is_none() || self.note.kind() == HIGHLIGHT_KIND {
                             return;
                         }
 
@@ -461,19 +481,7 @@ impl<'a, 'd> NoteView<'a, 'd> {
                                 })
                             });
 
-                        actionbar_ui(
-                            ui,
-                            counts,
-                            get_zapper(
-                                self.note_context.accounts,
-                                self.note_context.global_wallet,
-                                self.note_context.zaps,
-                            ),
-                            self.note,
-                            self.note_context.accounts.selected_account_pubkey(),
-                            note_key,
-                            self.note_context.i18n,
-                        )
+                        actionbar_ui(ui, counts, self.note_context, txn, self.note, note_key)
                     })
                     .inner
                     .or(note_action);
@@ -509,7 +517,8 @@ impl<'a, 'd> NoteView<'a, 'd> {
                 if !self.flags.contains(NoteOptions::NotificationPreview) {
                     NoteView::note_header(
                         ui,
-                        self.note_context.i18n,
+                        self.note_context,
+                        txn,
                         self.note,
                         profile,
                         self.flags,
@@ -525,7 +534,7 @@ impl<'a, 'd> NoteView<'a, 'd> {
                             .reply
                             .borrow(self.note.tags());
 
-                        if note_reply.reply().is_none() {
+                        if note_reply.reply().is_none() || self.note.kind() == HIGHLIGHT_KIND {
                             return;
                         }
 
@@ -623,6 +632,19 @@ impl<'a, 'd> NoteView<'a, 'd> {
             }
         }
 
+        if let Some(hitbox) = &maybe_hitbox {
+            if hitbox.drag_started() {
+                // notes can be dragged out of the timeline, eg. onto a notebook
+                DragAndDrop::set_payload(
+                    ui.ctx(),
+                    DraggedNote {
+                        note_id: NoteId::new(*self.note.id()),
+                        relays: self.note.relays(txn).map(|r| r.to_owned()).collect(),
+                    },
+                );
+            }
+        }
+
         note_action = note_hitbox_clicked(ui, hitbox_id, &response.response.rect, maybe_hitbox)
             .then_some(NoteAction::note(NoteId::new(*self.note.id())))
             .or(note_action);
@@ -699,8 +721,8 @@ impl PfpResponse {
 
 fn show_actual_pfp(
     ui: &mut egui::Ui,
-    images: &mut Images,
-    jobs: &MediaJobSender,
+    note_context: &mut NoteContext,
+    pubkey: &Pubkey,
     pic: &str,
     pfp_size: i8,
     note_key: NoteKey,
@@ -720,13 +742,20 @@ fn show_actual_pfp(
 
     let resp = resp.on_hover_cursor(egui::CursorIcon::PointingHand);
 
-    let mut pfp = ProfilePic::new(images, jobs, pic).size(size);
+    let mut pfp = ProfilePic::new(note_context.img_cache, note_context.jobs, pic).size(size);
     let pfp_resp = ui.put(rect, &mut pfp);
     let action = pfp.action;
 
     pfp_resp.on_hover_ui_at_pointer(|ui| {
         ui.set_max_width(300.0);
-        ui.add(ProfilePreview::new(profile.as_ref().unwrap(), images, jobs));
+        let profile = profile.as_ref().unwrap();
+        let nip05_status = note_context
+            .nip05_cache
+            .profile_status(pubkey, Some(profile));
+        ui.add(
+            ProfilePreview::new(profile, note_context.img_cache, note_context.jobs)
+                .nip05_status(note_context.i18n, nip05_status),
+        );
     });
 
     PfpResponse {
@@ -779,7 +808,14 @@ fn maybe_note_hitbox(ui: &mut egui::Ui, hitbox_id: egui::Id) -> Option<Response>
                 max: pos2(container_rect.max.x, container_rect.min.y + note_size.y),
             };
 
-            let response = ui.interact(rect, ui.id().with(hitbox_id), egui::Sense::click());
+            // Dragging notes would get in the way of scrolling on touch
+            // screens, so only allow it on wide screens
+            let sense = if is_narrow(ui.ctx()) {
+                egui::Sense::click()
+            } else {
+                egui::Sense::click_and_drag()
+            };
+            let response = ui.interact(rect, ui.id().with(hitbox_id), sense);
 
             response
                 .widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Other, true, "hitbox"));
@@ -887,20 +923,20 @@ fn is_root_note(note: &Note) -> bool {
 fn actionbar_ui(
     ui: &mut egui::Ui,
     counts: Option<nostrdb::CountsEntry<'_>>,
-    zapper: Option<Zapper<'_>>,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
     note: &Note,
-    current_user_pubkey: &Pubkey,
     note_key: NoteKey,
-    i18n: &mut Localization,
 ) -> Option<NoteAction> {
+    let current_user_pubkey = note_context.accounts.selected_account_pubkey();
     let mut action = None;
     let spacing = 24.0;
 
     ui.spacing_mut().item_spacing.x = 2.0;
     ui.set_min_height(26.0);
 
-    let reply_resp =
-        reply_button(ui, i18n, note_key).on_hover_cursor(egui::CursorIcon::PointingHand);
+    let reply_resp = reply_button(ui, note_context.i18n, note_key)
+        .on_hover_cursor(egui::CursorIcon::PointingHand);
 
     if let Some(c) = &counts {
         let count = if is_root_note(note) {
@@ -922,20 +958,21 @@ fn actionbar_ui(
         .data(|d| d.get_temp(reaction_sent_id(current_user_pubkey, note.id())))
         == Some(true);
 
-    let like_resp =
-        like_button(ui, i18n, note_key, filled).on_hover_cursor(egui::CursorIcon::PointingHand);
+    let like_resp = like_button(ui, note_context.i18n, note_key, filled)
+        .on_hover_cursor(egui::CursorIcon::PointingHand);
 
     if let Some(c) = &counts {
         let count = c.reactions();
         if count > 0 {
+            reactions::note_reactions_summary(ui, note_context, txn, note.id(), note_key, count);
             crate::anim::rolling_number(ui, egui::Id::new((note_key, "likes")), count);
         }
     }
 
     ui.add_space(spacing);
 
-    let quote_resp =
-        quote_repost_button(ui, i18n, note_key).on_hover_cursor(egui::CursorIcon::PointingHand);
+    let quote_resp = quote_repost_button(ui, note_context.i18n, note_key)
+        .on_hover_cursor(egui::CursorIcon::PointingHand);
 
     if let Some(c) = &counts {
         let count = c.quotes() + c.reposts();
@@ -953,15 +990,31 @@ fn actionbar_ui(
     if like_resp.clicked() {
         action = Some(NoteAction::React(ReactAction::new(
             NoteId::new(*note.id()),
-            "🤙🏻",
+            DEFAULT_REACTION,
         )));
     }
 
+    // right click or long press
+    like_resp.context_menu(|ui| {
+        if let Some(reaction) =
+            reactions::reaction_picker(ui, note_context, txn, NoteId::new(*note.id()))
+        {
+            action = Some(NoteAction::React(reaction));
+            ui.close_menu();
+        }
+    });
+
     if quote_resp.clicked() {
         action = Some(NoteAction::Repost(NoteId::new(*note.id())));
     }
 
-    action = zap_actionbar_button(ui, note.id(), note.pubkey(), zapper, i18n).or(action);
+    let zapper = get_zapper(
+        note_context.accounts,
+        note_context.global_wallet,
+        note_context.zaps,
+    );
+    action =
+        zap_actionbar_button(ui, note.id(), note.pubkey(), zapper, note_context.i18n).or(action);
 
     action
 }
@@ -1,5 +1,6 @@
 use crate::ProfilePic;
 use bitflags::bitflags;
+use notedeck::ContentWarningMode;
 
 bitflags! {
     // Attributes can be applied to flags types
@@ -47,6 +48,16 @@ bitflags! {
 
         /// There is enough trust to show media in this note
         const TrustMedia = 1 << 20;
+
+        /// Show notes with a content warning without asking first
+        const ShowContentWarned = 1 << 21;
+
+        /// Keep notes with a content warning hidden, without a way to
+        /// reveal them
+        const HideContentWarned = 1 << 22;
+
+        /// Render inline code, code blocks, emphasis and quotes in note text
+        const Markdown = 1 << 23;
     }
 }
 
@@ -66,6 +77,17 @@ impl NoteOptions {
         options
     }
 
+    pub fn set_content_warnings(&mut self, mode: ContentWarningMode) {
+        self.set(
+            NoteOptions::ShowContentWarned,
+            mode == ContentWarningMode::AlwaysShow,
+        );
+        self.set(
+            NoteOptions::HideContentWarned,
+            mode == ContentWarningMode::AlwaysHide,
+        );
+    }
+
     pub fn pfp_size(&self) -> i8 {
         if self.contains(NoteOptions::SmallPfp) {
             ProfilePic::small_size()
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use egui::{vec2, Color32, Sense, Stroke};
+use enostr::NoteId;
+use nostrdb::{Note, Transaction};
+use notedeck::note::{NoteAction, NoteContext, VoteAction};
+use notedeck::poll::{poll_tally, Poll, PollTally, PollType, WatchedPolls};
+use notedeck::{time_format, tr, tr_plural, unix_time_secs};
+
+use crate::secondary_label;
+
+/// How often we count a shown poll's votes again, in seconds
+const TALLY_REFRESH_SECS: f64 = 1.0;
+
+const RESULT_BAR_HEIGHT: f32 = 28.0;
+
+/// A NIP-88 poll's options. Until the account has voted, and while the poll
+/// is open, they can be picked and voted for. After that they show how many
+/// voted for each.
+pub fn poll_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    note: &Note,
+) -> Option<NoteAction> {
+    let poll = Poll::from_note(note)?;
+
+    let watched_id = egui::Id::new("watched-polls");
+    let mut watched: WatchedPolls = ui.data(|d| d.get_temp(watched_id)).unwrap_or_default();
+    watched.watch(note_context.pool, note.id(), ui.ctx().cumulative_pass_nr());
+    ui.data_mut(|d| d.insert_temp(watched_id, watched));
+
+    let tally = cached_poll_tally(ui, note_context, txn, note, &poll);
+    let ended = poll.has_ended(unix_time_secs());
+    let can_vote = !ended && tally.my_votes.is_empty();
+
+    let mut action = None;
+    ui.add_space(4.0);
+    ui.vertical(|ui| {
+        if can_vote {
+            action = poll_choices_ui(ui, note_context, note, &poll);
+        } else {
+            poll_results_ui(ui, &poll, &tally);
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            let voters = tally.voters;
+            secondary_label(
+                ui,
+                tr_plural!(
+                    note_context.i18n,
+                    "{count} vote",
+                    "{count} votes",
+                    "Number of people that voted in a poll",
+                    voters,
+                ),
+            );
+
+            if let Some(ends_at) = poll.ends_at {
+                let date = time_format(note_context.i18n, ends_at);
+                let label = if ended {
+                    tr!(
+                        note_context.i18n,
+                        "Ended {date}",
+                        "When a poll stopped taking votes",
+                        date = date
+                    )
+                } else {
+                    tr!(
+                        note_context.i18n,
+                        "Ends {date}",
+                        "When a poll will stop taking votes",
+                        date = date
+                    )
+                };
+                secondary_label(ui, "·");
+                secondary_label(ui, label);
+            }
+        });
+    });
+    ui.add_space(4.0);
+
+    action
+}
+
+/// Radio buttons or checkboxes for the options, and a vote button
+fn poll_choices_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    note: &Note,
+    poll: &Poll,
+) -> Option<NoteAction> {
+    let selected_id = egui::Id::new(("poll-selection", note.id()));
+    let mut selected: Vec<String> = ui.data(|d| d.get_temp(selected_id)).unwrap_or_default();
+
+    for option in &poll.options {
+        let is_selected = selected.contains(&option.id);
+        let resp = match poll.poll_type {
+            PollType::SingleChoice => ui.radio(is_selected, &option.label),
+            PollType::MultipleChoice => {
+                let mut checked = is_selected;
+                ui.checkbox(&mut checked, &option.label)
+            }
+        };
+
+        if !resp.clicked() {
+            continue;
+        }
+
+        match poll.poll_type {
+            PollType::SingleChoice => selected = vec![option.id.clone()],
+            PollType::MultipleChoice if is_selected => selected.retain(|id| id != &option.id),
+            PollType::MultipleChoice => selected.push(option.id.clone()),
+        }
+    }
+
+    let vote = ui
+        .add_enabled(
+            !selected.is_empty(),
+            egui::Button::new(tr!(
+                note_context.i18n,
+                "Vote",
+                "Button to send the options picked in a poll"
+            )),
+        )
+        .clicked();
+
+    if vote {
+        ui.data_mut(|d| d.remove::<Vec<String>>(selected_id));
+        return Some(NoteAction::Vote(VoteAction {
+            poll_id: NoteId::new(*note.id()),
+            options: selected,
+        }));
+    }
+
+    ui.data_mut(|d| d.insert_temp(selected_id, selected));
+    None
+}
+
+/// A bar per option filled by its share of the votes
+fn poll_results_ui(ui: &mut egui::Ui, poll: &Poll, tally: &PollTally) {
+    let fill = ui.visuals().selection.bg_fill.gamma_multiply(0.5);
+    let text_color = ui.visuals().text_color();
+    let stroke = Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color);
+
+    for (index, option) in poll.options.iter().enumerate() {
+        let fraction = tally.fraction(index);
+        let (rect, _) = ui.allocate_exact_size(
+            vec2(ui.available_width(), RESULT_BAR_HEIGHT),
+            Sense::hover(),
+        );
+
+        let painter = ui.painter_at(rect);
+        let rounding = 6.0;
+        painter.rect_stroke(rect, rounding, stroke);
+
+        let mut filled = rect;
+        filled.set_width(rect.width() * fraction);
+        painter.rect_filled(filled, rounding, fill);
+
+        let voted = tally.my_votes.contains(&option.id);
+        let label = if voted {
+            format!("{} ✓", option.label)
+        } else {
+            option.label.clone()
+        };
+
+        let font = egui::FontId::proportional(14.0);
+        painter.text(
+            rect.left_center() + vec2(8.0, 0.0),
+            egui::Align2::LEFT_CENTER,
+            label,
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            rect.right_center() - vec2(8.0, 0.0),
+            egui::Align2::RIGHT_CENTER,
+            format!("{:.0}%", fraction * 100.0),
+            font,
+            if voted { text_color } else { Color32::GRAY },
+        );
+
+        ui.add_space(2.0);
+    }
+}
+
+/// Counting votes means querying all of a poll's responses, so we only do it
+/// every so often rather than every frame
+fn cached_poll_tally(
+    ui: &mut egui::Ui,
+    note_context: &NoteContext,
+    txn: &Transaction,
+    note: &Note,
+    poll: &Poll,
+) -> Arc<PollTally> {
+    let id = egui::Id::new(("poll-tally", note.id()));
+    let now = ui.input(|i| i.time);
+
+    let cached = ui.ctx().data(|d| d.get_temp::<(f64, Arc<PollTally>)>(id));
+    if let Some((counted_at, tally)) = cached {
+        if now - counted_at < TALLY_REFRESH_SECS {
+            return tally;
+        }
+    }
+
+    let me = note_context.accounts.selected_account_pubkey_bytes();
+    let tally = Arc::new(poll_tally(note_context.ndb, txn, note.id(), poll, Some(me)));
+    ui.ctx()
+        .data_mut(|d| d.insert_temp(id, (now, tally.clone())));
+    tally
+}
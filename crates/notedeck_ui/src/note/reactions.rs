@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use egui::{Label, RichText, Sense};
+use enostr::NoteId;
+use nostrdb::{Ndb, NoteKey, Transaction};
+use notedeck::note::{NoteContext, ReactAction};
+use notedeck::reaction::{note_reactions, ReactionCount, ReactionUse, DEFAULT_REACTIONS};
+use notedeck::{tr, Images, MediaJobSender};
+
+use crate::emoji::{custom_emoji_picker, custom_emoji_ui};
+
+const PICKER_REACTION_SIZE: f32 = 22.0;
+const HISTORY_SHOWN: usize = 8;
+
+/// How many of the most common reactions we show next to a note's count
+const SUMMARY_SHOWN: usize = 3;
+
+/// The reaction picker shown when long-pressing or right-clicking the like
+/// button: the account's recent and frequently used reactions, some
+/// defaults and its custom emoji.
+pub fn reaction_picker(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    note_id: NoteId,
+) -> Option<ReactAction> {
+    let mut picked = None;
+
+    let recent = note_context.accounts.recent_reactions(HISTORY_SHOWN);
+    if !recent.is_empty() {
+        ui.weak(tr!(
+            note_context.i18n,
+            "Recent",
+            "Heading for recently used reactions in the reaction picker"
+        ));
+        picked = reaction_row(
+            ui,
+            note_context.img_cache,
+            note_context.jobs,
+            &recent,
+            note_id,
+        )
+        .or(picked);
+    }
+
+    let frequent = note_context.accounts.frequent_reactions(HISTORY_SHOWN);
+    if frequent.len() > 1 {
+        ui.weak(tr!(
+            note_context.i18n,
+            "Frequently used",
+            "Heading for frequently used reactions in the reaction picker"
+        ));
+        picked = reaction_row(
+            ui,
+            note_context.img_cache,
+            note_context.jobs,
+            &frequent,
+            note_id,
+        )
+        .or(picked);
+    }
+
+    ui.weak(tr!(
+        note_context.i18n,
+        "Emoji",
+        "Heading for the default emoji in the reaction picker"
+    ));
+    ui.horizontal_wrapped(|ui| {
+        ui.set_max_width(PICKER_REACTION_SIZE * 10.0);
+        for content in DEFAULT_REACTIONS {
+            if reaction_button(ui, content).clicked() {
+                picked = Some(ReactAction::new(note_id, *content));
+            }
+        }
+    });
+
+    ui.weak(tr!(
+        note_context.i18n,
+        "Custom emoji",
+        "Heading for the custom emoji in the reaction picker"
+    ));
+    let emojis =
+        note_context
+            .accounts
+            .custom_emojis(note_context.ndb, txn, note_context.unknown_ids);
+    if let Some(emoji) = custom_emoji_picker(
+        ui,
+        note_context.i18n,
+        note_context.img_cache,
+        note_context.jobs,
+        &emojis,
+    ) {
+        picked = Some(ReactAction::custom_emoji(note_id, emoji));
+    }
+
+    picked
+}
+
+/// Reactions from the account's history
+fn reaction_row(
+    ui: &mut egui::Ui,
+    images: &mut Images,
+    jobs: &MediaJobSender,
+    uses: &[&ReactionUse],
+    note_id: NoteId,
+) -> Option<ReactAction> {
+    let mut picked = None;
+    ui.horizontal_wrapped(|ui| {
+        for reaction in uses {
+            let resp = match &reaction.emoji {
+                Some(emoji) => custom_emoji_ui(ui, images, jobs, emoji, PICKER_REACTION_SIZE)
+                    .interact(Sense::click())
+                    .on_hover_cursor(egui::CursorIcon::PointingHand),
+                None => reaction_button(ui, &reaction.content),
+            };
+
+            if resp.clicked() {
+                picked = Some(match &reaction.emoji {
+                    Some(emoji) => ReactAction::custom_emoji(note_id, emoji.clone()),
+                    None => ReactAction::new(note_id, reaction.content.clone()),
+                });
+            }
+        }
+    });
+    picked
+}
+
+fn reaction_button(ui: &mut egui::Ui, content: &str) -> egui::Response {
+    ui.add(egui::Button::new(RichText::new(content).size(PICKER_REACTION_SIZE * 0.8)).frame(false))
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+}
+
+/// A reaction and how many times it was sent
+pub fn reaction_count_ui(
+    ui: &mut egui::Ui,
+    images: &mut Images,
+    jobs: &MediaJobSender,
+    reaction: &ReactionCount,
+    size: f32,
+) -> egui::Response {
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        reaction_glyph(ui, images, jobs, reaction, size);
+        ui.add(
+            Label::new(RichText::new(reaction.count.to_string()).size(size * 0.8))
+                .selectable(false),
+        );
+    })
+    .response
+}
+
+fn reaction_glyph(
+    ui: &mut egui::Ui,
+    images: &mut Images,
+    jobs: &MediaJobSender,
+    reaction: &ReactionCount,
+    size: f32,
+) -> egui::Response {
+    match &reaction.emoji {
+        Some(emoji) => custom_emoji_ui(ui, images, jobs, emoji, size),
+        None => {
+            ui.add(Label::new(RichText::new(&reaction.content).size(size * 0.8)).selectable(false))
+        }
+    }
+}
+
+/// The most common reactions to a note, drawn before its reaction count.
+/// Hovering shows all of them with their counts.
+pub fn note_reactions_summary(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    txn: &Transaction,
+    note_id: &[u8; 32],
+    note_key: NoteKey,
+    total: u32,
+) {
+    let groups = cached_note_reactions(ui, note_context.ndb, txn, note_id, note_key, total);
+    if groups.is_empty() {
+        return;
+    }
+
+    let resp = ui
+        .horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            for group in groups.iter().take(SUMMARY_SHOWN) {
+                reaction_glyph(ui, note_context.img_cache, note_context.jobs, group, 14.0);
+            }
+        })
+        .response;
+
+    resp.on_hover_ui(|ui| {
+        for group in groups.iter() {
+            reaction_count_ui(ui, note_context.img_cache, note_context.jobs, group, 16.0);
+        }
+    });
+}
+
+/// Grouping a note's reactions means querying all of them, so we only do it
+/// again when its reaction count changes
+fn cached_note_reactions(
+    ui: &mut egui::Ui,
+    ndb: &Ndb,
+    txn: &Transaction,
+    note_id: &[u8; 32],
+    note_key: NoteKey,
+    total: u32,
+) -> Arc<Vec<ReactionCount>> {
+    let id = egui::Id::new(("note-reactions", note_key));
+
+    let cached = ui
+        .ctx()
+        .data(|d| d.get_temp::<(u32, Arc<Vec<ReactionCount>>)>(id));
+    if let Some((cached_total, groups)) = cached {
+        if cached_total == total {
+            return groups;
+        }
+    }
+
+    let groups = Arc::new(note_reactions(ndb, txn, note_id));
+    ui.ctx()
+        .data_mut(|d| d.insert_temp(id, (total, groups.clone())));
+    groups
+}
@@ -0,0 +1,125 @@
+use egui::{Label, RichText, Sense};
+use nostrdb::Note;
+use notedeck::translate::TranslationState;
+use notedeck::{tr, NoteContext, NotedeckTextStyle};
+
+use crate::secondary_label;
+
+/// A note's translation, beneath its contents. Notes get a "Translate"
+/// link, and the ones that look like they're in a language the user doesn't
+/// read are translated right away when auto-translate is on.
+pub fn translation_ui(ui: &mut egui::Ui, note_context: &mut NoteContext, note: &Note) {
+    if !note_context.note_cache.translations.is_enabled() || note.content().trim().is_empty() {
+        return;
+    }
+
+    let Some(note_key) = note.key() else {
+        return;
+    };
+
+    let language = note_context
+        .note_cache
+        .cached_note_or_insert(note_key, note)
+        .language;
+    // we can't tell the language of every note, short ones especially, so
+    // our guess only decides what gets translated without asking
+    let foreign =
+        language.is_some_and(|language| !note_context.i18n.user_languages().contains(&language));
+    let target = note_context
+        .i18n
+        .get_current_locale()
+        .language
+        .as_str()
+        .to_owned();
+
+    let translations = &mut note_context.note_cache.translations;
+    let translate = match translations.get(note.id()) {
+        None if foreign && translations.auto_translate() => true,
+        None => translate_link(
+            ui,
+            tr!(
+                note_context.i18n,
+                "Translate",
+                "Link to translate a note into the app's language"
+            ),
+        )
+        .clicked(),
+        Some(TranslationState::Pending) => {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new().size(12.0));
+                secondary_label(
+                    ui,
+                    tr!(
+                        note_context.i18n,
+                        "Translating…",
+                        "Shown while a note is being translated"
+                    ),
+                );
+            });
+            false
+        }
+        Some(TranslationState::Failed(err)) => translate_link(
+            ui,
+            tr!(
+                note_context.i18n,
+                "Couldn't translate, try again",
+                "Link to retry translating a note after it failed"
+            ),
+        )
+        .on_hover_text(err)
+        .clicked(),
+        Some(TranslationState::Done(translation)) => {
+            let label = match translation.source.as_deref().or(language) {
+                Some(source) => tr!(
+                    note_context.i18n,
+                    "Translated from {language}",
+                    "Label above a note's translation",
+                    language = source.to_uppercase()
+                ),
+                None => tr!(
+                    note_context.i18n,
+                    "Translated",
+                    "Label above a note's translation when we don't know its language"
+                ),
+            };
+
+            ui.add_space(4.0);
+            ui.vertical(|ui| {
+                secondary_label(ui, label);
+                ui.add(
+                    Label::new(
+                        RichText::new(&translation.text)
+                            .text_style(NotedeckTextStyle::NoteBody.text_style()),
+                    )
+                    .wrap(),
+                );
+            });
+            false
+        }
+    };
+
+    // our guess is good enough to decide whether to translate, but the
+    // server is better at telling similar languages apart
+    if translate {
+        note_context.note_cache.translations.request(
+            ui.ctx(),
+            note.id(),
+            note.content(),
+            None,
+            &target,
+        );
+    }
+}
+
+fn translate_link(ui: &mut egui::Ui, text: String) -> egui::Response {
+    ui.add(
+        Label::new(
+            RichText::new(text)
+                .small()
+                .color(ui.visuals().hyperlink_color),
+        )
+        .sense(Sense::click())
+        .selectable(false),
+    )
+    .on_hover_cursor(egui::CursorIcon::PointingHand)
+}
@@ -9,12 +9,16 @@ pub use picture::ProfilePic;
 pub use preview::ProfilePreview;
 
 use egui::{load::TexturePoll, Label, RichText};
-use notedeck::{IsFollowing, NostrName, NotedeckTextStyle};
+use notedeck::{tr, IsFollowing, Localization, Nip05Status, NostrName, NotedeckTextStyle};
 
 use crate::{app_images, colors, widgets::styled_button_toggleable};
 
+/// `nip05_status` is the verification status of the profile's nip05, when
+/// we have a verifier to ask. We only show the verified badge once the
+/// domain agrees with the claim.
 pub fn display_name_widget<'a>(
     name: &'a NostrName<'a>,
+    nip05_status: Option<(&'a mut Localization, Nip05Status)>,
     add_placeholder_space: bool,
 ) -> impl egui::Widget + 'a {
     move |ui: &mut egui::Ui| -> egui::Response {
@@ -45,15 +49,8 @@ pub fn display_name_widget<'a>(
                 }
 
                 let nip05_resp = name.nip05.map(|nip05| {
-                    ui.horizontal_wrapped(|ui| {
-                        ui.spacing_mut().item_spacing.x = 2.0;
-
-                        ui.add(app_images::verified_image());
-
-                        ui.label(RichText::new(nip05).size(16.0).color(crate::colors::TEAL))
-                            .on_hover_text(nip05)
-                    })
-                    .inner
+                    ui.horizontal_wrapped(|ui| nip05_ui(ui, nip05, nip05_status))
+                        .inner
                 });
 
                 (username_resp, nip05_resp)
@@ -77,6 +74,70 @@ pub fn display_name_widget<'a>(
     }
 }
 
+fn nip05_ui(
+    ui: &mut egui::Ui,
+    nip05: &str,
+    status: Option<(&mut Localization, Nip05Status)>,
+) -> egui::Response {
+    ui.spacing_mut().item_spacing.x = 2.0;
+
+    let text = RichText::new(nip05).size(16.0);
+    match status {
+        Some((_, Nip05Status::Verified)) => {
+            ui.add(app_images::verified_image());
+            ui.label(text.color(colors::TEAL)).on_hover_text(nip05)
+        }
+
+        Some((
+            i18n,
+            Nip05Status::Mismatch {
+                actual: Some(actual),
+            },
+        )) => {
+            let color = ui.visuals().error_fg_color;
+            let warning = tr!(
+                i18n,
+                "Possible impersonation: {nip05} belongs to a different account ({account})",
+                "Hover text on a nip05 that the domain says belongs to another account",
+                nip05 = nip05,
+                account = actual.npub().unwrap_or_else(|| actual.hex())
+            );
+            ui.label(RichText::new("⚠").size(16.0).color(color))
+                .union(ui.label(text.color(color).strikethrough()))
+                .on_hover_text(warning)
+        }
+
+        Some((i18n, Nip05Status::Mismatch { actual: None })) => ui
+            .label(text.color(ui.visuals().warn_fg_color).strikethrough())
+            .on_hover_text(tr!(
+                i18n,
+                "{nip05} does not list this account",
+                "Hover text on a nip05 that the domain doesn't list for the account",
+                nip05 = nip05
+            )),
+
+        Some((i18n, Nip05Status::Unreachable)) => {
+            ui.label(text.color(colors::MID_GRAY)).on_hover_text(tr!(
+                i18n,
+                "Could not verify {nip05}",
+                "Hover text on a nip05 whose domain couldn't be reached",
+                nip05 = nip05
+            ))
+        }
+
+        Some((i18n, Nip05Status::Pending)) => {
+            ui.label(text.color(colors::MID_GRAY)).on_hover_text(tr!(
+                i18n,
+                "Verifying {nip05}...",
+                "Hover text on a nip05 that is being verified",
+                nip05 = nip05
+            ))
+        }
+
+        None => ui.label(text.color(colors::MID_GRAY)).on_hover_text(nip05),
+    }
+}
+
 pub fn about_section_widget<'a>(profile: Option<&'a ProfileRecord<'a>>) -> impl egui::Widget + 'a {
     move |ui: &mut egui::Ui| {
         if let Some(about) = profile
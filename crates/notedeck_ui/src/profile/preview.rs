@@ -5,7 +5,7 @@ use nostrdb::ProfileRecord;
 
 use notedeck::{
     name::get_display_name, profile::get_profile_url, tr, Images, Localization, MediaJobSender,
-    NotedeckTextStyle,
+    Nip05Status, NotedeckTextStyle,
 };
 
 use super::{about_section_widget, banner, display_name_widget};
@@ -15,6 +15,7 @@ pub struct ProfilePreview<'a, 'cache> {
     cache: &'cache mut Images,
     jobs: &'cache MediaJobSender,
     banner_height: Size,
+    nip05_status: Option<(&'cache mut Localization, Nip05Status)>,
 }
 
 impl<'a, 'cache> ProfilePreview<'a, 'cache> {
@@ -29,6 +30,7 @@ impl<'a, 'cache> ProfilePreview<'a, 'cache> {
             jobs,
             cache,
             banner_height,
+            nip05_status: None,
         }
     }
 
@@ -36,6 +38,15 @@ impl<'a, 'cache> ProfilePreview<'a, 'cache> {
         self.banner_height = size;
     }
 
+    pub fn nip05_status(
+        mut self,
+        i18n: &'cache mut Localization,
+        status: Option<Nip05Status>,
+    ) -> Self {
+        self.nip05_status = status.map(|status| (i18n, status));
+        self
+    }
+
     fn body(self, ui: &mut egui::Ui) {
         let padding = 12.0;
         crate::padding(padding, ui, |ui| {
@@ -53,6 +64,7 @@ impl<'a, 'cache> ProfilePreview<'a, 'cache> {
             );
             ui.add(display_name_widget(
                 &get_display_name(Some(self.profile)),
+                self.nip05_status,
                 false,
             ));
             ui.add(about_section_widget(Some(self.profile)));
@@ -110,7 +122,11 @@ impl egui::Widget for SimpleProfilePreview<'_, '_> {
                         .size(48.0),
                 );
                 ui.vertical(|ui| {
-                    ui.add(display_name_widget(&get_display_name(self.profile), true));
+                    ui.add(display_name_widget(
+                        &get_display_name(self.profile),
+                        None,
+                        true,
+                    ));
                     if !self.is_nsec {
                         ui.add(
                             Label::new(
@@ -1,6 +1,7 @@
 use egui::{Color32, RichText, Widget};
 use nostrdb::ProfileRecord;
-use notedeck::{fonts::NamedFontFamily, tr, Localization};
+use notedeck::emoji::CustomEmoji;
+use notedeck::{fonts::NamedFontFamily, tr, Images, Localization, MediaJobSender};
 
 pub struct Username<'a> {
     i18n: &'a mut Localization,
@@ -8,6 +9,14 @@ pub struct Username<'a> {
     pk: &'a [u8; 32],
     pk_colored: bool,
     abbrev: usize,
+    emoji: Option<NameEmoji<'a>>,
+}
+
+/// What we need to draw custom emoji in a name
+struct NameEmoji<'a> {
+    emojis: &'a [CustomEmoji],
+    images: &'a mut Images,
+    jobs: &'a MediaJobSender,
 }
 
 impl<'a> Username<'a> {
@@ -21,6 +30,23 @@ impl<'a> Username<'a> {
         self
     }
 
+    /// Draw the custom emoji (NIP-30) the profile declares in its name
+    pub fn emojis(
+        mut self,
+        emojis: &'a [CustomEmoji],
+        images: &'a mut Images,
+        jobs: &'a MediaJobSender,
+    ) -> Self {
+        if !emojis.is_empty() {
+            self.emoji = Some(NameEmoji {
+                emojis,
+                images,
+                jobs,
+            });
+        }
+        self
+    }
+
     pub fn new(
         i18n: &'a mut Localization,
         profile: Option<&'a ProfileRecord>,
@@ -34,12 +60,13 @@ impl<'a> Username<'a> {
             pk,
             pk_colored,
             abbrev,
+            emoji: None,
         }
     }
 }
 
 impl Widget for Username<'_> {
-    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
         ui.horizontal(|ui| {
             ui.spacing_mut().item_spacing.x = 0.0;
 
@@ -52,9 +79,15 @@ impl Widget for Username<'_> {
             if let Some(profile) = self.profile {
                 if let Some(prof) = profile.record().profile() {
                     if prof.display_name().is_some() && prof.display_name().unwrap() != "" {
-                        ui_abbreviate_name(ui, prof.display_name().unwrap(), self.abbrev, color);
+                        ui_abbreviate_name(
+                            ui,
+                            prof.display_name().unwrap(),
+                            self.abbrev,
+                            color,
+                            self.emoji.as_mut(),
+                        );
                     } else if let Some(name) = prof.name() {
-                        ui_abbreviate_name(ui, name, self.abbrev, color);
+                        ui_abbreviate_name(ui, name, self.abbrev, color, self.emoji.as_mut());
                     }
                 }
             } else {
@@ -84,7 +117,13 @@ fn colored_name(name: &str, color: Option<Color32>) -> RichText {
     txt
 }
 
-fn ui_abbreviate_name(ui: &mut egui::Ui, name: &str, len: usize, color: Option<Color32>) {
+fn ui_abbreviate_name(
+    ui: &mut egui::Ui,
+    name: &str,
+    len: usize,
+    color: Option<Color32>,
+    emoji: Option<&mut NameEmoji>,
+) {
     let should_abbrev = name.len() > len;
     let name = if should_abbrev {
         let closest = notedeck::abbrev::floor_char_boundary(name, len);
@@ -93,7 +132,13 @@ fn ui_abbreviate_name(ui: &mut egui::Ui, name: &str, len: usize, color: Option<C
         name
     };
 
-    ui.label(colored_name(name, color));
+    if let Some(emoji) = emoji {
+        crate::emoji::emoji_text_ui(ui, emoji.images, emoji.jobs, name, emoji.emojis, |text| {
+            colored_name(text, color)
+        });
+    } else {
+        ui.label(colored_name(name, color));
+    }
 
     if should_abbrev {
         ui.label(colored_name("..", color));
@@ -26,6 +26,7 @@ mkShell ({
     #heaptrack
   ] ++ lib.optionals (!stdenv.isDarwin) [
     zenity
+    ffmpeg
   ] ++ lib.optionals use_android [
     gradle
     jre